@@ -0,0 +1,81 @@
+// Datasets combined across the values of a parameter with `--aggregate`, e.g. across seeds, so that
+// a chart shows one series for each configuration of the other parameters. The samples of the
+// combined datasets are pooled, so means and ranges are over every sample of every value.
+use super::*;
+
+// Replaces the datasets which differ only in the parameter with a single dataset without it. The
+// baseline is aggregated the same way, so that it is still matched by name.
+pub(crate) fn aggregate(data: &mut StressTestData, parameter: &str) {
+    let num_datasets = data.datasets.len();
+    // Merged in name order, so that the pooled samples are in the same order on every run.
+    let mut separate: Vec<(String, DataSet)> = std::mem::take(&mut data.datasets).into_iter().collect();
+    separate.sort_by(|a, b| ordering::natural_cmp(&a.0, &b.0));
+
+    let mut datasets: HashMap<String, DataSet> = Default::default();
+    for (_, mut dataset) in separate {
+        dataset.parameters.remove(parameter);
+        let name = DataSet::get_name(dataset.base_name.clone(), &dataset.parameters);
+        match datasets.entry(name) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => entry.get_mut().merge(dataset),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(dataset);
+            },
+        }
+    }
+
+    if datasets.len() < num_datasets {
+        log::info!("Aggregated {} datasets into {} across the values of {}", num_datasets, datasets.len(), parameter);
+    }
+    data.datasets = datasets;
+
+    if let Some(baseline) = &mut data.baseline {
+        aggregate(baseline, parameter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_sample(data: &mut StressTestData, parameters: &[(&str, u64)], commits: u64, commit_time: f64) {
+        let parameters = parameters.iter().map(|(name, value)| (name.to_string(), ParameterValue::Int(*value))).collect();
+        let sample = Sample { run: 0, commit_time, sample_commit_time: commit_time, commits_per_second: 1000.0 / commit_time, queries_per_second: 0.0, query_latency: None, disk_size: None, write_amplification: None, memory_usage: None, heap_usage: None, plugin_metrics: Default::default(), timestamp: None };
+        data.add_sample("stress".to_string(), parameters, commits, &sample);
+    }
+
+    #[test]
+    fn pools_the_samples_of_datasets_differing_in_the_parameter() {
+        let mut data = StressTestData::new();
+        add_sample(&mut data, &[("readers", 0), ("seed", 2)], 1000, 0.3);
+        add_sample(&mut data, &[("readers", 0), ("seed", 1)], 1000, 0.1);
+        add_sample(&mut data, &[("readers", 0), ("seed", 1)], 2000, 0.2);
+        add_sample(&mut data, &[("readers", 4), ("seed", 1)], 1000, 0.5);
+        let mut baseline = StressTestData::new();
+        add_sample(&mut baseline, &[("readers", 0), ("seed", 1)], 1000, 0.1);
+        add_sample(&mut baseline, &[("readers", 0), ("seed", 2)], 1000, 0.2);
+        data.baseline = Some(Box::new(baseline));
+
+        aggregate(&mut data, "seed");
+
+        assert_eq!(data.sorted_names(), ["stress (readers=0)", "stress (readers=4)"]);
+        let pooled = &data.datasets["stress (readers=0)"];
+        assert!(!pooled.parameters.contains_key("seed"));
+        assert_eq!(pooled.sorted_values.iter().map(|value| value.num_commits).collect::<Vec<_>>(), [1000, 2000]);
+        // Samples are pooled in name order, seed=1 before seed=2.
+        assert_eq!(pooled.sorted_values[0].commit_time.samples, [0.1, 0.3]);
+        assert_eq!(pooled.max_commit_time, 0.3);
+        assert_eq!(data.datasets["stress (readers=4)"].sorted_values[0].commit_time.samples, [0.5]);
+        assert_eq!(data.get_baseline("stress (readers=0)").unwrap().sorted_values[0].commit_time.samples, [0.1, 0.2]);
+    }
+
+    #[test]
+    fn keeps_datasets_without_the_parameter() {
+        let mut data = StressTestData::new();
+        add_sample(&mut data, &[("readers", 0)], 1000, 0.1);
+        add_sample(&mut data, &[("readers", 4)], 1000, 0.2);
+
+        aggregate(&mut data, "seed");
+
+        assert_eq!(data.sorted_names(), ["stress (readers=0)", "stress (readers=4)"]);
+    }
+}
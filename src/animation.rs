@@ -0,0 +1,58 @@
+// Animated GIFs of the charts as the run progressed, with `--animate` or `--output-format gif`,
+// e.g. to show in a presentation how throughput changes as the database fills. Each frame adds
+// the next few commit buckets to every dataset, on axes fitted to the whole run so that they stay
+// still while the lines grow. Baselines are drawn whole from the first frame.
+use super::*;
+
+// Commit buckets added by each frame when `--output-format gif` is given without `--animate`.
+pub static DEFAULT_FRAME_BUCKETS: usize = 10;
+
+// Draws a frame for every `frame_buckets` commit counts of the datasets, and a last frame of the
+// whole run. The commit buckets are taken out of the datasets and put back a frame at a time, so
+// that the data is not copied for each frame.
+pub(crate) fn write_animation(path: &std::path::Path, data: Option<&mut StressTestData>, params: &Params, chart_specs: &[ChartSpec], frame_buckets: usize, frame_delay: u32) -> Result<(), Box<dyn Error>> {
+    if frame_buckets == 0 {
+        return Err("--animate needs at least one commit bucket per frame".into())
+    }
+
+    let image_size = params.get_image_size(&params.get_layout(chart_specs.len())?);
+    let root_area = BitMapBackend::gif(path, image_size, frame_delay)?.into_drawing_area();
+    let Some(data) = data else {
+        return draw_to_area(&root_area, None, params, chart_specs, true)
+    };
+
+    let frame_commits = get_frame_commits(data, frame_buckets);
+    if frame_commits.is_empty() {
+        return draw_to_area(&root_area, Some(data), params, chart_specs, true)
+    }
+    let mut remaining: Vec<(String, std::iter::Peekable<std::vec::IntoIter<ValueSet>>)> = data.datasets.iter_mut()
+        .map(|(name, dataset)| (name.clone(), std::mem::take(&mut dataset.sorted_values).into_iter().peekable()))
+        .collect();
+
+    for commits in &frame_commits {
+        for (name, values) in &mut remaining {
+            let sorted_values = &mut data.datasets.get_mut(name).expect("Datasets are not removed while animating").sorted_values;
+            while let Some(value) = values.next_if(|value| value.num_commits <= *commits) {
+                sorted_values.push(value);
+            }
+        }
+        draw_to_area(&root_area, Some(data), params, chart_specs, true)?;
+    }
+
+    log::debug!("Drew {} animation frames", frame_commits.len());
+    Ok(())
+}
+
+// The last commit count of each frame, every `frame_buckets` of the commit counts of all datasets
+// and the last of them, so that the final frame shows the whole run.
+fn get_frame_commits(data: &StressTestData, frame_buckets: usize) -> Vec<u64> {
+    let mut commits: Vec<u64> = data.datasets.values().flat_map(|dataset| dataset.sorted_values.iter().map(|value| value.num_commits)).collect();
+    commits.sort_unstable();
+    commits.dedup();
+
+    let mut frame_commits: Vec<u64> = commits.iter().skip(frame_buckets - 1).step_by(frame_buckets).copied().collect();
+    if let Some(last) = commits.last().filter(|last| frame_commits.last() != Some(*last)) {
+        frame_commits.push(*last);
+    }
+    frame_commits
+}
@@ -0,0 +1,73 @@
+// Events such as "compaction triggered" marked at a commit count with `--annotations`, drawn as
+// labelled vertical lines on every chart whose X axis is the number of commits.
+use super::*;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Annotation {
+    pub commits: u64,
+    pub label: String,
+}
+
+/// Reads annotations from a JSON array of `{"commits": ..., "label": ...}` objects if the file has
+/// a `.json` extension, or otherwise from CSV lines of `commits,label`, with an optional header.
+/// Annotations are returned in commit order.
+pub fn load_annotations(path: &Path) -> Result<Vec<Annotation>, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read annotations file {}: {}", path.display(), e))?;
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+    let mut annotations = match extension.as_deref() {
+        Some("json") => serde_json::from_str(&text).map_err(|e| format!("Failed to parse annotations file {}: {}", path.display(), e))?,
+        _ => parse_csv(&text).map_err(|e| format!("Failed to parse annotations file {}: {}", path.display(), e))?,
+    };
+    annotations.sort_by_key(|a: &Annotation| a.commits);
+    Ok(annotations)
+}
+
+fn parse_csv(text: &str) -> Result<Vec<Annotation>, String> {
+    let mut annotations: Vec<Annotation> = Default::default();
+    let lines = text.lines().enumerate().map(|(index, line)| (index, line.trim())).filter(|(_, line)| !line.is_empty());
+    for (position, (index, line)) in lines.enumerate() {
+        // Labels may contain commas, so only the first one separates the fields.
+        let (commits_text, label) = line.split_once(',').ok_or_else(|| format!("line {}: expected commits,label", index + 1))?;
+        let commits = match commits_text.trim().parse::<u64>() {
+            Ok(commits) => commits,
+            // A header line.
+            Err(_) if position == 0 => continue,
+            Err(_) => return Err(format!("line {}: invalid commit count '{}'", index + 1, commits_text.trim())),
+        };
+        let label = label.trim();
+        let label = label.strip_prefix('"').and_then(|l| l.strip_suffix('"')).unwrap_or(label);
+        annotations.push(Annotation { commits, label: label.to_string() });
+    }
+    Ok(annotations)
+}
+
+// Draws a dashed vertical line across the Y range at each annotation within the X range, with its
+// label beside the top of the line. Labels of nearby annotations are staggered so they do not
+// overlap. Called before the series are drawn, so that the lines stay behind them.
+pub(crate) fn draw_annotations<'a, DB: DrawingBackend + 'a, Y: Ranged<ValueType = f64>>(cc: &mut plotters::chart::ChartContext<'a, DB, Cartesian2d<plotters::coord::types::RangedCoordf64, Y>>, annotations: &[Annotation], theme: &Theme, font_family: &str, pixel_height: f64) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let (x_range, y_range) = (cc.x_range(), cc.y_range());
+    let visible: Vec<&Annotation> = annotations.iter().filter(|a| (x_range.start..=x_range.end).contains(&(a.commits as f64))).collect();
+    if visible.is_empty() {
+        return Ok(())
+    }
+
+    let colour = theme.muted_text();
+    let dash_size = (pixel_height * 0.006) as i32;
+    for annotation in &visible {
+        let x = annotation.commits as f64;
+        cc.draw_series(DashedLineSeries::new(vec![(x, y_range.start), (x, y_range.end)], dash_size, dash_size, colour.stroke_width(1)))?;
+    }
+
+    let font_size = pixel_height * 0.015;
+    let offset = (pixel_height * 0.005) as i32;
+    cc.draw_series(visible.iter().enumerate().map(|(index, annotation)| {
+        let y = offset + (index % 3) as i32 * (font_size * 1.2) as i32;
+        EmptyElement::at((annotation.commits as f64, y_range.end))
+        + Text::new(annotation.label.clone(), (offset, y), (font_family, font_size).into_font().color(&colour))
+    }))?;
+
+    Ok(())
+}
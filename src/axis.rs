@@ -0,0 +1,60 @@
+// Axis ranges pinned with `--y-range`, so that charts of different runs can be compared side by
+// side on identical axes.
+use std::fmt;
+
+// Either end left unset is fitted to the plotted values as usual.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AxisRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl AxisRange {
+    // Parses `MIN:MAX`, where either end may be empty, e.g. `0:50000` or `:50000`. An empty text
+    // leaves both ends fitted.
+    pub fn parse(text: &str) -> Result<AxisRange, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Ok(AxisRange::default())
+        }
+
+        let (min_text, max_text) = text.split_once(':').ok_or_else(|| format!("Invalid axis range '{}', expected MIN:MAX", text))?;
+        let parse_end = |end: &str| -> Result<Option<f64>, String> {
+            match end.trim() {
+                "" => Ok(None),
+                end => end.parse::<f64>().ok().filter(|v| v.is_finite()).map(Some).ok_or_else(|| format!("Invalid value '{}' in axis range '{}'", end, text)),
+            }
+        };
+        let range = AxisRange { min: parse_end(min_text)?, max: parse_end(max_text)? };
+
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min >= max {
+                return Err(format!("Invalid axis range '{}', the minimum must be below the maximum", text))
+            }
+        }
+        Ok(range)
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    // The fitted range with the pinned ends replaced. A pinned end beyond the other, fitted, end
+    // moves that end to keep the range non-empty.
+    pub fn apply(&self, fitted: std::ops::Range<f64>) -> std::ops::Range<f64> {
+        let min = self.min.unwrap_or(fitted.start);
+        let max = self.max.unwrap_or(fitted.end);
+        match (max > min, self.min.is_some()) {
+            (true, _) => min..max,
+            (false, true) => min..min + min.abs().max(1.0),
+            (false, false) => max - max.abs().max(1.0)..max,
+        }
+    }
+}
+
+impl fmt::Display for AxisRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let end = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        write!(f, "{}:{}", end(self.min), end(self.max))
+    }
+}
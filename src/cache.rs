@@ -0,0 +1,256 @@
+// Data parsed from each data file with `--cache`, kept so that a later run only parses the lines
+// appended to the file since, rather than the whole history of a long running stress test.
+use super::*;
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+// Changed along with the layout of the cached data, so that older caches are parsed again.
+static CACHE_VERSION: u32 = 5;
+
+// Level of the zlib compression of cache files. Low, as compressing a large cache at higher levels
+// can take longer than parsing the data file again.
+static COMPRESSION_LEVEL: u8 = 1;
+
+// Bytes at the start of a data file, and before the position its cache reached, which are compared
+// to tell a file which was appended to from one which was rewritten.
+static CHECKED_BYTES: u64 = 65536;
+
+// Read with the data owned and written with it borrowed.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<Data> {
+    position: FilePosition,
+    checksum: String,
+    data: Data,
+    // Line numbers and reasons of the malformed lines before the position.
+    malformed: Vec<(usize, String)>,
+}
+
+// A float which is cached as a string when it is not finite, as JSON has no NaN or infinity, and
+// rates of samples such as queries per second without any queries are NaN.
+struct Float(f64);
+
+impl Serialize for Float {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0 {
+            value if value.is_finite() => serializer.serialize_f64(value),
+            value if value.is_nan() => serializer.serialize_str("NaN"),
+            value if value > 0.0 => serializer.serialize_str("inf"),
+            _ => serializer.serialize_str("-inf"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Float {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Float, D::Error> {
+        deserializer.deserialize_any(FloatVisitor)
+    }
+}
+
+struct FloatVisitor;
+
+impl<'de> Visitor<'de> for FloatVisitor {
+    type Value = Float;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a number, NaN, inf or -inf")
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Float, E> {
+        Ok(Float(value))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Float, E> {
+        Ok(Float(value as f64))
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Float, E> {
+        Ok(Float(value as f64))
+    }
+
+    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Float, E> {
+        match value {
+            "NaN" => Ok(Float(f64::NAN)),
+            "inf" => Ok(Float(f64::INFINITY)),
+            "-inf" => Ok(Float(f64::NEG_INFINITY)),
+            _ => Err(E::invalid_value(serde::de::Unexpected::Str(value), &self)),
+        }
+    }
+}
+
+// For float fields of the cached data, with `#[serde(with = "cache::float")]`.
+pub(crate) mod float {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        Float(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        Float::deserialize(deserializer).map(|value| value.0)
+    }
+}
+
+// Sample sets are cached as their samples, and rebuilt by adding them again. Outliers are only
+// dropped after loading, so there is no count of them to keep.
+impl Serialize for SampleSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.samples.iter().map(|sample| Float(*sample)))
+    }
+}
+
+impl<'de> Deserialize<'de> for SampleSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SampleSet, D::Error> {
+        let mut sample_set = SampleSet::new();
+        for Float(sample) in Vec::<Float>::deserialize(deserializer)? {
+            sample_set.add_sample(sample);
+        }
+        Ok(sample_set)
+    }
+}
+
+// The data of a source from its cache and the lines appended since, updating the cache. None for
+// sources which cannot be read from a position, which are read in full as without `--cache`.
+//
+// A missing, unreadable or stale cache is replaced. A line without a newline at the end of the
+// file is read but not cached, as it may still be being written.
+pub(crate) fn load_cached_source(cache_dir: &Path, run: usize, source: &dyn DataSource, source_parameters: &BTreeMap<String, ParameterValue>, tags: &BTreeMap<String, ParameterValue>, options: &LoadOptions) -> Result<Option<FileData>, VisualizerError> {
+    let path = source.location();
+    let cache_path = cache_dir.join(format!("{}.json.zz", get_key(&path, run, source_parameters, tags, options)));
+    let entry = read_entry(&cache_path, &path);
+    let cached = entry.is_some();
+    let (start, mut data, malformed) = match entry {
+        Some(entry) => (entry.position, entry.data, entry.malformed),
+        None => (FilePosition::default(), StressTestData::new(), Default::default()),
+    };
+
+    let Some(appended) = source.appended_records(start) else {
+        return Ok(None)
+    };
+    let appended = appended?;
+    let mut malformed: Vec<VisualizerError> = malformed.into_iter().map(|(line_number, reason)| VisualizerError::InvalidLine { path: path.clone(), line_number, reason }).collect();
+    let num_appended = appended.records.len();
+
+    let appended_data = fold_records(appended.records.into_iter(), run, source_parameters, tags, options)?;
+    let stopped = options.strict && !appended_data.malformed.is_empty();
+    data.merge(appended_data.data);
+    malformed.extend(appended_data.malformed);
+    if cached {
+        log::info!("Parsed {} lines appended to {} since it was cached", num_appended, path.display());
+    }
+
+    // A strict load stops at the first malformed line, so has not reached the end of the lines.
+    if !stopped && (num_appended > 0 || !cached) {
+        if let Err(e) = write_entry(&cache_path, &path, appended.end, &data, &malformed) {
+            log::warn!("Failed to write cache {}: {}", cache_path.display(), e);
+        }
+    }
+
+    if !stopped {
+        let unfinished = fold_records(appended.unfinished.into_iter(), run, source_parameters, tags, options)?;
+        data.merge(unfinished.data);
+        malformed.extend(unfinished.malformed);
+    }
+    Ok(Some(FileData { data, malformed }))
+}
+
+// Cache files are named after a hash of everything the parsed data depends on other than the
+// contents of the data file.
+fn get_key(path: &Path, run: usize, source_parameters: &BTreeMap<String, ParameterValue>, tags: &BTreeMap<String, ParameterValue>, options: &LoadOptions) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let metrics: Vec<String> = plugins::metrics().iter().map(|metric| metric.cache_key()).collect();
+    let key = format!("{} {} {} {} {:?} {:?} {:?} {:?} {:?}", CACHE_VERSION, env!("CARGO_PKG_VERSION"), path.display(), run, options.input_format, options.schema, source_parameters, tags, metrics);
+    let hash = Sha256::digest(key.as_bytes());
+    hash.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Hash of the bytes at the start of the data file and before the position.
+fn get_checksum(path: &Path, position: FilePosition) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let length = CHECKED_BYTES.min(position.offset);
+    let mut hasher = Sha256::new();
+    for start in [0, position.offset - length] {
+        let mut bytes = vec![0; length as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut bytes)?;
+        hasher.update(&bytes);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// None if there is no cache, or the data file no longer starts with the bytes which were cached.
+fn read_entry(cache_path: &Path, path: &Path) -> Option<CacheEntry<StressTestData>> {
+    let compressed = std::fs::read(cache_path).ok()?;
+    let text = miniz_oxide::inflate::decompress_to_vec_zlib(&compressed).ok()?;
+    let entry: CacheEntry<StressTestData> = serde_json::from_slice(&text).ok()?;
+    match get_checksum(path, entry.position) {
+        Ok(checksum) if checksum == entry.checksum => Some(entry),
+        _ => None,
+    }
+}
+
+// Malformed records of data files are always lines.
+fn write_entry(cache_path: &Path, path: &Path, position: FilePosition, data: &StressTestData, malformed: &[VisualizerError]) -> Result<(), Box<dyn Error>> {
+    let malformed = malformed.iter().filter_map(|error| match error {
+        VisualizerError::InvalidLine { line_number, reason, .. } => Some((*line_number, reason.clone())),
+        _ => None,
+    }).collect();
+    let entry = CacheEntry { position, checksum: get_checksum(path, position)?, data, malformed };
+
+    let compressed = miniz_oxide::deflate::compress_to_vec_zlib(&serde_json::to_vec(&entry)?, COMPRESSION_LEVEL);
+
+    if let Some(parent) = cache_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Written alongside and renamed, so that an interrupted run does not leave a partial cache.
+    let temporary_path = cache_path.with_extension("tmp");
+    std::fs::write(&temporary_path, compressed)?;
+    std::fs::rename(&temporary_path, cache_path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static HEADER: &str = "name,readers,total_commits,total_commit_time,commits,commit_time,queries,query_time\n";
+    static LINES: &str = "stress,0,1000,0.05,1000,0.05,0,0.0\n\
+        stress,4,1000,0.07,1000,0.07,500,0.02\n";
+    static APPENDED_LINES: &str = "stress,0,2000,0.11,1000,0.06,0,0.0\n\
+        stress,4,2000,0.15,1000,0.08,500,0.03\n";
+
+    // The samples of every metric of every dataset, in order, with the dataset names.
+    fn samples(data: &StressTestData) -> Vec<(String, u64, Vec<f64>, Vec<f64>)> {
+        data.sorted_names().into_iter().flat_map(|name| data.datasets[name].sorted_values.iter().map(|value| (name.clone(), value.num_commits, value.commit_time.samples.clone(), value.queries_per_second.samples.clone()))).collect()
+    }
+
+    #[test]
+    fn appended_lines_are_added_to_the_cached_data() {
+        let dir = std::env::temp_dir().join(format!("parity-db-visualizer-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run1.csv");
+        let cache_dir = dir.join("cache");
+        let cached = LoadOptions { cache_dir: Some(cache_dir.clone()), ..Default::default() };
+
+        std::fs::write(&path, format!("{}{}", HEADER, LINES)).unwrap();
+        let first = load_stress_test_data(std::slice::from_ref(&path), &cached).unwrap();
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+        assert_eq!(first.max_commits, 1000);
+
+        std::fs::write(&path, format!("{}{}{}", HEADER, LINES, APPENDED_LINES)).unwrap();
+        let appended = load_stress_test_data(std::slice::from_ref(&path), &cached).unwrap();
+        let parsed = load_stress_test_data(std::slice::from_ref(&path), &LoadOptions::default()).unwrap();
+        assert_eq!(appended.max_commits, 2000);
+        assert_eq!(appended.max_commit_time, parsed.max_commit_time);
+        // Rates of samples without queries are NaN, which compare unequal, so are compared as text.
+        assert_eq!(format!("{:?}", samples(&appended)), format!("{:?}", samples(&parsed)));
+
+        // A rewritten file is parsed again rather than appended to the cache.
+        std::fs::write(&path, format!("{}{}", HEADER, APPENDED_LINES)).unwrap();
+        let rewritten = load_stress_test_data(std::slice::from_ref(&path), &cached).unwrap();
+        let parsed = load_stress_test_data(std::slice::from_ref(&path), &LoadOptions::default()).unwrap();
+        assert_eq!(format!("{:?}", samples(&rewritten)), format!("{:?}", samples(&parsed)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,99 @@
+// Regression check against `--baseline` with `--check`, for use as a performance gate in CI. Each
+// comparison is printed as a line of JSON, and the run fails with a `RegressionError` if the mean
+// throughput of any dataset drops by more than the threshold.
+use super::*;
+use serde::Serialize;
+
+// Metrics where a lower mean is a regression.
+static CHECKED_METRICS: [(&str, ChartType); 2] = [
+    ("commits-per-second", ChartType::CommitsPerSecond),
+    ("queries-per-second", ChartType::QueriesPerSecond),
+];
+
+#[derive(Debug, Serialize)]
+struct CheckRow {
+    dataset: String,
+    metric: &'static str,
+    // Number of commit buckets present in both the dataset and its baseline.
+    buckets: usize,
+    baseline_mean: f64,
+    mean: f64,
+    change_percent: f64,
+    regression: bool,
+}
+
+pub(crate) fn check_regressions(data: &StressTestData, threshold: f64) -> Result<(), Box<dyn Error>> {
+    let rows = get_check_rows(data, threshold);
+    if rows.is_empty() {
+        return Err("No datasets to check, none have a matching baseline dataset with throughput samples".into())
+    }
+
+    for row in &rows {
+        println!("{}", serde_json::to_string(row)?);
+    }
+
+    let regressions = rows.iter().filter(|row| row.regression).count();
+    match regressions {
+        0 => {
+            println!("Check passed: {} comparisons within {}% of the baseline", rows.len(), threshold);
+            Ok(())
+        },
+        _ => Err(RegressionError { regressions, comparisons: rows.len(), threshold }.into()),
+    }
+}
+
+fn get_check_rows(data: &StressTestData, threshold: f64) -> Vec<CheckRow> {
+    let names = data.sorted_names();
+
+    let mut rows: Vec<CheckRow> = Default::default();
+    for name in names {
+        let dataset = &data.datasets[name];
+        if dataset.derived {
+            continue;
+        }
+        let Some(baseline) = data.get_baseline(name) else {
+            continue;
+        };
+
+        for (metric, chart_type) in &CHECKED_METRICS {
+            // Only the commit counts reached by both runs are compared, so that a shorter run is
+            // not penalised for the throughput of later buckets.
+            let mut samples = SampleSet::new();
+            let mut baseline_samples = SampleSet::new();
+            let mut buckets = 0;
+            for value in &dataset.sorted_values {
+                let Ok(index) = baseline.sorted_values.binary_search_by_key(&value.num_commits, |v| v.num_commits) else {
+                    continue;
+                };
+                let (Some(sample_set), Some(baseline_sample_set)) = (value.get_sample_set(chart_type), baseline.sorted_values[index].get_sample_set(chart_type)) else {
+                    continue;
+                };
+                // Rates are NaN for samples without any queries, which the identity transform
+                // leaves out.
+                samples.merge(&sample_set.transformed(&Transform::default()));
+                baseline_samples.merge(&baseline_sample_set.transformed(&Transform::default()));
+                buckets += 1;
+            }
+            let (statistics, baseline_statistics) = (&samples.statistics, &baseline_samples.statistics);
+
+            let baseline_mean = baseline_statistics.mean();
+            if statistics.num == 0 || baseline_statistics.num == 0 || baseline_mean <= 0.0 {
+                continue;
+            }
+
+            let mean = statistics.mean();
+            let change_percent = (mean - baseline_mean) / baseline_mean * 100.0;
+            rows.push(CheckRow {
+                dataset: name.clone(),
+                metric,
+                buckets,
+                baseline_mean,
+                mean,
+                change_percent,
+                regression: change_percent < -threshold,
+            });
+        }
+    }
+
+    rows
+}
@@ -0,0 +1,181 @@
+// Statistical comparison of two runs of the same configuration, with `--compare old.csv new.csv`
+// or `--baseline-mode significance`. At each commit count reached by both runs, Welch's t-test on
+// the samples of the two buckets tells whether their means differ by more than the spread of the
+// samples explains, at 95% confidence. With hundreds of buckets about one in twenty differ by
+// chance, so isolated significant buckets are expected where runs of them are not.
+use super::*;
+use serde::Serialize;
+
+// Metrics reported with `--compare`.
+static COMPARED_METRICS: [(&str, ChartType); 3] = [
+    ("commits-per-second", ChartType::CommitsPerSecond),
+    ("queries-per-second", ChartType::QueriesPerSecond),
+    ("commit-time", ChartType::CommitTime),
+];
+
+// The change of the mean from the baseline at one commit count.
+#[derive(Debug, Clone)]
+pub struct BucketComparison {
+    pub commits: f64,
+    pub change_percent: f64,
+    // Whether both buckets had the two samples with some variance which the test needs.
+    pub tested: bool,
+    pub significant: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ComparisonRow {
+    dataset: String,
+    metric: &'static str,
+    // Number of commit buckets present in both the dataset and its baseline.
+    buckets: usize,
+    tested_buckets: usize,
+    significant_increases: usize,
+    significant_decreases: usize,
+    median_change_percent: f64,
+}
+
+// Welch's t statistic of the difference between two means and its degrees of freedom, without
+// assuming the samples have equal variances. None unless both have two samples and some variance.
+pub fn welch_t_test(statistics: &RunningStatistics, baseline: &RunningStatistics) -> Option<(f64, f64)> {
+    if statistics.num < 2 || baseline.num < 2 {
+        return None
+    }
+
+    let variance = statistics.variance() / statistics.num as f64;
+    let baseline_variance = baseline.variance() / baseline.num as f64;
+    let standard_error_squared = variance + baseline_variance;
+    if !standard_error_squared.is_finite() || standard_error_squared <= 0.0 {
+        return None
+    }
+
+    let t = (statistics.mean() - baseline.mean()) / standard_error_squared.sqrt();
+    let degrees_of_freedom = standard_error_squared.powi(2) / (variance.powi(2) / (statistics.num - 1) as f64 + baseline_variance.powi(2) / (baseline.num - 1) as f64);
+    Some((t, degrees_of_freedom))
+}
+
+// The change at each commit count present in both the dataset and its baseline, as the
+// percentage change of the mean.
+pub fn compare_buckets(dataset: &DataSet, baseline: &DataSet, chart_type: &ChartType, transform: &Transform) -> Vec<BucketComparison> {
+    dataset.sorted_values.iter().filter_map(|value| {
+        let index = baseline.sorted_values.binary_search_by_key(&value.num_commits, |v| v.num_commits).ok()?;
+        // Rates are NaN for samples without any queries, which the transform leaves out.
+        let sample_set = value.get_sample_set(chart_type)?.transformed(transform);
+        let baseline_sample_set = baseline.sorted_values[index].get_sample_set(chart_type)?.transformed(transform);
+        let baseline_mean = baseline_sample_set.get_mean();
+        if sample_set.is_empty() || baseline_sample_set.is_empty() || baseline_mean == 0.0 {
+            return None
+        }
+
+        // Fractional degrees of freedom are rounded down, which errs towards not significant.
+        let test = welch_t_test(&sample_set.statistics, &baseline_sample_set.statistics);
+        Some(BucketComparison {
+            commits: value.num_commits as f64,
+            change_percent: (sample_set.get_mean() - baseline_mean) / baseline_mean.abs() * 100.0,
+            tested: test.is_some(),
+            significant: test.is_some_and(|(t, degrees_of_freedom)| t.abs() > statistics::t_critical_95(degrees_of_freedom.floor() as u64)),
+        })
+    }).collect()
+}
+
+// Prints a line of JSON for each dataset and metric with the number of buckets which differ
+// significantly from the baseline, followed by the total.
+pub(crate) fn print_report(data: &StressTestData) -> Result<(), Box<dyn Error>> {
+    let rows = get_comparison_rows(data);
+    if rows.is_empty() {
+        return Err("No datasets to compare, none of the new run match a dataset of the old run with samples at the same commit counts".into())
+    }
+
+    for row in &rows {
+        println!("{}", serde_json::to_string(row)?);
+    }
+
+    let buckets: usize = rows.iter().map(|row| row.buckets).sum();
+    let tested: usize = rows.iter().map(|row| row.tested_buckets).sum();
+    let significant: usize = rows.iter().map(|row| row.significant_increases + row.significant_decreases).sum();
+    println!("{} of {} buckets differ significantly at 95% confidence", significant, tested);
+    if tested < buckets {
+        log::warn!("{} buckets were not tested, as they need at least two samples in both runs, e.g. from repeated runs in each data file", buckets - tested);
+    }
+    Ok(())
+}
+
+fn get_comparison_rows(data: &StressTestData) -> Vec<ComparisonRow> {
+    let mut rows: Vec<ComparisonRow> = Default::default();
+    for name in data.sorted_names() {
+        let dataset = &data.datasets[name];
+        if dataset.derived {
+            continue;
+        }
+        let Some(baseline) = data.get_baseline(name) else {
+            continue;
+        };
+
+        for (metric, chart_type) in &COMPARED_METRICS {
+            let comparisons = compare_buckets(dataset, baseline, chart_type, &Transform::default());
+            if comparisons.is_empty() {
+                continue;
+            }
+
+            let changes: Vec<f64> = comparisons.iter().map(|c| c.change_percent).collect();
+            rows.push(ComparisonRow {
+                dataset: name.clone(),
+                metric,
+                buckets: comparisons.len(),
+                tested_buckets: comparisons.iter().filter(|c| c.tested).count(),
+                significant_increases: comparisons.iter().filter(|c| c.significant && c.change_percent > 0.0).count(),
+                significant_decreases: comparisons.iter().filter(|c| c.significant && c.change_percent < 0.0).count(),
+                median_change_percent: statistics::median(&changes),
+            });
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statistics_of(samples: &[f64]) -> RunningStatistics {
+        let mut statistics = RunningStatistics::new();
+        for sample in samples {
+            statistics.add_sample(*sample);
+        }
+        statistics
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} is not {}", actual, expected);
+    }
+
+    #[test]
+    fn welch_t_test_of_unequal_variances() {
+        // Variances of 2.5 and 10: t = -3 / sqrt(0.5 + 2), with 2.5^2 / (0.5^2 / 4 + 2^2 / 4)
+        // degrees of freedom.
+        let (t, degrees_of_freedom) = welch_t_test(&statistics_of(&[1.0, 2.0, 3.0, 4.0, 5.0]), &statistics_of(&[2.0, 4.0, 6.0, 8.0, 10.0])).unwrap();
+        assert_close(t, -3.0 / 2.5f64.sqrt());
+        assert_close(degrees_of_freedom, 6.25 / 1.0625);
+        // Not significant, as |t| = 1.90 is below the critical value of 2.571 for 5 degrees of
+        // freedom.
+        assert!(t.abs() < statistics::t_critical_95(degrees_of_freedom.floor() as u64));
+    }
+
+    #[test]
+    fn welch_t_test_is_antisymmetric() {
+        let (a, b) = (statistics_of(&[10.0, 11.0, 12.0, 13.0]), statistics_of(&[20.0, 20.5, 21.0]));
+        let (t, degrees_of_freedom) = welch_t_test(&a, &b).unwrap();
+        let (reversed_t, reversed_degrees_of_freedom) = welch_t_test(&b, &a).unwrap();
+        assert_close(t, -reversed_t);
+        assert_close(degrees_of_freedom, reversed_degrees_of_freedom);
+        assert!(t.abs() > statistics::t_critical_95(degrees_of_freedom.floor() as u64));
+    }
+
+    #[test]
+    fn welch_t_test_needs_two_samples_with_variance() {
+        assert!(welch_t_test(&statistics_of(&[1.0]), &statistics_of(&[1.0, 2.0])).is_none());
+        assert!(welch_t_test(&statistics_of(&[3.0, 3.0]), &statistics_of(&[3.0, 3.0, 3.0])).is_none());
+        // One of them having variance is enough.
+        assert!(welch_t_test(&statistics_of(&[3.0, 3.0]), &statistics_of(&[1.0, 2.0])).is_some());
+    }
+}
@@ -0,0 +1,141 @@
+// Compressed data files, such as archived results, which are decompressed as they are read rather
+// than to disk first. A file is gzip-compressed if it starts with the gzip magic bytes or has a
+// `.gz` extension, and is read with the `gzip` feature. Zstandard files, `.zst`, are recognised in
+// the same way and read with the `zstd` feature.
+use super::*;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+static GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+static ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+impl Compression {
+    pub fn from_extension(path: &Path) -> Option<Compression> {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("gz") | Some("gzip") => Some(Compression::Gzip),
+            Some("zst") | Some("zstd") => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    fn from_magic(bytes: &[u8]) -> Option<Compression> {
+        if bytes.starts_with(&GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(&ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    // The compression of a data file, from its first bytes or else its extension.
+    pub fn of_file(path: &Path) -> Option<Compression> {
+        let mut magic = [0u8; 4];
+        let num_read = std::fs::File::open(path).and_then(|mut file| file.read(&mut magic)).unwrap_or(0);
+        Compression::from_magic(&magic[..num_read]).or_else(|| Compression::from_extension(path))
+    }
+}
+
+// The path without any compression extension, e.g. `run1.csv` for `run1.csv.gz`, by which the
+// format of a data file and the files next to it are found.
+pub fn uncompressed_path(path: &Path) -> PathBuf {
+    match Compression::from_extension(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+// The lines of a data file or standard input, decompressed if compressed.
+pub fn decompressed<R: BufRead + 'static>(path: &Path, mut reader: R) -> Result<Box<dyn BufRead>, VisualizerError> {
+    let magic = reader.fill_buf().map_err(|source| VisualizerError::Io { path: path.to_path_buf(), source })?;
+    let extension = match is_stdin(path) {
+        true => None,
+        false => Compression::from_extension(path),
+    };
+    match Compression::from_magic(magic).or(extension) {
+        None => Ok(Box::new(reader)),
+        Some(Compression::Gzip) => decompress_gzip(path, reader),
+        Some(Compression::Zstd) => decompress_zstd(path, reader),
+    }
+}
+
+// Concatenated gzip members, as written by appending to a compressed file, are read in turn.
+#[cfg(feature = "gzip")]
+fn decompress_gzip<R: BufRead + 'static>(_path: &Path, reader: R) -> Result<Box<dyn BufRead>, VisualizerError> {
+    Ok(Box::new(std::io::BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn decompress_gzip<R: BufRead + 'static>(path: &Path, _reader: R) -> Result<Box<dyn BufRead>, VisualizerError> {
+    Err(VisualizerError::InvalidFile { path: path.to_path_buf(), reason: "Gzip-compressed data files need the `gzip` feature".to_string() })
+}
+
+// Concatenated frames are read in turn, as for gzip members.
+#[cfg(feature = "zstd")]
+fn decompress_zstd<R: BufRead + 'static>(path: &Path, reader: R) -> Result<Box<dyn BufRead>, VisualizerError> {
+    let decoder = zstd::stream::read::Decoder::with_buffer(reader).map_err(|source| VisualizerError::Io { path: path.to_path_buf(), source })?;
+    Ok(Box::new(std::io::BufReader::new(decoder)))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress_zstd<R: BufRead + 'static>(path: &Path, _reader: R) -> Result<Box<dyn BufRead>, VisualizerError> {
+    Err(VisualizerError::InvalidFile { path: path.to_path_buf(), reason: "Zstandard-compressed data files need the `zstd` feature".to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "zstd")]
+    static FIXTURE: &str = "name,readers,total_commits,total_commit_time,commits,commit_time,queries,query_time\n\
+        stress,0,1000,0.05,1000,0.05,0,0.0\n\
+        stress,0,2000,0.11,1000,0.06,0,0.0\n\
+        stress,4,1000,0.07,1000,0.07,500,0.02\n";
+
+    #[cfg(feature = "zstd")]
+    fn read_all(path: &Path) -> String {
+        let mut text = String::new();
+        decompressed(path, std::io::BufReader::new(std::fs::File::open(path).unwrap())).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn recognises_compression_from_magic_or_extension() {
+        assert_eq!(Compression::from_magic(&[0x1f, 0x8b, 0x08]), Some(Compression::Gzip));
+        assert_eq!(Compression::from_magic(&ZSTD_MAGIC), Some(Compression::Zstd));
+        assert_eq!(Compression::from_magic(b"name"), None);
+        assert_eq!(Compression::from_extension(Path::new("run1.csv.zst")), Some(Compression::Zstd));
+        assert_eq!(uncompressed_path(Path::new("results/run1.csv.zst")), PathBuf::from("results/run1.csv"));
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn round_trips_zstd_data_file() {
+        let dir = std::env::temp_dir().join(format!("parity-db-visualizer-zstd-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Without the extension the file is recognised by its magic bytes alone.
+        let compressed = zstd::encode_all(FIXTURE.as_bytes(), 3).unwrap();
+        let (zst_path, magic_path, csv_path) = (dir.join("run1.csv.zst"), dir.join("run2.csv"), dir.join("run3.csv"));
+        std::fs::write(&zst_path, &compressed).unwrap();
+        std::fs::write(&magic_path, &compressed).unwrap();
+        std::fs::write(&csv_path, FIXTURE).unwrap();
+
+        assert_eq!(Compression::of_file(&magic_path), Some(Compression::Zstd));
+        assert_eq!(read_all(&zst_path), FIXTURE);
+        assert_eq!(read_all(&magic_path), FIXTURE);
+
+        let options = LoadOptions::default();
+        let from_zst = load_stress_test_data(&[zst_path], &options).unwrap();
+        let from_csv = load_stress_test_data(&[csv_path], &options).unwrap();
+        assert_eq!(from_zst.sorted_names(), from_csv.sorted_names());
+        assert_eq!(from_zst.max_commits, 2000);
+        assert_eq!(from_zst.max_commit_time, from_csv.max_commit_time);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,323 @@
+use super::*;
+use serde::Deserialize;
+use std::path::Path;
+
+// A TOML or YAML file, selected by extension. Apart from templates and parameter labels, settings
+// are used when the matching option is not given on the command line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub templates: BTreeMap<String, ChartTemplate>,
+    #[serde(default)]
+    pub parameters: ParameterLabels,
+    // Charts drawn in place of the default chart types, unless chart types are given explicitly.
+    #[serde(default)]
+    pub charts: Vec<TemplateChart>,
+    // Paths are relative to the directory of the config file.
+    #[serde(default)]
+    pub data: Vec<PathBuf>,
+    #[serde(default)]
+    pub baseline: Vec<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub stats_out: Option<PathBuf>,
+    pub annotations: Option<PathBuf>,
+    // Reference lines as given to `--hline`.
+    #[serde(default)]
+    pub hline: Vec<String>,
+    pub schema: Option<PathBuf>,
+    pub layout: Option<String>,
+    pub palette: Option<String>,
+    // A theme name, or the path of a theme file.
+    pub theme: Option<String>,
+    pub font_family: Option<String>,
+    // Percentages of the chart height, as on the command line.
+    pub title_font_size: Option<f64>,
+    pub axis_label_font_size: Option<f64>,
+    pub tick_label_font_size: Option<f64>,
+    pub legend_font_size: Option<f64>,
+    pub color_by: Option<String>,
+    pub style_by: Option<String>,
+    pub accessible: Option<bool>,
+    pub group_by: Option<String>,
+    pub title: Option<String>,
+    // May contain the same placeholders as `--footer`.
+    pub footer: Option<String>,
+    #[serde(skip)]
+    pub directory: PathBuf,
+    // The config file itself, for errors in its charts.
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+// Human readable name and unit for a parameter, used in legends and chart captions in place of
+// the raw column name.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ParameterLabel {
+    pub label: Option<String>,
+    pub unit: Option<String>,
+}
+
+pub type ParameterLabels = BTreeMap<String, ParameterLabel>;
+
+pub fn label_name(labels: &ParameterLabels, name: &str) -> String {
+    match labels.get(name).and_then(|l| l.label.as_ref()) {
+        Some(label) => label.clone(),
+        None => name.to_string(),
+    }
+}
+
+pub fn label_value(labels: &ParameterLabels, name: &str, value: &str) -> String {
+    match labels.get(name).and_then(|l| l.unit.as_ref()) {
+        Some(unit) => format!("{} {}", value, unit),
+        None => value.to_string(),
+    }
+}
+
+// A named set of charts which can be added with `--template`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ChartTemplate {
+    pub charts: Vec<TemplateChart>,
+}
+
+// A single chart within a template. Text fields may contain `${name}` placeholders which are
+// substituted when the template is instantiated. Unset options fall back to the command line.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct TemplateChart {
+    #[serde(rename = "type")]
+    pub chart_type: String,
+    #[serde(default)]
+    pub filter: String,
+    pub title: Option<String>,
+    pub transform: Option<String>,
+    pub y_range: Option<String>,
+    pub secondary: Option<String>,
+    pub stall_threshold: Option<f64>,
+    pub stall_window: Option<usize>,
+    pub histogram_bins: Option<usize>,
+    pub rolling_percentile: Option<f64>,
+    pub rolling_window: Option<usize>,
+    pub variance_window: Option<usize>,
+    pub final_buckets: Option<usize>,
+    pub raw_samples: Option<bool>,
+    pub jitter: Option<f64>,
+    pub log_y: Option<bool>,
+    pub smooth: Option<usize>,
+    pub smooth_mode: Option<SmoothMode>,
+    pub spread: Option<Spread>,
+    pub range: Option<String>,
+    pub gaps: Option<GapMode>,
+    pub marker_shape: Option<MarkerShape>,
+    pub marker_size: Option<u32>,
+    pub trend: Option<TrendFit>,
+    pub y_unit: Option<YUnit>,
+}
+
+// A template reference from the command line, `name` or `name:key=value,key=value`.
+#[derive(Debug)]
+pub struct TemplateInstance {
+    pub name: String,
+    pub substitutions: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Config, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+        let mut config: Config = match extension.as_deref() {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&text).map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))?,
+            _ => toml::from_str(&text).map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))?,
+        };
+        config.directory = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        config.path = path.to_path_buf();
+        Ok(config)
+    }
+
+    // Fills in the arguments which were not given on the command line.
+    pub fn apply(&self, args: &mut Args, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+        let from_command_line = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if !from_command_line("data_path") && !self.data.is_empty() {
+            args.data_path = Some(self.data.iter().map(|path| self.directory.join(path)).collect());
+        }
+        if !from_command_line("baseline") && !self.baseline.is_empty() {
+            args.baseline = self.baseline.iter().map(|path| self.directory.join(path)).collect();
+        }
+        if let (false, Some(output)) = (from_command_line("output"), &self.output) {
+            args.output = Some(self.directory.join(output));
+        }
+        if let (false, Some(stats_out)) = (from_command_line("stats_out"), &self.stats_out) {
+            args.stats_out = Some(self.directory.join(stats_out));
+        }
+        if let (false, Some(annotations)) = (from_command_line("annotations"), &self.annotations) {
+            args.annotations = Some(self.directory.join(annotations));
+        }
+        if !from_command_line("hline") && !self.hline.is_empty() {
+            args.hline = self.hline.clone();
+        }
+        if let (false, Some(schema)) = (from_command_line("schema"), &self.schema) {
+            args.schema = Some(self.directory.join(schema));
+        }
+        if let (false, Some(layout)) = (from_command_line("layout"), &self.layout) {
+            args.layout = Some(parse_layout(layout).map_err(|e| format!("Invalid config file: {}", e))?);
+        }
+        if let (false, Some(palette)) = (from_command_line("palette"), &self.palette) {
+            args.palette = Some(Palette::parse(palette).map_err(|e| format!("Invalid config file: {}", e))?);
+        }
+        if let (false, Some(theme)) = (from_command_line("theme"), &self.theme) {
+            args.theme = Some(match Theme::is_named(theme) {
+                true => theme.clone(),
+                false => self.directory.join(theme).to_string_lossy().into_owned(),
+            });
+        }
+        if let (false, Some(font_family)) = (from_command_line("font_family"), &self.font_family) {
+            args.font_family = font_family.clone();
+        }
+        for (id, size, arg) in [
+            ("title_font_size", self.title_font_size, &mut args.title_font_size),
+            ("axis_label_font_size", self.axis_label_font_size, &mut args.axis_label_font_size),
+            ("tick_label_font_size", self.tick_label_font_size, &mut args.tick_label_font_size),
+            ("legend_font_size", self.legend_font_size, &mut args.legend_font_size),
+        ] {
+            if let (false, Some(size)) = (from_command_line(id), size) {
+                *arg = size;
+            }
+        }
+        if let (false, Some(color_by)) = (from_command_line("color_by"), &self.color_by) {
+            args.color_by = Some(color_by.clone());
+        }
+        if let (false, Some(style_by)) = (from_command_line("style_by"), &self.style_by) {
+            args.style_by = Some(style_by.clone());
+        }
+        if let (false, Some(accessible)) = (from_command_line("accessible"), self.accessible) {
+            args.accessible = accessible;
+        }
+        if let (false, Some(group_by)) = (from_command_line("group_by"), &self.group_by) {
+            args.group_by = Some(group_by.clone());
+        }
+        if let (false, Some(title)) = (from_command_line("title"), &self.title) {
+            args.title = Some(title.clone());
+        }
+        if let (false, Some(footer)) = (from_command_line("footer"), &self.footer) {
+            args.footer = Some(footer.clone());
+        }
+
+        Ok(())
+    }
+
+    pub fn get_charts(&self, defaults: &ChartOptions) -> Result<Vec<ChartSpec>, Box<dyn Error>> {
+        instantiate_charts(&self.charts, &Default::default(), &format!("config file {}", self.path.display()), defaults)
+    }
+
+    pub fn instantiate_template(&self, instance: &TemplateInstance, defaults: &ChartOptions) -> Result<Vec<ChartSpec>, Box<dyn Error>> {
+        let template = self.templates.get(&instance.name).ok_or_else(|| format!("Unknown chart template: {}", instance.name))?;
+        instantiate_charts(&template.charts, &instance.substitutions, &format!("template {} of config file {}", instance.name, self.path.display()), defaults)
+    }
+}
+
+// Chart specs for the charts of a template or config file, described by `source` in errors.
+pub(crate) fn instantiate_charts(charts: &[TemplateChart], substitutions: &BTreeMap<String, String>, source: &str, defaults: &ChartOptions) -> Result<Vec<ChartSpec>, Box<dyn Error>> {
+    let mut chart_specs: Vec<ChartSpec> = Default::default();
+    for (index, chart) in charts.iter().enumerate() {
+        let chart_type = ChartType::get_from_string(&chart.chart_type)
+            .ok_or_else(|| format!("Unknown chart type in {}: {}", source, chart.chart_type))?;
+
+        let filter_text = substitute(&chart.filter, substitutions)?;
+        let filters = ParameterFilterSet::parse(&filter_text).map_err(|e| format!("Chart {} `filter` in {}: {}", index + 1, source, e))?;
+        let title = match &chart.title {
+            Some(title) => Some(substitute(title, substitutions)?),
+            None => None,
+        };
+        let transform = match &chart.transform {
+            Some(transform) => Transform::parse(&substitute(transform, substitutions)?)?,
+            None => Transform::default(),
+        };
+        let y_range = match &chart.y_range {
+            Some(y_range) => AxisRange::parse(&substitute(y_range, substitutions)?)?,
+            None => AxisRange::default(),
+        };
+        let secondary = match &chart.secondary {
+            Some(secondary) => {
+                let name = substitute(secondary, substitutions)?;
+                Some(ChartType::get_from_string(&name).ok_or_else(|| format!("Unknown secondary metric in {}: {}", source, name))?)
+            },
+            None => None,
+        };
+        let range = match &chart.range {
+            Some(range) => parse_sample_range(&substitute(range, substitutions)?)?,
+            None => defaults.range,
+        };
+
+        let options = ChartOptions {
+            stall_threshold: chart.stall_threshold.or(defaults.stall_threshold),
+            stall_window: chart.stall_window.unwrap_or(defaults.stall_window),
+            histogram_bins: chart.histogram_bins.unwrap_or(defaults.histogram_bins),
+            rolling_percentile: chart.rolling_percentile.unwrap_or(defaults.rolling_percentile),
+            rolling_window: chart.rolling_window.unwrap_or(defaults.rolling_window),
+            variance_window: chart.variance_window.unwrap_or(defaults.variance_window),
+            final_buckets: chart.final_buckets.unwrap_or(defaults.final_buckets),
+            raw_samples: chart.raw_samples.unwrap_or(defaults.raw_samples),
+            jitter: chart.jitter.unwrap_or(defaults.jitter),
+            log_y: chart.log_y.unwrap_or(defaults.log_y && chart_type.is_metric()),
+            smooth: chart.smooth.or(defaults.smooth.filter(|_| chart_type.supports_smoothing())),
+            smooth_mode: chart.smooth_mode.clone().unwrap_or_else(|| defaults.smooth_mode.clone()),
+            spread: chart.spread.clone().unwrap_or_else(|| defaults.spread.clone()),
+            range,
+            gaps: chart.gaps.unwrap_or(defaults.gaps),
+            marker_shape: chart.marker_shape.unwrap_or(defaults.marker_shape),
+            marker_size: chart.marker_size.or(defaults.marker_size),
+            trend: chart.trend.or(defaults.trend.filter(|_| chart_type.is_metric())),
+            y_unit: chart.y_unit.unwrap_or(defaults.y_unit),
+        };
+
+        chart_specs.push(ChartSpec {
+            chart_type,
+            filters,
+            title,
+            transform,
+            y_range,
+            x_range: AxisRange::default(),
+            secondary,
+            options,
+        });
+    }
+
+    Ok(chart_specs)
+}
+
+impl TemplateInstance {
+    pub fn parse(text: &str) -> Result<TemplateInstance, Box<dyn Error>> {
+        let (name, substitution_text) = match text.split_once(':') {
+            Some((name, rest)) => (name, rest),
+            None => (text, ""),
+        };
+
+        let mut substitutions: BTreeMap<String, String> = Default::default();
+        for pair in substitution_text.split(',').filter(|p| !p.trim().is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| format!("Invalid template substitution: {}", pair))?;
+            substitutions.insert(key.trim().to_string(), value.trim().to_string());
+        }
+
+        Ok(TemplateInstance { name: name.trim().to_string(), substitutions })
+    }
+}
+
+pub(crate) fn substitute(text: &str, substitutions: &BTreeMap<String, String>) -> Result<String, Box<dyn Error>> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let end = rest[start..].find('}').ok_or_else(|| format!("Unterminated placeholder in: {}", text))? + start;
+        let key = &rest[start + 2..end];
+        let value = substitutions.get(key).ok_or_else(|| format!("No value given for template placeholder: {}", key))?;
+
+        result += &rest[..start];
+        result += value;
+        rest = &rest[end + 1..];
+    }
+    result += rest;
+
+    Ok(result)
+}
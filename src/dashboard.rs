@@ -0,0 +1,224 @@
+// Charts served over HTTP with `--serve`, so that the results of running stress tests can be browsed
+// without installing the tool. The page at `/` picks the chart types and filters and reloads the
+// image of the charts, which is drawn on request from the data files as they were last read. Data
+// files are read again whenever they have changed.
+use super::*;
+use exporter::{get_fingerprint, read_request, write_response, Fingerprint};
+use image::ImageEncoder;
+use std::net::{TcpListener, TcpStream};
+
+static REFRESH_SECONDS: u32 = 10;
+
+static HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+static PNG_CONTENT_TYPE: &str = "image/png";
+static TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+// Query parameters of the page and image, repeated once per chart and paired in order, as with
+// `--chart-type` and `--chart-filter`.
+static CHART_TYPE_PARAMETER: &str = "chart-type";
+static FILTER_PARAMETER: &str = "filter";
+
+// Serves the page and charts until the process is stopped. If the data files cannot be read, e.g.
+// while a line is being written with `--strict`, the previous data is charted until they can.
+pub(crate) fn serve(address: &str, args: &Args, matches: &ArgMatches, config: &Config) -> Result<(), Box<dyn Error>> {
+    let params = &build_params(args, matches, config)?;
+    let listener = TcpListener::bind(address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+    log::info!("Serving charts on http://{}/", listener.local_addr()?);
+
+    let paths = args.data_path.clone().unwrap_or_default();
+    let mut loaded: Option<(Fingerprint, Params, StressTestData)> = None;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("Failed to accept connection: {}", e);
+                continue;
+            },
+        };
+
+        let fingerprint = get_fingerprint(&paths);
+        if loaded.as_ref().is_none_or(|(previous, _, _)| *previous != fingerprint) {
+            match prepare_data(args, matches, config) {
+                Ok((prepared, data)) => loaded = data.map(|data| (fingerprint, prepared, data)),
+                Err(e) => log::warn!("Failed to read data files: {}", e),
+            }
+        }
+
+        if let Err(e) = respond(&stream, args, params, loaded.as_ref().map(|(_, prepared, data)| (prepared, data))) {
+            log::warn!("Failed to answer request: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+// The data is charted with the params prepared along with it, as by a normal run.
+fn respond(stream: &TcpStream, args: &Args, params: &Params, loaded: Option<(&Params, &StressTestData)>) -> std::io::Result<()> {
+    let (method, target) = read_request(stream)?;
+    if method != "GET" {
+        return write_response(stream, "405 Method Not Allowed", TEXT_CONTENT_TYPE, b"Only GET requests are supported\n")
+    }
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    let charts = get_query_charts(query);
+
+    match (path, loaded) {
+        ("/", _) => write_response(stream, "200 OK", HTML_CONTENT_TYPE, get_page(params, &charts).as_bytes()),
+        ("/chart.png", Some((prepared, data))) => match render_png(data, args, params, prepared, &charts) {
+            Ok(png) => write_response(stream, "200 OK", PNG_CONTENT_TYPE, &png),
+            Err(e) => write_response(stream, "400 Bad Request", TEXT_CONTENT_TYPE, format!("{}\n", e).as_bytes()),
+        },
+        ("/chart.png", None) => write_response(stream, "503 Service Unavailable", TEXT_CONTENT_TYPE, b"The data files could not be read\n"),
+        _ => write_response(stream, "404 Not Found", TEXT_CONTENT_TYPE, b"Charts are served at /\n"),
+    }
+}
+
+// Chart type and filter of each chart of the query, skipping those without a chart type.
+fn get_query_charts(query: &str) -> Vec<(String, String)> {
+    let mut chart_types: Vec<String> = Default::default();
+    let mut filters: Vec<String> = Default::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match decode_component(name) {
+            name if name == CHART_TYPE_PARAMETER => chart_types.push(decode_component(value)),
+            name if name == FILTER_PARAMETER => filters.push(decode_component(value)),
+            _ => {},
+        }
+    }
+
+    chart_types.into_iter().enumerate()
+        .filter(|(_, chart_type)| !chart_type.trim().is_empty())
+        .map(|(i, chart_type)| (chart_type, filters.get(i).cloned().unwrap_or_default()))
+        .collect()
+}
+
+// The charts of the query, split with `--group-by` as the data is now.
+fn get_chart_specs(data: &StressTestData, args: &Args, params: &Params, charts: &[(String, String)]) -> Result<Vec<ChartSpec>, Box<dyn Error>> {
+    let mut chart_specs = charts.iter().map(|(chart_type, filter)| get_chart_spec(chart_type, filter, args)).collect::<Result<_, _>>()?;
+    if let Some(parameter) = &args.group_by {
+        chart_specs = group_chart_specs(chart_specs, data, parameter, &params.parameter_labels);
+    }
+    Ok(chart_specs)
+}
+
+// A chart with the options of the command line which apply to its type.
+fn get_chart_spec(chart_type: &str, filter: &str, args: &Args) -> Result<ChartSpec, Box<dyn Error>> {
+    let chart_type = ChartType::get_from_string(chart_type).ok_or_else(|| format!("Unknown chart type '{}'", chart_type))?;
+    let mut options = ChartOptions::new(args);
+    options.smooth = options.smooth.filter(|_| chart_type.supports_smoothing());
+    let spec = ChartSpec { options, ..ChartSpec::new(chart_type, ParameterFilterSet::parse(filter)?) };
+    spec.validate()?;
+    Ok(spec)
+}
+
+// The charts of the query drawn with the params of the command line, or else the charts of the
+// command line as prepared along with the data.
+fn render_png(data: &StressTestData, args: &Args, params: &Params, prepared: &Params, charts: &[(String, String)]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let (params, chart_specs) = match charts.is_empty() {
+        true => (prepared, prepared.chart_specs.clone()),
+        false => (params, get_chart_specs(data, args, params, charts)?),
+    };
+    if chart_specs.is_empty() {
+        return Err("No charts selected".into())
+    }
+    let (width, height) = params.get_image_size(&params.get_layout(chart_specs.len())?);
+
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+    draw_to_area(&BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area(), Some(data), params, &chart_specs, true)?;
+
+    let mut png: Vec<u8> = Default::default();
+    image::codecs::png::PngEncoder::new(&mut png).write_image(&pixels, width, height, image::ColorType::Rgb8)?;
+    Ok(png)
+}
+
+// A form with a row for each chart, and an empty row to add one, above the image of the charts.
+// Without any charts in the query, the rows are those of the command line.
+fn get_page(params: &Params, charts: &[(String, String)]) -> String {
+    let rows: Vec<(String, String)> = match charts.is_empty() {
+        true => params.chart_specs.iter().map(|spec| (spec.chart_type.to_string(), spec.filters.text().to_string())).collect(),
+        false => charts.to_vec(),
+    };
+
+    let mut chart_type_names: Vec<String> = <ChartType as clap::ValueEnum>::value_variants().iter().map(|chart_type| chart_type.to_string()).collect();
+    chart_type_names.extend(plugins::chart_type_names());
+
+    let mut form_rows = String::new();
+    for (chart_type, filter) in rows.iter().map(|(chart_type, filter)| (chart_type.as_str(), filter.as_str())).chain([("", "")]) {
+        let options: String = std::iter::once("").chain(chart_type_names.iter().map(|name| name.as_str())).map(|name| {
+            let selected = if name == chart_type { " selected" } else { "" };
+            format!("<option value=\"{}\"{}>{}</option>", escape_html(name), selected, escape_html(name))
+        }).collect();
+        form_rows += &format!("<div><select name=\"{}\">{}</select> <input type=\"text\" name=\"{}\" value=\"{}\" placeholder=\"readers>0, name==stress\"></div>\n",
+            CHART_TYPE_PARAMETER, options, FILTER_PARAMETER, escape_html(filter));
+    }
+
+    let query: Vec<String> = charts.iter().map(|(chart_type, filter)| format!("{}={}&{}={}", CHART_TYPE_PARAMETER, encode_component(chart_type), FILTER_PARAMETER, encode_component(filter))).collect();
+    let image_url = format!("/chart.png?{}", query.join("&"));
+    let title = params.title.clone().unwrap_or_else(|| "Stress test charts".to_string());
+
+    format!(r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 1em; }}
+form div {{ margin-bottom: 0.3em; }}
+input[type=text] {{ width: 30em; }}
+img {{ width: 100%; margin-top: 1em; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<form method="get" action="/">
+{form_rows}<button type="submit">Draw</button>
+</form>
+<img id="charts" src="{image_url}" alt="Charts">
+<script>
+// Loaded off screen and swapped in, so the charts do not flicker or vanish on an error.
+setInterval(function() {{
+    var next = new Image();
+    next.onload = function() {{ document.getElementById("charts").src = next.src; }};
+    next.src = "{image_url}&t=" + Date.now();
+}}, {refresh_ms});
+</script>
+</body>
+</html>
+"#, title = escape_html(&title), form_rows = form_rows, image_url = escape_html(&image_url), refresh_ms = REFRESH_SECONDS * 1000)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Percent encoding of a query parameter, leaving only unreserved characters as they are.
+fn encode_component(text: &str) -> String {
+    text.bytes().map(|b| match b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+        true => (b as char).to_string(),
+        false => format!("%{:02X}", b),
+    }).collect()
+}
+
+// Form values have spaces encoded as `+`.
+fn decode_component(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded: Vec<u8> = Default::default();
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            },
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
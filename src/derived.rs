@@ -0,0 +1,137 @@
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeriesOperation {
+    Difference,
+    Ratio,
+}
+
+// A synthetic series computed from the datasets matched by two filters, e.g.
+// `compress==true - compress==false`. Each dataset matched by the left filter is paired with the
+// dataset matched by the right filter which has the same values for every other parameter.
+#[derive(Debug)]
+pub struct DerivedSeries {
+    pub text: String,
+    pub left: ParameterFilterSet,
+    pub right: ParameterFilterSet,
+    pub operation: SeriesOperation,
+}
+
+impl SeriesOperation {
+    pub fn get_text(&self) -> String {
+        match self {
+            SeriesOperation::Difference => " - ".to_string(),
+            SeriesOperation::Ratio => " / ".to_string(),
+        }
+    }
+
+    pub fn apply(&self, left: f64, right: f64) -> Option<f64> {
+        let result = match self {
+            SeriesOperation::Difference => left - right,
+            SeriesOperation::Ratio => left / right,
+        };
+        match result.is_finite() {
+            true => Some(result),
+            false => None,
+        }
+    }
+}
+
+impl DerivedSeries {
+    pub fn parse(text: &str) -> Result<DerivedSeries, Box<dyn Error>> {
+        for operation in [SeriesOperation::Difference, SeriesOperation::Ratio] {
+            if let Some((left, right)) = text.split_once(&operation.get_text()) {
+                return Ok(DerivedSeries {
+                    text: text.trim().to_string(),
+                    left: ParameterFilterSet::parse(left).map_err(|e| format!("Invalid derived series '{}': {}", text, e))?,
+                    right: ParameterFilterSet::parse(right).map_err(|e| format!("Invalid derived series '{}': {}", text, e))?,
+                    operation,
+                })
+            }
+        }
+        Err(format!("Derived series must be of the form '<filter> - <filter>' or '<filter> / <filter>': {}", text).into())
+    }
+
+    // Builds one dataset per matching pair, keyed by display name.
+    pub(crate) fn derive(&self, datasets: &HashMap<String, DataSet>) -> Result<Vec<(String, DataSet)>, Box<dyn Error>> {
+        let constrained: HashSet<&String> = self.left.parameter_names().chain(self.right.parameter_names()).collect();
+        let unconstrained_parameters = |dataset: &DataSet| -> BTreeMap<String, ParameterValue> {
+            dataset.parameters.iter().filter(|(name, _)| !constrained.contains(name)).map(|(name, value)| (name.clone(), value.clone())).collect()
+        };
+
+        let mut lefts: Vec<&DataSet> = datasets.values().filter(|d| !d.derived && d.passes_filters(&self.left)).collect();
+        lefts.sort_by(|a, b| DataSet::get_name(a.base_name.clone(), &a.parameters).cmp(&DataSet::get_name(b.base_name.clone(), &b.parameters)));
+
+        let mut result: Vec<(String, DataSet)> = Default::default();
+        for left in lefts {
+            let parameters = unconstrained_parameters(left);
+            let right = datasets.values().find(|d| {
+                !d.derived && !std::ptr::eq(*d, left) && d.base_name == left.base_name && d.passes_filters(&self.right) && unconstrained_parameters(d) == parameters
+            });
+
+            if let Some(right) = right {
+                let base_name = format!("{} [{}]", left.base_name, self.text);
+                let dataset = self.derive_pair(base_name.clone(), parameters.clone(), left, right);
+                result.push((DataSet::get_name(base_name, &parameters), dataset));
+            }
+        }
+
+        if result.is_empty() {
+            return Err(format!("Derived series '{}' did not match any pair of datasets", self.text).into())
+        }
+
+        Ok(result)
+    }
+
+    // Combines the mean values of the two datasets at each commit count present in both.
+    fn derive_pair(&self, base_name: String, parameters: BTreeMap<String, ParameterValue>, left: &DataSet, right: &DataSet) -> DataSet {
+        let mut dataset = DataSet::new(base_name, parameters);
+        dataset.derived = true;
+
+        for left_value in &left.sorted_values {
+            let right_value = match right.sorted_values.binary_search_by(|probe| probe.num_commits.cmp(&left_value.num_commits)) {
+                Ok(index) => &right.sorted_values[index],
+                Err(_) => continue,
+            };
+
+            let mut value = ValueSet::new(left_value.num_commits);
+            // Metrics from optional columns may be missing from either side.
+            let combine = |l: &SampleSet, r: &SampleSet, result: &mut SampleSet| {
+                if l.is_empty() || r.is_empty() {
+                    return
+                }
+                if let Some(v) = self.operation.apply(l.get_mean(), r.get_mean()) {
+                    result.add_sample(v);
+                }
+            };
+            combine(&left_value.commit_time, &right_value.commit_time, &mut value.commit_time);
+            combine(&left_value.sample_commit_time, &right_value.sample_commit_time, &mut value.sample_commit_time);
+            combine(&left_value.commits_per_second, &right_value.commits_per_second, &mut value.commits_per_second);
+            combine(&left_value.queries_per_second, &right_value.queries_per_second, &mut value.queries_per_second);
+            combine(&left_value.disk_size, &right_value.disk_size, &mut value.disk_size);
+            combine(&left_value.write_amplification, &right_value.write_amplification, &mut value.write_amplification);
+            combine(&left_value.memory_usage, &right_value.memory_usage, &mut value.memory_usage);
+            combine(&left_value.heap_usage, &right_value.heap_usage, &mut value.heap_usage);
+            for (name, left_metric) in &left_value.plugin_metrics {
+                if let Some(right_metric) = right_value.plugin_metrics.get(name) {
+                    combine(left_metric, right_metric, value.plugin_metrics.entry(name.clone()).or_default());
+                }
+            }
+            // Placed at the times of the left dataset with `--x-axis time`.
+            value.timestamp.merge(&left_value.timestamp);
+
+            dataset.max_commits = std::cmp::max(dataset.max_commits, value.num_commits);
+            dataset.max_commit_time = dataset.max_commit_time.max(value.commit_time.value_max);
+            dataset.max_commits_per_second = dataset.max_commits_per_second.max(value.commits_per_second.value_max);
+            dataset.max_queries_per_second = dataset.max_queries_per_second.max(value.queries_per_second.value_max);
+            dataset.max_disk_size = dataset.max_disk_size.max(value.disk_size.value_max);
+            dataset.max_write_amplification = dataset.max_write_amplification.max(value.write_amplification.value_max);
+            dataset.max_memory_usage = dataset.max_memory_usage.max(value.memory_usage.value_max);
+            dataset.max_heap_usage = dataset.max_heap_usage.max(value.heap_usage.value_max);
+
+            dataset.sorted_values.push(value);
+        }
+
+        dataset
+    }
+}
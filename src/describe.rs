@@ -0,0 +1,65 @@
+// Overview of the loaded datasets printed with `--describe` in place of drawing, for choosing
+// filters without reading the data files: the parameters which vary across the datasets with the
+// values each takes, those every dataset shares, and then each dataset with its parameters,
+// samples, commit buckets and runs.
+use super::*;
+
+pub(crate) fn print_description(data: &StressTestData, params: &Params) {
+    let names = data.sorted_names();
+    let num_samples: usize = data.datasets.values().map(get_num_samples).sum();
+    println!("{} {}, {} samples", names.len(), if names.len() == 1 { "dataset" } else { "datasets" }, num_samples);
+    if names.is_empty() {
+        return
+    }
+
+    // Derived series only have the parameters of the datasets they were derived from.
+    let measured: Vec<&DataSet> = data.datasets.values().filter(|dataset| !dataset.derived).collect();
+    let varying = DataSet::get_varying_parameters(measured.iter().copied());
+    let mut parameter_values: BTreeMap<&String, Vec<&ParameterValue>> = Default::default();
+    for dataset in &measured {
+        for (name, value) in &dataset.parameters {
+            let values = parameter_values.entry(name).or_default();
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+    }
+    for values in parameter_values.values_mut() {
+        values.sort_by(|a, b| a.display_cmp(b));
+    }
+
+    println!();
+    println!("Parameters which vary:");
+    for (name, values) in parameter_values.iter().filter(|(name, _)| varying.contains(name.as_str())) {
+        let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        let num_missing = measured.iter().filter(|dataset| !dataset.parameters.contains_key(*name)).count();
+        match num_missing {
+            0 => println!("  {}: {}", name, values.join(", ")),
+            _ => println!("  {}: {} (missing from {} of {} datasets)", name, values.join(", "), num_missing, measured.len()),
+        }
+    }
+    println!("Parameters shared by every dataset:");
+    for (name, values) in parameter_values.iter().filter(|(name, _)| !varying.contains(name.as_str())) {
+        println!("  {}={}", name, values[0]);
+    }
+
+    println!();
+    for name in names {
+        let dataset = &data.datasets[name];
+        // Named by the parameters which vary, as in the legends of charts of every dataset.
+        let short_name = DataSet::get_name_including(dataset.base_name.clone(), &dataset.parameters, &varying, &params.parameter_labels);
+        println!("{}{}:", short_name, if dataset.derived { " (derived)" } else { "" });
+        let parameters: Vec<String> = dataset.parameters.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        println!("  parameters: {}", parameters.join(" "));
+        let commits = match (dataset.sorted_values.first(), dataset.sorted_values.last()) {
+            (Some(first), Some(last)) => format!("{}..{}", first.num_commits, last.num_commits),
+            _ => "none".to_string(),
+        };
+        println!("  samples={} buckets={} commits={} runs={}", get_num_samples(dataset), dataset.sorted_values.len(), commits, dataset.num_runs());
+    }
+}
+
+// Every sample of a dataset has a commit rate, whichever other metrics it has.
+fn get_num_samples(dataset: &DataSet) -> usize {
+    dataset.sorted_values.iter().map(|value| value.commits_per_second.len()).sum()
+}
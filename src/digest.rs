@@ -0,0 +1,194 @@
+// Percentiles of a stream of samples in bounded memory, for sample sets in `--low-memory` mode.
+// This is the merging t-digest of Dunning and Ertl: samples are buffered, then sorted and merged
+// into centroids which are small near the extremes, where percentiles such as p99 are read, and
+// larger around the median. The number of centroids stays around `COMPRESSION`.
+use std::f64::consts::PI;
+
+static COMPRESSION: f64 = 200.0;
+
+// Samples buffered before they are merged into the centroids.
+static BUFFER_SIZE: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Centroid {
+    pub mean: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TDigest {
+    // Sorted by mean.
+    centroids: Vec<Centroid>,
+    buffer: Vec<Centroid>,
+}
+
+impl TDigest {
+    pub fn new() -> TDigest {
+        Default::default()
+    }
+
+    pub fn add(&mut self, sample: f64) {
+        self.add_weighted(sample, 1.0);
+    }
+
+    pub fn add_weighted(&mut self, mean: f64, weight: f64) {
+        self.buffer.push(Centroid { mean, weight });
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.compress();
+        }
+    }
+
+    pub fn merge(&mut self, other: &TDigest) {
+        for centroid in other.centroids.iter().chain(&other.buffer) {
+            self.add_weighted(centroid.mean, centroid.weight);
+        }
+    }
+
+    // A copy with every sample multiplied by the factor.
+    pub fn scaled(&self, factor: f64) -> TDigest {
+        let scale = |centroids: &[Centroid]| centroids.iter().map(|c| Centroid { mean: c.mean * factor, weight: c.weight }).collect::<Vec<_>>();
+        let mut centroids = scale(&self.centroids);
+        if factor < 0.0 {
+            centroids.reverse();
+        }
+        TDigest { centroids, buffer: scale(&self.buffer) }
+    }
+
+    // The centroids in order of their means. Until the buffer first fills up they are the samples
+    // themselves.
+    pub fn centroids(&self) -> Vec<Centroid> {
+        match (self.centroids.is_empty(), self.buffer.is_empty()) {
+            (_, true) => self.centroids.clone(),
+            (true, false) => {
+                let mut buffer = self.buffer.clone();
+                buffer.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+                buffer
+            },
+            (false, false) => {
+                let mut digest = self.clone();
+                digest.compress();
+                digest.centroids
+            },
+        }
+    }
+
+    // Percentile (0-100) estimated between the centres of the centroids and the smallest and
+    // largest samples at the ends. While every centroid is a single sample it is exact,
+    // interpolating between closest ranks as `statistics::percentile` does.
+    pub fn percentile(&self, percentile: f64, min: f64, max: f64) -> f64 {
+        let centroids = self.centroids();
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        if centroids.is_empty() {
+            return 0.0
+        }
+        if centroids.iter().all(|c| c.weight == 1.0) {
+            let sorted: Vec<f64> = centroids.iter().map(|c| c.mean).collect();
+            return super::statistics::percentile(&sorted, percentile)
+        }
+
+        let index = percentile.clamp(0.0, 100.0) / 100.0 * total;
+        let mut previous = (0.0, min);
+        let mut cumulative = 0.0;
+        for centroid in &centroids {
+            let centre = cumulative + centroid.weight / 2.0;
+            if index < centre {
+                return previous.1 + (centroid.mean - previous.1) * (index - previous.0) / (centre - previous.0)
+            }
+            previous = (centre, centroid.mean);
+            cumulative += centroid.weight;
+        }
+        match total > previous.0 {
+            true => previous.1 + (max - previous.1) * (index - previous.0) / (total - previous.0),
+            false => max,
+        }
+    }
+
+    // Merges the buffer into the centroids, combining neighbours while the pair spans no more than
+    // one unit of the arcsine scale function.
+    fn compress(&mut self) {
+        let mut centroids = std::mem::take(&mut self.centroids);
+        centroids.append(&mut self.buffer);
+        centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        let scale = |q: f64| COMPRESSION / (2.0 * PI) * (2.0 * q - 1.0).clamp(-1.0, 1.0).asin();
+
+        let mut merged: Vec<Centroid> = Vec::with_capacity(centroids.len());
+        let mut weight_before = 0.0;
+        for centroid in centroids {
+            match merged.last_mut() {
+                Some(last) if scale((weight_before + last.weight + centroid.weight) / total) - scale(weight_before / total) <= 1.0 => {
+                    let weight = last.weight + centroid.weight;
+                    last.mean += (centroid.mean - last.mean) * centroid.weight / weight;
+                    last.weight = weight;
+                },
+                Some(last) => {
+                    weight_before += last.weight;
+                    merged.push(centroid);
+                },
+                None => merged.push(centroid),
+            }
+        }
+        self.centroids = merged;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The samples 0..count in a scrambled order, so that they do not arrive sorted.
+    fn scrambled(count: u64) -> Vec<f64> {
+        (0..count).map(|i| (i * 7919 % count) as f64).collect()
+    }
+
+    fn digest_of(samples: &[f64]) -> TDigest {
+        let mut digest = TDigest::new();
+        for sample in samples {
+            digest.add(*sample);
+        }
+        digest
+    }
+
+    #[test]
+    fn exact_while_every_centroid_is_a_sample() {
+        let samples = [5.0, 1.0, 4.0, 2.0, 3.0];
+        let digest = digest_of(&samples);
+        assert_eq!(digest.percentile(0.0, 1.0, 5.0), 1.0);
+        assert_eq!(digest.percentile(50.0, 1.0, 5.0), 3.0);
+        assert_eq!(digest.percentile(100.0, 1.0, 5.0), 5.0);
+        assert_eq!(digest.percentile(90.0, 1.0, 5.0), crate::statistics::percentile(&[1.0, 2.0, 3.0, 4.0, 5.0], 90.0));
+    }
+
+    #[test]
+    fn estimates_percentiles_of_many_samples() {
+        let count = 100_000;
+        let digest = digest_of(&scrambled(count));
+        assert!(digest.centroids().len() < 2 * COMPRESSION as usize);
+        // Within 0.5% of the range at the median, and closer at the tails where centroids are small.
+        for (percentile, tolerance) in [(50.0, 0.005), (90.0, 0.002), (99.0, 0.0005), (99.9, 0.0002)] {
+            let expected = percentile / 100.0 * (count - 1) as f64;
+            let estimate = digest.percentile(percentile, 0.0, (count - 1) as f64);
+            assert!((estimate - expected).abs() <= tolerance * count as f64, "p{} estimated {} rather than {}", percentile, estimate, expected);
+        }
+    }
+
+    #[test]
+    fn merged_digests_estimate_the_combined_samples() {
+        let samples = scrambled(20_000);
+        let (low, high) = samples.split_at(10_000);
+        let mut digest = digest_of(low);
+        digest.merge(&digest_of(high));
+        let estimate = digest.percentile(50.0, 0.0, 19_999.0);
+        assert!((estimate - 9_999.5).abs() < 100.0, "median estimated {}", estimate);
+    }
+
+    #[test]
+    fn negative_scale_reverses_the_centroids() {
+        let digest = digest_of(&scrambled(10_000)).scaled(-2.0);
+        let centroids = digest.centroids();
+        assert!(centroids.windows(2).all(|pair| pair[0].mean <= pair[1].mean));
+        let estimate = digest.percentile(10.0, -19_998.0, 0.0);
+        assert!((estimate + 18_000.0).abs() < 100.0, "p10 estimated {}", estimate);
+    }
+}
@@ -0,0 +1,109 @@
+// Metric charts with a second metric of the same datasets on a right hand Y axis, with `secondary`,
+// e.g. `commits-per-second secondary queries-per-second` to line up dips in write and read
+// throughput on one panel, or `memory-usage secondary heap-usage` to draw resident and heap memory
+// as two series. The mean lines of the secondary metric are dashed in the colours of
+// their datasets. Transforms only apply to the chart's own metric.
+use super::*;
+use plotters::coord::Shift;
+
+pub(crate) fn draw_dual_axis_chart<DB: DrawingBackend>(area: &DrawingArea<DB, Shift>, context: &ChartContext, data: &StressTestData, params: &Params, secondary: &ChartType) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let spec = context.spec;
+    let chart_type = &spec.chart_type;
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
+
+    let x_range = spec.x_range.apply(match params.x_axis {
+        XAxis::Commits => 0.0f64..data.max_commits as f64,
+        XAxis::Time | XAxis::Metric(_) => get_x_range(context.datasets.iter().map(|entry| entry.1), &params.x_axis).unwrap_or(0.0..1.0),
+    });
+    let y_range = spec.y_range.apply(context.y_range.clone());
+    let secondary_y_range = context.datasets.iter()
+        .filter_map(|entry| entry.1.get_transformed_range(secondary, &Transform::default()))
+        .fold(0.0..0.0, |range: std::ops::Range<f64>, (min, max)| range.start.min(min)..range.end.max(max));
+
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((6).percent_height())
+        .right_y_label_area_size((6).percent_height())
+        .margin((2).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(x_range.clone(), y_range.clone())?
+        .set_secondary_coord(x_range, secondary_y_range.clone());
+
+    let x_span = cc.x_range().end - cc.x_range().start;
+    let x_label_formatter = |v: &f64| match params.x_axis {
+        XAxis::Commits => format!("{:.0}", v),
+        XAxis::Time => time_axis::format_time_label(*v, x_span),
+        XAxis::Metric(_) => format!("{:.2}", v),
+    };
+    let y_label_formatter = units::get_y_label_formatter(spec.options.y_unit, units::YQuantity::of_chart(spec), y_range, false);
+    let secondary_spec = ChartSpec::new(secondary.clone(), ParameterFilterSet::from_static(""));
+    let secondary_label_formatter = units::get_y_label_formatter(spec.options.y_unit, units::YQuantity::of_chart(&secondary_spec), secondary_y_range, false);
+
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc(match &params.x_axis {
+            XAxis::Commits => "Commits".to_string(),
+            XAxis::Time => "Time".to_string(),
+            XAxis::Metric(chart_type) => chart_type.title(),
+        })
+        .y_desc(chart_type.title())
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&x_label_formatter);
+    if let Some(formatter) = &y_label_formatter {
+        mesh.y_label_formatter(formatter);
+    }
+    mesh.draw()?;
+
+    let mut secondary_axes = cc.configure_secondary_axes();
+    secondary_axes
+        .y_desc(secondary.title())
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text);
+    if let Some(formatter) = &secondary_label_formatter {
+        secondary_axes.y_label_formatter(formatter);
+    }
+    secondary_axes.draw()?;
+
+    if params.x_axis == XAxis::Commits {
+        annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
+    }
+
+    let legend_length = (pixel_height * 0.03) as i32;
+    for entry in &context.datasets {
+        let gaps = match spec.options.gaps {
+            GapMode::Break => entry.1.get_gap_positions(&params.x_axis),
+            GapMode::Interpolate | GapMode::Shade => Default::default(),
+        };
+        let display_name = context.display_name(entry.1);
+
+        let points = entry.1.get_mean_points_against(chart_type, &spec.transform, &params.x_axis);
+        for (run_index, run) in gaps::split_at_gaps(points, &gaps).into_iter().enumerate() {
+            let series = line_style::draw_line(&mut cc, run, entry.5, entry.3)?;
+            if run_index == 0 {
+                series.label(display_name.clone())
+                    .legend(move |point| line_style::legend_line(point, legend_length, entry.5, entry.3));
+            }
+        }
+
+        let (dash, gap) = ((pixel_height * 0.01) as i32, (pixel_height * 0.006) as i32);
+        let secondary_points = entry.1.get_mean_points_against(secondary, &Transform::default(), &params.x_axis);
+        for (run_index, run) in gaps::split_at_gaps(secondary_points, &gaps).into_iter().enumerate() {
+            let series = cc.draw_secondary_series(DashedLineSeries::new(run, dash, gap, entry.3))?;
+            if run_index == 0 {
+                series.label(format!("{} ({})", display_name, secondary.title()))
+                    .legend(move |(x, y)| plotters::element::DashedPathElement::new(vec![(x, y), (x + legend_length, y)], dash / 2, gap / 2, entry.3));
+            }
+        }
+    }
+
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::{error::Error, path::PathBuf};
+
+// Errors reading the data files, and the filters which select their datasets.
+#[derive(Debug)]
+pub enum VisualizerError {
+    Io { path: PathBuf, source: std::io::Error },
+    // A problem with the file as a whole, such as a missing header column.
+    InvalidFile { path: PathBuf, reason: String },
+    // A line which could not be parsed. Line numbers start from 1.
+    InvalidLine { path: PathBuf, line_number: usize, reason: String },
+    // A directory or glob pattern which holds no data files.
+    NoDataFiles { path: PathBuf },
+    // A `--schema` file which could not be parsed or does not describe a usable layout.
+    InvalidSchema { path: PathBuf, reason: String },
+    // A filter expression which could not be parsed, as written.
+    InvalidFilter { filter: String, reason: String },
+}
+
+impl std::fmt::Display for VisualizerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            VisualizerError::Io { path, source } => write!(f, "Failed to read {}: {}", path.display(), source),
+            VisualizerError::InvalidFile { path, reason } => write!(f, "Invalid data file {}: {}", path.display(), reason),
+            VisualizerError::InvalidLine { path, line_number, reason } => write!(f, "{}:{}: {}", path.display(), line_number, reason),
+            VisualizerError::NoDataFiles { path } => write!(f, "No data files found in {}", path.display()),
+            VisualizerError::InvalidSchema { path, reason } => write!(f, "Invalid schema file {}: {}", path.display(), reason),
+            VisualizerError::InvalidFilter { filter, reason } => write!(f, "Invalid filter '{}': {}", filter, reason),
+        }
+    }
+}
+
+impl Error for VisualizerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            VisualizerError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// A `--check` run where throughput dropped by more than the threshold on some datasets. The
+// command line tool exits with a distinct code for this so that CI can tell it from other failures.
+#[derive(Debug)]
+pub struct RegressionError {
+    pub regressions: usize,
+    pub comparisons: usize,
+    pub threshold: f64,
+}
+
+impl std::fmt::Display for RegressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Check failed: {} of {} comparisons regressed by more than {}% from the baseline", self.regressions, self.comparisons, self.threshold)
+    }
+}
+
+impl Error for RegressionError {}
@@ -0,0 +1,162 @@
+// Latest statistics of each dataset served over HTTP as Prometheus metrics with `--serve-metrics`,
+// so that existing alerts can watch long running stress tests. Data files are read again on a
+// scrape whenever they have changed since they were last read.
+use super::*;
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, SystemTime};
+
+static METRIC_PREFIX: &str = "paritydb_stress";
+static CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+static REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Labels of every sample, which parameters of the same name would otherwise clash with.
+static RESERVED_LABELS: [&str; 4] = ["dataset", "base", "metric", "quantile"];
+
+// Size and modification time of each data file, which change as a running test appends lines.
+pub(crate) type Fingerprint = Vec<Option<(u64, SystemTime)>>;
+
+pub(crate) fn get_fingerprint(paths: &[PathBuf]) -> Fingerprint {
+    paths.iter().map(|path| std::fs::metadata(path).ok().and_then(|metadata| Some((metadata.len(), metadata.modified().ok()?)))).collect()
+}
+
+// Serves `/metrics` until the process is stopped. If the data files cannot be read, e.g. while
+// a line is being written with `--strict`, the previous metrics are served until they can.
+pub(crate) fn serve_metrics(address: &str, args: &Args) -> Result<(), Box<dyn Error>> {
+    let listener = TcpListener::bind(address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+    log::info!("Serving metrics on http://{}/metrics", listener.local_addr()?);
+
+    let paths = args.data_path.clone().unwrap_or_default();
+    let mut loaded: Option<(Fingerprint, String)> = None;
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                log::warn!("Failed to accept connection: {}", e);
+                continue;
+            },
+        };
+
+        let fingerprint = get_fingerprint(&paths);
+        if loaded.as_ref().is_none_or(|(previous, _)| *previous != fingerprint) {
+            match get_stress_test_data(args) {
+                Ok(data) => loaded = data.map(|data| (fingerprint, get_metrics_text(&data, args.stats_buckets))),
+                Err(e) => log::warn!("Failed to read data files: {}", e),
+            }
+        }
+
+        if let Err(e) = respond(stream, loaded.as_ref().map(|(_, text)| text.as_str())) {
+            log::warn!("Failed to answer request: {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn respond(stream: TcpStream, metrics: Option<&str>) -> std::io::Result<()> {
+    let (method, target) = read_request(&stream)?;
+    let path = target.split('?').next().unwrap_or_default();
+    let (status, body) = match (method.as_str(), path, metrics) {
+        ("GET", "/metrics", Some(metrics)) => ("200 OK", metrics),
+        ("GET", "/metrics", None) => ("503 Service Unavailable", "The data files could not be read\n"),
+        ("GET", _, _) => ("404 Not Found", "Metrics are served at /metrics\n"),
+        _ => ("405 Method Not Allowed", "Only GET requests are supported\n"),
+    };
+    let content_type = match status {
+        "200 OK" => CONTENT_TYPE,
+        _ => "text/plain; charset=utf-8",
+    };
+
+    write_response(&stream, status, content_type, body.as_bytes())
+}
+
+// The method and target, such as `/metrics?x=1`, of a request. Requests are answered one at a
+// time, so a stalled client must not hold up the next one.
+pub(crate) fn read_request(stream: &TcpStream) -> std::io::Result<(String, String)> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut reader = std::io::BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are read up to the blank line which ends them, and ignored.
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 && !line.trim().is_empty() {
+        line.clear();
+    }
+
+    let mut request = request_line.split_whitespace();
+    Ok((request.next().unwrap_or_default().to_string(), request.next().unwrap_or_default().to_string()))
+}
+
+pub(crate) fn write_response(mut stream: &TcpStream, status: &str, content_type: &str, body: &[u8]) -> std::io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, content_type, body.len())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+// Prometheus text exposition of the summary statistics over the final `last_buckets` commit
+// buckets of each dataset, by default only the latest one.
+fn get_metrics_text(data: &StressTestData, last_buckets: Option<usize>) -> String {
+    let rows = summary::get_summary_rows(data, Some(last_buckets.unwrap_or(1)));
+
+    let names = data.sorted_names();
+    let labels: HashMap<&String, String> = names.iter().map(|name| (*name, get_dataset_labels(name, &data.datasets[*name]))).collect();
+
+    let mut text = String::new();
+    let mut family = |name: &str, help: &str, samples: Vec<(String, f64)>| {
+        text += &format!("# HELP {}_{} {}\n# TYPE {}_{} gauge\n", METRIC_PREFIX, name, help, METRIC_PREFIX, name);
+        for (sample_labels, value) in samples {
+            text += &format!("{}_{}{{{}}} {}\n", METRIC_PREFIX, name, sample_labels, format_value(value));
+        }
+    };
+
+    family("commits", "Number of commits of the latest commit bucket of each dataset.", names.iter().map(|name| (labels[*name].clone(), data.datasets[*name].max_commits as f64)).collect());
+
+    let row_labels = |row: &summary::SummaryRow| format!("{},metric=\"{}\"", labels[&row.dataset], escape_label_value(&row.metric));
+    family("samples", "Number of samples of each metric in the final commit buckets.", rows.iter().map(|row| (row_labels(row), row.samples as f64)).collect());
+    family("mean", "Mean of each metric over the final commit buckets.", rows.iter().map(|row| (row_labels(row), row.mean)).collect());
+    family("stddev", "Standard deviation of each metric over the final commit buckets.", rows.iter().map(|row| (row_labels(row), row.stddev)).collect());
+    family("min", "Minimum of each metric over the final commit buckets.", rows.iter().map(|row| (row_labels(row), row.min)).collect());
+    family("max", "Maximum of each metric over the final commit buckets.", rows.iter().map(|row| (row_labels(row), row.max)).collect());
+    family("quantile", "Percentiles of each metric over the final commit buckets.", rows.iter().flat_map(|row| {
+        [("0.5", row.p50), ("0.95", row.p95), ("0.99", row.p99)].map(|(quantile, value)| (format!("{},quantile=\"{}\"", row_labels(row), quantile), value))
+    }).collect());
+
+    text
+}
+
+// The dataset name and its base name, and each parameter under its name made a valid label name.
+// Parameters named like a reserved label are only part of the dataset name.
+fn get_dataset_labels(name: &str, dataset: &DataSet) -> String {
+    let mut labels = vec![format!("dataset=\"{}\"", escape_label_value(name)), format!("base=\"{}\"", escape_label_value(&dataset.base_name))];
+    for (parameter, value) in &dataset.parameters {
+        let label = get_label_name(parameter);
+        if !RESERVED_LABELS.contains(&label.as_str()) {
+            labels.push(format!("{}=\"{}\"", label, escape_label_value(&value.to_string())));
+        }
+    }
+    labels.join(",")
+}
+
+fn get_label_name(parameter: &str) -> String {
+    let label: String = parameter.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+    match label.starts_with(|c: char| c.is_ascii_digit()) {
+        true => format!("_{}", label),
+        false => label,
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    match value {
+        v if v.is_nan() => "NaN".to_string(),
+        f64::INFINITY => "+Inf".to_string(),
+        f64::NEG_INFINITY => "-Inf".to_string(),
+        v => v.to_string(),
+    }
+}
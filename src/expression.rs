@@ -0,0 +1,250 @@
+// Metrics defined on the command line with `--metric`, as arithmetic over the columns of each row,
+// e.g. `commits/commit_time`. They are registered as plugin metrics, so they are stored and
+// charted like any other metric, and their names can be given to `--chart-type`.
+use super::*;
+use std::ffi::OsString;
+use std::sync::Arc;
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Number(f64),
+    // A column of the data file, or a numeric parameter.
+    Column(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, char, Box<Expression>),
+}
+
+impl Expression {
+    // Numbers, column names, `+ - * /` and parentheses, with the usual precedence.
+    fn parse(text: &str) -> Result<Expression, String> {
+        let tokens = tokenize(text)?;
+        let mut position = 0;
+        let expression = parse_sum(&tokens, &mut position).map_err(|reason| format!("invalid metric expression '{}': {}", text, reason))?;
+        match tokens.get(position) {
+            None => Ok(expression),
+            Some(token) => Err(format!("invalid metric expression '{}': unexpected '{}'", text, token)),
+        }
+    }
+
+    // None if a column is missing from the row.
+    fn evaluate(&self, row: &plugins::Row) -> Option<f64> {
+        match self {
+            Expression::Number(value) => Some(*value),
+            Expression::Column(name) => get_column(row, name),
+            Expression::Negate(operand) => operand.evaluate(row).map(|value| -value),
+            Expression::Binary(left, operator, right) => {
+                let (left, right) = (left.evaluate(row)?, right.evaluate(row)?);
+                match operator {
+                    '+' => Some(left + right),
+                    '-' => Some(left - right),
+                    '*' => Some(left * right),
+                    _ => Some(left / right),
+                }
+            },
+        }
+    }
+}
+
+// Parameters are named with dashes in place of the underscores of their columns.
+fn get_column(row: &plugins::Row, name: &str) -> Option<f64> {
+    match name {
+        "total_commits" => Some(row.total_commits as f64),
+        "total_commit_time" => Some(row.total_commit_time),
+        "commits" => Some(row.commits as f64),
+        "commit_time" => Some(row.commit_time),
+        "queries" => Some(row.queries as f64),
+        "query_time" => Some(row.query_time),
+        "disk_size" => row.disk_size.map(|size| size as f64),
+        "bytes_written" => row.bytes_written.map(|bytes| bytes as f64),
+        "rss" => row.rss.map(|bytes| bytes as f64),
+        "heap" => row.heap.map(|bytes| bytes as f64),
+        _ => row.parameters.get(name).or_else(|| row.parameters.get(&name.replace('_', "-"))).and_then(|value| value.as_f64()),
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens: Vec<String> = Default::default();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {},
+            '+' | '-' | '*' | '/' | '(' | ')' => tokens.push(c.to_string()),
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '.' => {
+                let mut token = c.to_string();
+                while let Some(next) = chars.peek().copied().filter(|next| next.is_ascii_alphanumeric() || *next == '_' || *next == '.') {
+                    token.push(next);
+                    chars.next();
+                }
+                tokens.push(token);
+            },
+            _ => return Err(format!("invalid metric expression '{}': unexpected '{}'", text, c)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_sum(tokens: &[String], position: &mut usize) -> Result<Expression, String> {
+    let mut expression = parse_product(tokens, position)?;
+    while let Some(operator) = tokens.get(*position).filter(|token| *token == "+" || *token == "-") {
+        *position += 1;
+        let operator = operator.chars().next().unwrap_or_default();
+        expression = Expression::Binary(Box::new(expression), operator, Box::new(parse_product(tokens, position)?));
+    }
+    Ok(expression)
+}
+
+fn parse_product(tokens: &[String], position: &mut usize) -> Result<Expression, String> {
+    let mut expression = parse_factor(tokens, position)?;
+    while let Some(operator) = tokens.get(*position).filter(|token| *token == "*" || *token == "/") {
+        *position += 1;
+        let operator = operator.chars().next().unwrap_or_default();
+        expression = Expression::Binary(Box::new(expression), operator, Box::new(parse_factor(tokens, position)?));
+    }
+    Ok(expression)
+}
+
+fn parse_factor(tokens: &[String], position: &mut usize) -> Result<Expression, String> {
+    let token = tokens.get(*position).ok_or("unexpected end")?;
+    *position += 1;
+    match token.as_str() {
+        "-" => Ok(Expression::Negate(Box::new(parse_factor(tokens, position)?))),
+        "(" => {
+            let expression = parse_sum(tokens, position)?;
+            match tokens.get(*position).map(|token| token.as_str()) {
+                Some(")") => {
+                    *position += 1;
+                    Ok(expression)
+                },
+                _ => Err("missing ')'".to_string()),
+            }
+        },
+        token if token.starts_with(|c: char| c.is_ascii_digit() || c == '.') => token.parse().map(Expression::Number).map_err(|_| format!("invalid number '{}'", token)),
+        token if token.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') => Ok(Expression::Column(token.to_string())),
+        token => Err(format!("unexpected '{}'", token)),
+    }
+}
+
+// A metric given as `name=expression`, or as a bare expression named by its text.
+struct ExpressionMetric {
+    name: String,
+    expression: Expression,
+}
+
+impl plugins::MetricExtractor for ExpressionMetric {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn title(&self) -> String {
+        self.name.clone()
+    }
+
+    // Rows which are missing a column, or divide by zero, are skipped.
+    fn extract(&self, row: &plugins::Row) -> Option<f64> {
+        self.expression.evaluate(row).filter(|value| value.is_finite())
+    }
+
+    fn cache_key(&self) -> String {
+        format!("{}={:?}", self.name, self.expression)
+    }
+}
+
+fn parse_metric(text: &str) -> Result<ExpressionMetric, String> {
+    let (name, expression_text) = match text.split_once('=') {
+        Some((name, expression_text)) => (name.trim(), expression_text),
+        None => (text.trim(), text),
+    };
+    if name.is_empty() {
+        return Err(format!("invalid metric '{}', expected NAME=EXPRESSION or an expression", text))
+    }
+    if <ChartType as clap::ValueEnum>::value_variants().iter().filter_map(clap::ValueEnum::to_possible_value).any(|value| value.get_name() == name) {
+        return Err(format!("invalid metric '{}', {} is a built in chart type", text, name))
+    }
+    Ok(ExpressionMetric { name: name.to_string(), expression: Expression::parse(expression_text)? })
+}
+
+// Registers the `--metric` arguments. This happens before the arguments are parsed, so that the
+// metrics can be given to `--chart-type` alongside them. An `--x-axis` which is not a metric is
+// registered as one too, so that a column of the data files, or an expression over them, can be
+// plotted against.
+pub(crate) fn register_metrics(arguments: &[OsString]) -> Result<(), Box<dyn Error>> {
+    let mut texts: Vec<String> = Default::default();
+    let mut x_axis: Option<String> = None;
+    let mut arguments = arguments.iter().map(|argument| argument.to_string_lossy());
+    while let Some(argument) = arguments.next() {
+        if argument == "--metric" {
+            texts.extend(arguments.next().map(|text| text.into_owned()));
+        } else if let Some(text) = argument.strip_prefix("--metric=") {
+            texts.push(text.to_string());
+        } else if argument == "--x-axis" {
+            x_axis = arguments.next().map(|text| text.into_owned());
+        } else if let Some(text) = argument.strip_prefix("--x-axis=") {
+            x_axis = Some(text.to_string());
+        }
+    }
+
+    for text in texts {
+        plugins::register_metric(Arc::new(parse_metric(&text)?));
+    }
+
+    // Anything else which is not an expression is left for the argument parser to reject.
+    if let Some(text) = x_axis.filter(|text| !["commits", "time"].contains(&text.as_str()) && ChartType::get_from_string(text).is_none()) {
+        if let Ok(expression) = Expression::parse(&text) {
+            plugins::register_metric(Arc::new(ExpressionMetric { name: text, expression }));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(text: &str, parameters: &BTreeMap<String, ParameterValue>) -> Option<f64> {
+        let row = plugins::Row { base_name: "stress", parameters, total_commits: 2000, total_commit_time: 0.1, commits: 1000, commit_time: 0.05, queries: 400, query_time: 0.2, disk_size: Some(3_000_000), bytes_written: None, rss: None, heap: Some(1_000_000) };
+        Expression::parse(text).unwrap().evaluate(&row)
+    }
+
+    #[test]
+    fn evaluates_with_the_usual_precedence() {
+        let parameters = Default::default();
+        assert_eq!(evaluate("commits/commit_time", &parameters), Some(20000.0));
+        assert_eq!(evaluate("1 + 2 * 3 - 4 / 2", &parameters), Some(5.0));
+        assert_eq!(evaluate("(1 + 2) * 3", &parameters), Some(9.0));
+        assert_eq!(evaluate("10 - 4 - 3", &parameters), Some(3.0));
+        assert_eq!(evaluate("-queries / -2", &parameters), Some(200.0));
+        assert_eq!(evaluate("disk_size / heap", &parameters), Some(3.0));
+    }
+
+    #[test]
+    fn reads_numeric_parameters() {
+        let parameters: BTreeMap<String, ParameterValue> = [("readers".to_string(), ParameterValue::Int(4)), ("writer-sleep".to_string(), ParameterValue::Float(0.5))].into_iter().collect();
+        assert_eq!(evaluate("queries / readers", &parameters), Some(100.0));
+        // Underscores stand for the dashes of parameter names.
+        assert_eq!(evaluate("writer_sleep * 2", &parameters), Some(1.0));
+    }
+
+    #[test]
+    fn missing_columns_give_no_value() {
+        let parameters = Default::default();
+        assert_eq!(evaluate("rss / commits", &parameters), None);
+        assert_eq!(evaluate("missing + 1", &parameters), None);
+    }
+
+    #[test]
+    fn reports_invalid_expressions() {
+        assert_eq!(Expression::parse("(commits").unwrap_err(), "invalid metric expression '(commits': missing ')'");
+        assert_eq!(Expression::parse("commits commit_time").unwrap_err(), "invalid metric expression 'commits commit_time': unexpected 'commit_time'");
+        assert_eq!(Expression::parse("commits %").unwrap_err(), "invalid metric expression 'commits %': unexpected '%'");
+        assert_eq!(Expression::parse("commits /").unwrap_err(), "invalid metric expression 'commits /': unexpected end");
+    }
+
+    #[test]
+    fn names_metrics() {
+        let metric = parse_metric("cps=commits/commit_time").unwrap();
+        assert_eq!(metric.name, "cps");
+        let metric = parse_metric("queries/query_time").unwrap();
+        assert_eq!(metric.name, "queries/query_time");
+        assert!(matches!(parse_metric("commit-time=commits"), Err(e) if e == "invalid metric 'commit-time=commits', commit-time is a built in chart type"));
+    }
+}
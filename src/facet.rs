@@ -0,0 +1,129 @@
+// Small multiples with `--facet rows=<parameter> cols=<parameter>`: the chart is drawn once for
+// each combination of the values of the two parameters, in a grid with a row per value of one and
+// a column per value of the other, so that the effect of one parameter can be compared across the
+// values of the other. The cells share their Y axis, and their X axis where it is the commit count.
+use super::*;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Facet {
+    pub rows: Option<String>,
+    pub cols: Option<String>,
+}
+
+impl Facet {
+    // Parses `rows=<parameter>` and `cols=<parameter>`, given as separate values or in one value
+    // separated by spaces or commas.
+    pub fn parse(values: &[String]) -> Result<Facet, Box<dyn Error>> {
+        let mut facet = Facet::default();
+        for part in values.iter().flat_map(|value| value.split([' ', ',']).filter(|p| !p.trim().is_empty())) {
+            let (axis, parameter) = part.split_once('=').ok_or_else(|| format!("Invalid facet '{}', expected rows=<parameter> or cols=<parameter>", part))?;
+            let parameter = Some(parameter.trim().to_string()).filter(|p| !p.is_empty()).ok_or_else(|| format!("No parameter given in facet '{}'", part))?;
+            let target = match axis.trim() {
+                "rows" => &mut facet.rows,
+                "cols" => &mut facet.cols,
+                _ => return Err(format!("Invalid facet '{}', expected rows=<parameter> or cols=<parameter>", part).into()),
+            };
+            if target.is_some() {
+                return Err(format!("Facet {} given more than once", axis.trim()).into())
+            }
+            *target = Some(parameter);
+        }
+        match facet.rows.is_some() || facet.cols.is_some() {
+            true => Ok(facet),
+            false => Err("--facet needs rows=<parameter>, cols=<parameter> or both".into()),
+        }
+    }
+}
+
+// The chart repeated in each cell of the facet grid, row by row, with the layout of the grid.
+// Combinations without any datasets are kept as empty cells so that the grid stays aligned.
+pub fn facet_chart_specs(chart_specs: Vec<ChartSpec>, data: &StressTestData, facet: &Facet) -> Result<(Vec<ChartSpec>, Layout), Box<dyn Error>> {
+    let [spec] = &chart_specs[..] else {
+        return Err(format!("--facet draws a single chart type in every cell, but {} charts were given", chart_specs.len()).into())
+    };
+
+    let row_values = get_values(spec, data, facet.rows.as_deref())?;
+    let col_values = get_values(spec, data, facet.cols.as_deref())?;
+
+    let mut cells: Vec<ChartSpec> = Default::default();
+    for row_value in &row_values {
+        for col_value in &col_values {
+            let mut filters = spec.filters.clone();
+            for (parameter, value) in [(&facet.rows, row_value), (&facet.cols, col_value)] {
+                if let (Some(parameter), Some(value)) = (parameter, value) {
+                    filters = filters.with_equal(parameter, value);
+                }
+            }
+            cells.push(ChartSpec { filters, ..spec.clone() });
+        }
+    }
+
+    Ok((cells, Layout { rows: row_values.len(), cols: col_values.len() }))
+}
+
+// Values of the parameter among the datasets of the chart in display order, or a single cell
+// without a parameter.
+fn get_values(spec: &ChartSpec, data: &StressTestData, parameter: Option<&str>) -> Result<Vec<Option<ParameterValue>>, Box<dyn Error>> {
+    let Some(parameter) = parameter else {
+        return Ok(vec![None])
+    };
+
+    let mut values: Vec<&ParameterValue> = Default::default();
+    for dataset in data.datasets.values().filter(|d| !d.derived && d.passes_filters(&spec.filters)) {
+        if let Some(value) = dataset.parameters.get(parameter) {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+    }
+    if values.is_empty() {
+        return Err(format!("Cannot facet by {}, no dataset of the chart has that parameter", parameter).into())
+    }
+    values.sort_by(|a, b| a.display_cmp(b));
+    Ok(values.into_iter().map(|value| Some(value.clone())).collect())
+}
+
+// Pins the ends of the Y range of every cell which were not given explicitly to the extent of the
+// series of all the cells, so that the cells are drawn on the same scale. The autocorrelation and
+// the histogram keep their own axes, which are fixed or in bins of each cell.
+pub fn share_y_ranges(data: &StressTestData, params: &mut Params) -> Result<(), Box<dyn Error>> {
+    let Some(chart_type) = params.chart_specs.first().map(|spec| spec.chart_type.clone()) else {
+        return Ok(())
+    };
+    if matches!(chart_type, ChartType::CommitTimeAutocorrelation | ChartType::SampleIntervalHistogram) {
+        return Ok(())
+    }
+    let log_y = params.chart_specs[0].options.log_y;
+
+    let mut min_y: f64 = 0.0;
+    let mut max_y: f64 = 0.0;
+    let mut min_positive_y: Option<f64> = None;
+    for snapshot in testing::chart_series(data, params)? {
+        let values = snapshot.points.iter().map(|(_, y)| *y).chain(snapshot.spread.iter().flat_map(|s| [s.min, s.max]));
+        for y in values.filter(|y| y.is_finite()) {
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+            if y > 0.0 {
+                min_positive_y = Some(min_positive_y.map_or(y, |min| min.min(y)));
+            }
+        }
+    }
+    if max_y <= min_y {
+        return Ok(())
+    }
+
+    // As drawn, the bars and latency percentiles leave room above the highest value.
+    let max_y = match chart_type {
+        ChartType::FinalThroughputBars => max_y * 1.1,
+        ChartType::QueryLatency => max_y * 1.05,
+        _ => max_y,
+    };
+    let min_y = match log_y {
+        true => min_positive_y.unwrap_or(min_y),
+        false => min_y,
+    };
+    for spec in &mut params.chart_specs {
+        spec.y_range = AxisRange { min: spec.y_range.min.or(Some(min_y)), max: spec.y_range.max.or(Some(max_y)) };
+    }
+    Ok(())
+}
@@ -1,8 +1,10 @@
 use super::*;
+use config::{label_name, label_value};
+use regex::Regex;
 
 pub trait FilterSet {
-    fn passes_filters(&self, parameters: &BTreeMap<String, ParameterValue>) -> bool;
-    fn display_text(&self) -> String;
+    fn passes_filters(&self, base_name: &str, parameters: &BTreeMap<String, ParameterValue>) -> bool;
+    fn display_text(&self, labels: &ParameterLabels) -> String;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -10,164 +12,633 @@ pub enum Comparison {
     Less,
     LessEqual,
     Equal,
+    NotEqual,
     GreaterEqual,
     Greater,
 }
 
+// Filters on this name match the base name of the dataset, the test name of the data file, rather
+// than a parameter.
+pub(crate) static BASE_NAME_FILTER: &str = "name";
+
+static MATCH_TEXT: &str = "~=";
+
+static IN_TEXT: &str = "in";
+static NOT_TEXT: &str = "not";
+
+// Relative difference below which float parameters compare as equal.
+static FLOAT_EPSILON: f64 = 1e-9;
+
 // In order that they should be matched with text.
-static COMPARISONS: [Comparison; 5] = [Comparison::Equal, Comparison::LessEqual, Comparison::GreaterEqual, Comparison::Less, Comparison::Greater];
+static COMPARISONS: [Comparison; 6] = [Comparison::Equal, Comparison::NotEqual, Comparison::LessEqual, Comparison::GreaterEqual, Comparison::Less, Comparison::Greater];
 
 impl Comparison {
     pub fn get_text(&self) -> String {
         match self {
-            Comparison::Less => return "<".to_string(),
-            Comparison::LessEqual => return "<=".to_string(),
-            Comparison::Equal => return "==".to_string(),
-            Comparison::GreaterEqual => return ">=".to_string(),
-            Comparison::Greater => return ">".to_string(),
+            Comparison::Less => "<".to_string(),
+            Comparison::LessEqual => "<=".to_string(),
+            Comparison::Equal => "==".to_string(),
+            Comparison::NotEqual => "!=".to_string(),
+            Comparison::GreaterEqual => ">=".to_string(),
+            Comparison::Greater => ">".to_string(),
         }
     }
+
+    pub fn compare<T: PartialOrd>(&self, value: &T, reference: &T) -> bool {
+        match self {
+            Comparison::Less => value < reference,
+            Comparison::LessEqual => value <= reference,
+            Comparison::Equal => value == reference,
+            Comparison::NotEqual => value != reference,
+            Comparison::GreaterEqual => value >= reference,
+            Comparison::Greater => value > reference,
+        }
+    }
+
+    // Floats within a relative epsilon are treated as equal, so that `ratio==0.3` matches a value
+    // which went through arithmetic or a different decimal representation.
+    pub fn compare_float(&self, value: f64, reference: f64) -> bool {
+        let equal = (value - reference).abs() <= FLOAT_EPSILON * value.abs().max(reference.abs()).max(1.0);
+        match self {
+            Comparison::Less => value < reference && !equal,
+            Comparison::LessEqual => value < reference || equal,
+            Comparison::Equal => equal,
+            Comparison::NotEqual => !equal,
+            Comparison::GreaterEqual => value > reference || equal,
+            Comparison::Greater => value > reference && !equal,
+        }
+    }
+
+    pub fn is_equality(&self) -> bool {
+        matches!(self, Comparison::Equal | Comparison::NotEqual)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ParameterFilter {
-    // Bool filters are assumed to be Equal. Just stores the value to compare against.
-    Bool(String, bool),
+    // Bool filters store the value to compare against with == or !=.
+    Bool(String, Comparison, bool),
     // Int filters store the reference value and the Comparison to use between the value and reference value.
     Int(String, Comparison, u64),
+    // Float filters compare against integer parameters too, and integer filters against floats.
+    Float(String, Comparison, f64),
+    // Text filters store the value to compare against with == or !=.
+    Text(String, Comparison, String),
+    // Match filters pass values, of any type, which contain a match of the regular expression.
+    Match(String, Regex),
+    // Set filters pass values equal to one of the listed values with `in`, or to none of them with
+    // `not in`, which is stored as true.
+    Set(String, bool, Vec<ParameterValue>),
 }
 
 impl ParameterFilter {
     pub fn name(&self) -> &String {
         match self {
-            ParameterFilter::Bool(name, _) => {
-                return name
+            ParameterFilter::Bool(name, _, _) => {
+                name
             },
             ParameterFilter::Int(name, _, _) => {
-                return name
+                name
+            },
+            ParameterFilter::Float(name, _, _) => {
+                name
+            },
+            ParameterFilter::Text(name, _, _) => {
+                name
+            },
+            ParameterFilter::Match(name, _) => {
+                name
+            },
+            ParameterFilter::Set(name, _, _) => {
+                name
+            },
+        }
+    }
+
+    // A dataset without the parameter, or with a value of another type, is not constrained by the
+    // filter.
+    pub fn passes(&self, base_name: &str, parameters: &BTreeMap<String, ParameterValue>) -> bool {
+        if self.name() == BASE_NAME_FILTER {
+            return match self {
+                ParameterFilter::Text(_, comparison, filter_value) => comparison.compare(&base_name, &filter_value.as_str()),
+                ParameterFilter::Match(_, regex) => regex.is_match(base_name),
+                ParameterFilter::Set(_, negated, values) => values.iter().any(|v| matches!(v, ParameterValue::Text(text) if text == base_name)) != *negated,
+                _ => true,
             }
         }
+
+        match (self, parameters.get(self.name())) {
+            (ParameterFilter::Bool(_, comparison, filter_value), Some(ParameterValue::Bool(param_value))) => comparison.compare(param_value, filter_value),
+            (ParameterFilter::Int(_, comparison, filter_value), Some(ParameterValue::Int(param_value))) => comparison.compare(param_value, filter_value),
+            (ParameterFilter::Int(_, comparison, filter_value), Some(ParameterValue::Float(param_value))) => comparison.compare_float(*param_value, *filter_value as f64),
+            (ParameterFilter::Float(_, comparison, filter_value), Some(ParameterValue::Int(param_value))) => comparison.compare_float(*param_value as f64, *filter_value),
+            (ParameterFilter::Float(_, comparison, filter_value), Some(ParameterValue::Float(param_value))) => comparison.compare_float(*param_value, *filter_value),
+            (ParameterFilter::Text(_, comparison, filter_value), Some(ParameterValue::Text(param_value))) => comparison.compare(param_value, filter_value),
+            (ParameterFilter::Match(_, regex), Some(param_value)) => regex.is_match(&param_value.to_string()),
+            (ParameterFilter::Set(_, negated, values), Some(param_value)) => {
+                // Only the listed values of a type comparable with the parameter constrain it.
+                let equal: Vec<bool> = values.iter().filter_map(|value| set_value_equals(param_value, value)).collect();
+                equal.is_empty() || equal.contains(&true) != *negated
+            },
+            _ => true,
+        }
+    }
+
+    pub fn display_text(&self, labels: &ParameterLabels) -> String {
+        match self {
+            // Equality is shown with a single `=` to read as a setting rather than a comparison.
+            ParameterFilter::Bool(filter_name, filter_comp, filter_value) => match filter_comp {
+                Comparison::Equal => format!("{}={}", label_name(labels, filter_name), filter_value),
+                _ => format!("{}{}{}", label_name(labels, filter_name), filter_comp.get_text(), filter_value),
+            },
+            ParameterFilter::Int(filter_name, filter_comp, filter_value) => {
+                format!("{}{}{}", label_name(labels, filter_name), filter_comp.get_text(), label_value(labels, filter_name, &filter_value.to_string()))
+            },
+            ParameterFilter::Float(filter_name, filter_comp, filter_value) => {
+                format!("{}{}{}", label_name(labels, filter_name), filter_comp.get_text(), label_value(labels, filter_name, &filter_value.to_string()))
+            },
+            ParameterFilter::Text(filter_name, filter_comp, filter_value) => match filter_comp {
+                Comparison::Equal => format!("{}={}", label_name(labels, filter_name), filter_value),
+                _ => format!("{}{}{}", label_name(labels, filter_name), filter_comp.get_text(), filter_value),
+            },
+            ParameterFilter::Match(filter_name, regex) => format!("{}{}{}", label_name(labels, filter_name), MATCH_TEXT, regex.as_str()),
+            // A single value reads as a comparison, and the unit of several follows the set.
+            ParameterFilter::Set(filter_name, negated, values) => match (&values[..], negated) {
+                ([value], false) => format!("{}={}", label_name(labels, filter_name), label_value(labels, filter_name, &value.to_string())),
+                ([value], true) => format!("{}!={}", label_name(labels, filter_name), label_value(labels, filter_name, &value.to_string())),
+                _ => {
+                    let set = format!("[{}]", values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","));
+                    let operator = match negated {
+                        true => format!("{} {}", NOT_TEXT, IN_TEXT),
+                        false => IN_TEXT.to_string(),
+                    };
+                    format!("{} {} {}", label_name(labels, filter_name), operator, label_value(labels, filter_name, &set))
+                },
+            },
+        }
+    }
+}
+
+// Whether a parameter equals a value of a set filter, or None when their types cannot be compared.
+// Integers and floats compare as numbers.
+fn set_value_equals(param_value: &ParameterValue, value: &ParameterValue) -> Option<bool> {
+    match (param_value, value) {
+        (ParameterValue::Bool(a), ParameterValue::Bool(b)) => Some(a == b),
+        (ParameterValue::Text(a), ParameterValue::Text(b)) => Some(a == b),
+        (ParameterValue::Int(a), ParameterValue::Int(b)) => Some(a == b),
+        _ => match (param_value.as_f64(), value.as_f64()) {
+            (Some(a), Some(b)) => Some(Comparison::Equal.compare_float(a, b)),
+            _ => None,
+        },
+    }
+}
+
+// A filter expression. Comma separated terms must all pass, and `||` separated terms need only one
+// to pass. `||` binds more tightly than the comma, and parentheses group terms, e.g.
+// `readers>0 || writers>1, archive!=true`. `readers in [0,2,8]` and `writers not in [1]` compare
+// against a set of values. `name` compares the test name, e.g. `name==stress` or
+// `name~=^stress-(a|b)`, with `~=` matching a regular expression.
+#[derive(Debug, Clone)]
+pub enum FilterExpression {
+    Filter(ParameterFilter),
+    All(Vec<FilterExpression>),
+    Any(Vec<FilterExpression>),
+}
+
+impl FilterExpression {
+    pub fn passes(&self, base_name: &str, parameters: &BTreeMap<String, ParameterValue>) -> bool {
+        match self {
+            FilterExpression::Filter(filter) => filter.passes(base_name, parameters),
+            FilterExpression::All(terms) => terms.iter().all(|term| term.passes(base_name, parameters)),
+            FilterExpression::Any(terms) => terms.iter().any(|term| term.passes(base_name, parameters)),
+        }
+    }
+
+    fn add_parameter_names<'a>(&'a self, names: &mut Vec<&'a String>) {
+        let mut filters = Vec::new();
+        self.add_filters(&mut filters);
+        names.extend(filters.into_iter().map(ParameterFilter::name));
+    }
+
+    fn add_filters<'a>(&'a self, filters: &mut Vec<&'a ParameterFilter>) {
+        match self {
+            FilterExpression::Filter(filter) => filters.push(filter),
+            FilterExpression::All(terms) | FilterExpression::Any(terms) => {
+                for term in terms {
+                    term.add_filters(filters);
+                }
+            },
+        }
+    }
+
+    fn first_name(&self) -> Option<&String> {
+        let mut names = Vec::new();
+        self.add_parameter_names(&mut names);
+        names.first().copied()
+    }
+
+    pub fn display_text(&self, labels: &ParameterLabels) -> String {
+        match self {
+            FilterExpression::Filter(filter) => filter.display_text(labels),
+            FilterExpression::All(terms) => terms.iter().map(|term| term.display_text(labels)).collect::<Vec<_>>().join(", "),
+            FilterExpression::Any(terms) => terms.iter().map(|term| match term {
+                FilterExpression::All(_) => format!("({})", term.display_text(labels)),
+                _ => term.display_text(labels),
+            }).collect::<Vec<_>>().join(" || "),
+        }
     }
 }
 
-#[derive(Debug)]
+// Filter of the datasets of a chart. Several filter expressions separated by `;`, e.g.
+// `readers==0; readers>0`, split the chart into groups, with each dataset in the first group it
+// passes and labelled with that group in the legend.
+#[derive(Debug, Clone)]
 pub struct ParameterFilterSet {
-    filters: Vec<ParameterFilter>,
+    expression: FilterExpression,
+    // Each group of a filter with several, which the expression passes any of.
+    groups: Vec<FilterExpression>,
+    // The filter as written, which parses to the same expression.
+    text: String,
 }
 
 impl ParameterFilterSet {
-    pub fn new(filter_text: &String) -> ParameterFilterSet {
-        let mut comparisons: Vec<(String, Comparison, String)> = Default::default();
-
-        let pairs = filter_text.split(',').collect::<Vec<_>>();
-        for m in pairs.iter() {
-            for c in &COMPARISONS {
-                if let Some(pos) = m.find(&c.get_text()) {
-                    let first = &m[0..pos].trim();
-                    let second = &m[pos + c.get_text().len()..].trim();
-                    comparisons.push((first.to_string(), c.clone(), second.to_string()));
-                    break
-                }
+    // A filter written in the code, such as `""` for every dataset, which panics if it does not
+    // parse. Filters given by the user are read with `parse`, which reports the error instead.
+    pub fn from_static(filter_text: &'static str) -> ParameterFilterSet {
+        ParameterFilterSet::parse(filter_text).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn parse(filter_text: &str) -> Result<ParameterFilterSet, VisualizerError> {
+        let invalid = |reason: String| VisualizerError::InvalidFilter { filter: filter_text.to_string(), reason };
+        let mut parser = FilterParser { text: filter_text, position: 0 };
+        let mut groups = vec![parser.parse_all().map_err(invalid)?];
+        while parser.consume(";") {
+            groups.push(parser.parse_all().map_err(invalid)?);
+        }
+        if parser.position < filter_text.len() {
+            return Err(invalid(format!("unexpected '{}'", &filter_text[parser.position..])))
+        }
+
+        match groups.len() {
+            1 => Ok(ParameterFilterSet { expression: groups.remove(0), groups: Default::default(), text: filter_text.to_string() }),
+            _ => match groups.iter().any(|group| matches!(group, FilterExpression::All(terms) if terms.is_empty())) {
+                true => Err(invalid("empty group".to_string())),
+                false => Ok(ParameterFilterSet { expression: FilterExpression::Any(groups.clone()), groups, text: filter_text.to_string() }),
+            },
+        }
+    }
+
+    // Adds a term requiring the parameter to equal the value, as used by `--group-by`.
+    pub fn with_equal(self, name: &str, value: &ParameterValue) -> ParameterFilterSet {
+        if !self.groups.is_empty() {
+            let groups: Vec<FilterExpression> = self.groups.into_iter().map(|group| with_equal_term(group, name, value)).collect();
+            let text = self.text.split(';').map(|group| format!("({}), {}=={}", group.trim(), name, value)).collect::<Vec<_>>().join("; ");
+            return ParameterFilterSet { expression: FilterExpression::Any(groups.clone()), groups, text }
+        }
+
+        let text = match self.text.trim().is_empty() {
+            true => format!("{}=={}", name, value),
+            false => format!("({}), {}=={}", self.text, name, value),
+        };
+        ParameterFilterSet { expression: with_equal_term(self.expression, name, value), groups: Default::default(), text }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Position of the first group the dataset passes, or 0 without groups.
+    pub fn group_index(&self, base_name: &str, parameters: &BTreeMap<String, ParameterValue>) -> usize {
+        self.groups.iter().position(|group| group.passes(base_name, parameters)).unwrap_or(0)
+    }
+
+    // Legend label of the first group the dataset passes, when there are several groups.
+    pub fn group_label(&self, base_name: &str, parameters: &BTreeMap<String, ParameterValue>, labels: &ParameterLabels) -> Option<String> {
+        self.groups.iter().find(|group| group.passes(base_name, parameters)).map(|group| match group {
+            FilterExpression::Filter(_) | FilterExpression::All(_) => group.display_text(labels),
+            FilterExpression::Any(_) => format!("({})", group.display_text(labels)),
+        })
+    }
+
+    pub fn parameter_names(&self) -> impl Iterator<Item = &String> {
+        let mut names = Vec::new();
+        self.expression.add_parameter_names(&mut names);
+        names.into_iter()
+    }
+
+    // Every comparison in the filter, in the order written.
+    pub fn filters(&self) -> Vec<&ParameterFilter> {
+        let mut filters = Vec::new();
+        self.expression.add_filters(&mut filters);
+        filters
+    }
+}
+
+// The expression with a term requiring the parameter to equal the value.
+fn with_equal_term(expression: FilterExpression, name: &str, value: &ParameterValue) -> FilterExpression {
+    let filter = match value {
+        ParameterValue::Bool(v) => ParameterFilter::Bool(name.to_string(), Comparison::Equal, *v),
+        ParameterValue::Int(v) => ParameterFilter::Int(name.to_string(), Comparison::Equal, *v),
+        ParameterValue::Float(v) => ParameterFilter::Float(name.to_string(), Comparison::Equal, *v),
+        ParameterValue::Text(v) => ParameterFilter::Text(name.to_string(), Comparison::Equal, v.clone()),
+    };
+
+    let mut terms = match expression {
+        FilterExpression::All(terms) => terms,
+        expression => vec![expression],
+    };
+    terms.push(FilterExpression::Filter(filter));
+    terms.sort_by(|a, b| a.first_name().cmp(&b.first_name()));
+    FilterExpression::All(terms)
+}
+
+// Recursive descent parser for filter expressions.
+struct FilterParser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl FilterParser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.position = self.text.len() - self.rest().trim_start().len();
+    }
+
+    fn consume(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(token) {
+            self.position += token.len();
+            return true
+        }
+        false
+    }
+
+    // Comma separated terms. Terms are sorted by parameter name so equivalent filters display the
+    // same way.
+    fn parse_all(&mut self) -> Result<FilterExpression, String> {
+        let mut terms = vec![self.parse_any()?];
+        while self.consume(",") {
+            terms.push(self.parse_any()?);
+        }
+        terms.retain(|term| !matches!(term, FilterExpression::All(inner) if inner.is_empty()));
+        terms.sort_by(|a, b| a.first_name().cmp(&b.first_name()));
+
+        match terms.len() {
+            1 => Ok(terms.remove(0)),
+            _ => Ok(FilterExpression::All(terms)),
+        }
+    }
+
+    fn parse_any(&mut self) -> Result<FilterExpression, String> {
+        let mut terms = vec![self.parse_term()?];
+        while self.consume("||") {
+            terms.push(self.parse_term()?);
+        }
+
+        match terms.len() {
+            1 => Ok(terms.remove(0)),
+            _ => Ok(FilterExpression::Any(terms)),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<FilterExpression, String> {
+        if self.consume("(") {
+            let expression = self.parse_all()?;
+            if !self.consume(")") {
+                return Err("missing ')'".to_string())
             }
+            return Ok(expression)
+        }
+
+        self.skip_whitespace();
+        let length = ["(", ")", ",", ";", "||"].iter().filter_map(|token| self.rest().find(token)).min().unwrap_or(self.rest().len());
+        let length = match (self.rest()[..length].find(MATCH_TEXT), self.rest()[..length].find('[')) {
+            (Some(start), _) => start + MATCH_TEXT.len() + get_pattern_length(&self.rest()[start + MATCH_TEXT.len()..]),
+            // The values of a set are separated by commas.
+            (None, Some(start)) => start + self.rest()[start..].find(']').ok_or_else(|| "missing ']'".to_string())? + 1,
+            (None, None) => length,
+        };
+        let comparison_text = self.rest()[..length].trim().to_string();
+        self.position += length;
+
+        if comparison_text.is_empty() {
+            return Ok(FilterExpression::All(Default::default()))
+        }
+
+        parse_comparison(&comparison_text).map(FilterExpression::Filter)
+    }
+}
+
+// Length of a regular expression up to the end of its term. Parentheses in the expression are
+// skipped over when balanced, as are escaped characters.
+fn get_pattern_length(text: &str) -> usize {
+    let mut depth = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            },
+            '(' => depth += 1,
+            ')' if depth == 0 => return index,
+            ')' => depth -= 1,
+            ',' | ';' if depth == 0 => return index,
+            '|' if depth == 0 && text[index + 1..].starts_with('|') => return index,
+            _ => {},
         }
+    }
+    text.len()
+}
 
-        let mut filters: Vec<ParameterFilter> = Default::default();
+fn parse_comparison(text: &str) -> Result<ParameterFilter, String> {
+    if let Some(pos) = text.find(MATCH_TEXT) {
+        let name = text[0..pos].trim().to_string();
+        let pattern = text[pos + MATCH_TEXT.len()..].trim();
+        let regex = Regex::new(pattern).map_err(|e| format!("invalid regular expression for {}: {}", name, e))?;
+        return Ok(ParameterFilter::Match(name, regex))
+    }
+
+    if let Some(filter) = parse_set(text) {
+        return filter
+    }
 
-        for (name, comparison, value_text) in &comparisons {
+    for c in &COMPARISONS {
+        if let Some(pos) = text.find(&c.get_text()) {
+            let name = text[0..pos].trim().to_string();
+            let value_text = text[pos + c.get_text().len()..].trim();
+
+            // Test names are text, even when they look like a number.
+            if name == BASE_NAME_FILTER && !value_text.is_empty() {
+                if !c.is_equality() {
+                    return Err("only ==, != and ~= are supported for name".to_string())
+                }
+                return Ok(ParameterFilter::Text(name, c.clone(), value_text.to_string()))
+            }
             if let Ok(v) = value_text.parse::<bool>() {
-                assert_eq!(*comparison, Comparison::Equal);
-                filters.push(ParameterFilter::Bool(name.clone(), v));
+                if !c.is_equality() {
+                    return Err(format!("only == and != are supported for bool parameter {}", name))
+                }
+                return Ok(ParameterFilter::Bool(name, c.clone(), v))
+            }
+            if let Ok(v) = value_text.parse::<u64>() {
+                return Ok(ParameterFilter::Int(name, c.clone(), v))
             }
-            else if let Ok(v) = value_text.parse::<u64>() {
-                filters.push(ParameterFilter::Int(name.clone(), comparison.clone(), v));
+            if let Some(v) = value_text.parse::<f64>().ok().filter(|v| v.is_finite()) {
+                return Ok(ParameterFilter::Float(name, c.clone(), v))
             }
+            if value_text.is_empty() {
+                return Err(format!("no value given for parameter {}", name))
+            }
+            if !c.is_equality() {
+                return Err(format!("only == and != are supported for text parameter {}", name))
+            }
+            return Ok(ParameterFilter::Text(name, c.clone(), value_text.to_string()))
         }
+    }
+    Err(format!("no comparison in '{}'", text))
+}
 
-        filters.sort_by(|a, b| a.name().cmp(b.name()));
+// A set filter, `name in [a,b]` or `name not in [a,b]`, or None if the text is not one.
+fn parse_set(text: &str) -> Option<Result<ParameterFilter, String>> {
+    let (head, rest) = text.split_once('[')?;
+    let words: Vec<&str> = head.split_whitespace().collect();
+    let (name, negated) = match words[..] {
+        [name, operator] if operator == IN_TEXT => (name.to_string(), false),
+        [name, not, operator] if not == NOT_TEXT && operator == IN_TEXT => (name.to_string(), true),
+        _ => return None,
+    };
 
-        ParameterFilterSet { filters: filters }
+    let Some(list) = rest.trim_end().strip_suffix(']') else {
+        return Some(Err(format!("unexpected text after the set of {}", name)))
+    };
+    let value_texts: Vec<&str> = list.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()).collect();
+    if value_texts.is_empty() {
+        return Some(Err(format!("no values given in the set of {}", name)))
     }
+
+    // Test names are text, even when they look like a number.
+    let values = value_texts.into_iter().map(|v| match name == BASE_NAME_FILTER {
+        true => ParameterValue::Text(v.to_string()),
+        false => ParameterValue::parse(v).expect("Value is not empty"),
+    }).collect();
+    Some(Ok(ParameterFilter::Set(name, negated, values)))
 }
 
 impl FilterSet for ParameterFilterSet {
-    fn passes_filters(&self, parameters: &BTreeMap<String, ParameterValue>) -> bool {
-        let mut passes = true;
-        for filter in &self.filters {
-            match filter {
-                ParameterFilter::Bool(filter_name, filter_value) => {
-                    if let Some(param) = parameters.get(filter_name) {
-                        match param {
-                            ParameterValue::Bool(param_value) => {
-                                if param_value != filter_value {
-                                    passes = false;
-                                }
-                            },
-                            _ => {
-                            },
-                        }
-                    };
-                },
-                ParameterFilter::Int(filter_name, filter_comp, filter_value) => {
-                    if let Some(param) = parameters.get(filter_name) {
-                        match param {
-                            ParameterValue::Int(param_value) => {
-                                match filter_comp {
-                                    Comparison::Less => {
-                                        if param_value >= filter_value {
-                                            passes = false;
-                                        }
-                                    },
-                                    Comparison::LessEqual => {
-                                        if param_value > filter_value {
-                                            passes = false;
-                                        }
-                                    },
-                                    Comparison::Equal => {
-                                        if param_value != filter_value {
-                                            passes = false;
-                                        }
-                                    },
-                                    Comparison::GreaterEqual => {
-                                        if param_value < filter_value {
-                                            passes = false;
-                                        }
-                                    },
-                                    Comparison::Greater => {
-                                        if param_value <= filter_value {
-                                            passes = false;
-                                        }
-                                    },
-                                }
-                            },
-                            _ => {
-                            },
-                        }
-                    };
-                }
-            }
+    fn passes_filters(&self, base_name: &str, parameters: &BTreeMap<String, ParameterValue>) -> bool {
+        self.expression.passes(base_name, parameters)
+    }
+
+    fn display_text(&self, labels: &ParameterLabels) -> String {
+        match self.groups.is_empty() {
+            true => self.expression.display_text(labels),
+            false => self.groups.iter().map(|group| group.display_text(labels)).collect::<Vec<_>>().join("; "),
         }
-        passes
     }
+}
 
-    fn display_text(&self) -> String {
-        let mut text = String::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut prev_filter = false;
-        for filter in &self.filters {
-            if prev_filter {
-                text += ", ";
-            }
-            match filter {
-                ParameterFilter::Bool(filter_name, filter_value) => {
-                    text += &format!("{}={}", filter_name, filter_value);
-                },
-                ParameterFilter::Int(filter_name, filter_comp, filter_value) => {
-                    text += &format!("{}{}{}", filter_name, filter_comp.get_text(), filter_value);
-                },
+    fn parameters(values: &[(&str, &str)]) -> BTreeMap<String, ParameterValue> {
+        values.iter().map(|(name, value)| (name.to_string(), ParameterValue::parse(value).unwrap())).collect()
+    }
+
+    fn passes(filter: &str, base_name: &str, values: &[(&str, &str)]) -> bool {
+        ParameterFilterSet::parse(filter).unwrap().passes_filters(base_name, &parameters(values))
+    }
+
+    #[test]
+    fn commas_require_every_term() {
+        assert!(passes("readers==0, compress==true", "stress", &[("readers", "0"), ("compress", "true")]));
+        assert!(!passes("readers==0, compress==true", "stress", &[("readers", "0"), ("compress", "false")]));
+        assert!(passes("", "stress", &[("readers", "4")]));
+    }
+
+    #[test]
+    fn or_binds_tighter_than_commas() {
+        let filter = "readers==0 || writers>1, compress==true";
+        assert!(passes(filter, "stress", &[("readers", "0"), ("writers", "1"), ("compress", "true")]));
+        assert!(passes(filter, "stress", &[("readers", "4"), ("writers", "2"), ("compress", "true")]));
+        assert!(!passes(filter, "stress", &[("readers", "4"), ("writers", "1"), ("compress", "true")]));
+        assert!(!passes(filter, "stress", &[("readers", "0"), ("writers", "1"), ("compress", "false")]));
+    }
+
+    #[test]
+    fn parentheses_group_terms() {
+        let filter = "(readers==0, compress==true) || writers>1";
+        assert!(passes(filter, "stress", &[("readers", "0"), ("writers", "1"), ("compress", "true")]));
+        assert!(passes(filter, "stress", &[("readers", "4"), ("writers", "2"), ("compress", "false")]));
+        assert!(!passes(filter, "stress", &[("readers", "0"), ("writers", "1"), ("compress", "false")]));
+        assert_eq!(ParameterFilterSet::parse(filter).unwrap().display_text(&Default::default()), "(compress=true, readers==0) || writers>1");
+    }
+
+    #[test]
+    fn matches_regular_expressions() {
+        assert!(passes("name~=^stress-(a|b)$", "stress-b", &[]));
+        assert!(!passes("name~=^stress-(a|b)$", "stress-c", &[]));
+        // The parentheses and `|` of the pattern do not end the term.
+        assert!(passes("name~=(a|b), readers==0", "stress-a", &[("readers", "0")]));
+        assert!(!passes("name~=(a|b), readers==0", "stress-a", &[("readers", "4")]));
+    }
+
+    #[test]
+    fn sets_match_any_of_their_values() {
+        assert!(passes("readers in [0, 4]", "stress", &[("readers", "4")]));
+        assert!(!passes("readers in [0, 4]", "stress", &[("readers", "2")]));
+        assert!(passes("readers not in [0, 4]", "stress", &[("readers", "2")]));
+        assert!(!passes("readers not in [0, 4]", "stress", &[("readers", "0")]));
+        assert!(passes("name in [stress, 42]", "42", &[]));
+    }
+
+    #[test]
+    fn compares_floats_within_epsilon() {
+        assert!(passes("ratio==0.3", "stress", &[("ratio", "0.30000000000000004")]));
+        assert!(!passes("ratio<0.3", "stress", &[("ratio", "0.30000000000000004")]));
+        assert!(passes("ratio>=0.25", "stress", &[("ratio", "0.3")]));
+    }
+
+    #[test]
+    fn semicolons_split_groups() {
+        let filter = ParameterFilterSet::parse("readers==0; readers>0").unwrap();
+        assert_eq!(filter.group_index("stress", &parameters(&[("readers", "0")])), 0);
+        assert_eq!(filter.group_index("stress", &parameters(&[("readers", "4")])), 1);
+        assert_eq!(filter.group_label("stress", &parameters(&[("readers", "4")]), &Default::default()).as_deref(), Some("readers>0"));
+    }
+
+    #[test]
+    fn reports_invalid_filters() {
+        for (filter, reason) in [("(readers==0", "missing ')'"), ("readers in [0", "missing ']'"), ("readers", "no comparison in 'readers'"), ("compress>true", "only == and != are supported for bool parameter compress"), ("readers==0)", "unexpected ')'"), ("readers==0;", "empty group")] {
+            match ParameterFilterSet::parse(filter) {
+                Err(VisualizerError::InvalidFilter { reason: actual, .. }) => assert_eq!(actual, reason, "{}", filter),
+                other => panic!("{} parsed as {:?}", filter, other.map(|filter| filter.text().to_string())),
             }
-            prev_filter = true;
         }
+    }
 
-        text
+    #[test]
+    fn compares_text_parameters() {
+        assert!(passes("db-backend==paritydb", "stress", &[("db-backend", "paritydb")]));
+        assert!(!passes("db-backend==paritydb", "stress", &[("db-backend", "rocksdb")]));
+        assert!(passes("db-backend!=paritydb", "stress", &[("db-backend", "rocksdb")]));
+        assert_eq!(DataSet::get_name("stress".to_string(), &parameters(&[("db-backend", "rocksdb"), ("readers", "4")])), "stress (db-backend=rocksdb readers=4)");
+        assert!(matches!(ParameterFilterSet::parse("db-backend>paritydb"), Err(VisualizerError::InvalidFilter { reason, .. }) if reason == "only == and != are supported for text parameter db-backend"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parses_and_names_float_parameters() {
+        assert_eq!(ParameterValue::parse("0.3"), Some(ParameterValue::Float(0.3)));
+        assert_eq!(ParameterValue::parse("4"), Some(ParameterValue::Int(4)));
+        // Non-finite values are kept as text rather than compared as numbers.
+        assert_eq!(ParameterValue::parse("inf"), Some(ParameterValue::Text("inf".to_string())));
+        assert_eq!(DataSet::get_name("stress".to_string(), &parameters(&[("compression-ratio", "0.3")])), "stress (compression-ratio=0.3)");
+        // Integer filters compare with float parameters, and float filters with integer ones.
+        assert!(passes("compression-ratio<1", "stress", &[("compression-ratio", "0.3")]));
+        assert!(passes("readers>2.5", "stress", &[("readers", "4")]));
+        assert!(!passes("readers==3.5", "stress", &[("readers", "4")]));
+    }
+}
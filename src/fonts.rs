@@ -0,0 +1,62 @@
+// Font family and sizes of the text of each chart, with `--font-family` and `--title-font-size`,
+// `--axis-label-font-size`, `--tick-label-font-size` and `--legend-font-size`, e.g. to keep tick
+// labels readable when small images are embedded in documents. Sizes are percentages of the
+// height of the chart, so that they scale with the image.
+use super::*;
+use plotters::style::RelativeSize;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fonts {
+    pub family: String,
+    pub title: f64,
+    // The descriptions of the axes, such as `Commits`.
+    pub axis_label: f64,
+    // The values along the axes.
+    pub tick_label: f64,
+    pub legend: f64,
+}
+
+// The sizes the charts were drawn with before they could be configured.
+impl Default for Fonts {
+    fn default() -> Fonts {
+        Fonts { family: "sans-serif".to_string(), title: 3.0, axis_label: 2.0, tick_label: 2.0, legend: 2.0 }
+    }
+}
+
+impl Fonts {
+    pub fn new(args: &Args) -> Result<Fonts, Box<dyn Error>> {
+        let sizes = [("--title-font-size", args.title_font_size), ("--axis-label-font-size", args.axis_label_font_size), ("--tick-label-font-size", args.tick_label_font_size), ("--legend-font-size", args.legend_font_size)];
+        for (option, size) in sizes {
+            if !(size.is_finite() && size > 0.0) {
+                return Err(format!("{} must be a positive percentage of the chart height, not {}", option, size).into())
+            }
+        }
+        if args.font_family.trim().is_empty() {
+            return Err("--font-family must not be empty".into())
+        }
+
+        Ok(Fonts {
+            family: args.font_family.clone(),
+            title: args.title_font_size,
+            axis_label: args.axis_label_font_size,
+            tick_label: args.tick_label_font_size,
+            legend: args.legend_font_size,
+        })
+    }
+
+    pub fn title<'a>(&'a self, colour: &'a RGBColor) -> (&'a str, RelativeSize, &'a RGBColor) {
+        (&self.family, self.title.percent_height(), colour)
+    }
+
+    pub fn axis_label<'a>(&'a self, colour: &'a RGBColor) -> (&'a str, RelativeSize, &'a RGBColor) {
+        (&self.family, self.axis_label.percent_height(), colour)
+    }
+
+    pub fn tick_label<'a>(&'a self, colour: &'a RGBColor) -> (&'a str, RelativeSize, &'a RGBColor) {
+        (&self.family, self.tick_label.percent_height(), colour)
+    }
+
+    pub fn legend<'a>(&'a self, colour: &'a RGBColor) -> (&'a str, RelativeSize, &'a RGBColor) {
+        (&self.family, self.legend.percent_height(), colour)
+    }
+}
@@ -0,0 +1,96 @@
+// A title above the charts of an image and a footer below them, set with `--title` and `--footer`,
+// so that images shared on their own still say what was run and where, with the machines of
+// `--machine-metadata` listed between the charts and the footer.
+use super::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::process::Command;
+
+// Placeholders filled in when they appear in the footer.
+static DATE_PLACEHOLDER: &str = "${date}";
+static HOSTNAME_PLACEHOLDER: &str = "${hostname}";
+static GIT_COMMIT_PLACEHOLDER: &str = "${git-commit}";
+
+// The footer with the current UTC date, the name of the machine and the git commit checked out in
+// the current directory in place of their placeholders.
+pub(crate) fn expand_footer(text: &str) -> Result<String, Box<dyn Error>> {
+    let mut values: BTreeMap<String, String> = Default::default();
+    if text.contains(DATE_PLACEHOLDER) {
+        values.insert("date".to_string(), get_date());
+    }
+    if text.contains(HOSTNAME_PLACEHOLDER) {
+        values.insert("hostname".to_string(), get_hostname());
+    }
+    if text.contains(GIT_COMMIT_PLACEHOLDER) {
+        values.insert("git-commit".to_string(), get_git_commit()?);
+    }
+    config::substitute(text, &values).map_err(|e| format!("Invalid --footer '{}': {}", text, e).into())
+}
+
+// Draws the title and footer, returning the area left for the charts. Without either the charts
+// fill the whole image.
+pub(crate) fn draw_frame<DB: DrawingBackend>(root_area: &DrawingArea<DB, plotters::coord::Shift>, params: &Params) -> Result<DrawingArea<DB, plotters::coord::Shift>, Box<dyn Error>> where DB::ErrorType: 'static {
+    let text_size = |fraction: f64| (params.chart_size as f64 * fraction) as u32;
+
+    let mut area = root_area.clone();
+    if let Some(title) = &params.title {
+        area = area.titled(title, (params.fonts.family.as_str(), text_size(0.04)).into_font().color(&params.theme.text))?;
+    }
+
+    if let Some(footer) = &params.footer {
+        let height = area.dim_in_pixel().1;
+        let footer_height = text_size(0.03).min(height / 2);
+        let (charts_area, footer_area) = area.split_vertically(height - footer_height);
+        let style = (params.fonts.family.as_str(), text_size(0.015)).into_font().color(&params.theme.text).pos(Pos::new(HPos::Left, VPos::Center));
+        footer_area.draw(&Text::new(footer.clone(), (text_size(0.01) as i32, footer_height as i32 / 2), style))?;
+        area = charts_area;
+    }
+
+    if !params.machine_metadata.is_empty() {
+        let height = area.dim_in_pixel().1;
+        let line_height = text_size(0.02);
+        let block_height = (line_height * params.machine_metadata.len() as u32 + line_height / 2).min(height / 2);
+        let (charts_area, block_area) = area.split_vertically(height - block_height);
+        let style = (params.fonts.family.as_str(), text_size(0.013)).into_font().color(&params.theme.text).pos(Pos::new(HPos::Left, VPos::Center));
+        for (index, line) in params.machine_metadata.iter().enumerate() {
+            let y = line_height * index as u32 + line_height / 2;
+            block_area.draw(&Text::new(line.clone(), (text_size(0.01) as i32, y as i32), style.clone()))?;
+        }
+        area = charts_area;
+    }
+
+    Ok(area)
+}
+
+// As `YYYY-MM-DD HH:MM UTC`, from the days since the Unix epoch (Howard Hinnant's civil_from_days).
+fn get_date() -> String {
+    let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default() as i64;
+    let (days, seconds_of_day) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, seconds_of_day / 3600, seconds_of_day % 3600 / 60)
+}
+
+fn get_hostname() -> String {
+    let from_command = Command::new("hostname").output().ok().filter(|output| output.status.success()).map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    from_command.filter(|name| !name.is_empty())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_else(|| "unknown host".to_string())
+}
+
+fn get_git_commit() -> Result<String, Box<dyn Error>> {
+    let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output().map_err(|e| format!("Failed to run git for {}: {}", GIT_COMMIT_PLACEHOLDER, e))?;
+    match output.status.success() {
+        true => Ok(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => Err(format!("Failed to read the git commit for {}: {}", GIT_COMMIT_PLACEHOLDER, String::from_utf8_lossy(&output.stderr).trim()).into()),
+    }
+}
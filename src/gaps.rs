@@ -0,0 +1,70 @@
+// Missing commit buckets, such as where a run crashed or a test was restarted. A gap is a step
+// between consecutive buckets of a dataset more than half as long again as its usual step, the
+// median of its steps. Gaps are reported when the data is read, and metric charts break their
+// lines at them rather than connecting across, unless `--gaps` says otherwise.
+use super::*;
+
+// How metric charts draw the mean line of a dataset across a gap.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GapMode {
+    // End the line at the bucket before the gap and start again after it.
+    Break,
+    // Connect the buckets either side of the gap with a straight line.
+    Interpolate,
+    // Connect across the gap and shade the commit counts it spans.
+    Shade,
+}
+
+// The commit counts of the buckets either side of a gap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gap {
+    pub start: u64,
+    pub end: u64,
+}
+
+static GAP_STEPS: f64 = 1.5;
+
+// Gaps in sorted commit counts. Without two steps there is no usual step to compare against.
+pub fn find_gaps(commits: &[u64]) -> Vec<Gap> {
+    let steps: Vec<f64> = commits.windows(2).map(|pair| (pair[1] - pair[0]) as f64).collect();
+    if steps.len() < 2 {
+        return Default::default()
+    }
+
+    let usual_step = statistics::median(&steps);
+    commits.windows(2)
+        .filter(|pair| (pair[1] - pair[0]) as f64 > usual_step * GAP_STEPS)
+        .map(|pair| Gap { start: pair[0], end: pair[1] })
+        .collect()
+}
+
+// The points split into runs which do not cross a gap, given by its ends along the X axis. There
+// is always at least one run, so that a series without points still has a legend entry.
+pub fn split_at_gaps(points: Vec<(f64, f64)>, gaps: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+    let mut runs: Vec<Vec<(f64, f64)>> = vec![Default::default()];
+    for point in points {
+        let crosses = runs.last().and_then(|run| run.last()).is_some_and(|last| gaps.iter().any(|(start, end)| last.0 <= *start && point.0 >= *end));
+        match runs.last_mut() {
+            Some(run) if !crosses => run.push(point),
+            _ => runs.push(vec![point]),
+        }
+    }
+    runs
+}
+
+// Warns of each dataset with gaps, so that a crashed run is noticed even where it is not drawn.
+pub fn warn_gaps(data: &StressTestData) {
+    for name in data.sorted_names() {
+        let dataset = &data.datasets[name];
+        if dataset.derived {
+            continue;
+        }
+
+        match &dataset.get_gaps()[..] {
+            [] => {},
+            [gap] => log::warn!("{} is missing commit buckets between {} and {} commits", name, gap.start, gap.end),
+            gaps => log::warn!("{} is missing commit buckets in {} gaps, the first between {} and {} commits", name, gaps.len(), gaps[0].start, gaps[0].end),
+        }
+    }
+}
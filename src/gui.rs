@@ -0,0 +1,190 @@
+// A window for exploring the charts of the data, with `--gui`, in place of editing the arguments,
+// drawing the image again and opening it. Datasets are shown or hidden with their checkboxes, and
+// the chart type, filter and range of commits drawn are changed in the side panel. The chart is
+// drawn from the loaded StressTestData by the same renderer as image files, whenever they change.
+use super::*;
+use clap::ValueEnum;
+use eframe::egui;
+
+// Size of the chart in pixels, smaller than that of image files so that it is drawn again quickly.
+static CHART_SIZE: u32 = 1080;
+
+// Fraction of the range of commits shown by each step of the mouse wheel over the chart.
+static ZOOM_STEP: f64 = 0.2;
+
+pub fn show(data: StressTestData, params: Params) -> Result<(), Box<dyn Error>> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([1400.0, 1120.0]),
+        ..Default::default()
+    };
+    eframe::run_native(env!("CARGO_PKG_NAME"), options, Box::new(|_| Ok(Box::new(Explorer::new(data, params)))))
+        .map_err(|e| format!("Failed to open the window: {}", e).into())
+}
+
+struct Explorer {
+    data: StressTestData,
+    params: Params,
+    // Options of the first chart of the arguments, which every chart type is drawn with.
+    options: ChartOptions,
+    chart_type_names: Vec<String>,
+    chart_type: String,
+    filter_text: String,
+    // The last filter which parsed, drawn while the text being typed does not.
+    filters: ParameterFilterSet,
+    filter_error: Option<String>,
+    shown: BTreeMap<String, bool>,
+    // Lowest and highest commit counts drawn.
+    commits: (u64, u64),
+    texture: Option<egui::TextureHandle>,
+    render_error: Option<String>,
+    // Whether a setting changed since the chart was last drawn.
+    stale: bool,
+}
+
+impl Explorer {
+    fn new(data: StressTestData, mut params: Params) -> Explorer {
+        let mut chart_type_names: Vec<String> = ChartType::value_variants().iter().map(|t| t.to_string()).collect();
+        chart_type_names.extend(plugins::chart_type_names());
+
+        let first_spec = params.chart_specs.first();
+        let chart_type = first_spec.map_or_else(|| ChartType::CommitsPerSecond.to_string(), |spec| spec.chart_type.to_string());
+        let options = first_spec.map_or_else(ChartOptions::default, |spec| spec.options.clone());
+        params.chart_size = CHART_SIZE;
+        params.layout = None;
+
+        let shown = data.datasets.keys().map(|name| (name.clone(), true)).collect();
+        let commits = (0, data.max_commits);
+        Explorer { data, params, options, chart_type_names, chart_type, filter_text: String::new(), filters: ParameterFilterSet::from_static(""), filter_error: None, shown, commits, texture: None, render_error: None, stale: true }
+    }
+
+    fn redraw(&mut self, ctx: &egui::Context) {
+        let Some(chart_type) = ChartType::get_from_string(&self.chart_type) else {
+            return
+        };
+        let mut spec = ChartSpec::new(chart_type, self.filters.clone());
+        spec.options = self.options.clone();
+        // Charts along other X axes are fitted to the buckets left in the range.
+        if self.params.x_axis == XAxis::Commits && self.commits != (0, self.data.max_commits) {
+            spec.x_range = AxisRange { min: Some(self.commits.0 as f64), max: Some(self.commits.1 as f64) };
+        }
+        self.params.chart_specs = vec![spec];
+
+        let view = get_view(&self.data, &|name| self.shown.get(name).copied().unwrap_or(true), self.commits);
+        match testing::render_data(&view, &self.params) {
+            Ok(image) => {
+                let image = egui::ColorImage::from_rgb([image.width as usize, image.height as usize], &image.pixels);
+                match &mut self.texture {
+                    Some(texture) => texture.set(image, Default::default()),
+                    None => self.texture = Some(ctx.load_texture("chart", image, Default::default())),
+                }
+                self.render_error = None;
+            },
+            Err(e) => self.render_error = Some(e.to_string()),
+        }
+    }
+
+    fn draw_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Chart");
+        egui::ComboBox::from_id_salt("chart_type").selected_text(self.chart_type.as_str()).show_ui(ui, |ui| {
+            for name in &self.chart_type_names {
+                self.stale |= ui.selectable_value(&mut self.chart_type, name.clone(), name.as_str()).changed();
+            }
+        });
+
+        ui.label("Filter, e.g. readers==0 || writers>1");
+        if ui.text_edit_singleline(&mut self.filter_text).changed() {
+            match ParameterFilterSet::parse(&self.filter_text) {
+                Ok(filters) => {
+                    self.filters = filters;
+                    self.filter_error = None;
+                    self.stale = true;
+                },
+                Err(e) => self.filter_error = Some(e.to_string()),
+            }
+        }
+        if let Some(error) = &self.filter_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+
+        ui.separator();
+        ui.heading("Commits");
+        let max_commits = self.data.max_commits;
+        self.stale |= ui.add(egui::Slider::new(&mut self.commits.0, 0..=max_commits).text("from")).changed();
+        self.stale |= ui.add(egui::Slider::new(&mut self.commits.1, 0..=max_commits).text("to")).changed();
+        self.commits.1 = self.commits.1.max(self.commits.0);
+        if ui.button("Show all commits").clicked() {
+            self.commits = (0, max_commits);
+            self.stale = true;
+        }
+
+        ui.separator();
+        ui.heading("Datasets");
+        ui.horizontal(|ui| {
+            for (text, shown) in [("Show all", true), ("Hide all", false)] {
+                if ui.button(text).clicked() {
+                    self.shown.values_mut().for_each(|value| *value = shown);
+                    self.stale = true;
+                }
+            }
+        });
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (name, shown) in &mut self.shown {
+                self.stale |= ui.checkbox(shown, name.as_str()).changed();
+            }
+        });
+    }
+
+    // Scrolling over the chart zooms in or out around the middle of the range of commits.
+    fn zoom(&mut self, scroll: f32) {
+        let (from, to) = (self.commits.0 as f64, self.commits.1 as f64);
+        let middle = (from + to) / 2.0;
+        let half_span = ((to - from) / 2.0 * (1.0 - ZOOM_STEP).powf(scroll as f64 / 50.0)).max(1.0);
+        let max_commits = self.data.max_commits as f64;
+        self.commits = ((middle - half_span).clamp(0.0, max_commits) as u64, (middle + half_span).clamp(0.0, max_commits) as u64);
+        self.stale = true;
+    }
+}
+
+impl eframe::App for Explorer {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::SidePanel::left("settings").resizable(true).show(ctx, |ui| self.draw_settings(ui));
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if let Some(error) = &self.render_error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+            if let Some(texture) = &self.texture {
+                let response = ui.add(egui::Image::new(texture).shrink_to_fit()).on_hover_text("Scroll to zoom in or out");
+                let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+                if response.hovered() && scroll != 0.0 {
+                    self.zoom(scroll);
+                }
+            }
+        });
+
+        if self.stale {
+            self.stale = false;
+            self.redraw(ctx);
+            ctx.request_repaint();
+        }
+    }
+}
+
+// The shown datasets with only their buckets within the range of commits, and the same of any
+// baseline.
+fn get_view(data: &StressTestData, shown: &dyn Fn(&str) -> bool, commits: (u64, u64)) -> StressTestData {
+    let mut view = StressTestData::new();
+    for (name, dataset) in data.datasets.iter().filter(|(name, _)| shown(name)) {
+        let sorted_values = dataset.sorted_values.iter().filter(|value| (commits.0..=commits.1).contains(&value.num_commits)).map(|value| {
+            let mut copy = ValueSet::new(value.num_commits);
+            copy.merge(value);
+            copy
+        }).collect();
+        let mut zoomed = dataset.with_values(sorted_values);
+        zoomed.max_commits = zoomed.sorted_values.last().map_or(0, |value| value.num_commits);
+        view.datasets.insert(name.clone(), zoomed);
+    }
+    view.update_maxima();
+    view.max_commits = view.datasets.values().fold(0, |max, d| max.max(d.max_commits));
+    view.baseline = data.baseline.as_ref().map(|baseline| Box::new(get_view(baseline, &|_| true, commits)));
+    view
+}
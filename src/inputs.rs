@@ -0,0 +1,91 @@
+// Expansion of `--data-path` and `--baseline` entries into data files. Directories are searched
+// recursively and glob patterns such as `results/**/*.csv` are matched, each in sorted order so
+// that run indices are stable between invocations.
+use super::*;
+use std::path::Path;
+
+// Extensions of the files picked up from directories, also when compressed, e.g. `run1.csv.gz`.
+static DATA_EXTENSIONS: [&str; 4] = ["csv", "json", "ndjson", "jsonl"];
+
+/// Replaces directories and glob patterns with the data files they contain, keeping plain file
+/// paths as given. Fails if a directory or pattern yields no data files.
+pub fn find_data_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, VisualizerError> {
+    let mut files: Vec<PathBuf> = Default::default();
+    for path in paths {
+        let found = if path.is_dir() {
+            let mut found = Default::default();
+            find_in_directory(path, &mut found)?;
+            remove_workload_configs(found)
+        } else if is_pattern(path) {
+            match_pattern(path)?
+        } else {
+            files.push(path.clone());
+            continue;
+        };
+
+        if found.is_empty() {
+            return Err(VisualizerError::NoDataFiles { path: path.clone() })
+        }
+        log::info!("Found {} data files in {}", found.len(), path.display());
+        files.extend(found);
+    }
+    Ok(files)
+}
+
+fn is_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+fn match_pattern(pattern: &Path) -> Result<Vec<PathBuf>, VisualizerError> {
+    // Hidden files are skipped unless the pattern names them, as when searching directories.
+    let options = glob::MatchOptions { require_literal_leading_dot: true, ..Default::default() };
+    let matches = glob::glob_with(&pattern.to_string_lossy(), options)
+        .map_err(|e| VisualizerError::InvalidFile { path: pattern.to_path_buf(), reason: format!("Invalid pattern: {}", e) })?;
+
+    let mut files: Vec<PathBuf> = Default::default();
+    for entry in matches {
+        let path = entry.map_err(|e| VisualizerError::Io { path: e.path().to_path_buf(), source: e.into() })?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+fn find_in_directory(directory: &Path, files: &mut Vec<PathBuf>) -> Result<(), VisualizerError> {
+    let io_error = |source| VisualizerError::Io { path: directory.to_path_buf(), source };
+
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(directory).map_err(io_error)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>().map_err(io_error)?;
+    entries.sort();
+
+    for path in entries {
+        let hidden = path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.'));
+        if hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            find_in_directory(&path, files)?;
+        } else if has_data_extension(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn has_data_extension(path: &Path) -> bool {
+    let extension = compression::uncompressed_path(path).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+    extension.is_some_and(|e| DATA_EXTENSIONS.contains(&e.as_str()))
+}
+
+// A JSON file with the same name as another data file is the stress test config of that file,
+// see `workload`, rather than data.
+fn remove_workload_configs(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let is_config = |path: &PathBuf| {
+        path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("json"))
+            && files.iter().any(|other| other != path && compression::uncompressed_path(other).with_extension("") == path.with_extension(""))
+    };
+    files.iter().filter(|path| !is_config(path)).cloned().collect()
+}
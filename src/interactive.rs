@@ -0,0 +1,167 @@
+use super::*;
+use clap::ValueEnum;
+use std::io::Write;
+
+// Prompts for chart types and filters after listing the parameters found in the data, then prints
+// the equivalent command line and config template so the selection can be reused.
+pub fn build_chart_specs(data: &StressTestData, args: &Args, options: &ChartOptions) -> Result<Vec<ChartSpec>, Box<dyn Error>> {
+    let mut parameter_values: BTreeMap<String, Vec<ParameterValue>> = Default::default();
+    for dataset in data.datasets.values().filter(|d| !d.derived) {
+        for (name, value) in &dataset.parameters {
+            let values = parameter_values.entry(name.clone()).or_default();
+            if !values.contains(value) {
+                values.push(value.clone());
+            }
+        }
+    }
+
+    println!();
+    println!("Discovered parameters:");
+    for (name, values) in &mut parameter_values {
+        values.sort_by(|a, b| a.display_cmp(b));
+        let value_text: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        println!("  {}: {}", name, value_text.join(", "));
+    }
+
+    let mut chart_type_names: Vec<String> = ChartType::value_variants().iter().map(|t| t.to_string()).collect();
+    chart_type_names.extend(plugins::chart_type_names());
+
+    println!();
+    println!("Chart types:");
+    for (index, name) in chart_type_names.iter().enumerate() {
+        println!("  {}) {}", index + 1, name);
+    }
+
+    let mut selections: Vec<(String, String)> = Default::default();
+    let mut selection_filters: Vec<ParameterFilterSet> = Default::default();
+    loop {
+        println!();
+        let chart_number = selections.len() + 1;
+        let type_text = prompt(&format!("Chart {} type, by number or name (blank to finish): ", chart_number))?;
+        if type_text.is_empty() {
+            break;
+        }
+
+        let type_name = match type_text.parse::<usize>() {
+            Ok(index) if index >= 1 && index <= chart_type_names.len() => chart_type_names[index - 1].clone(),
+            _ => type_text,
+        };
+        if ChartType::get_from_string(&type_name).is_none() {
+            println!("Unknown chart type: {}", type_name);
+            continue;
+        }
+
+        // A filter which does not parse is asked for again, rather than ending the session.
+        let (filter_text, filters) = loop {
+            let filter_text = prompt(&format!("Chart {} filter, e.g. readers==0 || writers>1, compress!=true (blank for all): ", chart_number))?;
+            match ParameterFilterSet::parse(&filter_text) {
+                Ok(filters) => break (filter_text, filters),
+                Err(e) => println!("{}", e),
+            }
+        };
+        let num_matches = data.datasets.values().filter(|d| !d.derived && d.passes_filters(&filters)).count();
+        println!("Filter matches {} of {} datasets", num_matches, data.datasets.len());
+
+        selections.push((type_name, filter_text));
+        selection_filters.push(filters);
+    }
+
+    print_equivalent(args, &selections);
+
+    let chart_specs = selections.iter().zip(selection_filters).map(|((type_name, _), filters)| {
+        let chart_type = ChartType::get_from_string(type_name).expect("Chart type was validated");
+        let mut options = options.clone();
+        options.smooth = options.smooth.filter(|_| chart_type.supports_smoothing());
+        ChartSpec {
+            chart_type,
+            filters,
+            title: None,
+            transform: Transform::default(),
+            y_range: AxisRange::default(),
+            x_range: AxisRange::default(),
+            secondary: None,
+            options,
+        }
+    }).collect();
+
+    Ok(chart_specs)
+}
+
+// Returns the trimmed line, or an empty string at the end of input.
+fn prompt(text: &str) -> Result<String, Box<dyn Error>> {
+    print!("{}", text);
+    std::io::stdout().flush()?;
+
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+fn print_equivalent(args: &Args, selections: &[(String, String)]) {
+    if selections.is_empty() {
+        return
+    }
+
+    let mut command = env!("CARGO_PKG_NAME").to_string();
+    command += " -d";
+    for path in args.data_path.iter().flatten() {
+        command += &format!(" {}", path.display());
+    }
+    if !args.tag.is_empty() {
+        command += " --tag";
+        for tag in &args.tag {
+            command += &format!(" {}", tag);
+        }
+    }
+    if args.tag_by_file {
+        command += " --tag-by-file";
+    }
+    if let Some(schema) = &args.schema {
+        command += &format!(" --schema {}", schema.display());
+    }
+    if args.merge_policy != MergePolicy::Merge {
+        command += &format!(" --merge-policy {}", clap::ValueEnum::to_possible_value(&args.merge_policy).expect("Merge policies are not skipped").get_name());
+    }
+    for metric in &args.metric {
+        command += &format!(" --metric '{}'", metric);
+    }
+    for pattern in &args.include_dataset {
+        command += &format!(" --include-dataset '{}'", pattern);
+    }
+    for pattern in &args.exclude_dataset {
+        command += &format!(" --exclude-dataset '{}'", pattern);
+    }
+    for parameter in &args.aggregate {
+        command += &format!(" --aggregate {}", parameter);
+    }
+    if let Some(filter) = &args.drop_outliers {
+        command += &format!(" --drop-outliers {}", filter);
+    }
+    if args.x_axis != XAxis::Commits {
+        command += &format!(" --x-axis {}", args.x_axis);
+    }
+    if let Some(parameter) = &args.group_by {
+        command += &format!(" --group-by {}", parameter);
+    }
+    command += " -c";
+    for (type_name, _) in selections {
+        command += &format!(" {}", type_name);
+    }
+    command += " -f";
+    for (_, filter_text) in selections {
+        command += &format!(" \"{}\"", filter_text);
+    }
+
+    println!();
+    println!("Equivalent command line:");
+    println!("  {}", command);
+
+    println!();
+    println!("Equivalent config template:");
+    for (type_name, filter_text) in selections {
+        println!("[[templates.interactive.charts]]");
+        println!("type = \"{}\"", type_name);
+        println!("filter = \"{}\"", filter_text);
+    }
+    println!();
+}
@@ -1,633 +1,4365 @@
-use clap::Parser;
-use plotters::{prelude::*};
+//! Visualize data from multiple parity-db stress test runs.
+//!
+//! Besides the command line tool, the crate can be used as a library. Load data files with
+//! [`load_stress_test_data`], inspect the [`DataSet`]s and [`SampleSet`]s of the returned
+//! [`StressTestData`], and draw charts described by [`Params`] with [`render_charts`]:
+//!
+//! ```no_run
+//! use parity_db_visualizer::*;
+//!
+//! let paths = vec![std::path::PathBuf::from("run1.csv")];
+//! let data = load_stress_test_data(&paths, &LoadOptions::default())?;
+//! for (name, dataset) in &data.datasets {
+//!     let final_rate = dataset.sorted_values.last().map(|value| value.commits_per_second.get_mean());
+//!     println!("{}: {:?} commits per second at the end of the run", name, final_rate);
+//! }
+//!
+//! let params = Params::new(vec![ChartSpec::new(ChartType::CommitsPerSecond, ParameterFilterSet::parse("readers==0")?)]);
+//! render_charts(std::path::Path::new("commits.png"), &OutputFormat::Png, &data, &params)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! To draw the charts with another backend, implement [`Renderer`] and pass it to
+//! [`render_with`], which hands it the aggregated datasets of each chart in turn.
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser};
+use plotters::{prelude::*, style::text_anchor::{HPos, Pos, VPos}};
+use rayon::prelude::*;
 use std::{error::Error, io::BufRead, collections::{HashMap, HashSet, BTreeMap}, path::PathBuf, fmt::Debug};
 
+mod aggregate;
+mod animation;
+mod annotations;
+mod axis;
+mod cache;
+mod check;
+mod compare;
+mod compression;
+mod config;
+mod dashboard;
+mod derived;
+mod describe;
+mod digest;
+mod dual_axis;
+mod exporter;
+mod expression;
+pub mod error;
+mod facet;
 mod filter;
-use filter::{FilterSet, ParameterFilterSet};
+mod frame;
+mod fonts;
+mod gaps;
+#[cfg(feature = "gui")]
+mod gui;
+mod inputs;
+mod interactive;
+mod line_style;
+mod listen;
+mod logging;
+mod machine;
+mod normalize;
+mod ordering;
+mod outliers;
+mod palette;
+mod pdf;
+mod points;
+mod presets;
+pub mod plugins;
+mod provenance;
+mod reference_lines;
+mod renderer;
+mod schema;
+mod selection;
+pub mod source;
+mod spec;
+mod stalls;
+mod statistics;
+mod summary;
+mod terminal;
+mod time_axis;
+pub mod testing;
+mod theme;
+mod transform;
+mod trend;
+mod units;
+mod unmatched;
+mod workload;
+pub use annotations::{load_annotations, Annotation};
+pub use axis::AxisRange;
+use config::{Config, TemplateInstance};
+use testing::PointSpread;
+pub use config::{ParameterLabel, ParameterLabels};
+pub use derived::DerivedSeries;
+pub use error::{RegressionError, VisualizerError};
+pub use facet::Facet;
+pub use filter::{FilterSet, ParameterFilterSet};
+pub use fonts::Fonts;
+pub use gaps::{Gap, GapMode};
+pub use inputs::find_data_files;
+pub use line_style::LineStyle;
+pub use ordering::SortOrder;
+pub use outliers::OutlierFilter;
+pub use palette::Palette;
+pub use points::PointsFormat;
+pub use presets::Preset;
+use provenance::Provenance;
+pub use reference_lines::ReferenceLine;
+pub use renderer::{render_with, PlottersRenderer, Renderer};
+pub use schema::{ColumnRole, ColumnType, Schema, SchemaColumn};
+pub use source::{AppendedRecords, DataSource, FilePosition, Record};
+pub use stalls::{Stall, StallBucket, StallDetection};
+pub use theme::Theme;
+pub use transform::Transform;
+pub use trend::{Trend, TrendFit};
+pub use units::YUnit;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum ParameterValue {
     Bool(bool),
     Int(u64),
+    Float(f64),
+    Text(String),
 }
 
-#[derive(Debug, Clone, clap::ValueEnum)]
+impl ParameterValue {
+    // Parses a data file value, or None if it is empty.
+    pub fn parse(text: &str) -> Option<ParameterValue> {
+        if let Ok(v) = text.parse::<bool>() {
+            Some(ParameterValue::Bool(v))
+        } else if let Ok(v) = text.parse::<u64>() {
+            Some(ParameterValue::Int(v))
+        } else if let Some(v) = text.parse::<f64>().ok().filter(|v| v.is_finite()) {
+            Some(ParameterValue::Float(v))
+        } else if !text.is_empty() {
+            Some(ParameterValue::Text(text.to_string()))
+        } else {
+            None
+        }
+    }
+
+    // The value of a numeric parameter, so that integers and floats can be compared.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ParameterValue::Int(v) => Some(*v as f64),
+            ParameterValue::Float(v) => Some(*v),
+            ParameterValue::Bool(_) | ParameterValue::Text(_) => None,
+        }
+    }
+
+    // Numbers in numeric order, other values by their text.
+    pub fn display_cmp(&self, other: &ParameterValue) -> std::cmp::Ordering {
+        match (self, other) {
+            (ParameterValue::Int(a), ParameterValue::Int(b)) => a.cmp(b),
+            _ => match (self.as_f64(), other.as_f64()) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                _ => ordering::natural_cmp(&self.to_string(), &other.to_string()),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ParameterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParameterValue::Bool(v) => write!(f, "{}", v),
+            ParameterValue::Int(v) => write!(f, "{}", v),
+            // The shortest text which reads back as the same value, e.g. `0.3`.
+            ParameterValue::Float(v) => write!(f, "{}", v),
+            ParameterValue::Text(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, clap::ValueEnum)]
 pub enum ChartType {
     CommitTime,
     CommitsPerSecond,
     QueriesPerSecond,
+    CommitTimeAutocorrelation,
+    SampleIntervalHistogram,
+    CommitTimeRollingPercentile,
+    CommitsPerSecondRunVariance,
+    QueryLatency,
+    CommitTimeOverWallClock,
+    DiskSize,
+    WriteAmplification,
+    CommitTimeScatter,
+    CommitsPerSecondScatter,
+    QueriesPerSecondScatter,
+    FinalThroughputBars,
+    QueryCommitRatio,
+    CommitsPerSecondVariation,
+    // Resident set size and heap size from the `rss` and `heap` columns, as separate series. Plot
+    // both on one panel with `memory-usage secondary heap-usage`.
+    MemoryUsage,
+    HeapUsage,
+    // Chart types registered through the plugins module.
+    #[value(skip)]
+    PluginMetric(String),
+    #[value(skip)]
+    PluginChart(String),
 }
 
 impl ChartType {
-    pub fn get_from_string(text: &String) -> Option<ChartType> {
-        match text.as_str() {
+    pub fn get_from_string(text: &str) -> Option<ChartType> {
+        match text {
             "commit-time" => Some(ChartType::CommitTime),
             "commits-per-second" => Some(ChartType::CommitsPerSecond),
             "queries-per-second" => Some(ChartType::QueriesPerSecond),
-            _ => None,
+            "commit-time-autocorrelation" => Some(ChartType::CommitTimeAutocorrelation),
+            "sample-interval-histogram" => Some(ChartType::SampleIntervalHistogram),
+            "commit-time-rolling-percentile" => Some(ChartType::CommitTimeRollingPercentile),
+            "commits-per-second-run-variance" => Some(ChartType::CommitsPerSecondRunVariance),
+            "query-latency" => Some(ChartType::QueryLatency),
+            "commit-time-over-wall-clock" => Some(ChartType::CommitTimeOverWallClock),
+            "disk-size" => Some(ChartType::DiskSize),
+            "write-amplification" => Some(ChartType::WriteAmplification),
+            "commit-time-scatter" => Some(ChartType::CommitTimeScatter),
+            "commits-per-second-scatter" => Some(ChartType::CommitsPerSecondScatter),
+            "queries-per-second-scatter" => Some(ChartType::QueriesPerSecondScatter),
+            "final-throughput-bars" => Some(ChartType::FinalThroughputBars),
+            "query-commit-ratio" => Some(ChartType::QueryCommitRatio),
+            "commits-per-second-variation" => Some(ChartType::CommitsPerSecondVariation),
+            "memory-usage" => Some(ChartType::MemoryUsage),
+            "heap-usage" => Some(ChartType::HeapUsage),
+            _ => {
+                if plugins::get_metric(text).is_some() {
+                    Some(ChartType::PluginMetric(text.to_string()))
+                } else if plugins::get_chart(text).is_some() {
+                    Some(ChartType::PluginChart(text.to_string()))
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            ChartType::CommitTime => "Commit Time".to_string(),
+            ChartType::CommitsPerSecond => "Commits per Second".to_string(),
+            ChartType::QueriesPerSecond => "Queries per Second".to_string(),
+            ChartType::CommitTimeAutocorrelation => "Commit Time Autocorrelation".to_string(),
+            ChartType::SampleIntervalHistogram => "Sample Interval Histogram".to_string(),
+            ChartType::CommitTimeRollingPercentile => "Rolling Percentile Commit Time".to_string(),
+            ChartType::CommitsPerSecondRunVariance => "Commits per Second Run Variance".to_string(),
+            ChartType::QueryLatency => "Query Latency".to_string(),
+            ChartType::CommitTimeOverWallClock => "Commits per Second over Wall Clock Time".to_string(),
+            ChartType::DiskSize => "Disk Size (MB)".to_string(),
+            ChartType::WriteAmplification => "Write Amplification".to_string(),
+            ChartType::CommitTimeScatter => "Commit Time Samples".to_string(),
+            ChartType::CommitsPerSecondScatter => "Commits per Second Samples".to_string(),
+            ChartType::QueriesPerSecondScatter => "Queries per Second Samples".to_string(),
+            ChartType::FinalThroughputBars => "Final Throughput".to_string(),
+            ChartType::QueryCommitRatio => "Queries per Commit".to_string(),
+            ChartType::CommitsPerSecondVariation => "Commits per Second Variation".to_string(),
+            ChartType::MemoryUsage => "Resident Memory (MB)".to_string(),
+            ChartType::HeapUsage => "Heap Usage (MB)".to_string(),
+            ChartType::PluginMetric(name) => plugins::get_metric(name).map(|metric| metric.title()).unwrap_or_else(|| name.clone()),
+            ChartType::PluginChart(name) => plugins::get_chart(name).map(|chart| chart.title()).unwrap_or_else(|| name.clone()),
+        }
+    }
+
+    // Chart types which plot the mean and spread of a metric at each commit count.
+    pub fn is_metric(&self) -> bool {
+        matches!(self, ChartType::CommitTime | ChartType::CommitsPerSecond | ChartType::QueriesPerSecond | ChartType::DiskSize | ChartType::WriteAmplification | ChartType::MemoryUsage | ChartType::HeapUsage | ChartType::PluginMetric(_))
+    }
+
+    // Chart types computed from per-bucket statistics, which smoothing pools across neighbouring
+    // buckets. The others use every raw sample, which pooling would count several times.
+    pub fn supports_smoothing(&self) -> bool {
+        !matches!(self, ChartType::SampleIntervalHistogram | ChartType::CommitTimeRollingPercentile | ChartType::FinalThroughputBars) && !self.is_scatter()
+    }
+
+    // Chart types drawn from the raw samples rather than the statistics of each commit count, which
+    // `--low-memory` does not keep.
+    pub fn needs_raw_samples(&self) -> bool {
+        matches!(self, ChartType::SampleIntervalHistogram | ChartType::CommitTimeRollingPercentile | ChartType::PluginChart(_)) || self.is_scatter()
+    }
+
+    // Chart types which plot every raw sample as a point rather than aggregating each commit count.
+    pub fn is_scatter(&self) -> bool {
+        matches!(self, ChartType::CommitTimeScatter | ChartType::CommitsPerSecondScatter | ChartType::QueriesPerSecondScatter)
+    }
+}
+
+impl std::fmt::Display for ChartType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ChartType::PluginMetric(name) | ChartType::PluginChart(name) => write!(f, "{}", name),
+            _ => write!(f, "{}", clap::ValueEnum::to_possible_value(self).expect("Built in chart types are not skipped").get_name()),
+        }
+    }
+}
+
+// Accepts plugin chart types as well as the built in ones.
+fn parse_chart_type(text: &str) -> Result<ChartType, String> {
+    ChartType::get_from_string(text).ok_or_else(|| {
+        let mut names: Vec<String> = <ChartType as clap::ValueEnum>::value_variants().iter().map(|t| t.to_string()).collect();
+        names.extend(plugins::chart_type_names());
+        format!("unknown chart type '{}', expected one of: {}", text, names.join(", "))
+    })
+}
+
+// How datasets found in several data files, such as reruns of the same configuration, are
+// combined.
+#[derive(Debug, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum MergePolicy {
+    // The samples of every file are merged into one dataset.
+    #[default]
+    Merge,
+    // Only the samples of the last file containing the dataset are kept.
+    KeepLatest,
+    // The datasets of each file are kept apart by a `run` parameter holding the index of the file,
+    // starting from 1.
+    KeepSeparateWithRunIndex,
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+pub enum InputFormat {
+    // Comma separated values with a header line naming the columns.
+    Csv,
+    // One JSON object per line.
+    Json,
+}
+
+impl InputFormat {
+    pub fn from_path(path: &std::path::Path) -> InputFormat {
+        match compression::uncompressed_path(path).extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+            Some("json") | Some("ndjson") | Some("jsonl") => InputFormat::Json,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+pub enum OutputFormat {
+    Png,
+    Svg,
+    /// A report with a cover page, a page for each chart and a table of summary statistics.
+    Pdf,
+    /// An animation of the charts as the run progressed, see `--animate`.
+    Gif,
+}
+
+impl OutputFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Gif => "gif",
+        }
+    }
+}
+
+// How `--smooth` is drawn on metric charts. Other charts always plot the smoothed data.
+#[derive(Debug, Clone, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmoothMode {
+    // Draw the rolling mean over the faded unsmoothed mean line and error bars.
+    Overlay,
+    // Plot the smoothed data in place of the unsmoothed data.
+    Replace,
+}
+
+// How the spread of the samples around each mean is drawn on metric charts.
+#[derive(Debug, Clone, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Spread {
+    // Error bars from the smallest to the largest sample.
+    MinMax,
+    // A shaded band over the 95% confidence interval of the mean, which narrows as buckets hold
+    // more samples.
+    Ci95,
+    // Error bars over the 95% confidence interval of the mean.
+    Ci95Bars,
+    // A shaded band over the range of the samples given by `--range`.
+    RangeBand,
+    // Error bars over the range of the samples given by `--range`.
+    RangeBars,
+    // Only the mean.
+    None,
+}
+
+// The range of the samples around each mean drawn by `--spread range-band` and `range-bars`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleRange {
+    // Twice the standard deviation either side of the mean, which may reach below zero for
+    // durations with a long tail.
+    StdDev,
+    // Between two percentiles of the samples, e.g. `percentile:5-95`, which stays within them.
+    Percentile(f64, f64),
+}
+
+impl std::fmt::Display for SampleRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SampleRange::StdDev => write!(f, "stddev"),
+            SampleRange::Percentile(lower, upper) => write!(f, "percentile:{}-{}", lower, upper),
         }
     }
 }
 
+pub(crate) fn parse_sample_range(text: &str) -> Result<SampleRange, String> {
+    let text = text.trim();
+    if text == "stddev" {
+        return Ok(SampleRange::StdDev)
+    }
+    let error = || format!("invalid range '{}', expected stddev or percentile:LOWER-UPPER, e.g. percentile:5-95", text);
+    let (lower, upper) = text.strip_prefix("percentile:").and_then(|bounds| bounds.split_once('-')).ok_or_else(error)?;
+    let lower: f64 = lower.trim().parse().map_err(|_| error())?;
+    let upper: f64 = upper.trim().parse().map_err(|_| error())?;
+    match (0.0..=100.0).contains(&lower) && (0.0..=100.0).contains(&upper) && lower < upper {
+        true => Ok(SampleRange::Percentile(lower, upper)),
+        false => Err(format!("invalid range '{}', the percentiles must be between 0 and 100 with the lower one first", text)),
+    }
+}
+
+
+// Shape of the markers at each mean of metric charts and of the points of scatter charts.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MarkerShape {
+    Circle,
+    Cross,
+    Triangle,
+}
+
+// What metric charts are plotted against.
+#[derive(Debug, Clone, PartialEq, clap::ValueEnum)]
+pub enum XAxis {
+    // The number of commits at each bucket.
+    Commits,
+    // The mean time at which the samples of each bucket were taken, from the `timestamp` column.
+    Time,
+    // The mean of the samples of a metric at each bucket, such as the disk size or a column of the
+    // data files.
+    #[value(skip)]
+    Metric(ChartType),
+}
+
+impl std::fmt::Display for XAxis {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            XAxis::Metric(chart_type) => write!(f, "{}", chart_type),
+            _ => write!(f, "{}", clap::ValueEnum::to_possible_value(self).expect("Built in X axes are not skipped").get_name()),
+        }
+    }
+}
+
+// Columns of the data files are registered as metrics of the same name, see `expression`.
+fn parse_x_axis(text: &str) -> Result<XAxis, String> {
+    if let Ok(x_axis) = <XAxis as clap::ValueEnum>::from_str(text, false) {
+        return Ok(x_axis)
+    }
+    match ChartType::get_from_string(text) {
+        Some(chart_type) if chart_type.is_metric() => Ok(XAxis::Metric(chart_type)),
+        _ => Err(format!("unknown X axis '{}', expected commits, time, a metric chart type such as disk-size, or a column of the data files", text)),
+    }
+}
+
+// How a chart compares its datasets against the matching datasets of `--baseline`.
+#[derive(Debug, Clone, PartialEq, clap::ValueEnum)]
+pub enum BaselineMode {
+    // Draw the baseline means as dashed lines alongside the current ones.
+    Overlay,
+    // Plot the percentage change of the mean from the baseline at each commit count.
+    Delta,
+    // As delta, marking the commit counts where Welch's t-test finds that the means differ at 95%
+    // confidence.
+    Significance,
+}
+
+// The grid of rows and columns which charts tiled into one image are drawn in, filled row by row.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl Layout {
+    // The default grid for a number of charts: a single row of up to three charts, otherwise the
+    // smallest grid with at least as many columns as rows.
+    pub fn for_charts(num_charts: usize) -> Layout {
+        match num_charts {
+            0..=3 => Layout { rows: 1, cols: std::cmp::max(num_charts, 1) },
+            _ => {
+                let cols = (1..).find(|cols| cols * cols >= num_charts).unwrap_or(1);
+                Layout { rows: num_charts.div_ceil(cols), cols }
+            },
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.rows * self.cols
+    }
+}
+
+impl std::fmt::Display for Layout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}x{}", self.rows, self.cols)
+    }
+}
+
+fn parse_layout(text: &str) -> Result<Layout, String> {
+    let error = || format!("invalid layout '{}', expected ROWSxCOLS, e.g. 3x2", text);
+    let (rows, cols) = text.trim().split_once(['x', 'X']).ok_or_else(error)?;
+    let rows: usize = rows.trim().parse().map_err(|_| error())?;
+    let cols: usize = cols.trim().parse().map_err(|_| error())?;
+    match rows > 0 && cols > 0 {
+        true => Ok(Layout { rows, cols }),
+        false => Err(error()),
+    }
+}
+
+// Parameter added to every dataset read from a tagged data file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub value: ParameterValue,
+}
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}={}", self.name, self.value)
+    }
+}
+
+// Name of the parameter set by `--tag-by-file` and by tags without a name.
+static RUN_PARAMETER: &str = "run";
+
+fn parse_tag(text: &str) -> Result<Tag, String> {
+    let (name, value) = match text.split_once('=') {
+        Some((name, value)) => (name.trim(), value),
+        None => (RUN_PARAMETER, text),
+    };
+    match (name.is_empty(), ParameterValue::parse(value)) {
+        (false, Some(value)) => Ok(Tag { name: name.to_string(), value }),
+        _ => Err(format!("invalid tag '{}', expected a value or name=value", text)),
+    }
+}
+
 #[derive(Debug, Parser)]
 pub struct Args {
-    #[arg(short, long, required = true, num_args(0..))]
+    /// Data files to read, one per run, or directories and glob patterns such as
+    /// `results/**/*.csv` to search for them. `-` reads CSV records from standard input, or JSON
+    /// records with `--input-format json`. Gzip-compressed files, e.g. `run1.csv.gz`, and
+    /// Zstandard-compressed files, e.g. `run1.csv.zst`, are decompressed as they are read, with the
+    /// `gzip` and `zstd` features, both on by default. Required unless given in the config file.
+    #[arg(short, long, num_args(0..))]
     pub data_path: Option<Vec<PathBuf>>,
 
-    #[arg(short, long, value_enum, default_values_t = [ChartType::CommitsPerSecond, ChartType::QueriesPerSecond], num_args(0..))]
+    #[arg(short, long, value_parser = parse_chart_type, default_values_t = [ChartType::CommitsPerSecond, ChartType::QueriesPerSecond], num_args(0..))]
     pub chart_type: Vec<ChartType>,
 
-    #[arg(short, long, default_values_t = ["progressive==true, readers==0".to_string(), "progressive==true, readers>0".to_string()], num_args(0..))]
+    /// Metric computed from the columns of each row, as `NAME=EXPRESSION` or just an expression
+    /// which is then its name, e.g. `commit-rate=commits/commit_time` or
+    /// `queries/(queries+commits)`. Expressions use `+ - * /`, parentheses, numbers, the columns
+    /// of the data files and numeric parameters, and rows where they are undefined are skipped.
+    /// The name can then be given to `--chart-type`. May be repeated.
+    #[arg(long)]
+    pub metric: Vec<String>,
+
+    /// Filter of the datasets of each chart, in order, such as `readers>0, archive==true` or `readers in [0,2,8]`. `name`
+    /// filters on the test name, with `name==stress` or a regular expression such as
+    /// `name~=^stress`. Several filters separated by `;`, e.g. `readers==0; readers>0`, draw each
+    /// group on the same chart, labelled in the legend.
+    #[arg(short = 'f', long, default_values_t = ["progressive==true, readers==0".to_string(), "progressive==true, readers>0".to_string()], num_args(0..))]
     pub chart_filter: Vec<String>,
 
+    /// Transform applied to the metric of the chart at the same position before plotting, e.g.
+    /// `reciprocal`, `log` or `scale:1000`. Several transforms can be chained with commas.
+    #[arg(short = 't', long, num_args(0..))]
+    pub chart_transform: Vec<String>,
+
+    /// Y axis range of the chart at the same position as `MIN:MAX`, pinned instead of fitted to
+    /// the plotted values, e.g. `0:50000`. Either end may be left out, e.g. `:50000`, and an empty
+    /// range fits both.
+    #[arg(long, num_args(0..))]
+    pub y_range: Vec<String>,
+
+    /// Caption of the chart at the same position, in place of the title made from its chart type
+    /// and filter. An empty caption keeps the generated title.
+    #[arg(long, num_args(0..))]
+    pub chart_title: Vec<String>,
+
+    /// Metric plotted against a second Y axis on the right of the metric chart at the same
+    /// position, e.g. `queries-per-second` to compare with `commits-per-second` on one chart, or
+    /// `heap-usage` to draw the heap size beside the resident memory of a `memory-usage` chart. An
+    /// empty metric draws the chart without one.
+    #[arg(long, num_args(0..))]
+    pub secondary_metric: Vec<String>,
+
+    /// Chart given whole, as its chart type followed by any of the clauses `where FILTER`,
+    /// `title TEXT`, `transform TRANSFORM`, `y-range MIN:MAX`, `logy`, `smooth WINDOW`,
+    /// `spread SPREAD`, `range RANGE`, `trend FIT`, `y-unit UNIT` and `secondary METRIC`, e.g.
+    /// `commits-per-second where readers>0 title 'Read-heavy' logy`. Quote titles of several
+    /// words. Replaces the default chart types, and is drawn after any given with `--chart-type`.
+    /// May be repeated.
+    #[arg(long)]
+    pub chart: Vec<String>,
+
+    /// Plot metric charts against commits, or against the time of day from the `timestamp`
+    /// column of the data files, as Unix seconds or a date and time such as
+    /// `2024-03-01T12:00:00Z`. Times without an offset are local, as are the axis labels. Charts
+    /// can also be plotted against the mean of a metric at each commit count, such as
+    /// `disk-size`, `commit-time` for the time elapsed or a `--metric`, or of any numeric column
+    /// of the data files, e.g. `bytes_written`, with the commit buckets regrouped into equal bins
+    /// along the X axis so that points are joined in order of X.
+    /// Annotations and baseline overlays, which are tied to commits, are left out of charts
+    /// against anything else, and `--baseline-mode delta` charts stay against commits.
+    #[arg(long, value_parser = parse_x_axis, default_value_t = XAxis::Commits)]
+    pub x_axis: XAxis,
+
+    /// Synthetic series plotted as the difference or ratio of the datasets matched by two filters,
+    /// paired by their other parameters, e.g. `compress==true - compress==false`.
+    #[arg(long, num_args(0..))]
+    pub derived_series: Vec<String>,
+
+    /// Data files, directories or glob patterns of a baseline run to compare against, e.g. from
+    /// another branch. Datasets are matched by name and parameters.
+    #[arg(long, num_args(0..))]
+    pub baseline: Vec<PathBuf>,
+
+    /// How metric charts are compared against the baseline.
+    #[arg(long, value_enum, default_value_t = BaselineMode::Overlay)]
+    pub baseline_mode: BaselineMode,
+
+    /// Tag the datasets of each `--data-path` entry, in order, with a parameter given as
+    /// `name=value`, or as a value of the `run` parameter. Tags apply to every file found in a
+    /// directory or by a pattern, so that filters and legends can tell apart identical
+    /// configurations run on different branches or machines.
+    #[arg(long, value_parser = parse_tag, num_args(0..))]
+    pub tag: Vec<Tag>,
+
+    /// Tag the datasets of each data file with its file name as the `run` parameter.
+    #[arg(long, default_value_t = false)]
+    pub tag_by_file: bool,
+
+    /// Read the machine each data file was produced on from the file next to it with the same
+    /// name and a `.machine.toml` or `.machine.json` extension, with fields such as `hostname`,
+    /// `cpu`, `disk`, `kernel` and `parity-db-version`. They are listed below the charts and added
+    /// as parameters prefixed with `machine-`, e.g. `machine-hostname==bench-1`.
+    #[arg(long, default_value_t = false)]
+    pub machine_metadata: bool,
+
+    /// How datasets with the same parameters in several data files, such as reruns, are combined:
+    /// merged into one dataset, only kept from the last file containing them, or kept apart by a
+    /// `run` parameter holding the index of their file.
+    #[arg(long, value_enum, default_value_t = MergePolicy::Merge)]
+    pub merge_policy: MergePolicy,
+
+    /// Format of the data files. Inferred from the extension of each file when not given, with
+    /// `.json`, `.ndjson` and `.jsonl` files read as JSON.
+    #[arg(long, value_enum)]
+    pub input_format: Option<InputFormat>,
+
+    /// TOML or YAML file describing the columns of CSV data files, with the name, role and type of
+    /// each, for versions of the stress test whose columns differ from the usual ones. Without it
+    /// the columns are found by their usual names in the header line.
+    #[arg(long)]
+    pub schema: Option<PathBuf>,
+
+    /// Stop at the first malformed line of a data file instead of reporting and skipping it, and
+    /// at a chart whose filter matches no datasets instead of drawing it empty.
+    #[arg(long, default_value_t = false)]
+    pub strict: bool,
+
+    /// Keep the data parsed from each data file in `visualizer_output/cache` in the current
+    /// directory, and on later runs only parse the lines appended to the file since. A file which
+    /// was rewritten rather than appended to, or whose stress test config changed, is parsed again.
+    #[arg(long, default_value_t = false, conflicts_with = "low_memory")]
+    pub cache: bool,
+
+    /// Keep only the count, mean, variance, minimum and maximum of the samples of each metric at
+    /// each commit count, with a t-digest estimating their percentiles, rather than every sample.
+    /// Data files are read, parsed and added one chunk of lines at a time, so that only the
+    /// records of one chunk are held. Bounds the memory used by very long runs. Non-finite samples are left out, and charts of
+    /// the raw samples, such as scatter charts, and `--drop-outliers` are not available.
+    #[arg(long, default_value_t = false)]
+    pub low_memory: bool,
+
     #[arg(short, long, default_value_t = false)]
     pub small_image: bool,
+
+    /// Write each chart to its own file, named after the chart type and filter, instead of tiling
+    /// them into one image.
+    #[arg(long, default_value_t = false)]
+    pub separate_files: bool,
+
+    /// Grid the charts are tiled into as `ROWSxCOLS`, e.g. `3x2`. Defaults to a single row for up
+    /// to three charts and a near square grid for more.
+    #[arg(long, value_parser = parse_layout)]
+    pub layout: Option<Layout>,
+
+    /// Colours of the datasets: `default`, `category10`, the colour blind safe `okabe-ito` and
+    /// `viridis`, or a list of hex colours such as `#1f77b4,#ff7f0e`.
+    #[arg(long, value_parser = Palette::parse)]
+    pub palette: Option<Palette>,
+
+    /// Colours of the background, grid and text: `light`, `dark` or a TOML theme file with
+    /// `background`, `text` and `grid` hex colours, a `palette` and a `base` theme for the rest.
+    /// The theme's palette is used unless `--palette` is given.
+    #[arg(long)]
+    pub theme: Option<String>,
+
+    /// Font family of the text of the charts, such as `serif` or the name of an installed font.
+    #[arg(long, default_value = "sans-serif")]
+    pub font_family: String,
+
+    /// Size of the chart titles, as a percentage of the chart height.
+    #[arg(long, default_value_t = 3.0)]
+    pub title_font_size: f64,
+
+    /// Size of the axis descriptions, such as `Commits`, as a percentage of the chart height.
+    #[arg(long, default_value_t = 2.0)]
+    pub axis_label_font_size: f64,
+
+    /// Size of the values along the axes, as a percentage of the chart height. Raise it for small
+    /// images embedded in documents, where the default is hard to read.
+    #[arg(long, default_value_t = 2.0)]
+    pub tick_label_font_size: f64,
+
+    /// Size of the legend entries, as a percentage of the chart height.
+    #[arg(long, default_value_t = 2.0)]
+    pub legend_font_size: f64,
+
+    /// Colour datasets by the value of this parameter instead of by their sort order, so that a
+    /// configuration gets the same colour whichever other datasets are loaded.
+    #[arg(long)]
+    pub color_by: Option<String>,
+
+    /// Draw the lines of datasets solid, dashed or dotted by the value of this parameter, e.g.
+    /// `compress` to draw compressed runs dashed. Without it lines get the next pattern each time
+    /// the colours of the palette run out and repeat.
+    #[arg(long)]
+    pub style_by: Option<String>,
+
+    /// Draw for readers with colour blindness: the Okabe-Ito palette unless `--palette` is given,
+    /// thicker lines and a different marker shape for each dataset.
+    #[arg(long, default_value_t = false)]
+    pub accessible: bool,
+
+    /// Divide the metric values of every dataset by the mean of the reference dataset matched by
+    /// this filter at the same commit count, e.g. `db=paritydb`, so that the reference is drawn at
+    /// 1.0. When the filter matches several datasets, each dataset is compared to the one with the
+    /// same values for the other parameters. Only supported by metric charts.
+    #[arg(long)]
+    pub normalize_to: Option<String>,
+
+    /// Drop the samples of each metric which lie further than this many standard deviations from
+    /// the mean of their commit bucket, or with `iqr` or `iqr:K` beyond K (1.5 by default) times
+    /// the interquartile range outside the quartiles, before anything is computed from them. Only
+    /// buckets with at least three samples are filtered, and cumulative commit times are kept. No
+    /// sample of a bucket of N samples lies more than (N-1)/sqrt(N) standard deviations from its
+    /// mean, so `iqr` suits buckets of only a few runs.
+    #[arg(long, value_parser = OutlierFilter::parse)]
+    pub drop_outliers: Option<OutlierFilter>,
+
+    /// Split each chart into one chart per value of this parameter among its datasets, e.g.
+    /// `readers`, tiled in order of the values.
+    #[arg(long)]
+    pub group_by: Option<String>,
+
+    /// Draw the chart once for each combination of the values of two parameters, in a grid with a
+    /// row per value of one and a column per value of the other, e.g. `rows=readers cols=writers`.
+    /// Either may be left out for a single column or row. The cells share their Y axis, and the
+    /// grid replaces `--layout`. Only a single chart type can be faceted.
+    #[arg(long, num_args = 1..=2, value_name = "AXIS=PARAMETER", conflicts_with = "group_by")]
+    pub facet: Vec<String>,
+
+    /// Combine the datasets which differ only in this parameter into one, e.g. `uniform`, pooling
+    /// their samples. Can be given several times.
+    #[arg(long)]
+    pub aggregate: Vec<String>,
+
+    /// Only chart the datasets whose names contain this text, such as `readers=4` or the whole
+    /// name listed when the data files are read, e.g. `stress (progressive readers=4 writers=1)`.
+    /// May be repeated to keep the datasets matching any of them.
+    #[arg(long)]
+    pub include_dataset: Vec<String>,
+
+    /// Leave out the datasets whose names contain this text from every chart, e.g. to drop a
+    /// misconfigured run. Applies after `--include-dataset`. May be repeated.
+    #[arg(long)]
+    pub exclude_dataset: Vec<String>,
+
+    /// Order the datasets in the legend of each chart by a parameter, or by the mean of a metric at
+    /// the last commit count, e.g. `commits-per-second:desc`. Datasets are otherwise in name
+    /// order, with numbers in numeric order.
+    #[arg(long, value_parser = SortOrder::parse)]
+    pub sort_by: Option<SortOrder>,
+
+    /// Title drawn above all of the charts of the image, or on the cover of a PDF report.
+    #[arg(long)]
+    pub title: Option<String>,
+
+    /// Line of text drawn below the charts, such as where and when they were made. `${date}`,
+    /// `${hostname}` and `${git-commit}` are replaced by the current UTC date, the name of the
+    /// machine and the git commit checked out in the current directory.
+    #[arg(long)]
+    pub footer: Option<String>,
+
+    /// Format of the output file, an image or a PDF report. Inferred from the extension of
+    /// `--output` when not given.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Png)]
+    pub output_format: OutputFormat,
+
+    /// Output file path. Missing directories are created. Defaults to
+    /// `visualizer_output/stress_test_charts.<format>` in the current directory.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Write an animated GIF of the charts as the run progressed, adding this many commit buckets
+    /// in each frame, e.g. to show how throughput changes as the database fills. Implies
+    /// `--output-format gif`, which otherwise adds 10 buckets in each frame.
+    #[arg(long)]
+    pub animate: Option<usize>,
+
+    /// Milliseconds each frame of an animation is shown for.
+    #[arg(long, default_value_t = 200)]
+    pub frame_delay: u32,
+
+    /// Write summary statistics of each dataset and metric to this file, as CSV or as JSON if it
+    /// has a `.json` extension.
+    #[arg(long)]
+    pub stats_out: Option<PathBuf>,
+
+    /// Write the points of each plotted series to a file in this directory, with the mean at each
+    /// commit count and, for the means of metric charts, the smallest and largest sample and the
+    /// range of twice the standard deviation around the mean. Missing directories are created.
+    #[arg(long)]
+    pub export_points: Option<PathBuf>,
+
+    /// Format of the files written by `--export-points`.
+    #[arg(long, value_enum, default_value_t = PointsFormat::Csv)]
+    pub export_points_format: PointsFormat,
+
+    /// Only include the final N commit buckets of each dataset in the summary statistics.
+    #[arg(long)]
+    pub stats_buckets: Option<usize>,
+
+    /// Load and analyse the data and print a summary of each chart without drawing anything.
+    #[arg(long, default_value_t = false)]
+    pub no_render: bool,
+
+    /// Print the parameters which vary across the datasets with the values each takes, then every
+    /// dataset with its parameters, number of samples, range of commit buckets and number of runs,
+    /// without drawing anything. Useful for choosing `--filter`s.
+    #[arg(long, default_value_t = false)]
+    pub describe: bool,
+
+    /// Compare the mean commits and queries per second of each dataset against its `--baseline`
+    /// dataset instead of drawing charts, printing each comparison as a line of JSON. Exits with
+    /// code 2 if any mean dropped by more than `--check-threshold`.
+    #[arg(long, default_value_t = false)]
+    pub check: bool,
+
+    /// Compare two runs of the same configuration, the old and then the new data files. Prints how
+    /// many commit buckets of each dataset and metric differ significantly by Welch's t-test, and
+    /// draws metric charts as the change from the old run with those buckets marked. Short for
+    /// `--data-path NEW --baseline OLD --baseline-mode significance`.
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"], conflicts_with_all = ["data_path", "baseline"])]
+    pub compare: Option<Vec<PathBuf>>,
+
+    /// Largest drop in mean throughput from the baseline, in percent, accepted by `--check`.
+    #[arg(long, default_value_t = 5.0)]
+    pub check_threshold: f64,
+
+    /// Render the charts in memory and compare them against this golden file instead of writing
+    /// an output file: a PNG image, compared within a small tolerance, or with any other extension
+    /// a file holding the size and SHA-256 hash of the exact pixels. Fails if they differ, writing
+    /// the actual image next to it with an `.actual.png` extension.
+    #[arg(long)]
+    pub golden: Option<PathBuf>,
+
+    /// Write the charts as the new `--golden` file instead of comparing against it.
+    #[arg(long, default_value_t = false, requires = "golden")]
+    pub bless: bool,
+
+    /// Draw the charts as Unicode plots on the terminal instead of writing an image file. Plots
+    /// are as wide as the `COLUMNS` environment variable, or 100 characters.
+    #[arg(long, default_value_t = false)]
+    pub terminal: bool,
+
+    /// List the parameters found in the data and prompt for the charts to draw.
+    #[arg(short, long, default_value_t = false)]
+    pub interactive: bool,
+
+    /// Open a window exploring the charts instead of writing an image file, in which datasets are
+    /// shown or hidden and the chart type, filter and range of commits drawn can be changed. Starts
+    /// from the first chart given. Needs the `gui` feature.
+    #[arg(long, default_value_t = false)]
+    pub gui: bool,
+
+    /// Serve the latest statistics of each dataset as Prometheus metrics at `/metrics` on this
+    /// address, e.g. `0.0.0.0:9100`, instead of drawing charts. Data files are read again when
+    /// they have changed since the previous scrape. Statistics cover the final `--stats-buckets`
+    /// commit buckets of each dataset, or only the latest one.
+    #[arg(long)]
+    pub serve_metrics: Option<String>,
+
+    /// Serve the charts over HTTP on this address, e.g. `0.0.0.0:8080`, instead of writing an
+    /// image file. The page at `/` picks the chart types and filters, starting from those given
+    /// here, and reloads the charts every 10 seconds. Data files are read again when they have
+    /// changed.
+    #[arg(long)]
+    pub serve: Option<String>,
+
+    /// Read records streamed by running stress tests over a socket, on a TCP address such as
+    /// `0.0.0.0:9000` or the path of a Unix socket, instead of from data files. Each connection
+    /// is a run, sending the lines of a data file as they are written: a CSV header line and then
+    /// the records, or JSON lines with `--input-format json`. The output file is drawn again
+    /// every `--flush-interval` seconds while records arrive.
+    #[arg(long, conflicts_with_all = ["data_path", "compare", "serve", "serve_metrics"])]
+    pub listen: Option<String>,
+
+    /// Seconds between redrawing the output file with `--listen`.
+    #[arg(long, default_value_t = 5.0)]
+    pub flush_interval: f64,
+
+    /// File of events to mark on every chart plotted against commits, as CSV lines of
+    /// `commits,label` or, with a `.json` extension, an array of `{"commits": ..., "label": ...}`.
+    #[arg(long)]
+    pub annotations: Option<PathBuf>,
+
+    /// Horizontal line at a target value on every chart of a metric, as `CHART_TYPE=VALUE` with an
+    /// optional `:LABEL`, e.g. `commits-per-second=20000:SLO`. Left off charts whose values are
+    /// transformed or normalized. May be repeated.
+    #[arg(long)]
+    pub hline: Vec<String>,
+
+    /// Report and annotate commit buckets whose mean sample commit time exceeds this multiple of the
+    /// local median.
+    #[arg(long)]
+    pub stall_threshold: Option<f64>,
+
+    /// Number of commit buckets around each bucket used to compute the local median for stall
+    /// detection. Each bucket holds the samples of every run at one commit count.
+    #[arg(long, default_value_t = 20)]
+    pub stall_window: usize,
+
+    /// Number of bins used by histogram charts.
+    #[arg(long, default_value_t = 50)]
+    pub histogram_bins: usize,
+
+    /// Percentile plotted by rolling percentile charts.
+    #[arg(long, default_value_t = 99.0)]
+    pub rolling_percentile: f64,
+
+    /// Number of samples in the sliding window used by rolling percentile charts.
+    #[arg(long, default_value_t = 50)]
+    pub rolling_window: usize,
+
+    /// Number of samples around each sample used to estimate within-run variance.
+    #[arg(long, default_value_t = 10)]
+    pub variance_window: usize,
+
+    /// Number of final commit buckets of each dataset averaged by final throughput bar charts.
+    #[arg(long, default_value_t = 10)]
+    pub final_buckets: usize,
+
+    /// Use a logarithmic Y axis for the charts at these positions, starting from 1, or for every
+    /// metric chart when no positions are given.
+    #[arg(long, num_args(0..))]
+    pub log_y: Option<Vec<usize>>,
+
+    /// Smooth charts with a rolling mean over this many commit buckets, centred on each bucket.
+    /// Applies to every chart type apart from histograms and rolling percentiles.
+    #[arg(long)]
+    pub smooth: Option<usize>,
+
+    /// Whether the rolling mean of metric charts is drawn over the unsmoothed series or replaces
+    /// it.
+    #[arg(long, value_enum, default_value_t = SmoothMode::Overlay)]
+    pub smooth_mode: SmoothMode,
+
+    /// How the spread of the samples at each commit count is drawn on metric charts: error bars
+    /// from the minimum to the maximum, a band or error bars over the 95% confidence interval of
+    /// the mean or over `--range`, or not at all.
+    #[arg(long, value_enum, default_value_t = Spread::MinMax)]
+    pub spread: Spread,
+
+    /// Range of the samples at each commit count drawn by `--spread range-band` and `range-bars`:
+    /// twice the standard deviation either side of the mean, `stddev`, or between two percentiles
+    /// of the samples, e.g. `percentile:5-95`.
+    #[arg(long, value_parser = parse_sample_range, default_value_t = SampleRange::StdDev)]
+    pub range: SampleRange,
+
+    /// How the mean lines of metric charts are drawn across missing commit buckets, such as from a
+    /// crashed or restarted run: broken at the gap, connected across it, or connected with the gap
+    /// shaded.
+    #[arg(long, value_enum, default_value_t = GapMode::Break)]
+    pub gaps: GapMode,
+
+    /// Shape of the markers at each mean of metric charts and of the points of scatter charts.
+    #[arg(long, value_enum, default_value_t = MarkerShape::Circle)]
+    pub marker_shape: MarkerShape,
+
+    /// Size of the markers in pixels, with the caps of error bars sized to match. By default they
+    /// are sized by the height of each chart.
+    #[arg(long)]
+    pub marker_size: Option<u32>,
+
+    /// Fit a trend line over the mean points of each series of metric charts, with its slope in
+    /// the legend: `linear` per million commits, or per hour against time, and `log-linear` per
+    /// tenfold increase in commits.
+    #[arg(long, value_enum)]
+    pub trend: Option<TrendFit>,
+
+    /// Merge neighbouring commit buckets of datasets with more than this many, so that dense
+    /// series are quicker to draw and their markers and error bars stay apart. 0 draws every
+    /// bucket. Applies to the chart types which can be smoothed.
+    #[arg(long, default_value_t = 2000)]
+    pub max_points_per_series: usize,
+
+    /// Unit of the Y axis labels of throughput charts, `k` or `M`, and of commit time charts,
+    /// `us`, `ms` or `s`. By default each chart picks the unit fitting its largest value, as it
+    /// does for charts to which the given unit does not apply. `none` labels plain values.
+    #[arg(long, value_enum, default_value_t = YUnit::Auto)]
+    pub y_unit: YUnit,
+
+    /// Scatter the raw samples behind the mean line.
+    #[arg(long, default_value_t = false)]
+    pub raw_samples: bool,
+
+    /// Horizontal jitter applied to raw samples, as a fraction of the spacing between samples.
+    #[arg(long, default_value_t = 0.0)]
+    pub jitter: f64,
+
+    /// TOML or YAML configuration file defining charts, chart templates, parameter labels and
+    /// defaults for the data, baseline, output, annotations, layout, theme, font, colour and grouping options.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Chart template from the config file to add, with optional substitutions for its
+    /// `${name}` placeholders, e.g. `readers-scaling:writers=4,readers=8`.
+    #[arg(long, num_args(0..))]
+    pub template: Vec<String>,
+
+    /// Built-in set of charts to add: `throughput-overview` for commits and queries per second
+    /// with the final throughput and its variance between runs, `latency-deep-dive` for commit
+    /// times and query latency, or `readers-scaling` comparing the numbers of readers. A single
+    /// preset is drawn in its own layout unless `--layout` is given.
+    #[arg(long, value_enum)]
+    pub preset: Vec<Preset>,
+
+    /// Also report the files, lines and datasets read and the charts drawn, with the time taken
+    /// by each phase.
+    #[arg(short, long, default_value_t = false, conflicts_with = "quiet")]
+    pub verbose: bool,
+
+    /// Only report warnings, such as skipped malformed lines.
+    #[arg(short, long, default_value_t = false)]
+    pub quiet: bool,
+}
+
+// Per-chart statistics and styling. Defaults come from the command line and can be overridden by
+// chart templates.
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    pub stall_threshold: Option<f64>,
+    pub stall_window: usize,
+    pub histogram_bins: usize,
+    pub rolling_percentile: f64,
+    pub rolling_window: usize,
+    pub variance_window: usize,
+    pub final_buckets: usize,
+    pub raw_samples: bool,
+    pub jitter: f64,
+    pub log_y: bool,
+    pub smooth: Option<usize>,
+    pub smooth_mode: SmoothMode,
+    pub spread: Spread,
+    pub range: SampleRange,
+    pub gaps: GapMode,
+    pub marker_shape: MarkerShape,
+    pub marker_size: Option<u32>,
+    pub trend: Option<TrendFit>,
+    pub y_unit: YUnit,
+}
+
+impl ChartOptions {
+    pub fn new(args: &Args) -> ChartOptions {
+        ChartOptions {
+            stall_threshold: args.stall_threshold,
+            stall_window: args.stall_window,
+            histogram_bins: args.histogram_bins,
+            rolling_percentile: args.rolling_percentile,
+            rolling_window: args.rolling_window,
+            variance_window: args.variance_window,
+            final_buckets: args.final_buckets,
+            raw_samples: args.raw_samples,
+            jitter: args.jitter,
+            log_y: args.log_y.as_ref().is_some_and(|positions| positions.is_empty()),
+            smooth: args.smooth,
+            smooth_mode: args.smooth_mode.clone(),
+            spread: args.spread.clone(),
+            range: args.range,
+            gaps: args.gaps,
+            marker_shape: args.marker_shape,
+            marker_size: args.marker_size,
+            trend: args.trend,
+            y_unit: args.y_unit,
+        }
+    }
+
+    pub fn stall_detection(&self) -> Option<StallDetection> {
+        self.stall_threshold.map(|threshold| StallDetection { threshold, window: self.stall_window })
+    }
+}
+
+// The same defaults as the command line.
+impl Default for ChartOptions {
+    fn default() -> ChartOptions {
+        ChartOptions {
+            stall_threshold: None,
+            stall_window: 20,
+            histogram_bins: 50,
+            rolling_percentile: 99.0,
+            rolling_window: 50,
+            variance_window: 10,
+            final_buckets: 10,
+            raw_samples: false,
+            jitter: 0.0,
+            log_y: false,
+            smooth: None,
+            smooth_mode: SmoothMode::Overlay,
+            spread: Spread::MinMax,
+            range: SampleRange::StdDev,
+            gaps: GapMode::Break,
+            marker_shape: MarkerShape::Circle,
+            marker_size: None,
+            trend: None,
+            y_unit: YUnit::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChartSpec {
+    pub chart_type: ChartType,
+    pub filters: ParameterFilterSet,
+    pub title: Option<String>,
+    pub transform: Transform,
+    pub y_range: AxisRange,
+    /// Range of the X axis of charts along commits or `--x-axis`, fitted to the data unless pinned,
+    /// as when zooming in with `--gui`.
+    pub x_range: AxisRange,
+    /// Metric plotted against a second Y axis on the right of a metric chart.
+    pub secondary: Option<ChartType>,
+    pub options: ChartOptions,
+}
+
+impl ChartSpec {
+    /// A chart of the datasets passing the filters, with the default title, transform and options.
+    pub fn new(chart_type: ChartType, filters: ParameterFilterSet) -> ChartSpec {
+        ChartSpec { chart_type, filters, title: None, transform: Transform::default(), y_range: AxisRange::default(), x_range: AxisRange::default(), secondary: None, options: ChartOptions::default() }
+    }
+
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if !self.transform.is_identity() && !self.chart_type.is_metric() {
+            return Err(format!("Transform '{}' is not supported by {:?} charts", self.transform, self.chart_type).into())
+        }
+        if self.options.log_y && !self.chart_type.is_metric() {
+            return Err(format!("Logarithmic Y axis is not supported by {:?} charts", self.chart_type).into())
+        }
+        if self.options.smooth.is_some() && !self.chart_type.supports_smoothing() {
+            return Err(format!("Smoothing is not supported by {:?} charts", self.chart_type).into())
+        }
+        if self.options.trend.is_some() && !self.chart_type.is_metric() {
+            return Err(format!("Trend lines are not supported by {:?} charts", self.chart_type).into())
+        }
+        if self.options.smooth == Some(0) {
+            return Err("Smoothing window must be at least one bucket".into())
+        }
+        if let Some(secondary) = &self.secondary {
+            if !self.chart_type.is_metric() || !secondary.is_metric() {
+                return Err(format!("A secondary Y axis of {} is only supported by metric charts of metrics, not {}", secondary, self.chart_type).into())
+            }
+            if self.options.log_y {
+                return Err("Logarithmic Y axis is not supported by charts with a secondary Y axis".into())
+            }
+        }
+        if self.options.log_y && self.y_range.min.or(self.y_range.max).is_some_and(|v| v <= 0.0) {
+            return Err(format!("Y range '{}' of a logarithmic Y axis must be positive", self.y_range).into())
+        }
+        Ok(())
+    }
+
+    pub fn get_title(&self, labels: &ParameterLabels) -> String {
+        if let Some(custom_title) = &self.title {
+            return custom_title.clone()
+        }
+
+        let mut title = self.chart_type.title();
+        if let Some(secondary) = &self.secondary {
+            title += &format!(" and {}", secondary.title());
+        }
+
+        let filter_text = self.filters.display_text(labels);
+        if !filter_text.is_empty() {
+            title += " (";
+            title += &filter_text;
+            title += ")";
+        }
+
+        if !self.transform.is_identity() {
+            title += &format!(" [{}]", self.transform);
+        }
+
+        title
+    }
+}
+
+/// Charts to draw and how to draw them.
+#[derive(Debug)]
+pub struct Params {
+    pub stroke_width: u64,
+    /// Width and height in pixels of each chart in the grid.
+    pub chart_size: u32,
+    pub chart_specs: Vec<ChartSpec>,
+    pub stall_detection: Option<StallDetection>,
+    pub parameter_labels: ParameterLabels,
+    pub derived_series: Vec<DerivedSeries>,
+    pub baseline_mode: BaselineMode,
+    pub layout: Option<Layout>,
+    pub palette: Palette,
+    /// Parameter whose value selects the colour of each dataset.
+    pub colour_by: Option<String>,
+    /// Parameter whose value selects the dash pattern of the lines of each dataset.
+    pub style_by: Option<String>,
+    /// Give each dataset its own marker shape, for readers who cannot tell the colours apart.
+    pub accessible: bool,
+    /// Order of the datasets in the legend, which are otherwise in name order.
+    pub sort_by: Option<SortOrder>,
+    /// Events drawn as vertical lines on charts plotted against commits.
+    pub annotations: Vec<Annotation>,
+    /// Horizontal lines at target values of metrics.
+    pub reference_lines: Vec<ReferenceLine>,
+    pub theme: Theme,
+    pub fonts: Fonts,
+    /// Filter matching the reference datasets the values were divided by.
+    pub normalize_to: Option<ParameterFilterSet>,
+    /// Title of the whole image.
+    pub title: Option<String>,
+    /// Footer below the charts, with its placeholders filled in.
+    pub footer: Option<String>,
+    /// Lines describing the machines the data was produced on, drawn below the charts.
+    pub machine_metadata: Vec<String>,
+    pub x_axis: XAxis,
+    /// Number of commit buckets above which datasets are downsampled when drawn.
+    pub max_points_per_series: Option<usize>,
+}
+
+impl Params {
+    /// Full size images of the charts, with nothing else enabled.
+    pub fn new(chart_specs: Vec<ChartSpec>) -> Params {
+        Params {
+            stroke_width: 2,
+            chart_size: 2160,
+            chart_specs,
+            stall_detection: None,
+            parameter_labels: Default::default(),
+            derived_series: Default::default(),
+            baseline_mode: BaselineMode::Overlay,
+            layout: None,
+            palette: Palette::default(),
+            colour_by: None,
+            style_by: None,
+            accessible: false,
+            sort_by: None,
+            annotations: Default::default(),
+            reference_lines: Default::default(),
+            theme: Theme::light(),
+            fonts: Fonts::default(),
+            normalize_to: None,
+            title: None,
+            footer: None,
+            machine_metadata: Default::default(),
+            x_axis: XAxis::Commits,
+            max_points_per_series: None,
+        }
+    }
+
+    /// The title of a chart, noting the reference datasets when the values are normalized.
+    pub fn get_chart_title(&self, spec: &ChartSpec) -> String {
+        let title = spec.get_title(&self.parameter_labels);
+        match &self.normalize_to {
+            Some(filters) => format!("{} relative to {}", title, filters.display_text(&self.parameter_labels)),
+            None => title,
+        }
+    }
+
+    // The grid for the given charts. An explicit `--layout` only applies to images holding several
+    // charts, so that it is ignored for `--separate-files`.
+    pub fn get_layout(&self, num_charts: usize) -> Result<Layout, Box<dyn Error>> {
+        match self.layout {
+            Some(layout) if num_charts > 1 => match layout.capacity() >= num_charts {
+                true => Ok(layout),
+                false => Err(format!("Layout {} has room for {} charts but {} were given", layout, layout.capacity(), num_charts).into()),
+            },
+            _ => Ok(Layout::for_charts(num_charts)),
+        }
+    }
+
+    // The dataset as drawn on a chart of the type, when it has too many buckets to draw each one or
+    // metric charts are plotted against a metric rather than commits. Percentage changes from a
+    // baseline are computed bucket by bucket, so are not downsampled.
+    pub fn get_downsampled(&self, dataset: &DataSet, chart_type: &ChartType) -> Option<DataSet> {
+        if let (XAxis::Metric(_), true, BaselineMode::Overlay) = (&self.x_axis, chart_type.is_metric(), &self.baseline_mode) {
+            return Some(dataset.rebucketed(&self.x_axis, self.max_points_per_series))
+        }
+        match (self.max_points_per_series, &self.baseline_mode) {
+            (Some(max_points), BaselineMode::Overlay) if chart_type.supports_smoothing() => dataset.downsampled(max_points),
+            _ => None,
+        }
+    }
+
+    pub fn get_image_size(&self, layout: &Layout) -> (u32, u32) {
+        (self.chart_size * layout.cols as u32, self.chart_size * layout.rows as u32)
+    }
+}
+
+/// Runs the command line tool with the arguments of the process.
+pub fn run_visualizer() -> Result<(), Box<dyn Error>> {
+    let arguments: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    expression::register_metrics(&arguments)?;
+    let matches = Args::command().get_matches_from(arguments);
+    run(&matches)
+}
+
+/// Runs the command line tool with the given arguments, starting with the program name. Unlike
+/// [`run_visualizer`], invalid arguments and `--help` are returned as errors rather than exiting.
+pub fn run_visualizer_with_args<I, T>(arguments: I) -> Result<(), Box<dyn Error>> where I: IntoIterator<Item = T>, T: Into<std::ffi::OsString> + Clone {
+    let arguments: Vec<std::ffi::OsString> = arguments.into_iter().map(Into::into).collect();
+    expression::register_metrics(&arguments)?;
+    let matches = Args::command().try_get_matches_from(arguments)?;
+    run(&matches)
+}
+
+fn run(matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let mut args = Args::from_arg_matches(matches)?;
+    logging::init(args.verbose, args.quiet);
+
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    config.apply(&mut args, matches)?;
+    apply_compare(&mut args);
+    match (&args.data_path, &args.listen) {
+        (None, None) => return Err("No data files given, with --data-path or in the config file".into()),
+        (Some(_), Some(_)) => return Err("--listen reads records from a socket, so cannot be used with data files from the config file".into()),
+        _ => {},
+    }
+    if args.gui && !cfg!(feature = "gui") {
+        return Err("--gui needs the `gui` feature, build with `--features gui`".into())
+    }
+    if args.check && args.baseline.is_empty() {
+        return Err("--check needs a baseline to compare against, with --baseline or in the config file".into())
+    }
+    if args.tag_by_file && args.merge_policy == MergePolicy::KeepSeparateWithRunIndex {
+        return Err("--tag-by-file and --merge-policy keep-separate-with-run-index both set the run parameter, use only one of them".into())
+    }
+    resolve_data_paths(&mut args)?;
+
+    if let Some(address) = &args.serve_metrics {
+        return exporter::serve_metrics(address, &args)
+    }
+
+    if let Some(address) = &args.serve {
+        return dashboard::serve(address, &args, matches, &config)
+    }
+
+    if let Some(address) = &args.listen {
+        let params = build_params(&args, matches, &config)?;
+        let (output_path, output_format) = get_output_path(&args, matches.value_source("output_format") == Some(ValueSource::CommandLine));
+        if let Some(parent) = output_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create output directory {}: {}", parent.display(), e))?;
+        }
+        return listen::listen(address, &args, &params, &output_path, &output_format)
+    }
+
+    let (params, mut data) = prepare_data(&args, matches, &config)?;
+
+    if let Some(data_value) = &data {
+        if let Some(stats_path) = &args.stats_out {
+            summary::write_summary(data_value, stats_path, args.stats_buckets)?;
+            log::info!("Wrote file: {}", stats_path.display());
+        }
+
+        if let Some(directory) = &args.export_points {
+            points::export_points(data_value, &params, directory, args.export_points_format)?;
+            log::info!("Wrote plotted points to {}", directory.display());
+        }
+    }
+
+    if args.describe {
+        if let Some(data_value) = &data {
+            describe::print_description(data_value, &params);
+        }
+        return Ok(())
+    }
+
+    if let (Some(_), Some(data_value)) = (&args.compare, &data) {
+        compare::print_report(data_value)?;
+    }
+
+    if args.check {
+        if let Some(data_value) = &data {
+            return check::check_regressions(data_value, args.check_threshold)
+        }
+    }
+
+    if let (Some(golden_path), Some(data_value)) = (&args.golden, &data) {
+        return testing::check_golden(data_value, &params, golden_path, args.bless)
+    }
+
+    if args.terminal {
+        if let Some(data_value) = &data {
+            return terminal::print_charts(data_value, &params)
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    if args.gui {
+        if let Some(data_value) = data {
+            return gui::show(data_value, params)
+        }
+    }
+
+    if args.no_render {
+        if let Some(data_value) = &data {
+            print_summary(data_value, &params);
+        }
+        return Ok(())
+    }
+
+    let explicit_output_format = matches.value_source("output_format") == Some(ValueSource::CommandLine);
+    let (mut output_path, mut output_format) = get_output_path(&args, explicit_output_format);
+    if args.animate.is_some() && !matches!(output_format, OutputFormat::Gif) {
+        if explicit_output_format {
+            return Err("--animate writes a GIF and is not supported with other output formats".into())
+        }
+        output_path.set_extension(OutputFormat::Gif.extension());
+        output_format = OutputFormat::Gif;
+    }
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create output directory {}: {}", parent.display(), e))?;
+    }
+
+    let data_paths = args.data_path.clone().unwrap_or_default();
+    let provenance = Provenance::new(args.config.as_ref(), &data_paths)?;
+
+    let outputs: Vec<(PathBuf, &[ChartSpec])> = match args.separate_files {
+        false => vec![(output_path, &params.chart_specs)],
+        true => params.chart_specs.iter().enumerate().map(|(i, spec)| (get_chart_file_path(&output_path, i, spec), std::slice::from_ref(spec))).collect(),
+    };
+
+    for (path, chart_specs) in outputs {
+        let draw_start = std::time::Instant::now();
+        match output_format {
+            OutputFormat::Gif => animation::write_animation(&path, data.as_mut(), &params, chart_specs, args.animate.unwrap_or(animation::DEFAULT_FRAME_BUCKETS), args.frame_delay)?,
+            _ => draw_to_file(&path, &output_format, data.as_ref(), &params, chart_specs)?,
+        }
+        log::debug!("Drew {} {} in {:.3}s", chart_specs.len(), if chart_specs.len() == 1 { "chart" } else { "charts" }, draw_start.elapsed().as_secs_f64());
+
+        match output_format {
+            OutputFormat::Png => provenance.write_png_text(&path)?,
+            OutputFormat::Svg => provenance.write_svg_metadata(&path)?,
+            OutputFormat::Pdf => provenance.write_pdf_info(&path)?,
+            // GIF comments are not read by image viewers, so are not worth writing.
+            OutputFormat::Gif => {},
+        }
+
+        log::info!("Wrote file: {}", path.display());
+    }
+    log::debug!("Finished in {:.3}s", logging::get_elapsed().as_secs_f64());
+
+    Ok(())
+}
+
+// The params and the data files read as they are charted: the charts picked with `--interactive`,
+// split with `--group-by` and `--facet` and checked against the data, and the data with outliers
+// dropped, normalized and with any derived series. Shared by every way of charting the data, so
+// that they all draw the same charts. None for the data if there are no data files.
+fn prepare_data(args: &Args, matches: &ArgMatches, config: &Config) -> Result<(Params, Option<StressTestData>), Box<dyn Error>> {
+    let mut params = build_params(args, matches, config)?;
+    let mut data = get_stress_test_data(args)?;
+
+    // `--serve` picks the charts on its page rather than asking for them each time the data files
+    // are read.
+    if args.interactive && args.serve.is_none() {
+        if let Some(data_value) = &data {
+            params.chart_specs = interactive::build_chart_specs(data_value, args, &ChartOptions::new(args))?;
+        }
+    }
+
+    if let (Some(data_value), Some(parameter)) = (&data, &args.group_by) {
+        params.chart_specs = group_chart_specs(std::mem::take(&mut params.chart_specs), data_value, parameter, &params.parameter_labels);
+    }
+
+    let facet = match args.facet.is_empty() {
+        true => None,
+        false => Some(Facet::parse(&args.facet)?),
+    };
+    if let (Some(data_value), Some(facet)) = (&data, &facet) {
+        let (chart_specs, layout) = facet::facet_chart_specs(std::mem::take(&mut params.chart_specs), data_value, facet)?;
+        params.chart_specs = chart_specs;
+        params.layout = Some(layout);
+    }
+
+    if let (XAxis::Time | XAxis::Metric(_), Some(data_value)) = (&params.x_axis, &data) {
+        if !data_value.datasets.values().any(|dataset| dataset.sorted_values.iter().any(|value| value.get_x(&params.x_axis).is_some())) {
+            return match &params.x_axis {
+                XAxis::Metric(chart_type) => Err(format!("--x-axis {} has no values in the data files", chart_type).into()),
+                _ => Err("--x-axis time needs a timestamp column in the data files".into()),
+            }
+        }
+        if params.chart_specs.iter().any(|spec| spec.chart_type.is_metric() && spec.options.raw_samples) {
+            return Err(format!("--raw-samples is not supported with --x-axis {}, as samples are only placed by the mean of their bucket", params.x_axis).into())
+        }
+        if params.x_axis == XAxis::Time && params.chart_specs.iter().any(|spec| spec.options.trend == Some(TrendFit::LogLinear)) {
+            return Err("Log-linear trends are fitted against the commit count and are not supported with --x-axis time".into())
+        }
+    }
+
+    // Other chart types plot raw samples, which are not normalized.
+    if let (Some(_), Some(spec)) = (&params.normalize_to, params.chart_specs.iter().find(|spec| !spec.chart_type.is_metric())) {
+        return Err(format!("--normalize-to is not supported by {:?} charts", spec.chart_type).into())
+    }
+
+    let analysis_start = std::time::Instant::now();
+    if let Some(data_value) = &mut data {
+        if let Some(filter) = &args.drop_outliers {
+            outliers::drop_outliers(data_value, filter);
+        }
+
+        gaps::warn_gaps(data_value);
+
+        if let Some(stall_detection) = &params.stall_detection {
+            print_stalls(data_value, stall_detection);
+        }
+
+        if let Some(filters) = &params.normalize_to {
+            normalize::normalize_to(data_value, filters)?;
+        }
+
+        for series in &params.derived_series {
+            data_value.add_derived_series(series)?;
+        }
+
+        if facet.is_some() {
+            facet::share_y_ranges(data_value, &mut params)?;
+        }
+
+        unmatched::check_chart_filters(data_value, &params, args.strict)?;
+        log::debug!("Analysed the data in {:.3}s", analysis_start.elapsed().as_secs_f64());
+    }
+
+    Ok((params, data))
+}
+
+// Options which need the raw samples, which `--low-memory` does not keep.
+fn check_low_memory(args: &Args, params: &Params) -> Result<(), Box<dyn Error>> {
+    if args.drop_outliers.is_some() {
+        return Err("--drop-outliers is not supported with --low-memory, which does not keep the samples".into())
+    }
+    if let Some(spec) = params.chart_specs.iter().find(|spec| spec.chart_type.needs_raw_samples() || (spec.chart_type.is_metric() && spec.options.raw_samples)) {
+        let what = match spec.options.raw_samples && !spec.chart_type.needs_raw_samples() {
+            true => "--raw-samples is".to_string(),
+            false => format!("{} charts are", spec.chart_type),
+        };
+        return Err(format!("{} not supported with --low-memory, which does not keep the samples", what).into())
+    }
+    Ok(())
+}
+
+// The output file and its format. An `--output` with a known extension selects the format unless
+// `--output-format` was given explicitly.
+fn get_output_path(args: &Args, explicit_output_format: bool) -> (PathBuf, OutputFormat) {
+    match &args.output {
+        Some(path) => {
+            let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+            let output_format = match (explicit_output_format, extension.as_deref()) {
+                (false, Some("svg")) => OutputFormat::Svg,
+                (false, Some("png")) => OutputFormat::Png,
+                (false, Some("pdf")) => OutputFormat::Pdf,
+                (false, Some("gif")) => OutputFormat::Gif,
+                _ => args.output_format.clone(),
+            };
+            (path.clone(), output_format)
+        },
+        None => {
+            let mut output_path = std::env::current_dir().expect("Cannot resolve current dir");
+            output_path.push("visualizer_output");
+            output_path.push("stress_test_charts");
+            output_path.set_extension(args.output_format.extension());
+            (output_path, args.output_format.clone())
+        },
+    }
+}
+
+// Output file for a single chart with `--separate-files`, named after the chart type and filter, e.g.
+// `stress_test_charts-1-commits-per-second-readers-0.png`.
+fn get_chart_file_path(output_path: &std::path::Path, index: usize, spec: &ChartSpec) -> PathBuf {
+    let mut name = format!("{}-{}", index + 1, spec.chart_type);
+
+    let slug = get_slug(&spec.filters.display_text(&Default::default()));
+    if !slug.is_empty() {
+        name += "-";
+        name += &slug;
+    }
+
+    let stem = output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("chart");
+    let mut path = output_path.with_file_name(format!("{}-{}", stem, name));
+    if let Some(extension) = output_path.extension() {
+        path.set_extension(extension);
+    }
+    path
+}
+
+// Lower case letters and digits of the text, with a dash in place of each run of other characters,
+// for use in file names.
+pub(crate) fn get_slug(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Draws the charts of the params into a single image file.
+pub fn render_charts(path: &std::path::Path, output_format: &OutputFormat, data: &StressTestData, params: &Params) -> Result<(), Box<dyn Error>> {
+    draw_to_file(path, output_format, Some(data), params, &params.chart_specs)
+}
+
+fn draw_to_file(path: &std::path::Path, output_format: &OutputFormat, data: Option<&StressTestData>, params: &Params, chart_specs: &[ChartSpec]) -> Result<(), Box<dyn Error>> {
+    let image_size = params.get_image_size(&params.get_layout(chart_specs.len())?);
+
+    match output_format {
+        OutputFormat::Png => draw_to_area(&BitMapBackend::new(path, image_size).into_drawing_area(), data, params, chart_specs, true),
+        OutputFormat::Svg => draw_to_area(&SVGBackend::new(path, image_size).into_drawing_area(), data, params, chart_specs, false),
+        OutputFormat::Pdf => pdf::write_report(path, data, params, chart_specs),
+        // A single frame of the whole run.
+        OutputFormat::Gif => draw_to_area(&BitMapBackend::gif(path, image_size, 0)?.into_drawing_area(), data, params, chart_specs, true),
+    }
+}
+
+// Charts are drawn on several threads when the area is of a bitmap backend.
+fn draw_to_area<DB: DrawingBackend>(root_area: &DrawingArea<DB, plotters::coord::Shift>, data: Option<&StressTestData>, params: &Params, chart_specs: &[ChartSpec], bitmap: bool) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    root_area.fill(&params.theme.background)?;
+    let charts_area = frame::draw_frame(root_area, params)?;
+
+    if let Some(data_value) = data {
+        match bitmap && chart_specs.len() > 1 {
+            true => renderer::render_in_parallel(&charts_area, data_value, params, chart_specs)?,
+            false => draw_stress_test_data(&charts_area, data_value, params, chart_specs)?,
+        }
+    }
+
+    root_area.present().expect("Unable to write result to file");
+
+    Ok(())
+}
+
+fn build_params(args: &Args, matches: &ArgMatches, config: &Config) -> Result<Params, Box<dyn Error>> {
+    let (stroke_width, chart_size) = match args.small_image {
+        false => (2, 2160),
+        true => (1, 1080),
+    };
+    let stroke_width = stroke_width + args.accessible as u64;
+
+    let options = ChartOptions::new(args);
+
+    // Templates, presets, `--chart` and config file charts replace the default chart types unless
+    // chart types were also given explicitly.
+    let explicit_chart_types = matches.value_source("chart_type") == Some(ValueSource::CommandLine);
+    let num_chart_types = match (args.template.is_empty() && args.preset.is_empty() && args.chart.is_empty() && config.charts.is_empty()) || explicit_chart_types {
+        true => args.chart_type.len(),
+        false => 0,
+    };
+
+    let mut chart_specs: Vec<ChartSpec> = Default::default();
+    for i in 0..num_chart_types {
+        let chart_type = args.chart_type[i].clone();
+
+        let filter_text = if i < args.chart_filter.len() {
+            args.chart_filter[i].clone()
+        } else {
+            "".to_string()
+        };
+
+        let filters = ParameterFilterSet::parse(&filter_text)?;
+
+        let transform = match args.chart_transform.get(i) {
+            Some(text) => Transform::parse(text)?,
+            None => Transform::default(),
+        };
+
+        let y_range = match args.y_range.get(i) {
+            Some(text) => AxisRange::parse(text)?,
+            None => AxisRange::default(),
+        };
+
+        // Without positions a log axis is used by every chart type that supports one.
+        let mut chart_options = options.clone();
+        chart_options.log_y = match &args.log_y {
+            Some(positions) if positions.is_empty() => chart_type.is_metric(),
+            Some(positions) => positions.contains(&(i + 1)),
+            None => false,
+        };
+        chart_options.smooth = options.smooth.filter(|_| chart_type.supports_smoothing());
+        chart_options.trend = options.trend.filter(|_| chart_type.is_metric());
+
+        let title = args.chart_title.get(i).filter(|title| !title.trim().is_empty()).cloned();
+
+        let secondary = match args.secondary_metric.get(i).filter(|text| !text.trim().is_empty()) {
+            Some(text) => Some(parse_chart_type(text.trim())?),
+            None => None,
+        };
+
+        let chart_spec = ChartSpec {
+            chart_type,
+            filters,
+            title,
+            transform,
+            y_range,
+            x_range: AxisRange::default(),
+            secondary,
+            options: chart_options,
+        };
+
+        chart_specs.push(chart_spec);
+    }
+
+    for chart_text in &args.chart {
+        chart_specs.push(spec::parse_chart_spec(chart_text, &options)?);
+    }
+
+    if !explicit_chart_types && args.chart.is_empty() {
+        chart_specs.append(&mut config.get_charts(&options)?);
+    }
+
+    for template_text in &args.template {
+        let instance = TemplateInstance::parse(template_text)?;
+        chart_specs.append(&mut config.instantiate_template(&instance, &options)?);
+    }
+
+    let num_other_charts = chart_specs.len();
+    for preset in &args.preset {
+        chart_specs.append(&mut preset.get_charts(&options)?);
+    }
+
+    // A preset drawn on its own keeps its layout over that of the config file.
+    let layout = match args.preset[..] {
+        [preset] if num_other_charts == 0 && matches.value_source("layout") != Some(ValueSource::CommandLine) => Some(preset.layout()),
+        _ => args.layout,
+    };
+
+    for chart_spec in &chart_specs {
+        chart_spec.validate()?;
+    }
+
+    let stall_detection = options.stall_detection();
+
+    let reference_lines = args.hline.iter().map(|text| ReferenceLine::parse(text)).collect::<Result<Vec<_>, _>>()?;
+
+    let mut derived_series: Vec<DerivedSeries> = Default::default();
+    for series_text in &args.derived_series {
+        derived_series.push(DerivedSeries::parse(series_text)?);
+    }
+
+    let annotations = match &args.annotations {
+        Some(path) => load_annotations(path)?,
+        None => Default::default(),
+    };
+
+    let theme = match &args.theme {
+        Some(text) => Theme::load(text)?,
+        None => Theme::light(),
+    };
+    let palette = match (&args.palette, args.accessible) {
+        (Some(palette), _) => palette.clone(),
+        (None, true) => Palette::accessible(theme.background),
+        (None, false) => theme.palette.clone(),
+    };
+    let normalize_to = args.normalize_to.as_ref().map(|text| ParameterFilterSet::parse(text)).transpose()?;
+    let footer = args.footer.as_ref().map(|text| frame::expand_footer(text)).transpose()?;
+    let machine_metadata = match (args.machine_metadata, &args.data_path) {
+        (true, Some(paths)) => machine::get_metadata_lines(paths)?,
+        _ => Default::default(),
+    };
+
+    let params = Params { stroke_width, chart_size, chart_specs, stall_detection, parameter_labels: config.parameters.clone(), derived_series, baseline_mode: args.baseline_mode.clone(), layout, palette, colour_by: args.color_by.clone(), style_by: args.style_by.clone(), accessible: args.accessible, sort_by: args.sort_by.clone(), annotations, reference_lines, theme, fonts: Fonts::new(args)?, normalize_to, title: args.title.clone(), footer, machine_metadata, x_axis: args.x_axis.clone(), max_points_per_series: Some(args.max_points_per_series).filter(|max| *max > 0) };
+    if args.low_memory {
+        check_low_memory(args, &params)?;
+    }
+    Ok(params)
+}
+
+/// Replaces each chart with one chart per value of the parameter among the datasets passing its
+/// filters, in order of the values. Charts whose datasets lack the parameter, or whose filter
+/// already selects a single value of it, are kept as they are.
+pub fn group_chart_specs(chart_specs: Vec<ChartSpec>, data: &StressTestData, parameter: &str, labels: &ParameterLabels) -> Vec<ChartSpec> {
+    let mut grouped: Vec<ChartSpec> = Default::default();
+    for spec in chart_specs {
+        let mut values: Vec<&ParameterValue> = Default::default();
+        for dataset in data.datasets.values().filter(|d| !d.derived && d.passes_filters(&spec.filters)) {
+            if let Some(value) = dataset.parameters.get(parameter) {
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+        values.sort_by(|a, b| a.display_cmp(b));
+
+        // A chart whose filter already selects a single value needs no splitting.
+        let filtered = spec.filters.parameter_names().any(|name| name == parameter);
+        if values.is_empty() || (values.len() == 1 && filtered) {
+            grouped.push(spec);
+            continue;
+        }
+
+        for value in values {
+            // Custom titles would otherwise be repeated across the group.
+            let title = spec.title.as_ref().map(|title| format!("{} ({})", title, ParameterFilterSet::from_static("").with_equal(parameter, value).display_text(labels)));
+            grouped.push(ChartSpec {
+                chart_type: spec.chart_type.clone(),
+                filters: spec.filters.clone().with_equal(parameter, value),
+                title,
+                transform: spec.transform.clone(),
+                y_range: spec.y_range.clone(),
+                x_range: spec.x_range.clone(),
+                secondary: spec.secondary.clone(),
+                options: spec.options.clone(),
+            });
+        }
+    }
+    grouped
+}
+
+/// Mean and variance of a stream of samples, updated as each sample is added.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct RunningStatistics {
+    pub num: u64,
+    #[serde(with = "cache::float")]
+    pub old_m: f64,
+    #[serde(with = "cache::float")]
+    pub new_m: f64,
+    #[serde(with = "cache::float")]
+    pub old_s: f64,
+    #[serde(with = "cache::float")]
+    pub new_s: f64,
+}
+
+impl Default for RunningStatistics {
+    fn default() -> RunningStatistics {
+        RunningStatistics::new()
+    }
+}
+
+impl RunningStatistics {
+    pub fn new() -> RunningStatistics {
+        RunningStatistics { num: 0, old_m: 0.0, new_m: 0.0, old_s: 0.0, new_s: 0.0 }
+    }
+
+    pub fn add_sample(&mut self, sample: f64) {
+        self.num += 1;
+
+        if self.num == 1 {
+            self.old_m = sample;
+            self.new_m = sample;
+            self.old_s = 0.0;
+        }
+        else {
+            self.new_m = self.old_m + ((sample - self.old_m) / self.num as f64);
+            self.new_s = self.old_s + ((sample - self.old_m) * (sample - self.new_m));
+
+            self.old_m = self.new_m;
+            self.old_s = self.new_s;
+        }
+    }
+
+    pub fn mean(&self) -> f64 {
+        if self.num > 0 {
+            return self.new_m
+        }
+        0.0
+    }
+
+    pub fn variance(&self) -> f64 {
+        if self.num > 1 {
+            return self.new_s / ((self.num - 1) as f64)
+        }
+        0.0
+    }
+
+    /// Combines the statistics of another stream, as if its samples had been added to this one.
+    pub fn merge(&mut self, other: &RunningStatistics) {
+        if other.num == 0 {
+            return
+        }
+        if self.num == 0 {
+            *self = RunningStatistics { num: other.num, old_m: other.new_m, new_m: other.new_m, old_s: other.new_s, new_s: other.new_s };
+            return
+        }
+
+        let num = self.num + other.num;
+        let delta = other.new_m - self.new_m;
+        let mean = self.new_m + delta * other.num as f64 / num as f64;
+        let sum_of_squares = self.new_s + other.new_s + delta * delta * self.num as f64 * other.num as f64 / num as f64;
+        *self = RunningStatistics { num, old_m: mean, new_m: mean, old_s: sum_of_squares, new_s: sum_of_squares };
+    }
+}
+
+/// The samples of one metric at one commit count, with their running statistics.
+pub struct SampleSet {
+    pub samples : Vec<f64>,
+    // The samples in order, sorted the first time a percentile is needed rather than as each
+    // sample is added.
+    sorted_samples : std::sync::OnceLock<Vec<f64>>,
+    pub value_min : f64,
+    pub value_max : f64,
+    pub statistics : RunningStatistics,
+    /// Number of samples left out with `--drop-outliers`.
+    pub dropped_outliers : usize,
+    /// Estimates the percentiles in low memory mode, in which the samples are not kept and
+    /// non-finite samples are left out.
+    pub digest : Option<digest::TDigest>,
+}
+
+impl Default for SampleSet {
+    fn default() -> SampleSet {
+        SampleSet::new()
+    }
+}
+
+impl SampleSet {
+    pub fn new() -> SampleSet {
+        SampleSet::with_low_memory(false)
+    }
+
+    /// A sample set which in low memory mode keeps only the running statistics, the smallest and
+    /// largest samples and a digest for percentiles, rather than every sample. Memory then no
+    /// longer grows with the number of samples, but percentiles are estimates and charts of the
+    /// raw samples cannot be drawn.
+    pub fn with_low_memory(low_memory: bool) -> SampleSet {
+        SampleSet { samples: Default::default(), sorted_samples: Default::default(), value_min: 0.0, value_max: 0.0, statistics: RunningStatistics::new(), dropped_outliers: 0, digest: low_memory.then(digest::TDigest::new) }
+    }
+
+    pub fn is_low_memory(&self) -> bool {
+        self.digest.is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.statistics.num as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statistics.num == 0
+    }
+
+    pub fn add_sample(&mut self, sample: f64) {
+        if let Some(digest) = &mut self.digest {
+            if !sample.is_finite() {
+                return
+            }
+            digest.add(sample);
+        }
+
+        match self.statistics.num {
+            0 => {
+                self.value_min = sample;
+                self.value_max = sample;
+            },
+            _ => {
+                self.value_min = self.value_min.min(sample);
+                self.value_max = self.value_max.max(sample);
+            },
+        }
+
+        if self.digest.is_none() {
+            self.samples.push(sample);
+            self.sorted_samples.take();
+        }
+
+        self.statistics.add_sample(sample);
+    }
+
+    pub fn get_mean(&self) -> f64 {
+        self.statistics.mean()
+    }
+
+    // The mean from the running statistics, or None without samples or when a sample is not
+    // finite, such as the rate of a sample without any queries.
+    pub(crate) fn get_finite_mean(&self) -> Option<f64> {
+        let mean = self.get_mean();
+        (!self.is_empty() && mean.is_finite()).then_some(mean)
+    }
+
+    /// The samples in ascending order, empty in low memory mode.
+    pub fn sorted_samples(&self) -> &[f64] {
+        self.sorted_samples.get_or_init(|| {
+            let mut sorted = self.samples.clone();
+            sorted.sort_by(f64::total_cmp);
+            sorted
+        })
+    }
+
+    // A set in low memory mode merged into one which keeps its samples turns it into a set in low
+    // memory mode too.
+    pub fn merge(&mut self, other: &SampleSet) {
+        match &other.digest {
+            None => {
+                for sample in &other.samples {
+                    self.add_sample(*sample);
+                }
+            },
+            Some(other_digest) => {
+                if self.digest.is_none() {
+                    let mut digest = digest::TDigest::new();
+                    let mut statistics = RunningStatistics::new();
+                    for sample in self.samples.drain(..).filter(|sample| sample.is_finite()) {
+                        digest.add(sample);
+                        statistics.add_sample(sample);
+                    }
+                    self.sorted_samples = Default::default();
+                    self.statistics = statistics;
+                    self.digest = Some(digest);
+                }
+                if other.is_empty() {
+                    return
+                }
+                (self.value_min, self.value_max) = match self.is_empty() {
+                    true => (other.value_min, other.value_max),
+                    false => (self.value_min.min(other.value_min), self.value_max.max(other.value_max)),
+                };
+                self.statistics.merge(&other.statistics);
+                if let Some(digest) = &mut self.digest {
+                    digest.merge(other_digest);
+                }
+            },
+        }
+        self.dropped_outliers += other.dropped_outliers;
+    }
+
+    pub fn get_percentile(&self, percentile: f64) -> f64 {
+        match &self.digest {
+            Some(digest) => digest.percentile(percentile, self.value_min, self.value_max),
+            None => statistics::percentile(self.sorted_samples(), percentile),
+        }
+    }
+
+    pub fn get_half_range(&self) -> f64 {
+        //self.statistics.variance() * 4.0
+        f64::sqrt(self.statistics.variance()) * 2.0
+    }
+
+    /// Half the width of the 95% confidence interval of the mean, from Student's t-distribution
+    /// so that buckets with few samples get wider intervals. None with fewer than two samples.
+    pub fn get_confidence_half_width(&self) -> Option<f64> {
+        let num = self.statistics.num;
+        match num {
+            0 | 1 => None,
+            _ => Some(statistics::t_critical_95(num - 1) * (self.statistics.variance() / num as f64).sqrt()),
+        }
+    }
+
+    pub fn get_range(&self, range: &SampleRange) -> (f64, f64) {
+        match range {
+            SampleRange::StdDev => (self.statistics.mean() - self.get_half_range(), self.statistics.mean() + self.get_half_range()),
+            SampleRange::Percentile(lower, upper) => (self.get_percentile(*lower), self.get_percentile(*upper)),
+        }
+    }
+
+    /// A copy of the sample set with the transform applied to every sample. Samples which have no
+    /// finite transformed value are dropped. In low memory mode transforms other than scaling are
+    /// applied to the centroids of the digest, so that the statistics of the result are estimates.
+    pub fn transformed(&self, transform: &Transform) -> SampleSet {
+        let Some(digest) = &self.digest else {
+            let mut result = SampleSet::new();
+            for sample in self.samples.iter().filter_map(|sample| transform.apply(*sample)) {
+                result.add_sample(sample);
+            }
+            return result
+        };
+
+        let factors: Option<Vec<f64>> = transform.steps.iter().map(|step| match step {
+            transform::TransformStep::Scale(factor) => Some(*factor),
+            _ => None,
+        }).collect();
+        if let Some(factor) = factors.map(|factors| factors.iter().product::<f64>()).filter(|factor| factor.is_finite()) {
+            return self.scaled(factor)
+        }
+
+        let mut result_digest = digest::TDigest::new();
+        let mut result = SampleSet { digest: None, ..SampleSet::new() };
+        let mut range: Option<(f64, f64)> = None;
+        for centroid in digest.centroids() {
+            let Some(mean) = transform.apply(centroid.mean) else {
+                continue;
+            };
+            range = Some(range.map_or((mean, mean), |(min, max)| (min.min(mean), max.max(mean))));
+            result.statistics.merge(&RunningStatistics { num: centroid.weight as u64, old_m: mean, new_m: mean, old_s: 0.0, new_s: 0.0 });
+            result_digest.add_weighted(mean, centroid.weight);
+        }
+        // The transforms are monotonic, so the extremes of the samples map to those of the result.
+        if let Some(range) = range {
+            let extremes = [self.value_min, self.value_max].into_iter().filter_map(|value| transform.apply(value));
+            (result.value_min, result.value_max) = extremes.fold(range, |(min, max), value| (min.min(value), max.max(value)));
+        }
+        result.digest = Some(result_digest);
+        result
+    }
+
+    /// A copy with every sample multiplied by the factor, which is exact in low memory mode too.
+    pub fn scaled(&self, factor: f64) -> SampleSet {
+        let Some(digest) = &self.digest else {
+            let mut result = SampleSet { digest: None, ..SampleSet::new() };
+            for sample in &self.samples {
+                result.add_sample(sample * factor);
+            }
+            return result
+        };
+
+        let (min, max) = (self.value_min * factor, self.value_max * factor);
+        let statistics = &self.statistics;
+        SampleSet {
+            value_min: min.min(max),
+            value_max: min.max(max),
+            statistics: RunningStatistics { num: statistics.num, old_m: statistics.old_m * factor, new_m: statistics.new_m * factor, old_s: statistics.old_s * factor * factor, new_s: statistics.new_s * factor * factor },
+            dropped_outliers: self.dropped_outliers,
+            digest: Some(digest.scaled(factor)),
+            ..SampleSet::new()
+        }
+    }
+
+    // Smallest positive sample, estimated from the digest in low memory mode.
+    pub fn get_min_positive(&self) -> Option<f64> {
+        match &self.digest {
+            Some(_) if self.value_min > 0.0 && !self.is_empty() => Some(self.value_min),
+            Some(digest) => digest.centroids().iter().map(|c| c.mean).find(|mean| *mean > 0.0),
+            None => self.samples.iter().copied().filter(|sample| *sample > 0.0).reduce(f64::min),
+        }
+    }
+}
+
+/// Metric values read from a single timing sample.
+pub struct Sample {
+    /// Index of the run (data file) the sample was read from.
+    pub run: usize,
+    pub commit_time: f64,
+    pub sample_commit_time: f64,
+    pub commits_per_second: f64,
+    pub queries_per_second: f64,
+    /// Microseconds per query, if any queries were made during the sample.
+    pub query_latency: Option<f64>,
+    /// Size of the database on disk in megabytes, if the data file has a `disk_size` column.
+    pub disk_size: Option<f64>,
+    /// Bytes written to disk so far per byte on disk, if the data file has `disk_size` and
+    /// `bytes_written` columns.
+    pub write_amplification: Option<f64>,
+    /// Resident set size of the stress test in megabytes, if the data file has an `rss` column.
+    pub memory_usage: Option<f64>,
+    /// Heap size of the stress test in megabytes, if the data file has a `heap` column.
+    pub heap_usage: Option<f64>,
+    /// Values of registered plugin metrics, keyed by metric name.
+    pub plugin_metrics: BTreeMap<String, f64>,
+    /// Seconds since the Unix epoch at which the sample was taken, if the data file has a
+    /// `timestamp` column.
+    pub timestamp: Option<f64>,
+}
+
+/// All samples of a dataset at one commit count, by metric.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ValueSet {
+    pub num_commits : u64,
+    pub commit_time : SampleSet,
+    pub sample_commit_time : SampleSet,
+    pub commits_per_second : SampleSet,
+    pub queries_per_second : SampleSet,
+    pub query_latency : SampleSet,
+    pub disk_size : SampleSet,
+    pub write_amplification : SampleSet,
+    pub memory_usage : SampleSet,
+    pub heap_usage : SampleSet,
+    pub run_commits_per_second : BTreeMap<usize, RunningStatistics>,
+    pub plugin_metrics : BTreeMap<String, SampleSet>,
+    pub timestamp : SampleSet,
+    // Times at which the samples of each run were taken, for the intervals between them.
+    pub run_timestamps : BTreeMap<usize, RunningStatistics>,
+}
+
+impl ValueSet {
+    pub fn new(num_commits: u64) -> ValueSet {
+        ValueSet::with_low_memory(num_commits, false)
+    }
+
+    /// A value set whose sample sets are in low memory mode, see [`SampleSet::with_low_memory`].
+    pub fn with_low_memory(num_commits: u64, low_memory: bool) -> ValueSet {
+        let new = || SampleSet::with_low_memory(low_memory);
+        ValueSet { num_commits, commit_time: new(), sample_commit_time: new(), commits_per_second: new(), queries_per_second: new(), query_latency: new(), disk_size: new(), write_amplification: new(), memory_usage: new(), heap_usage: new(), run_commits_per_second: Default::default(), plugin_metrics: Default::default(), timestamp: new(), run_timestamps: Default::default() }
+    }
+
+    pub fn add_sample(&mut self, sample: &Sample) {
+        self.commit_time.add_sample(sample.commit_time);
+        self.sample_commit_time.add_sample(sample.sample_commit_time);
+        self.commits_per_second.add_sample(sample.commits_per_second);
+        self.queries_per_second.add_sample(sample.queries_per_second);
+        if let Some(query_latency) = sample.query_latency {
+            self.query_latency.add_sample(query_latency);
+        }
+        if let Some(disk_size) = sample.disk_size {
+            self.disk_size.add_sample(disk_size);
+        }
+        if let Some(write_amplification) = sample.write_amplification {
+            self.write_amplification.add_sample(write_amplification);
+        }
+        if let Some(memory_usage) = sample.memory_usage {
+            self.memory_usage.add_sample(memory_usage);
+        }
+        if let Some(heap_usage) = sample.heap_usage {
+            self.heap_usage.add_sample(heap_usage);
+        }
+        self.run_commits_per_second.entry(sample.run).or_default().add_sample(sample.commits_per_second);
+        // Plugin metrics are kept in the same mode as the built in ones.
+        let low_memory = self.commit_time.is_low_memory();
+        for (name, value) in &sample.plugin_metrics {
+            self.plugin_metrics.entry(name.clone()).or_insert_with(|| SampleSet::with_low_memory(low_memory)).add_sample(*value);
+        }
+        if let Some(timestamp) = sample.timestamp {
+            self.timestamp.add_sample(timestamp);
+            self.run_timestamps.entry(sample.run).or_default().add_sample(timestamp);
+        }
+    }
+
+    /// Adds the samples of another value set, keeping this one's commit count.
+    pub fn merge(&mut self, other: &ValueSet) {
+        self.commit_time.merge(&other.commit_time);
+        self.sample_commit_time.merge(&other.sample_commit_time);
+        self.commits_per_second.merge(&other.commits_per_second);
+        self.queries_per_second.merge(&other.queries_per_second);
+        self.query_latency.merge(&other.query_latency);
+        self.disk_size.merge(&other.disk_size);
+        self.write_amplification.merge(&other.write_amplification);
+        self.memory_usage.merge(&other.memory_usage);
+        self.heap_usage.merge(&other.heap_usage);
+        for (run, run_statistics) in &other.run_commits_per_second {
+            self.run_commits_per_second.entry(*run).or_default().merge(run_statistics);
+        }
+        for (name, sample_set) in &other.plugin_metrics {
+            self.plugin_metrics.entry(name.clone()).or_default().merge(sample_set);
+        }
+        self.timestamp.merge(&other.timestamp);
+        for (run, run_timestamps) in &other.run_timestamps {
+            self.run_timestamps.entry(*run).or_default().merge(run_timestamps);
+        }
+    }
+
+    // Mean time at which the samples were taken, None without timestamps.
+    pub fn get_time(&self) -> Option<f64> {
+        match self.timestamp.is_empty() {
+            true => None,
+            false => Some(self.timestamp.get_mean()),
+        }
+    }
+
+    // Position of the bucket along the X axis of a metric chart.
+    pub fn get_x(&self, x_axis: &XAxis) -> Option<f64> {
+        match x_axis {
+            XAxis::Commits => Some(self.num_commits as f64),
+            XAxis::Time => self.get_time(),
+            XAxis::Metric(chart_type) => self.get_sample_set(chart_type).and_then(SampleSet::get_finite_mean),
+        }
+    }
+
+    /// The samples summarised by `--stats-out`, keyed by metric name. Sample commit times are in
+    /// seconds, query latencies in microseconds and disk sizes, resident memory and heap usage in megabytes.
+    pub fn get_summary_sample_sets(&self) -> Vec<(String, &SampleSet)> {
+        let mut sample_sets = vec![
+            ("commits-per-second".to_string(), &self.commits_per_second),
+            ("queries-per-second".to_string(), &self.queries_per_second),
+            ("sample-commit-time".to_string(), &self.sample_commit_time),
+            ("query-latency".to_string(), &self.query_latency),
+            ("disk-size".to_string(), &self.disk_size),
+            ("write-amplification".to_string(), &self.write_amplification),
+            ("memory-usage".to_string(), &self.memory_usage),
+            ("heap-usage".to_string(), &self.heap_usage),
+        ];
+        sample_sets.extend(self.plugin_metrics.iter().map(|(name, sample_set)| (name.clone(), sample_set)));
+        sample_sets
+    }
+
+    /// The samples plotted by mean line chart types.
+    pub fn get_sample_set(&self, chart_type: &ChartType) -> Option<&SampleSet> {
+        match chart_type {
+            ChartType::CommitTime => Some(&self.commit_time),
+            ChartType::CommitsPerSecond => Some(&self.commits_per_second),
+            ChartType::QueriesPerSecond => Some(&self.queries_per_second),
+            ChartType::DiskSize => Some(&self.disk_size),
+            ChartType::WriteAmplification => Some(&self.write_amplification),
+            ChartType::MemoryUsage => Some(&self.memory_usage),
+            ChartType::HeapUsage => Some(&self.heap_usage),
+            ChartType::PluginMetric(name) => self.plugin_metrics.get(name),
+            _ => None,
+        }
+    }
+
+    // Raw samples plotted by a scatter chart type, or otherwise those of a metric chart type.
+    pub fn get_raw_sample_set(&self, chart_type: &ChartType) -> Option<&SampleSet> {
+        match chart_type {
+            ChartType::CommitTimeScatter => Some(&self.sample_commit_time),
+            ChartType::CommitsPerSecondScatter => Some(&self.commits_per_second),
+            ChartType::QueriesPerSecondScatter => Some(&self.queries_per_second),
+            _ => self.get_sample_set(chart_type),
+        }
+    }
+}
+
+/// The samples of one test configuration, a base name with a set of parameter values, across
+/// every data file.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct DataSet {
+    pub base_name : String,
+    pub parameters: BTreeMap<String, ParameterValue>,
+
+    /// Values at each commit count, in increasing order of commits.
+    pub sorted_values : Vec<ValueSet>,
+
+    /// Synthetic datasets hold a single combined value per commit count rather than raw samples.
+    pub derived: bool,
+
+    /// Value sets added for new commit counts are in low memory mode, see
+    /// [`SampleSet::with_low_memory`].
+    #[serde(skip)]
+    pub low_memory: bool,
+
+    pub max_commits: u64,
+    #[serde(with = "cache::float")]
+    pub max_commit_time: f64,
+    #[serde(with = "cache::float")]
+    pub max_commits_per_second: f64,
+    #[serde(with = "cache::float")]
+    pub max_queries_per_second: f64,
+    #[serde(with = "cache::float")]
+    pub max_disk_size: f64,
+    #[serde(with = "cache::float")]
+    pub max_write_amplification: f64,
+    #[serde(with = "cache::float")]
+    pub max_memory_usage: f64,
+    #[serde(with = "cache::float")]
+    pub max_heap_usage: f64,
+}
+
+impl DataSet {
+    pub fn new(base_name: String, parameters: BTreeMap<String, ParameterValue>) -> DataSet {
+        DataSet {
+            base_name,
+            parameters,
+            sorted_values: Default::default(), 
+            derived: false,
+            low_memory: false,
+            max_commits: 0, max_commit_time: 0.0f64, max_commits_per_second: 0.0f64, max_queries_per_second: 0.0f64, max_disk_size: 0.0f64, max_write_amplification: 0.0f64, max_memory_usage: 0.0f64, max_heap_usage: 0.0f64 }
+    }
+
+    pub fn add_sample(&mut self, commits: u64, sample: &Sample) {
+        self.max_commits = std::cmp::max(self.max_commits, commits);
+        self.max_commit_time = self.max_commit_time.max(sample.commit_time);
+        self.max_commits_per_second = self.max_commits_per_second.max(sample.commits_per_second);
+        self.max_queries_per_second = self.max_queries_per_second.max(sample.queries_per_second);
+        self.max_disk_size = self.max_disk_size.max(sample.disk_size.unwrap_or(0.0));
+        self.max_write_amplification = self.max_write_amplification.max(sample.write_amplification.unwrap_or(0.0));
+        self.max_memory_usage = self.max_memory_usage.max(sample.memory_usage.unwrap_or(0.0));
+        self.max_heap_usage = self.max_heap_usage.max(sample.heap_usage.unwrap_or(0.0));
+
+        match self.sorted_values.binary_search_by(|probe| probe.num_commits.cmp(&commits)) {
+            Ok(val) => self.sorted_values[val].add_sample(sample),
+            Err(val) => {
+                let mut valueset = ValueSet::with_low_memory(commits, self.low_memory);
+                valueset.add_sample(sample);
+                self.sorted_values.insert(val, valueset);
+            },
+        }
+    }
+
+    /// Adds the values of another part of the same dataset, such as one read by another thread.
+    /// Samples at a shared commit count are added after this dataset's own.
+    pub fn merge(&mut self, other: DataSet) {
+        self.max_commits = std::cmp::max(self.max_commits, other.max_commits);
+        self.max_commit_time = self.max_commit_time.max(other.max_commit_time);
+        self.max_commits_per_second = self.max_commits_per_second.max(other.max_commits_per_second);
+        self.max_queries_per_second = self.max_queries_per_second.max(other.max_queries_per_second);
+        self.max_disk_size = self.max_disk_size.max(other.max_disk_size);
+        self.max_write_amplification = self.max_write_amplification.max(other.max_write_amplification);
+        self.max_memory_usage = self.max_memory_usage.max(other.max_memory_usage);
+        self.max_heap_usage = self.max_heap_usage.max(other.max_heap_usage);
+
+        for value in other.sorted_values {
+            match self.sorted_values.binary_search_by(|probe| probe.num_commits.cmp(&value.num_commits)) {
+                Ok(index) => self.sorted_values[index].merge(&value),
+                Err(index) => self.sorted_values.insert(index, value),
+            }
+        }
+    }
+
+    pub fn get_name(base_name: String, parameters: &BTreeMap<String, ParameterValue>) -> String {
+        let mut suffix = String::new();
+
+        let mut prev_param = false;
+        for (name, value) in parameters {
+            if prev_param {
+                suffix += " ";
+            }
+
+            match value {
+                ParameterValue::Bool(v) => {
+                    if *v {
+                        suffix += name;
+                        prev_param = true;
+                    }
+                },
+                ParameterValue::Int(_) | ParameterValue::Float(_) => {
+                    suffix += &format!("{}={}", name, value);
+                    prev_param = true;
+                },
+                ParameterValue::Text(v) => {
+                    suffix += &format!("{}={}", name, v);
+                    prev_param = true;
+                },
+            }
+        }
+        if !suffix.is_empty() {
+            suffix = " (".to_string() + &suffix + ")";
+        }
+
+        base_name.clone() + &suffix
+    }
+
+    pub fn get_name_including(base_name: String, parameters: &BTreeMap<String, ParameterValue>, include_parameters: &HashSet<String>, labels: &ParameterLabels) -> String {
+        let mut suffix = String::new();
+
+        let mut prev_param = false;
+        for (name, value) in parameters {
+            if include_parameters.contains(name) {
+                if prev_param {
+                    suffix += " ";
+                }
+    
+                match value {
+                    ParameterValue::Bool(v) => {
+                        if *v {
+                            suffix += &config::label_name(labels, name);
+                            prev_param = true;
+                        }
+                    },
+                    ParameterValue::Int(_) | ParameterValue::Float(_) => {
+                        suffix += &format!("{}={}", config::label_name(labels, name), config::label_value(labels, name, &value.to_string()));
+                        prev_param = true;
+                    },
+                    ParameterValue::Text(v) => {
+                        suffix += &format!("{}={}", config::label_name(labels, name), v);
+                        prev_param = true;
+                    },
+                }
+            }
+        }
+        if !suffix.is_empty() {
+            suffix = " (".to_string() + &suffix + ")";
+        }
+
+        base_name.clone() + &suffix
+    }
+
+    // Parameters which some of the datasets have and others have not, or have with another value,
+    // so are needed to tell the datasets apart.
+    pub fn get_varying_parameters<'a>(datasets: impl IntoIterator<Item = &'a DataSet>) -> HashSet<String> {
+        let mut varying: HashSet<String> = Default::default();
+        let mut datasets = datasets.into_iter();
+        let Some(first) = datasets.next() else {
+            return varying
+        };
+        for other in datasets {
+            for (name, value) in &first.parameters {
+                if other.parameters.get(name) != Some(value) {
+                    varying.insert(name.clone());
+                }
+            }
+            for name in other.parameters.keys() {
+                if !first.parameters.contains_key(name) {
+                    varying.insert(name.clone());
+                }
+            }
+        }
+        varying
+    }
+
+    pub fn passes_filters(&self, filters: &impl FilterSet) -> bool {
+        filters.passes_filters(&self.base_name, &self.parameters)
+    }
+
+    // Smallest and largest transformed sample of a metric chart type, or None if no sample has a
+    // finite transformed value.
+    pub fn get_transformed_range(&self, chart_type: &ChartType, transform: &Transform) -> Option<(f64, f64)> {
+        let mut range: Option<(f64, f64)> = None;
+        for value in &self.sorted_values {
+            if let Some(sample_set) = value.get_sample_set(chart_type) {
+                let transformed = sample_set.transformed(transform);
+                if transformed.is_empty() {
+                    continue;
+                }
+                range = match range {
+                    Some((min, max)) => Some((min.min(transformed.value_min), max.max(transformed.value_max))),
+                    None => Some((transformed.value_min, transformed.value_max)),
+                };
+            }
+        }
+        range
+    }
+
+    // Smallest positive transformed sample of a metric chart type, the lower bound of a log axis.
+    pub fn get_min_positive(&self, chart_type: &ChartType, transform: &Transform) -> Option<f64> {
+        self.sorted_values.iter()
+            .filter_map(|value| value.get_sample_set(chart_type))
+            .filter_map(|sample_set| sample_set.transformed(transform).get_min_positive())
+            .reduce(f64::min)
+    }
+
+    // Mean of the finite transformed samples of a metric chart type over the final commit buckets,
+    // None without any.
+    pub fn get_final_mean(&self, chart_type: &ChartType, transform: &Transform, buckets: usize) -> Option<f64> {
+        let mut final_samples = SampleSet::new();
+        for value in &self.sorted_values[self.sorted_values.len().saturating_sub(buckets)..] {
+            if let Some(sample_set) = value.get_sample_set(chart_type) {
+                final_samples.merge(&sample_set.transformed(transform));
+            }
+        }
+        match final_samples.is_empty() {
+            true => None,
+            false => Some(final_samples.get_mean()),
+        }
+    }
+
+    // Mean of the transformed samples of a metric chart type at each commit count.
+    pub fn get_mean_points(&self, chart_type: &ChartType, transform: &Transform) -> Vec<(f64, f64)> {
+        self.get_mean_points_against(chart_type, transform, &XAxis::Commits)
+    }
+
+    // Mean points positioned along the given X axis, leaving out buckets without a position.
+    pub fn get_mean_points_against(&self, chart_type: &ChartType, transform: &Transform, x_axis: &XAxis) -> Vec<(f64, f64)> {
+        self.sorted_values.iter().filter_map(|value| {
+            let x = value.get_x(x_axis)?;
+            let sample_set = value.get_sample_set(chart_type)?.transformed(transform);
+            match sample_set.is_empty() {
+                true => None,
+                false => Some((x, sample_set.get_mean())),
+            }
+        }).collect()
+    }
+
+    // The spread of the samples at each of the mean points, in the same order.
+    pub fn get_mean_spread(&self, chart_type: &ChartType, transform: &Transform) -> Vec<PointSpread> {
+        self.sorted_values.iter().filter_map(|value| {
+            let sample_set = value.get_sample_set(chart_type)?.transformed(transform);
+            match sample_set.is_empty() {
+                true => None,
+                false => Some(PointSpread { min: sample_set.value_min, max: sample_set.value_max, range: sample_set.get_half_range() }),
+            }
+        }).collect()
+    }
+
+    // Transformed raw samples of a metric or scatter chart type at their commit count, moved along
+    // the X axis by up to half of `jitter` times the average spacing between commit counts. The
+    // offsets are deterministic, see `statistics::jitter`.
+    pub fn get_raw_points(&self, chart_type: &ChartType, transform: &Transform, jitter: f64) -> Vec<(f64, f64)> {
+        let num_values = self.sorted_values.len();
+        let commits_per_bucket = match num_values {
+            0 | 1 => 0.0,
+            _ => (self.sorted_values[num_values - 1].num_commits - self.sorted_values[0].num_commits) as f64 / (num_values - 1) as f64,
+        };
+        let jitter_width = commits_per_bucket * jitter;
+
+        let mut points: Vec<(f64, f64)> = Default::default();
+        for value in &self.sorted_values {
+            if let Some(sample_set) = value.get_raw_sample_set(chart_type) {
+                for (index, sample) in sample_set.samples.iter().enumerate() {
+                    if let Some(sample) = transform.apply(*sample) {
+                        let seed = value.num_commits.wrapping_mul(31).wrapping_add(index as u64);
+                        points.push((value.num_commits as f64 + statistics::jitter(seed) * jitter_width, sample));
+                    }
+                }
+            }
+        }
+        points
+    }
+
+    // Every sample plotted by a scatter chart type, with commit times in milliseconds.
+    pub fn get_scatter_points(&self, chart_type: &ChartType, jitter: f64) -> Vec<(f64, f64)> {
+        let transform = match chart_type {
+            ChartType::CommitTimeScatter => Transform { steps: vec![transform::TransformStep::Scale(1000.0)] },
+            _ => Transform::default(),
+        };
+        self.get_raw_points(chart_type, &transform, jitter)
+    }
+
+    // Percentage change of the mean from the baseline dataset at each commit count present in both.
+    pub fn get_relative_change(&self, baseline: &DataSet, chart_type: &ChartType, transform: &Transform) -> Vec<(f64, f64)> {
+        let baseline_points = baseline.get_mean_points(chart_type, transform);
+        self.get_mean_points(chart_type, transform).into_iter().filter_map(|(x, mean)| {
+            let index = baseline_points.binary_search_by(|probe| probe.0.total_cmp(&x)).ok()?;
+            let baseline_mean = baseline_points[index].1;
+            match baseline_mean == 0.0 {
+                true => None,
+                false => Some((x, (mean - baseline_mean) / baseline_mean.abs() * 100.0)),
+            }
+        }).collect()
+    }
+
+    /// A copy with the samples at each commit count pooled with those of the neighbouring commit
+    /// counts, `window` buckets centred on each one, so that the means become a rolling mean.
+    pub fn smoothed(&self, window: usize) -> DataSet {
+        let before = window.saturating_sub(1) / 2;
+        let after = window.saturating_sub(1) - before;
+
+        let mut sorted_values: Vec<ValueSet> = Default::default();
+        for index in 0..self.sorted_values.len() {
+            let mut value = ValueSet::new(self.sorted_values[index].num_commits);
+            let end = std::cmp::min(self.sorted_values.len(), index + after + 1);
+            for neighbour in &self.sorted_values[index.saturating_sub(before)..end] {
+                value.merge(neighbour);
+            }
+            sorted_values.push(value);
+        }
+
+        self.with_values(sorted_values)
+    }
+
+    // A copy with runs of consecutive buckets merged into at most `max_points` buckets, each at the
+    // commit count of the middle bucket of its run, or None if there are no more buckets than that.
+    pub fn downsampled(&self, max_points: usize) -> Option<DataSet> {
+        if self.sorted_values.len() <= max_points {
+            return None
+        }
+
+        let run_length = self.sorted_values.len().div_ceil(max_points.max(1));
+        let sorted_values = self.sorted_values.chunks(run_length).map(|run| {
+            let mut value = ValueSet::new(run[run.len() / 2].num_commits);
+            for other in run {
+                value.merge(other);
+            }
+            value
+        }).collect();
+
+        Some(self.with_values(sorted_values))
+    }
+
+    // A copy with its buckets regrouped by their position along an X axis of a metric, in place of
+    // their commit counts: the X axis is split into as many equal bins as there are buckets with a
+    // position, or `max_buckets` if fewer, and the buckets in each bin are merged, in order of X.
+    // The merged buckets are numbered in place of a commit count, so that a metric which does not
+    // grow with the commits is plotted as a line along X rather than doubling back on itself.
+    pub fn rebucketed(&self, x_axis: &XAxis, max_buckets: Option<usize>) -> DataSet {
+        let positions: Vec<(f64, &ValueSet)> = self.sorted_values.iter().filter_map(|value| Some((value.get_x(x_axis)?, value))).collect();
+        let min = positions.iter().map(|(x, _)| *x).fold(f64::INFINITY, f64::min);
+        let max = positions.iter().map(|(x, _)| *x).fold(f64::NEG_INFINITY, f64::max);
+        let num_bins = max_buckets.map_or(positions.len(), |max_buckets| positions.len().min(max_buckets)).max(1);
+
+        let mut bins: BTreeMap<usize, Vec<&ValueSet>> = Default::default();
+        for (x, value) in positions {
+            let bin = match max > min {
+                true => (((x - min) / (max - min) * num_bins as f64) as usize).min(num_bins - 1),
+                false => 0,
+            };
+            bins.entry(bin).or_default().push(value);
+        }
+        let sorted_values = bins.into_values().enumerate().map(|(index, values)| {
+            let mut value = ValueSet::new(index as u64);
+            for other in values {
+                value.merge(other);
+            }
+            value
+        }).collect();
+
+        self.with_values(sorted_values)
+    }
+
+    // A copy with other values in place of its own, with the same name, parameters and number of
+    // commits and the maxima of the new values.
+    pub fn with_values(&self, sorted_values: Vec<ValueSet>) -> DataSet {
+        let mut dataset = DataSet { sorted_values, derived: self.derived, low_memory: self.low_memory, max_commits: self.max_commits, ..DataSet::new(self.base_name.clone(), self.parameters.clone()) };
+        dataset.update_maxima();
+        dataset
+    }
+
+    // Maxima of the metrics after samples were removed or regrouped. The number of commits is
+    // unchanged.
+    pub(crate) fn update_maxima(&mut self) {
+        self.max_commit_time = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.commit_time.value_max));
+        self.max_commits_per_second = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.commits_per_second.value_max));
+        self.max_queries_per_second = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.queries_per_second.value_max));
+        self.max_disk_size = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.disk_size.value_max));
+        self.max_write_amplification = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.write_amplification.value_max));
+        self.max_memory_usage = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.memory_usage.value_max));
+        self.max_heap_usage = self.sorted_values.iter().fold(0.0, |max, v| max.max(v.heap_usage.value_max));
+    }
+
+    // Number of samples of the chart type's metric left out with `--drop-outliers`.
+    pub fn get_dropped_outliers(&self, chart_type: &ChartType) -> usize {
+        self.sorted_values.iter().filter_map(|v| v.get_raw_sample_set(chart_type)).map(|samples| samples.dropped_outliers).sum()
+    }
+
+    // Missing commit buckets, see `gaps`.
+    pub fn get_gaps(&self) -> Vec<Gap> {
+        let commits: Vec<u64> = self.sorted_values.iter().map(|v| v.num_commits).collect();
+        gaps::find_gaps(&commits)
+    }
+
+    // The ends of each gap along the X axis, leaving out gaps next to buckets without a position.
+    pub fn get_gap_positions(&self, x_axis: &XAxis) -> Vec<(f64, f64)> {
+        let gaps = self.get_gaps();
+        self.sorted_values.windows(2)
+            .filter(|pair| gaps.iter().any(|gap| gap.start == pair[0].num_commits))
+            .filter_map(|pair| Some((pair[0].get_x(x_axis)?, pair[1].get_x(x_axis)?)))
+            .collect()
+    }
+
+    pub fn get_stalls(&self, stall_detection: &StallDetection) -> Vec<Stall> {
+        let mut values: Vec<StallBucket> = Default::default();
+        for (index, value) in self.sorted_values.iter().enumerate() {
+            // The runs of a dataset were taken at different times, so intervals are only taken
+            // between the samples of the same run.
+            let previous = index.checked_sub(1).map(|previous| &self.sorted_values[previous].run_timestamps);
+            let intervals: Vec<f64> = value.run_timestamps.iter()
+                .filter_map(|(run, timestamps)| Some(timestamps.mean() - previous?.get(run)?.mean()))
+                .collect();
+            let interval = (!intervals.is_empty()).then(|| intervals.iter().sum::<f64>() / intervals.len() as f64);
+            values.push(StallBucket { commits: value.num_commits, commit_time: value.sample_commit_time.get_mean(), interval });
+        }
+        stall_detection.detect(&values)
+    }
+
+    // Milliseconds between successive timing samples of each run, from the `timestamp` column, so
+    // that time the writer spent sleeping or descheduled shows up as well as the commits.
+    // Without timestamps each interval is taken as the commit time of the sample plus the time the
+    // writer sleeps per sample, from the `writer-sleep-time` milliseconds it sleeps every
+    // `writer-commits-per-sleep` commits.
+    pub fn get_sample_intervals(&self) -> Vec<f64> {
+        let mut run_times: BTreeMap<usize, Vec<f64>> = Default::default();
+        for value in &self.sorted_values {
+            for (run, timestamps) in &value.run_timestamps {
+                run_times.entry(*run).or_default().push(timestamps.mean());
+            }
+        }
+        if !run_times.is_empty() {
+            return run_times.values().flat_map(|times| times.windows(2).map(|pair| (pair[1] - pair[0]) * 1000.0)).filter(|interval| *interval >= 0.0).collect()
+        }
+
+        let parameter = |name: &str| self.parameters.get(name).and_then(ParameterValue::as_f64);
+        let sleep_time = match (parameter("writer-sleep-time"), parameter("writer-commits-per-sleep"), parameter("commits-per-timing-sample")) {
+            (Some(sleep_time), Some(commits_per_sleep), Some(commits_per_sample)) if commits_per_sleep > 0.0 => sleep_time * commits_per_sample / commits_per_sleep,
+            _ => 0.0,
+        };
+        self.sorted_values.iter().flat_map(|v| v.sample_commit_time.samples.iter().map(move |s| s * 1000.0 + sleep_time)).collect()
+    }
+
+    // Percentile of the raw per-sample commit times (in milliseconds) over a sliding window of the
+    // most recent samples, evaluated at each bucket.
+    pub fn get_rolling_commit_time_percentile(&self, percentile: f64, window: usize) -> Vec<(f64, f64)> {
+        let samples: Vec<(u64, f64)> = self.sorted_values.iter()
+            .flat_map(|v| v.sample_commit_time.samples.iter().map(move |s| (v.num_commits, s * 1000.0)))
+            .collect();
+
+        let window = window.max(1);
+        let mut points: Vec<(f64, f64)> = Default::default();
+        for i in 0..samples.len() {
+            // Only emit a point for the last sample in each bucket.
+            if i + 1 < samples.len() && samples[i + 1].0 == samples[i].0 {
+                continue;
+            }
+
+            let start = (i + 1).saturating_sub(window);
+            let mut windowed: Vec<f64> = samples[start..=i].iter().map(|s| s.1).collect();
+            windowed.sort_by(|a, b| a.total_cmp(b));
+            points.push((samples[i].0 as f64, statistics::percentile(&windowed, percentile)));
+        }
+
+        points
+    }
+
+    // Mean commits per second at each bucket against the mean elapsed time in seconds, i.e. the
+    // cumulative commit time, at which the bucket was reached.
+    pub fn get_commits_per_second_over_wall_clock(&self) -> Vec<(f64, f64)> {
+        self.sorted_values.iter().map(|v| (v.commit_time.get_mean(), v.commits_per_second.get_mean())).collect()
+    }
+
+    // Mean queries per second over mean commits per second at each commit count, the balance of
+    // reads and writes. Buckets without both rates, such as those of samples without any queries,
+    // whose rates are NaN, or without any commits are left out.
+    pub fn get_query_commit_ratio(&self) -> Vec<(f64, f64)> {
+        self.sorted_values.iter().filter_map(|value| {
+            let (Some(queries), Some(commits)) = (value.queries_per_second.get_finite_mean(), value.commits_per_second.get_finite_mean()) else {
+                return None
+            };
+            let ratio = queries / commits;
+            ratio.is_finite().then_some((value.num_commits as f64, ratio))
+        }).collect()
+    }
+
+    // Coefficient of variation of commits per second at each commit count, the standard deviation
+    // of the samples of a bucket over their mean, which measures jitter apart from speed. Buckets
+    // of fewer than two samples, such as those of a single run, are left out.
+    pub fn get_commits_per_second_variation(&self) -> Vec<(f64, f64)> {
+        self.sorted_values.iter().filter_map(|value| {
+            let statistics = &value.commits_per_second.statistics;
+            if statistics.num < 2 {
+                return None
+            }
+            let variation = statistics.variance().sqrt() / statistics.mean().abs();
+            variation.is_finite().then_some((value.num_commits as f64, variation))
+        }).collect()
+    }
+
+    pub fn num_runs(&self) -> usize {
+        let runs: HashSet<usize> = self.sorted_values.iter().flat_map(|v| v.run_commits_per_second.keys().copied()).collect();
+        runs.len()
+    }
+
+    // Standard deviation of commits per second at each bucket, split into the deviation between
+    // the means of different runs and the pooled deviation within each run. Within-run deviation
+    // is estimated from each run's bucket means over a window of neighbouring buckets.
+    pub fn get_commits_per_second_run_deviation(&self, window: usize) -> Vec<(f64, f64, f64)> {
+        let half_window = window / 2;
+        let mut points: Vec<(f64, f64, f64)> = Default::default();
+        for i in 0..self.sorted_values.len() {
+            let value = &self.sorted_values[i];
+
+            let mut between = RunningStatistics::new();
+            for run_statistics in value.run_commits_per_second.values() {
+                between.add_sample(run_statistics.mean());
+            }
+
+            let start = i.saturating_sub(half_window);
+            let end = std::cmp::min(self.sorted_values.len(), i + half_window + 1);
+            let mut within_sum = 0.0;
+            let mut within_degrees = 0;
+            for run in value.run_commits_per_second.keys() {
+                let mut within = RunningStatistics::new();
+                for neighbour in &self.sorted_values[start..end] {
+                    if let Some(run_statistics) = neighbour.run_commits_per_second.get(run) {
+                        within.add_sample(run_statistics.mean());
+                    }
+                }
+                if within.num > 1 {
+                    within_sum += within.variance() * (within.num - 1) as f64;
+                    within_degrees += within.num - 1;
+                }
+            }
+            let within_variance = if within_degrees > 0 { within_sum / within_degrees as f64 } else { 0.0 };
+
+            points.push((value.num_commits as f64, f64::sqrt(between.variance()), f64::sqrt(within_variance)));
+        }
+
+        points
+    }
+
+    // Autocorrelation of the mean per-sample commit time across buckets, with the lag expressed in
+    // commits. Lags run up to half the number of buckets.
+    pub fn get_commit_time_autocorrelation(&self) -> Vec<(f64, f64)> {
+        let num_values = self.sorted_values.len();
+        if num_values < 2 {
+            return Vec::new()
+        }
+
+        let first_commits = self.sorted_values[0].num_commits;
+        let last_commits = self.sorted_values[num_values - 1].num_commits;
+        let commits_per_bucket = (last_commits - first_commits) as f64 / (num_values - 1) as f64;
+
+        let means: Vec<f64> = self.sorted_values.iter().map(|v| v.sample_commit_time.get_mean()).collect();
+
+        statistics::autocorrelation(&means, num_values / 2)
+            .iter()
+            .enumerate()
+            .map(|(lag, r)| (lag as f64 * commits_per_bucket, *r))
+            .collect()
+    }
+}
+
+/// The datasets read from a set of data files, keyed by their full name.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct StressTestData {
+    pub datasets : HashMap<String, DataSet>,
+
+    pub max_commits: u64,
+    #[serde(with = "cache::float")]
+    pub max_commit_time: f64,
+    #[serde(with = "cache::float")]
+    pub max_commits_per_second: f64,
+    #[serde(with = "cache::float")]
+    pub max_queries_per_second: f64,
+    #[serde(with = "cache::float")]
+    pub max_disk_size: f64,
+    #[serde(with = "cache::float")]
+    pub max_write_amplification: f64,
+    #[serde(with = "cache::float")]
+    pub max_memory_usage: f64,
+    #[serde(with = "cache::float")]
+    pub max_heap_usage: f64,
+
+    /// Data read from the `--baseline` files.
+    #[serde(skip)]
+    pub baseline: Option<Box<StressTestData>>,
+
+    /// Datasets added from samples are in low memory mode, see [`SampleSet::with_low_memory`].
+    #[serde(skip)]
+    pub low_memory: bool,
+}
+
+impl Default for StressTestData {
+    fn default() -> StressTestData {
+        StressTestData::new()
+    }
+}
+
+impl StressTestData {
+    pub fn new() -> StressTestData {
+        StressTestData { datasets: Default::default(), max_commits: 0, max_commit_time: 0.0f64, max_commits_per_second: 0.0f64, max_queries_per_second: 0.0f64, max_disk_size: 0.0f64, max_write_amplification: 0.0f64, max_memory_usage: 0.0f64, max_heap_usage: 0.0f64, baseline: None, low_memory: false }
+    }
+
+    /// Names of the datasets, with numbers in numeric order so that `readers=2` comes before
+    /// `readers=10`.
+    pub fn sorted_names(&self) -> Vec<&String> {
+        let mut names: Vec<&String> = self.datasets.keys().collect();
+        names.sort_by(|a, b| ordering::natural_cmp(a, b));
+        names
+    }
+
+    /// The baseline dataset with the same name and parameters as a dataset.
+    pub fn get_baseline(&self, name: &str) -> Option<&DataSet> {
+        self.baseline.as_ref()?.datasets.get(name)
+    }
+
+    pub fn add_sample(&mut self, base_name: String, parameters: BTreeMap<String, ParameterValue>, commits: u64, sample: &Sample) {
+        self.max_commits = std::cmp::max(self.max_commits, commits);
+        self.max_commit_time = self.max_commit_time.max(sample.commit_time);
+        self.max_commits_per_second = self.max_commits_per_second.max(sample.commits_per_second);
+        self.max_queries_per_second = self.max_queries_per_second.max(sample.queries_per_second);
+        self.max_disk_size = self.max_disk_size.max(sample.disk_size.unwrap_or(0.0));
+        self.max_write_amplification = self.max_write_amplification.max(sample.write_amplification.unwrap_or(0.0));
+        self.max_memory_usage = self.max_memory_usage.max(sample.memory_usage.unwrap_or(0.0));
+        self.max_heap_usage = self.max_heap_usage.max(sample.heap_usage.unwrap_or(0.0));
+
+        let full_name = DataSet::get_name(base_name.clone(), &parameters);
+
+        match self.datasets.entry(full_name) {
+            std::collections::hash_map::Entry::Occupied(mut entry) => {
+                entry.get_mut().add_sample(commits, sample);
+            },
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let mut dataset = DataSet { low_memory: self.low_memory, ..DataSet::new(base_name, parameters) };
+                dataset.add_sample(commits, sample);
+                entry.insert(dataset);
+            },
+        }
+    }
+
+    // Maxima of the metrics of the datasets, after some were removed or replaced. The number of
+    // commits also covers the baseline, so is left to the caller.
+    pub(crate) fn update_maxima(&mut self) {
+        self.max_commit_time = self.datasets.values().fold(0.0, |max, d| max.max(d.max_commit_time));
+        self.max_commits_per_second = self.datasets.values().fold(0.0, |max, d| max.max(d.max_commits_per_second));
+        self.max_queries_per_second = self.datasets.values().fold(0.0, |max, d| max.max(d.max_queries_per_second));
+        self.max_disk_size = self.datasets.values().fold(0.0, |max, d| max.max(d.max_disk_size));
+        self.max_write_amplification = self.datasets.values().fold(0.0, |max, d| max.max(d.max_write_amplification));
+        self.max_memory_usage = self.datasets.values().fold(0.0, |max, d| max.max(d.max_memory_usage));
+        self.max_heap_usage = self.datasets.values().fold(0.0, |max, d| max.max(d.max_heap_usage));
+    }
+
+    // Adds the datasets of data read separately, e.g. from another file or part of a file.
+    fn merge(&mut self, other: StressTestData) {
+        self.max_commits = std::cmp::max(self.max_commits, other.max_commits);
+        self.max_commit_time = self.max_commit_time.max(other.max_commit_time);
+        self.max_commits_per_second = self.max_commits_per_second.max(other.max_commits_per_second);
+        self.max_queries_per_second = self.max_queries_per_second.max(other.max_queries_per_second);
+        self.max_disk_size = self.max_disk_size.max(other.max_disk_size);
+        self.max_write_amplification = self.max_write_amplification.max(other.max_write_amplification);
+        self.max_memory_usage = self.max_memory_usage.max(other.max_memory_usage);
+        self.max_heap_usage = self.max_heap_usage.max(other.max_heap_usage);
+
+        for (name, dataset) in other.datasets {
+            match self.datasets.entry(name) {
+                std::collections::hash_map::Entry::Occupied(mut entry) => entry.get_mut().merge(dataset),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(dataset);
+                },
+            }
+        }
+    }
+
+    pub fn add_derived_series(&mut self, series: &DerivedSeries) -> Result<(), Box<dyn Error>> {
+        for (name, dataset) in series.derive(&self.datasets)? {
+            self.datasets.insert(name, dataset);
+        }
+        Ok(())
+    }
+}
+
+// A data path which reads from standard input, so that the stress test can be piped straight into
+// the visualizer.
+pub(crate) static STDIN_PATH: &str = "-";
+
+pub(crate) fn is_stdin(path: &std::path::Path) -> bool {
+    path.as_os_str() == STDIN_PATH
+}
+
+// With `--compare`, the new run is read as the data and the old run as its baseline.
+fn apply_compare(args: &mut Args) {
+    if let Some([old, new]) = args.compare.clone().as_deref() {
+        args.data_path = Some(vec![new.clone()]);
+        args.baseline = vec![old.clone()];
+        args.baseline_mode = BaselineMode::Significance;
+    }
+}
+
+// Replaces the directories and glob patterns of the data and baseline paths with their files,
+// repeating the tag of each data path for every file found.
+fn resolve_data_paths(args: &mut Args) -> Result<(), Box<dyn Error>> {
+    if let Some(paths) = &args.data_path {
+        if !args.tag.is_empty() && args.tag.len() != paths.len() {
+            return Err(format!("Got {} tags for {} data paths, expected one tag per data path", args.tag.len(), paths.len()).into())
+        }
+
+        let mut files: Vec<PathBuf> = Default::default();
+        let mut tags: Vec<Tag> = Default::default();
+        for (index, path) in paths.iter().enumerate() {
+            let path_files = find_data_files(std::slice::from_ref(path))?;
+            if let Some(tag) = args.tag.get(index) {
+                tags.extend(std::iter::repeat_n(tag.clone(), path_files.len()));
+            }
+            files.extend(path_files);
+        }
+        args.data_path = Some(files);
+        args.tag = tags;
+    }
+    args.baseline = find_data_files(&args.baseline)?;
+
+    let num_stdin = args.data_path.iter().flatten().chain(&args.baseline).filter(|path| is_stdin(path)).count();
+    if num_stdin > 1 {
+        return Err("Standard input can only be read once, give '-' as a single data path".into())
+    }
+    if num_stdin > 0 && args.interactive {
+        return Err("--interactive reads its answers from standard input, so cannot be used with '-' as a data path".into())
+    }
+    if num_stdin > 0 && args.serve_metrics.is_some() {
+        return Err("--serve-metrics reads the data files again as they grow, so cannot be used with '-' as a data path".into())
+    }
+    if num_stdin > 0 && args.serve.is_some() {
+        return Err("--serve reads the data files again as they grow, so cannot be used with '-' as a data path".into())
+    }
+    Ok(())
+}
+
+fn get_stress_test_data(args: &Args) -> Result<Option<StressTestData>, VisualizerError> {
+    let Some(paths) = args.data_path.clone() else {
+        return Ok(None)
+    };
+
+    let tags = args.tag.iter().map(|tag| BTreeMap::from([(tag.name.clone(), tag.value.clone())])).collect();
+    let cache_dir = args.cache.then(|| std::env::current_dir().expect("Cannot resolve current dir").join("visualizer_output").join("cache"));
+    let schema = match &args.schema {
+        Some(path) => Some(std::sync::Arc::new(Schema::load(path)?)),
+        None => None,
+    };
+    let options = LoadOptions { input_format: args.input_format.clone(), schema, strict: args.strict, tags, tag_by_file: args.tag_by_file, machine_metadata: args.machine_metadata, merge_policy: args.merge_policy.clone(), cache_dir, low_memory: args.low_memory };
+    let mut data = logging::with_progress("Reading data files", || load_stress_test_data(&paths, &options))?;
+
+    // Baseline datasets are matched by name, so are read without tags or machine parameters.
+    if !args.baseline.is_empty() {
+        log::info!("Reading baseline");
+        let baseline_options = LoadOptions { tags: Default::default(), tag_by_file: false, machine_metadata: false, ..options };
+        let baseline = logging::with_progress("Reading baseline", || load_stress_test_data(&args.baseline, &baseline_options))?;
+        data.max_commits = data.max_commits.max(baseline.max_commits);
+        data.baseline = Some(Box::new(baseline));
+    }
+
+    // Before aggregating, so that a dropped run is not pooled with the others.
+    selection::select_datasets(&mut data, &args.include_dataset, &args.exclude_dataset);
+
+    for parameter in &args.aggregate {
+        aggregate::aggregate(&mut data, parameter);
+    }
+
+    Ok(Some(data))
+}
+
+/// How data files are read by [`load_stress_test_data`].
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Format of every data file. Inferred from the extension of each file when not given.
+    pub input_format: Option<InputFormat>,
+    /// Columns of CSV data files, in place of those named in the header line.
+    pub schema: Option<std::sync::Arc<Schema>>,
+    /// Fail at the first malformed line instead of reporting and skipping it.
+    pub strict: bool,
+    /// Extra parameters for the datasets of each file, by position in the paths.
+    pub tags: Vec<BTreeMap<String, ParameterValue>>,
+    /// Add the file name of each file as the `run` parameter of its datasets.
+    pub tag_by_file: bool,
+    /// Add the fields of the machine metadata file next to each file as parameters of its
+    /// datasets, prefixed with `machine-`.
+    pub machine_metadata: bool,
+    /// How datasets found in several files are combined.
+    pub merge_policy: MergePolicy,
+    /// Directory to keep the data parsed from each file in, so that later loads only parse the
+    /// lines appended since.
+    pub cache_dir: Option<PathBuf>,
+    /// Keep only the statistics and a digest of the samples, see [`SampleSet::with_low_memory`],
+    /// and read the files one chunk of lines at a time.
+    pub low_memory: bool,
+}
+
+// The data read from one source, with the malformed records skipped in order.
+struct FileData {
+    data: StressTestData,
+    malformed: Vec<VisualizerError>,
+}
+
+/// Reads stress test data files, each one a separate run of the same tests. Settings from the
+/// stress test config next to a data file are added to the parameters of its datasets.
+///
+/// Files, and chunks of lines within each file, are parsed in parallel. The parts are merged in
+/// order, so the result is the same as reading the lines one after another.
+///
+/// Malformed lines, such as those cut short by an interrupted run, are reported and skipped unless
+/// `strict` is set.
+pub fn load_stress_test_data(paths: &[PathBuf], options: &LoadOptions) -> Result<StressTestData, VisualizerError> {
+    let sources: Vec<Box<dyn DataSource>> = paths.iter().map(|path| source::file_source(path, options.input_format.clone(), options.schema.clone())).collect();
+    load_from_sources(&sources, options)
+}
+
+/// Reads the records of each source, each one a separate run of the same tests, as
+/// [`load_stress_test_data`] reads data files. The `input_format` of the options is not used.
+pub fn load_from_sources(sources: &[Box<dyn DataSource>], options: &LoadOptions) -> Result<StressTestData, VisualizerError> {
+    let (start, start_lines) = (std::time::Instant::now(), logging::get_lines_read());
+    for (run, source) in sources.iter().enumerate() {
+        log::info!("Reading data file {}/{}: {}", run + 1, sources.len(), source.location().display());
+    }
+
+    let files: Vec<Result<FileData, VisualizerError>> = sources.par_iter().enumerate().map(|(run, source)| {
+        let file_start = std::time::Instant::now();
+        let file = load_source(run, source.as_ref(), options);
+        if let Ok(file) = &file {
+            log::debug!("Parsed {} into {} datasets in {:.3}s", source.location().display(), file.data.datasets.len(), file_start.elapsed().as_secs_f64());
+        }
+        file
+    }).collect();
+
+    // Errors and skipped lines are reported in file order, whichever thread finished first.
+    let mut data = StressTestData::new();
+    let mut replaced: HashSet<String> = Default::default();
+    for (source, file) in sources.iter().zip(files) {
+        let file = file?;
+        if options.strict {
+            if let Some(error) = file.malformed.into_iter().next() {
+                return Err(error)
+            }
+        } else if !file.malformed.is_empty() {
+            for error in &file.malformed {
+                log::warn!("Skipping malformed line {}", error);
+            }
+            log::warn!("Skipped {} malformed lines in {}", file.malformed.len(), source.location().display());
+        }
+        if options.merge_policy == MergePolicy::KeepLatest {
+            for name in file.data.datasets.keys() {
+                if data.datasets.remove(name).is_some() {
+                    replaced.insert(name.clone());
+                }
+            }
+        }
+        data.merge(file.data);
+    }
+
+    if !replaced.is_empty() {
+        data.update_maxima();
+        data.max_commits = data.datasets.values().fold(0, |max, d| max.max(d.max_commits));
+        log::info!("Kept the latest run of {} datasets found in several data files", replaced.len());
+    }
+    if sources.len() > 1 && options.merge_policy == MergePolicy::Merge {
+        print_runs(&data);
+    }
+    log::info!("Read {} lines of {} data files into {} datasets in {:.2}s", logging::get_lines_read() - start_lines, sources.len(), data.datasets.len(), start.elapsed().as_secs_f64());
+
+    Ok(data)
+}
+
+// The number of data files each dataset was merged from.
+fn print_runs(data: &StressTestData) {
+    let names = data.sorted_names();
+
+    log::info!("Runs merged into each dataset:");
+    for name in names {
+        let num_runs = data.datasets[name].num_runs();
+        log::info!("  {}: {} {}", name, num_runs, if num_runs == 1 { "run" } else { "runs" });
+    }
+}
+
+fn load_source(run: usize, source: &dyn DataSource, options: &LoadOptions) -> Result<FileData, VisualizerError> {
+    let mut source_parameters = source.parameters()?;
+    if options.machine_metadata {
+        let path = source.location();
+        let machine_parameters = machine::get_machine_parameters(&path).map_err(|e| VisualizerError::InvalidFile { path: path.clone(), reason: format!("Failed to read machine metadata: {}", e) })?;
+        source_parameters.extend(machine_parameters);
+    }
+
+    let mut tags: BTreeMap<String, ParameterValue> = Default::default();
+    if options.tag_by_file {
+        tags.insert(RUN_PARAMETER.to_string(), ParameterValue::Text(source.run_name()));
+    }
+    if options.merge_policy == MergePolicy::KeepSeparateWithRunIndex {
+        tags.insert(RUN_PARAMETER.to_string(), ParameterValue::Int(run as u64 + 1));
+    }
+    if let Some(file_tags) = options.tags.get(run) {
+        tags.extend(file_tags.clone());
+    }
+
+    if let Some(cache_dir) = &options.cache_dir {
+        if let Some(file) = cache::load_cached_source(cache_dir, run, source, &source_parameters, &tags, options)? {
+            return Ok(file)
+        }
+    }
+
+    fold_records(source.records(source::batch_lines(options.low_memory))?, run, &source_parameters, &tags, options)
+}
+
+// The datasets of the records of a source and its malformed records. The records are added a batch
+// at a time as they are parsed, so that only one batch of records is held. Only the first malformed
+// record is kept when strict. An error reading the source is returned rather than skipped.
+fn fold_records(records: impl Iterator<Item = Result<Record, VisualizerError>>, run: usize, source_parameters: &BTreeMap<String, ParameterValue>, tags: &BTreeMap<String, ParameterValue>, options: &LoadOptions) -> Result<FileData, VisualizerError> {
+    let batch_lines = source::batch_lines(options.low_memory);
+    let mut data = StressTestData::new();
+    let mut batch: Vec<Record> = Vec::with_capacity(batch_lines);
+    let mut malformed: Vec<VisualizerError> = Default::default();
+    for record in records {
+        match record {
+            Ok(record) => {
+                batch.push(record);
+                if batch.len() == batch_lines {
+                    data.merge(get_run_data(std::mem::replace(&mut batch, Vec::with_capacity(batch_lines)), run, source_parameters, tags, options.low_memory));
+                }
+            },
+            Err(error @ VisualizerError::Io { .. }) => return Err(error),
+            Err(error) => {
+                malformed.push(error);
+                if options.strict {
+                    break;
+                }
+            },
+        }
+    }
+    data.merge(get_run_data(batch, run, source_parameters, tags, options.low_memory));
+    Ok(FileData { data, malformed })
+}
+
+// The datasets of records of a run, with the parameters of their source and tags added.
+fn get_run_data(records: Vec<Record>, run: usize, source_parameters: &BTreeMap<String, ParameterValue>, tags: &BTreeMap<String, ParameterValue>, low_memory: bool) -> StressTestData {
+    let parts: Vec<StressTestData> = records.into_par_iter().chunks(source::CHUNK_LINES).map(|chunk| {
+        let mut part = StressTestData { low_memory, ..StressTestData::new() };
+        for mut record in chunk {
+            for (name, value) in source_parameters {
+                record.parameters.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+            record.parameters.extend(tags.clone());
+            let sample = get_sample(&record, run);
+            part.add_sample(record.base_name, record.parameters, record.total_commits, &sample);
+        }
+        part
+    }).collect();
+
+    let mut data = StressTestData::new();
+    for part in parts {
+        data.merge(part);
+    }
+    data
+}
+
+fn get_sample(record: &Record, run: usize) -> Sample {
+    let row = plugins::Row {
+        base_name: &record.base_name,
+        parameters: &record.parameters,
+        total_commits: record.total_commits,
+        total_commit_time: record.total_commit_time,
+        commits: record.commits,
+        commit_time: record.commit_time,
+        queries: record.queries,
+        query_time: record.query_time,
+        disk_size: record.disk_size,
+        bytes_written: record.bytes_written,
+        rss: record.rss,
+        heap: record.heap,
+    };
+
+    Sample {
+        run,
+        commit_time: record.total_commit_time,
+        sample_commit_time: record.commit_time,
+        commits_per_second: record.commits as f64 / record.commit_time,
+        queries_per_second: record.queries as f64 / record.query_time,
+        query_latency: match record.queries {
+            0 => None,
+            _ => Some(record.query_time / record.queries as f64 * 1_000_000.0),
+        },
+        disk_size: record.disk_size.map(|size| size as f64 / 1_000_000.0),
+        write_amplification: match (record.bytes_written, record.disk_size) {
+            (Some(bytes_written), Some(disk_size)) if disk_size > 0 => Some(bytes_written as f64 / disk_size as f64),
+            _ => None,
+        },
+        memory_usage: record.rss.map(|size| size as f64 / 1_000_000.0),
+        heap_usage: record.heap.map(|size| size as f64 / 1_000_000.0),
+        plugin_metrics: plugins::extract_metrics(&row),
+        timestamp: record.timestamp,
+    }
+}
+
+fn print_stalls(data: &StressTestData, stall_detection: &StallDetection) {
+    println!("Stalls (bucket commit time > {}x median of {} buckets):", stall_detection.threshold, stall_detection.window);
+
+    let names = data.sorted_names();
+
+    for name in names {
+        let stalls = data.datasets[name].get_stalls(stall_detection);
+        println!("  {}: {}", name, stalls.len());
+        for stall in &stalls {
+            println!("    commits={} magnitude={:.2}x duration={:.4}s", stall.commits, stall.magnitude, stall.duration);
+        }
+    }
+}
+
+// Per chart summary of the plotted datasets, used in place of drawing with `--no-render`.
+fn print_summary(data: &StressTestData, params: &Params) {
+    let mut names = data.sorted_names();
+    if let Some(sort_by) = &params.sort_by {
+        names.sort_by(|a, b| sort_by.cmp(&data.datasets[*a], &data.datasets[*b]));
+    }
+
+    for spec in &params.chart_specs {
+        println!("{}:", params.get_chart_title(spec));
+
+        for name in &names {
+            let dataset = &data.datasets[*name];
+            if !dataset.passes_filters(&spec.filters) || (dataset.derived && !spec.chart_type.is_metric()) {
+                continue;
+            }
+
+            if !spec.chart_type.is_metric() {
+                println!("  {}", name);
+                continue;
+            }
+
+            let mut means = SampleSet::new();
+            for (_, mean) in dataset.get_mean_points(&spec.chart_type, &spec.transform) {
+                means.add_sample(mean);
+            }
+
+            let mut line = format!("  {}: points={} mean={:.4} min={:.4} max={:.4}", name, means.len(), means.get_mean(), means.value_min, means.value_max);
+
+            if let Some(baseline) = data.get_baseline(name) {
+                let mut changes = SampleSet::new();
+                for (_, change) in dataset.get_relative_change(baseline, &spec.chart_type, &spec.transform) {
+                    changes.add_sample(change);
+                }
+                if !changes.is_empty() {
+                    line += &format!(" change={:+.2}% min_change={:+.2}% max_change={:+.2}%", changes.get_mean(), changes.value_min, changes.value_max);
+                }
+            }
+
+            println!("{}", line);
+        }
+    }
+}
+
+// A dataset of a chart with how it is drawn: its full name, the dataset, the styles of its line and
+// of its thick line, the colour of its fill, the pattern of its line and any marker shape of its own.
+pub type DatasetEntry<'a> = (&'a String, &'a DataSet, ShapeStyle, ShapeStyle, RGBAColor, LineStyle, Option<MarkerShape>);
+
+// The state needed to draw a single chart, as aggregated by `render_with`.
+pub struct ChartContext<'a> {
+    pub title: String,
+    pub spec: &'a ChartSpec,
+    // Datasets which pass the chart's filters.
+    pub datasets: Vec<&'a DatasetEntry<'a>>,
+    // Smoothed copies of the datasets, in the same order, when drawn over them.
+    pub smoothed: Vec<&'a DatasetEntry<'a>>,
+    // Parameters which differ between the chart's datasets and so are shown in the legend.
+    pub include_parameters: HashSet<String>,
+    // Range of the values of a metric chart's datasets, from zero unless any are negative.
+    pub y_range: std::ops::Range<f64>,
+    // Smallest positive value, where the Y axis starts with `--log-y`.
+    pub min_positive_y: Option<f64>,
+    pub parameter_labels: &'a ParameterLabels,
+    pub max_commits: u64,
+    pub annotations: &'a [Annotation],
+    pub theme: &'a Theme,
+    pub fonts: &'a Fonts,
+}
+
+impl ChartContext<'_> {
+    // The legend entry of a dataset, noting how many of the plotted samples were outliers.
+    pub fn display_name(&self, dataset: &DataSet) -> String {
+        let name = DataSet::get_name_including(dataset.base_name.clone(), &dataset.parameters, &self.include_parameters, self.parameter_labels);
+        let name = match self.spec.filters.group_label(&dataset.base_name, &dataset.parameters, self.parameter_labels) {
+            Some(group) => format!("{}: {}", group, name),
+            None => name,
+        };
+        match dataset.get_dropped_outliers(&self.spec.chart_type) {
+            0 => name,
+            1 => format!("{} (1 outlier dropped)", name),
+            dropped => format!("{} ({} outliers dropped)", name, dropped),
+        }
+    }
+}
+
+// Colour of a dataset from its position in name order, or from its `--color-by` parameter value.
+// Datasets without the parameter, such as derived series, keep their sort order.
+fn get_dataset_colour(params: &Params, sort_index: usize, dataset: &DataSet) -> RGBColor {
+    let colour_index = match params.colour_by.as_ref().and_then(|name| dataset.parameters.get(name)) {
+        Some(value) => palette::parameter_colour_index(value),
+        None => sort_index,
+    };
+    params.palette.colour(colour_index)
+}
+
+// With `--accessible` each dataset gets the next marker shape in name order, and otherwise every
+// dataset has the marker shape of the chart.
+fn get_dataset_marker(params: &Params, sort_index: usize) -> Option<MarkerShape> {
+    let shapes = [MarkerShape::Circle, MarkerShape::Triangle, MarkerShape::Cross];
+    params.accessible.then(|| shapes[sort_index % shapes.len()])
+}
+
+fn draw_stress_test_data<DB: DrawingBackend>(b: &DrawingArea<DB, plotters::coord::Shift>, data: &StressTestData, params: &Params, chart_specs: &[ChartSpec]) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut renderer = PlottersRenderer::new(b, params, chart_specs.len())?;
+    render_with(&mut renderer, data, params, chart_specs)
+}
+
+// Lowest and highest bucket positions of the datasets along the X axis, padded when they are the
+// same so that the axis is not empty.
+fn get_x_range<'a>(datasets: impl Iterator<Item = &'a DataSet>, x_axis: &XAxis) -> Option<std::ops::Range<f64>> {
+    let positions: Vec<f64> = datasets.flat_map(|dataset| dataset.sorted_values.iter().filter_map(|value| value.get_x(x_axis))).collect();
+    let min = positions.iter().copied().reduce(f64::min)?;
+    let max = positions.iter().copied().reduce(f64::max)?;
+    match max > min {
+        true => Some(min..max),
+        false => Some(min - 1.0..max + 1.0),
+    }
+}
+
+// Mean line, error bars and annotations of each dataset on a metric chart, on either a linear or a
+// log Y axis.
+fn draw_metric_chart<'a, DB: DrawingBackend + 'a, Y: Ranged<ValueType = f64> + plotters::coord::ranged1d::ValueFormatter<f64>>(mut cc: plotters::chart::ChartContext<'a, DB, Cartesian2d<plotters::coord::types::RangedCoordf64, Y>>, context: &'a ChartContext<'a>, data: &'a StressTestData, params: &'a Params, pixel_height: f64) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let spec = context.spec;
+    let chart_type = &spec.chart_type;
+
+    let x_span = cc.x_range().end - cc.x_range().start;
+    // Enough decimals to tell apart labels a tenth of the axis apart.
+    let x_decimals = (1.0 - (x_span / 10.0).log10().floor()).clamp(0.0, 6.0) as usize;
+    let x_label_formatter = |v: &f64| match params.x_axis {
+        XAxis::Commits => format!("{:.0}", v),
+        XAxis::Time => time_axis::format_time_label(*v, x_span),
+        XAxis::Metric(_) => format!("{:.*}", x_decimals, v),
+    };
+    // Normalized values are ratios rather than in the units of the chart type.
+    let y_quantity = match params.normalize_to {
+        Some(_) => units::YQuantity::Other,
+        None => units::YQuantity::of_chart(spec),
+    };
+    let y_label_formatter = units::get_y_label_formatter(spec.options.y_unit, y_quantity, cc.y_range(), spec.options.log_y);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc(match &params.x_axis {
+            XAxis::Commits => "Commits".to_string(),
+            XAxis::Time => "Time".to_string(),
+            XAxis::Metric(chart_type) => chart_type.title(),
+        })
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&x_label_formatter);
+    if let Some(formatter) = &y_label_formatter {
+        mesh.y_label_formatter(formatter);
+    }
+    mesh.draw()?;
+
+    if params.x_axis == XAxis::Commits {
+        annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
+    }
+    reference_lines::draw_reference_lines(&mut cc, context, params, pixel_height)?;
+
+    // Values below the axis, which on a log axis are the non-positive ones, are left out.
+    let min_y = cc.y_range().start;
+
+    let marker_size = spec.options.marker_size.map_or((pixel_height * 0.0025) as i32, |size| size as i32);
+    let errorbar_size = spec.options.marker_size.map_or((pixel_height * 0.004) as i32, |size| (size * 8 / 5) as i32);
+
+    for (index, entry) in context.datasets.iter().enumerate() {
+        if spec.options.raw_samples {
+            let raw_points: Vec<(f64, f64)> = entry.1.get_raw_points(chart_type, &spec.transform, spec.options.jitter).into_iter().filter(|point| point.1 >= min_y).collect();
+
+            let raw_size = std::cmp::max(2, marker_size * 2 / 3);
+            cc.draw_series(raw_points.iter().map(|point| Circle::new(*point, raw_size, entry.4.mix(0.5).filled())))?;
+        }
+
+        let mut points: Vec<(f64, f64)> = Default::default();
+        let mut points_neg: Vec<(f64, f64)> = Default::default();
+        let mut points_pos: Vec<(f64, f64)> = Default::default();
+        let mut errorbars: Vec<(f64, f64, f64, f64)> = Default::default();
+        let mut band: Vec<(f64, f64, f64)> = Default::default();
+        for value in &entry.1.sorted_values {
+            let Some(x) = value.get_x(&params.x_axis) else {
+                continue;
+            };
+
+            // Plugin metrics may have skipped every row at this commit count.
+            let Some(sample_set) = value.get_sample_set(chart_type) else {
+                continue;
+            };
+
+            let transformed;
+            let sample_set = match spec.transform.is_identity() {
+                true => sample_set,
+                false => {
+                    transformed = sample_set.transformed(&spec.transform);
+                    &transformed
+                },
+            };
+
+            if sample_set.is_empty() || sample_set.get_mean() < min_y {
+                continue;
+            }
+
+            let (range_start, range_end) = sample_set.get_range(&spec.options.range);
+            let value_data = (x, sample_set.value_min.max(min_y), range_start, sample_set.get_mean(), range_end, sample_set.value_max);
+
+            points.push((value_data.0, value_data.3));
+            points_neg.push((value_data.0, value_data.2));
+            points_pos.push((value_data.0, value_data.4));
+            errorbars.push((value_data.0, value_data.1, value_data.3, value_data.5));
+            if let Some(half_width) = sample_set.get_confidence_half_width() {
+                band.push((value_data.0, value_data.3 - half_width, value_data.3 + half_width));
+            }
+        }
+
+        // Lines are broken at gaps unless drawn across them, when the gaps may be shaded beneath.
+        let gap_positions = entry.1.get_gap_positions(&params.x_axis);
+        let line_gaps: &[(f64, f64)] = match spec.options.gaps {
+            GapMode::Break => &gap_positions,
+            GapMode::Interpolate | GapMode::Shade => &[],
+        };
+        if spec.options.gaps == GapMode::Shade {
+            let max_y = cc.y_range().end;
+            cc.draw_series(gap_positions.iter().map(|(start, end)| Rectangle::new([(*start, min_y), (*end, max_y)], entry.4.mix(0.1).filled())))?;
+        }
+
+        // The band is kept within the axis so that wide intervals from small buckets do not spill
+        // over neighbouring charts.
+        let range_band: Vec<(f64, f64, f64)> = points_neg.iter().zip(&points_pos).map(|((x, lower), (_, upper))| (*x, *lower, *upper)).collect();
+        let band_drawn = match spec.options.spread {
+            Spread::Ci95 => &band,
+            Spread::RangeBand => &range_band,
+            _ => &Vec::new(),
+        };
+        if !band_drawn.is_empty() {
+            let max_y = cc.y_range().end;
+            let outline: Vec<(f64, f64)> = band_drawn.iter().map(|(x, _, upper)| (*x, upper.clamp(min_y, max_y)))
+                .chain(band_drawn.iter().rev().map(|(x, lower, _)| (*x, lower.clamp(min_y, max_y))))
+                .collect();
+            cc.draw_series(std::iter::once(Polygon::new(outline, entry.4.mix(0.25).filled())))?;
+        }
+
+        let display_name = context.display_name(entry.1);
+        let trend = spec.options.trend.and_then(|fit| Trend::fit(&points, fit));
+
+        // An overlaid rolling mean takes the legend entry from the faded unsmoothed mean line.
+        let mean_line = match context.smoothed.get(index) {
+            Some(smoothed) => {
+                for run in gaps::split_at_gaps(points, line_gaps) {
+                    line_style::draw_line(&mut cc, run, entry.5, entry.4.mix(0.4).stroke_width(params.stroke_width as u32))?;
+                }
+                smoothed.1.get_mean_points_against(chart_type, &spec.transform, &params.x_axis).into_iter().filter(|(_, y)| *y >= min_y).collect()
+            },
+            None => points,
+        };
+        for (run_index, run) in gaps::split_at_gaps(mean_line, line_gaps).into_iter().enumerate() {
+            let series = line_style::draw_line(&mut cc, run, entry.5, entry.3)?;
+            if run_index == 0 {
+                series.label(display_name.clone())
+                    .legend(move |point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
+            }
+        }
+
+        if let (BaselineMode::Overlay, XAxis::Commits, Some(baseline)) = (&params.baseline_mode, &params.x_axis, data.get_baseline(entry.0)) {
+            let downsampled = params.get_downsampled(baseline, chart_type);
+            let baseline = downsampled.as_ref().unwrap_or(baseline);
+            let dash_size = (pixel_height * 0.01) as i32;
+            let baseline_gaps = match spec.options.gaps {
+                GapMode::Break => baseline.get_gap_positions(&XAxis::Commits),
+                GapMode::Interpolate | GapMode::Shade => Default::default(),
+            };
+            let baseline_points = baseline.get_mean_points(chart_type, &spec.transform).into_iter().filter(|(_, y)| *y >= min_y).collect();
+            for (run_index, run) in gaps::split_at_gaps(baseline_points, &baseline_gaps).into_iter().enumerate() {
+                let series = cc.draw_series(DashedLineSeries::new(run, dash_size, dash_size, entry.3))?;
+                if run_index == 0 {
+                    series.label(format!("{} (baseline)", context.display_name(baseline)))
+                        .legend(move |(x, y)| plotters::element::DashedPathElement::new(vec![(x, y), (x + (pixel_height * 0.03) as i32, y)], dash_size / 2, dash_size / 2, entry.3));
+                }
+            }
+        }
+
+        // Thin and finely dashed to stand apart from both the mean line and the baseline.
+        if let Some(trend) = &trend {
+            let dash_size = std::cmp::max(2, (pixel_height * 0.004) as i32);
+            cc.draw_series(DashedLineSeries::new(trend.get_points().into_iter().filter(|(_, y)| *y >= min_y), dash_size, dash_size, entry.2))?
+                .label(format!("{} trend: {}", context.display_name(entry.1), trend.describe(spec, &params.x_axis)))
+                .legend(move |(x, y)| plotters::element::DashedPathElement::new(vec![(x, y), (x + (pixel_height * 0.03) as i32, y)], dash_size, dash_size, entry.2));
+        }
+
+        cc.draw_series(errorbars.iter().map(|(x, _, mean, _)| get_marker(entry.6.unwrap_or(spec.options.marker_shape), (*x, *mean), marker_size, entry.2.filled())))?;
+
+        // The caps are anchored at their own points, as the pixel distance between two values
+        // depends on the axis scale.
+        let errorbars_drawn: Vec<(f64, f64, f64)> = match spec.options.spread {
+            Spread::MinMax => errorbars.iter().map(|(x, min, _, max)| (*x, *min, *max)).collect(),
+            Spread::Ci95Bars => band.iter().map(|(x, lower, upper)| (*x, lower.max(min_y), *upper)).collect(),
+            Spread::RangeBars => range_band.iter().map(|(x, lower, upper)| (*x, lower.max(min_y), *upper)).collect(),
+            Spread::Ci95 | Spread::RangeBand | Spread::None => Default::default(),
+        };
+        cc.draw_series(errorbars_drawn.iter().skip_while(|(_, min, max)| { max <= min }).map(|(x, min, max)| PathElement::new(vec![(*x, *min), (*x, *max)], entry.2)))?;
+        let caps: Vec<(f64, f64)> = errorbars_drawn.iter().skip_while(|(_, min, max)| { max <= min }).flat_map(|(x, min, max)| [(*x, *min), (*x, *max)]).collect();
+        cc.draw_series(caps.iter().map(|point| {
+            EmptyElement::at(*point)
+            + PathElement::new(vec![(-errorbar_size, 0), (errorbar_size, 0)], entry.2)
+        }))?;
+
+        if let (Some(stall_detection), false) = (&spec.options.stall_detection(), entry.1.derived) {
+            let stalls = entry.1.get_stalls(stall_detection);
+            let stall_points: Vec<(f64, f64, f64)> = stalls.iter().filter_map(|stall| {
+                errorbars.iter().find(|(x, _, _, _)| *x == stall.commits as f64).map(|(x, _, mean, _)| (*x, *mean, stall.magnitude))
+            }).collect();
+
+            cc.draw_series(stall_points.iter().map(|(x, mean, magnitude)| {
+                EmptyElement::at((*x, *mean))
+                + Cross::new((0, 0), errorbar_size * 2, entry.3)
+                + Text::new(format!("{:.1}x", magnitude), (errorbar_size * 2, -errorbar_size * 4), (context.fonts.family.as_str(), pixel_height * 0.015).into_font().color(&entry.4))
+            }))?;
+        }
+    }
+
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
 }
 
-#[derive(Debug)]
-pub struct ChartSpec {
-    pub chart_type: ChartType,
-    pub filters: ParameterFilterSet,
+// A marker of any shape, so that the shape can be chosen per chart.
+fn get_marker<DB: DrawingBackend, Coord: Clone + 'static>(shape: MarkerShape, point: Coord, size: i32, style: ShapeStyle) -> DynElement<'static, DB, Coord> {
+    match shape {
+        MarkerShape::Circle => Circle::new(point, size, style).into_dyn(),
+        MarkerShape::Cross => Cross::new(point, size, style).into_dyn(),
+        MarkerShape::Triangle => TriangleMarker::new(point, size, style).into_dyn(),
+    }
 }
 
-#[derive(Debug)]
-pub struct Params {
-    pub stroke_width: u64,
-    pub chart_specs: Vec<ChartSpec>,
-}
+// Percentage change of each dataset's mean from its baseline dataset, for metric charts with
+// `--baseline-mode delta`, or with `significance` with a marker at each significant change.
+fn draw_baseline_delta_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext, baseline: &StressTestData, significance: bool) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let chart_type = &context.spec.chart_type;
 
-pub fn run_visualizer() -> Result<(), Box<dyn Error>> {
-    let args = Args::parse();
+    let mut series = Vec::new();
+    let mut min_y: f64 = 0.0;
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let Some(baseline_dataset) = baseline.datasets.get(entry.0) else {
+            continue;
+        };
+        let comparisons = match significance {
+            true => compare::compare_buckets(entry.1, baseline_dataset, chart_type, &context.spec.transform),
+            false => Default::default(),
+        };
+        let points = match significance {
+            true => comparisons.iter().map(|c| (c.commits, c.change_percent)).collect(),
+            false => entry.1.get_relative_change(baseline_dataset, chart_type, &context.spec.transform),
+        };
+        let significant: Vec<(f64, f64)> = comparisons.iter().filter(|c| c.significant).map(|c| (c.commits, c.change_percent)).collect();
+        for (_, y) in &points {
+            min_y = min_y.min(*y);
+            max_y = max_y.max(*y);
+        }
+        series.push((entry, points, significant));
+    }
 
-    let mut output_path = std::env::current_dir().expect("Cannot resolve current dir");
-    output_path.push("visualizer_output");
-    std::fs::create_dir_all(&output_path).expect("Failed to create visualizer_output directory");
-    output_path.push("stress_test_charts.png");
+    // Keep some room around the zero line when there is little or no change.
+    let margin = ((max_y - min_y) * 0.05).max(1.0);
+    let (min_y, max_y) = (min_y - margin, max_y + margin);
 
-    let chart_size_scale = match args.small_image { 
-        false => 2,
-        true => 1,
-    };
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-    let chart_width = 1080 * chart_size_scale;
-    let chart_height = 1080 * chart_size_scale;
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((6).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(match significance {
+            true => format!("{} vs baseline, marked where significant at 95%", context.title),
+            false => format!("{} vs baseline", context.title),
+        }, context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(context.spec.x_range.apply(0.0f64..context.max_commits as f64), context.spec.y_range.apply(min_y..max_y))?;
 
-    let image_size = match args.chart_type.len() {
-        0 => {(chart_width, chart_height)},
-        1 => {(chart_width, chart_height)},
-        2 => {(chart_width * 2, chart_height)},
-        3 => {(chart_width * 3, chart_height)},
-        _ => {(chart_width * 2, chart_height * 2)},
-    };
+    cc.configure_mesh()
+        .x_desc("Commits")
+        .y_desc("Change from baseline (%)")
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v))
+        .y_label_formatter(&|v| format!("{:+.0}%", v))
+        .draw()?;
 
-    // Params
-    let params = {
-        let stroke_width = match args.small_image {
-            false => 2,
-            true => 1,
-        };
+    annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
 
-        let mut chart_specs: Vec<ChartSpec> = Default::default();
-        for i in 0..args.chart_type.len() {
-            let chart_type = args.chart_type[i].clone();
+    cc.draw_series(LineSeries::new(vec![(0.0, 0.0), (context.max_commits as f64, 0.0)], context.theme.text.stroke_width(1)))?;
 
-            let filter_text = if i < args.chart_filter.len() {
-                args.chart_filter[i].clone()
-            } else {
-                "".to_string()
-            };
+    let marker_size = (pixel_height * 0.004) as i32;
+    for (entry, points, significant) in series {
+        line_style::draw_line(&mut cc, points, entry.5, entry.3)?
+            .label(context.display_name(entry.1))
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
+        cc.draw_series(significant.into_iter().map(|point| Circle::new(point, marker_size, entry.4.filled())))?;
+    }
 
-            let filters = ParameterFilterSet::new(&filter_text);
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
 
-            let chart_spec = ChartSpec {
-                chart_type: chart_type,
-                filters: filters,
-            };
+    Ok(())
+}
 
-            chart_specs.push(chart_spec);
+fn draw_autocorrelation_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_x: f64 = 0.0;
+    for entry in &context.datasets {
+        let points = entry.1.get_commit_time_autocorrelation();
+        if let Some(last) = points.last() {
+            max_x = max_x.max(last.0);
         }
+        series.push((entry, points));
+    }
 
-        Params { stroke_width: stroke_width, chart_specs: chart_specs }
-    };
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-    let root_area = BitMapBackend::new(output_path.as_path(), image_size).into_drawing_area();
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((6).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(0.0f64..max_x.max(1.0), context.spec.y_range.apply(-1.0f64..1.0f64))?;
 
-    root_area.fill(&WHITE)?;
+    cc.configure_mesh()
+        .x_desc("Lag (Commits)")
+        .x_labels(10)
+        .y_labels(9)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v))
+        .draw()?;
 
-    let data = get_stress_test_data(&args);
-    
-    if let Some(data_value) = data {
-        draw_stress_test_data(&root_area, &data_value, &params)?;
-    }
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
 
-    root_area.present().expect("Unable to write result to file");
+        line_style::draw_line(&mut cc, points, entry.5, entry.3)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
+    }
 
-    println!("Wrote file: {}", output_path.display());
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
 
     Ok(())
 }
 
-struct RunningStatistics {
-    pub num: u64,
-    pub old_m: f64,
-    pub new_m: f64,
-    pub old_s: f64,
-    pub new_s: f64,
-}
+fn draw_sample_interval_histogram<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_x: f64 = 0.0;
+    for entry in &context.datasets {
+        let intervals = entry.1.get_sample_intervals();
+        max_x = intervals.iter().fold(max_x, |a, b| a.max(*b));
+        series.push((entry, intervals));
+    }
+    max_x = max_x.max(1.0);
 
-impl RunningStatistics {
-    pub fn new() -> RunningStatistics {
-        RunningStatistics { num: 0, old_m: 0.0, new_m: 0.0, old_s: 0.0, new_s: 0.0 }
+    let num_bins = context.spec.options.histogram_bins.max(1);
+    let bin_width = max_x / num_bins as f64;
+
+    let mut max_y: u64 = 0;
+    let mut histograms = Vec::new();
+    for (entry, intervals) in series {
+        let counts = statistics::histogram(&intervals, 0.0, max_x, num_bins);
+        max_y = counts.iter().fold(max_y, |a, b| a.max(*b));
+        histograms.push((entry, counts));
     }
 
-    pub fn add_sample(&mut self, sample: f64) {
-        self.num += 1;
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-        if self.num == 1 {
-            self.old_m = sample;
-            self.new_m = sample;
-            self.old_s = 0.0;
-        }
-        else {
-            self.new_m = self.old_m + ((sample - self.old_m) / self.num as f64);
-            self.new_s = self.old_s + ((sample - self.old_m) * (sample - self.new_m));
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((6).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(0.0f64..max_x, context.spec.y_range.apply(0.0f64..(max_y.max(1) as f64 * 1.05)))?;
 
-            self.old_m = self.new_m;
-            self.old_s = self.new_s;
-        }
-    }
+    cc.configure_mesh()
+        .x_desc("Time Between Samples (ms)")
+        .y_desc("Samples")
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .y_label_formatter(&|v| format!("{:.0}", v))
+        .draw()?;
 
-    pub fn mean(&self) -> f64 {
-        if self.num > 0 {
-            return self.new_m
-        }
-        0.0
-    }
+    for (entry, counts) in histograms {
+        let display_name = context.display_name(entry.1);
 
-    pub fn variance(&self) -> f64 {
-        if self.num > 1 {
-            return self.new_s / ((self.num - 1) as f64)
+        cc.draw_series(counts.iter().enumerate().filter(|(_, count)| **count > 0).map(|(bin, count)| {
+            let x0 = bin as f64 * bin_width;
+            Rectangle::new([(x0, 0.0), (x0 + bin_width, *count as f64)], entry.4.mix(0.3).filled())
+        }))?;
+
+        let mut outline: Vec<(f64, f64)> = vec![(0.0, 0.0)];
+        for (bin, count) in counts.iter().enumerate() {
+            let x0 = bin as f64 * bin_width;
+            outline.push((x0, *count as f64));
+            outline.push((x0 + bin_width, *count as f64));
         }
-        0.0
+        outline.push((max_x, 0.0));
+
+        line_style::draw_line(&mut cc, outline, entry.5, entry.2)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
     }
-}
 
-struct SampleSet {
-    pub samples : Vec<f64>,
-    pub value_min : f64,
-    pub value_max : f64,
-    pub statistics : RunningStatistics,
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
 }
 
-impl SampleSet {
-    pub fn new() -> SampleSet {
-        SampleSet { samples: Default::default(), value_min: 0.0, value_max: 0.0, statistics: RunningStatistics::new() }
+fn draw_rolling_percentile_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points = entry.1.get_rolling_commit_time_percentile(context.spec.options.rolling_percentile, context.spec.options.rolling_window);
+        max_y = points.iter().fold(max_y, |a, b| a.max(b.1));
+        series.push((entry, points));
     }
 
-    pub fn add_sample(&mut self, sample: f64) {
-        match self.samples.len() {
-            0 => {
-                self.value_min = sample;
-                self.value_max = sample;
-            },
-            _ => {
-                self.value_min = self.value_min.min(sample);
-                self.value_max = self.value_max.max(sample);
-            },
-        }
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-        self.samples.push(sample);
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(context.spec.x_range.apply(0.0f64..context.max_commits as f64), context.spec.y_range.apply(0.0f64..max_y.max(f64::MIN_POSITIVE)))?;
 
-        self.statistics.add_sample(sample);
-    }
+    let y_desc = format!("p{} Commit Time (ms), {} Sample Window", context.spec.options.rolling_percentile, context.spec.options.rolling_window);
 
-    pub fn get_mean(&self) -> f64 {
-        self.statistics.mean()
-    }
+    cc.configure_mesh()
+        .x_desc("Commits")
+        .y_desc(y_desc)
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v))
+        .draw()?;
 
-    fn get_half_range(&self) -> f64 {
-        //self.statistics.variance() * 4.0
-        f64::sqrt(self.statistics.variance()) * 2.0
+    annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
+
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
+
+        line_style::draw_line(&mut cc, points, entry.5, entry.3)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
     }
 
-    pub fn get_range_start(&self) -> f64 {
-        self.statistics.mean() - self.get_half_range()
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
+}
+
+// A group of two bars for each dataset, the mean commits and queries per second over its final
+// commit buckets, so that the fastest configuration overall stands out. Bars are labelled with
+// their values, and datasets without queries only have a commits bar.
+fn draw_final_throughput_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let options = &context.spec.options;
+    let mut groups = Vec::new();
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let commits = entry.1.get_final_mean(&ChartType::CommitsPerSecond, &context.spec.transform, options.final_buckets);
+        let queries = entry.1.get_final_mean(&ChartType::QueriesPerSecond, &context.spec.transform, options.final_buckets).filter(|mean| *mean > 0.0);
+        max_y = commits.iter().chain(&queries).fold(max_y, |a, b| a.max(*b));
+        groups.push((entry, commits, queries));
     }
 
-    pub fn get_range_end(&self) -> f64 {
-        self.statistics.mean() + self.get_half_range()
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
+
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(0.0f64..groups.len().max(1) as f64, context.spec.y_range.apply(0.0f64..(max_y.max(f64::MIN_POSITIVE) * 1.1)))?;
+
+    let x_desc = format!("Commits (solid) and queries (light) per second over the final {} commit buckets", options.final_buckets);
+    let y_label_formatter = units::get_y_label_formatter(options.y_unit, units::YQuantity::of_chart(context.spec), cc.y_range(), false);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc(x_desc)
+        .y_desc("Operations per Second")
+        .disable_x_mesh()
+        .x_labels(0)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid());
+    match &y_label_formatter {
+        Some(formatter) => mesh.y_label_formatter(formatter),
+        None => mesh.y_label_formatter(&|v| format!("{:.0}", v)),
+    };
+    mesh.draw()?;
+
+    let font = (context.fonts.family.as_str(), pixel_height * 0.015).into_font().color(&context.theme.text);
+    for (index, (entry, commits, queries)) in groups.into_iter().enumerate() {
+        let display_name = context.display_name(entry.1);
+        let start = index as f64;
+        let bars = [(commits, start + 0.15, entry.4.filled()), (queries, start + 0.5, entry.4.mix(0.35).filled())];
+
+        cc.draw_series(bars.iter().filter_map(|(mean, x, style)| mean.map(|mean| Rectangle::new([(*x, 0.0), (*x + 0.35, mean)], *style))))?
+            .label(display_name)
+            .legend(move |(x, y)| Rectangle::new([(x, y - (pixel_height * 0.006) as i32), (x + (pixel_height * 0.03) as i32, y + (pixel_height * 0.006) as i32)], entry.4.filled()));
+
+        cc.draw_series(bars.iter().filter_map(|(mean, x, _)| mean.map(|mean| {
+            EmptyElement::at((*x + 0.175, mean))
+            + Text::new(format!("{:.0}", mean), (0, -(pixel_height * 0.02) as i32), font.clone().pos(Pos::new(HPos::Center, VPos::Top)))
+        })))?;
     }
-}
 
-struct ValueSet {
-    pub num_commits : u64,
-    pub commit_time : SampleSet,
-    pub commits_per_second : SampleSet,
-    pub queries_per_second : SampleSet,
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
 }
 
-impl ValueSet {
-    pub fn new(num_commits: u64) -> ValueSet {
-        ValueSet { num_commits: num_commits, commit_time: SampleSet::new(), commits_per_second: SampleSet::new(), queries_per_second: SampleSet::new() }
+// Every raw sample as a translucent point, so that outlying commit counts which the means hide
+// stand out. Points are jittered along the X axis with `--jitter`.
+fn draw_scatter_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let chart_type = &context.spec.chart_type;
+    let mut series = Vec::new();
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points = entry.1.get_scatter_points(chart_type, context.spec.options.jitter);
+        max_y = points.iter().fold(max_y, |a, b| a.max(b.1));
+        series.push((entry, points));
     }
 
-    pub fn add_sample(&mut self, commit_time: f64, commits_per_second: f64, queries_per_second: f64) {
-        self.commit_time.add_sample(commit_time);
-        self.commits_per_second.add_sample(commits_per_second);
-        self.queries_per_second.add_sample(queries_per_second);
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
+
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(context.spec.x_range.apply(0.0f64..context.max_commits as f64), context.spec.y_range.apply(0.0f64..max_y.max(f64::MIN_POSITIVE)))?;
+
+    let y_desc = match chart_type {
+        ChartType::CommitTimeScatter => "Commit Time (ms)",
+        ChartType::CommitsPerSecondScatter => "Commits per Second",
+        _ => "Queries per Second",
+    };
+
+    let y_label_formatter = units::get_y_label_formatter(context.spec.options.y_unit, units::YQuantity::of_chart(context.spec), cc.y_range(), false);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc("Commits")
+        .y_desc(y_desc)
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v));
+    if let Some(formatter) = &y_label_formatter {
+        mesh.y_label_formatter(formatter);
     }
-}
+    mesh.draw()?;
 
-struct DataSet {
-    pub base_name : String,
-    pub parameters: BTreeMap<String, ParameterValue>,
+    annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
 
-    pub sorted_values : Vec<ValueSet>,
+    let point_size = context.spec.options.marker_size.map_or(std::cmp::max(2, (pixel_height * 0.0015) as i32), |size| size as i32);
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
+        let colour = entry.4.mix(0.3);
+        let marker_shape = entry.6.unwrap_or(context.spec.options.marker_shape);
 
-    pub max_commits: u64,
-    pub max_commit_time: f64,
-    pub max_commits_per_second: f64,
-    pub max_queries_per_second: f64,
+        cc.draw_series(points.into_iter().map(|point| get_marker(marker_shape, point, point_size, colour.filled())))?
+            .label(display_name)
+            .legend(move |(x, y)| get_marker(marker_shape, (x + (pixel_height * 0.015) as i32, y), point_size * 2, entry.4.filled()));
+    }
+
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
 }
 
-impl DataSet {
-    pub fn new(base_name: String, parameters: BTreeMap<String, ParameterValue>) -> DataSet {
-        DataSet {
-            base_name: base_name,
-            parameters: parameters,
-            sorted_values: Default::default(), 
-            max_commits: 0, max_commit_time: 0.0f64, max_commits_per_second: 0.0f64, max_queries_per_second: 0.0f64 }
+fn draw_wall_clock_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_x: f64 = 0.0;
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points = entry.1.get_commits_per_second_over_wall_clock();
+        max_x = points.iter().fold(max_x, |a, b| a.max(b.0));
+        max_y = points.iter().fold(max_y, |a, b| a.max(b.1));
+        series.push((entry, points));
     }
 
-    pub fn add_sample(&mut self, commits: u64, commit_time: f64, commits_per_second: f64, queries_per_second: f64) {
-        self.max_commits = std::cmp::max(self.max_commits, commits);
-        self.max_commit_time = self.max_commit_time.max(commit_time);
-        self.max_commits_per_second = self.max_commits_per_second.max(commits_per_second);
-        self.max_queries_per_second = self.max_queries_per_second.max(queries_per_second);
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-        match self.sorted_values.binary_search_by(|probe| probe.num_commits.cmp(&commits)) {
-            Ok(val) => self.sorted_values[val].add_sample(commit_time, commits_per_second, queries_per_second),
-            Err(val) => {
-                let mut valueset = ValueSet::new(commits);
-                valueset.add_sample(commit_time, commits_per_second, queries_per_second);
-                self.sorted_values.insert(val, valueset);
-            },
-        }
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(0.0f64..max_x.max(f64::MIN_POSITIVE), context.spec.y_range.apply(0.0f64..max_y.max(f64::MIN_POSITIVE)))?;
+
+    let y_label_formatter = units::get_y_label_formatter(context.spec.options.y_unit, units::YQuantity::of_chart(context.spec), cc.y_range(), false);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc("Elapsed Time (s)")
+        .y_desc("Commits per Second")
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid());
+    if let Some(formatter) = &y_label_formatter {
+        mesh.y_label_formatter(formatter);
     }
+    mesh.draw()?;
 
-    pub fn get_name(base_name: String, parameters: &BTreeMap<String, ParameterValue>) -> String {
-        let mut suffix = String::new();
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
 
-        let mut prev_param = false;
-        for (name, value) in parameters {
-            if prev_param {
-                suffix += " ";
-            }
+        line_style::draw_line(&mut cc, points, entry.5, entry.3)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
+    }
 
-            match value {
-                ParameterValue::Bool(v) => {
-                    if *v {
-                        suffix += name;
-                        prev_param = true;
-                    }
-                },
-                ParameterValue::Int(v) => {
-                    suffix += &format!("{}={}", name, *v);
-                    prev_param = true;
-                },
-            }
-        }
-        if suffix.len() > 0 {
-            suffix = " (".to_string() + &suffix + ")";
-        }
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
 
-        base_name.clone() + &suffix
+    Ok(())
+}
+
+// A line of one value per commit bucket for each dataset, from zero up.
+fn draw_bucket_line_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext, y_desc: &str, get_points: fn(&DataSet) -> Vec<(f64, f64)>) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points = get_points(entry.1);
+        max_y = points.iter().fold(max_y, |a, b| a.max(b.1));
+        series.push((entry, points));
     }
 
-    pub fn get_name_including(base_name: String, parameters: &BTreeMap<String, ParameterValue>, include_parameters: &HashSet<String>) -> String {
-        let mut suffix = String::new();
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-        let mut prev_param = false;
-        for (name, value) in parameters {
-            if include_parameters.contains(name) {
-                if prev_param {
-                    suffix += " ";
-                }
-    
-                match value {
-                    ParameterValue::Bool(v) => {
-                        if *v {
-                            suffix += name;
-                            prev_param = true;
-                        }
-                    },
-                    ParameterValue::Int(v) => {
-                        suffix += &format!("{}={}", name, *v);
-                        prev_param = true;
-                    },
-                }
-            }
-        }
-        if suffix.len() > 0 {
-            suffix = " (".to_string() + &suffix + ")";
-        }
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(context.spec.x_range.apply(0.0f64..context.max_commits as f64), context.spec.y_range.apply(0.0f64..max_y.max(f64::MIN_POSITIVE)))?;
 
-        base_name.clone() + &suffix
+    let y_label_formatter = units::get_y_label_formatter(context.spec.options.y_unit, units::YQuantity::of_chart(context.spec), cc.y_range(), false);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc("Commits")
+        .y_desc(y_desc)
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v));
+    if let Some(formatter) = &y_label_formatter {
+        mesh.y_label_formatter(formatter);
     }
+    mesh.draw()?;
 
-    pub fn passes_filters(&self, filters: &impl FilterSet) -> bool {
-        filters.passes_filters(&self.parameters)
+    annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
+
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
+
+        line_style::draw_line(&mut cc, points, entry.5, entry.3)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
     }
-}
 
-struct StressTestData {
-    pub datasets : HashMap<String, DataSet>,
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
 
-    pub max_commits: u64,
-    pub max_commit_time: f64,
-    pub max_commits_per_second: f64,
-    pub max_queries_per_second: f64,
+    Ok(())
 }
 
-impl StressTestData {
-    pub fn new() -> StressTestData {
-        StressTestData { datasets: Default::default(), max_commits: 0, max_commit_time: 0.0f64, max_commits_per_second: 0.0f64, max_queries_per_second: 0.0f64 }
+fn draw_run_variance_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points = entry.1.get_commits_per_second_run_deviation(context.spec.options.variance_window);
+        max_y = points.iter().fold(max_y, |a, b| a.max(b.1).max(b.2));
+        series.push((entry, points));
     }
 
-    pub fn add_sample(&mut self, base_name: String, parameters: BTreeMap<String, ParameterValue>, commits: u64, commit_time: f64, commits_per_second: f64, queries_per_second: f64) {
-        self.max_commits = std::cmp::max(self.max_commits, commits);
-        self.max_commit_time = self.max_commit_time.max(commit_time);
-        self.max_commits_per_second = self.max_commits_per_second.max(commits_per_second);
-        self.max_queries_per_second = self.max_queries_per_second.max(queries_per_second);
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
+    let dash_size = (pixel_height * 0.01) as i32;
 
-        let full_name = DataSet::get_name(base_name.clone(), &parameters);
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(context.spec.x_range.apply(0.0f64..context.max_commits as f64), context.spec.y_range.apply(0.0f64..max_y.max(f64::MIN_POSITIVE)))?;
 
-        match self.datasets.entry(full_name) {
-            std::collections::hash_map::Entry::Occupied(mut entry) => {
-                entry.get_mut().add_sample(commits, commit_time, commits_per_second, queries_per_second);
-            },
-            std::collections::hash_map::Entry::Vacant(entry) => {
-                let mut dataset = DataSet::new(base_name, parameters);
-                dataset.add_sample(commits, commit_time, commits_per_second, queries_per_second);
-                entry.insert(dataset);
-            },
-        }
+    let y_label_formatter = units::get_y_label_formatter(context.spec.options.y_unit, units::YQuantity::of_chart(context.spec), cc.y_range(), false);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_desc("Commits")
+        .y_desc("Commits per Second Std Dev")
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v));
+    if let Some(formatter) = &y_label_formatter {
+        mesh.y_label_formatter(formatter);
     }
-}
+    mesh.draw()?;
 
-fn get_stress_test_data(args: &Args) -> Option<StressTestData> {
-    let paths = args.data_path.clone()?;
+    annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
 
-    let mut data = StressTestData::new();
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
 
-    for path in paths {
-        println!("Reading data file: {}", path.display());
-
-        let file = std::fs::OpenOptions::new()
-            .read(true)
-            .open(path.as_path()).expect(format!("Failed to open data file {}", path.display()).as_str());
-
-        let reader = std::io::BufReader::new(file);
-
-        // First line is column names, so skip.
-        for line in reader.lines().skip(1).map(|l| l.unwrap()) {
-            let mut elements = line.split(',');
-
-            let base_name = elements.next().unwrap().to_string();
-
-            let archive: bool = elements.next().unwrap().parse().unwrap();
-            let compress: bool = elements.next().unwrap().parse().unwrap();
-            let ordered: bool = elements.next().unwrap().parse().unwrap();
-            let uniform: bool = elements.next().unwrap().parse().unwrap();
-            let num_readers: u64 = elements.next().unwrap().parse().unwrap();
-            let num_writers: u64 = elements.next().unwrap().parse().unwrap();
-            let writer_commits_per_sleep: u64 = elements.next().unwrap().parse().unwrap();
-            let writer_sleep_time: u64 = elements.next().unwrap().parse().unwrap();
-            let commits_per_timing_sample: u64 = elements.next().unwrap().parse().unwrap();
-            let progressive: bool = elements.next().unwrap().parse().unwrap();
-
-            let total_commits = elements.next().unwrap().parse().unwrap();
-            let total_commit_time = elements.next().unwrap().parse().unwrap();
-
-            let commits: u64 = elements.next().unwrap().parse().unwrap();
-            let commit_time: f64 = elements.next().unwrap().parse().unwrap();
-
-            let queries: u64 = elements.next().unwrap().parse().unwrap();
-            let query_time: f64 = elements.next().unwrap().parse().unwrap();
-
-            let commits_per_second = commits as f64 / commit_time;
-            let queries_per_second = queries as f64 / query_time;
-
-            let mut parameters: BTreeMap<String, ParameterValue> = Default::default();
-            parameters.insert("archive".to_string(), ParameterValue::Bool(archive));
-            parameters.insert("compress".to_string(), ParameterValue::Bool(compress));
-            parameters.insert("ordered".to_string(), ParameterValue::Bool(ordered));
-            parameters.insert("uniform".to_string(), ParameterValue::Bool(uniform));
-            parameters.insert("readers".to_string(), ParameterValue::Int(num_readers));
-            parameters.insert("writers".to_string(), ParameterValue::Int(num_writers));
-            parameters.insert("writer-commits-per-sleep".to_string(), ParameterValue::Int(writer_commits_per_sleep));
-            parameters.insert("writer-sleep-time".to_string(), ParameterValue::Int(writer_sleep_time));
-            parameters.insert("commits-per-timing-sample".to_string(), ParameterValue::Int(commits_per_timing_sample));
-            parameters.insert("progressive".to_string(), ParameterValue::Bool(progressive));
-    
-            data.add_sample(base_name, parameters, total_commits, total_commit_time, commits_per_second, queries_per_second);
+        if entry.1.num_runs() > 1 {
+            line_style::draw_line(&mut cc, points.iter().map(|p| (p.0, p.1)), entry.5, entry.3)?
+                .label(format!("{} between runs", display_name))
+                .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
         }
+
+        cc.draw_series(DashedLineSeries::new(points.iter().map(|p| (p.0, p.2)), dash_size, dash_size, entry.2))?
+            .label(format!("{} within run", display_name))
+            .legend(move |(x, y)| plotters::element::DashedPathElement::new(vec![(x, y), (x + (pixel_height * 0.03) as i32, y)], dash_size / 2, dash_size / 2, entry.2));
     }
 
-    Some(data)
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
+    Ok(())
 }
 
-fn draw_stress_test_data<DB: DrawingBackend>(b: &DrawingArea<DB, plotters::coord::Shift>, data: &StressTestData, params: &Params) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+// Shaded p50-p90 and p90-p99 bands of query latency at each commit count, with the median as a
+// line.
+fn draw_query_latency_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let mut series = Vec::new();
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points: Vec<(f64, f64, f64, f64)> = entry.1.sorted_values.iter().filter(|v| !v.query_latency.is_empty()).map(|v| {
+            (v.num_commits as f64, v.query_latency.get_percentile(50.0), v.query_latency.get_percentile(90.0), v.query_latency.get_percentile(99.0))
+        }).collect();
+        max_y = points.iter().fold(max_y, |a, b| a.max(b.3));
+        series.push((entry, points));
+    }
 
-    let mut colours : Vec<RGBColor> = Default::default();
-    colours.push(full_palette::LIGHTBLUE);
-    colours.push(full_palette::GREEN);
-    colours.push(full_palette::YELLOW);
-    colours.push(full_palette::RED);
-    colours.push(full_palette::BLACK);
-    colours.push(full_palette::BROWN_400);
-    colours.push(full_palette::PINK);
-    colours.push(full_palette::ORANGE);
-    colours.push(full_palette::GREY);
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-    let mut datasets_presort = Vec::new();
-    for entry in &data.datasets {
-        datasets_presort.push((entry.0, entry.1));
-    }
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(context.spec.x_range.apply(0.0f64..context.max_commits as f64), context.spec.y_range.apply(0.0f64..max_y.max(f64::MIN_POSITIVE) * 1.05))?;
 
-    datasets_presort.sort_by(|a, b| a.0.cmp(b.0));
+    cc.configure_mesh()
+        .x_desc("Commits")
+        .y_desc("Query Latency (us), p50 / p90 / p99")
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .x_label_formatter(&|v| format!("{:.0}", v))
+        .draw()?;
 
-    let mut datasets = Vec::new();
-    let mut colour_index = 0;
-    for entry in datasets_presort {
-        datasets.push((entry.0, entry.1, colours[colour_index].clone().stroke_width(params.stroke_width as u32), colours[colour_index].clone().stroke_width(params.stroke_width as u32 * 2), colours[colour_index].mix(0.75)));
-        colour_index = (colour_index + 1) % colours.len();
-    }
+    annotations::draw_annotations(&mut cc, context.annotations, context.theme, &context.fonts.family, pixel_height)?;
 
-    {
-        let mut areas = Vec::new();
-        let area_values = match params.chart_specs.len() {
-            0 => {
-                Vec::new()
-            }
-            1 => {
-                areas.push(b);
-                Vec::new()
-            }
-            2 => {
-                b.split_evenly((1, 2))
-            }
-            3 => {
-                b.split_evenly((1, 3))
-            }
-            _ => {
-                b.split_evenly((2, 2))
-            }
+    for (entry, points) in series {
+        if points.is_empty() {
+            continue;
+        }
+
+        let band = |lower: fn(&(f64, f64, f64, f64)) -> f64, upper: fn(&(f64, f64, f64, f64)) -> f64| -> Vec<(f64, f64)> {
+            points.iter().map(|p| (p.0, upper(p))).chain(points.iter().rev().map(|p| (p.0, lower(p)))).collect()
         };
-        for area in area_values.iter() {
-            areas.push(area);
-        }
-
-        let chart_types: Vec<ChartType> = params.chart_specs.iter().map(|x| x.chart_type.clone()).collect();
-
-        for i in 0..std::cmp::min(areas.len(), chart_types.len()) {
-            let area = areas[i];
-            let chart_type = &chart_types[i];
-
-            let mut title = match chart_type {
-                ChartType::CommitTime => "Commit Time",
-                ChartType::CommitsPerSecond => "Commits per Second",
-                ChartType::QueriesPerSecond => "Queries per Second",
-            }.to_string();
-
-            let filter_text = params.chart_specs[i].filters.display_text();
-            if filter_text.len() > 0 {
-                title += " (";
-                title += &filter_text;
-                title += ")";
-            }
-
-            let mut max_y: f64 = 0.0;
-            let mut first_dataset: Option<&DataSet> = None;
-            let mut include_parameters: HashSet<String> = Default::default();
-            for entry in &datasets {
-                let passed_filters = entry.1.passes_filters(&params.chart_specs[i].filters);
-                if passed_filters {
-                    let dataset_max_y = match chart_type {
-                        ChartType::CommitTime => entry.1.max_commit_time,
-                        ChartType::CommitsPerSecond => entry.1.max_commits_per_second,
-                        ChartType::QueriesPerSecond => entry.1.max_queries_per_second,
-                    };
-                    max_y = max_y.max(dataset_max_y as f64);
-
-                    match first_dataset {
-                        Some(dataset) => {
-                            let other = entry.1;
-                            for (name, value) in &dataset.parameters {
-                                match other.parameters.get(name) {
-                                    Some(other_value) => {
-                                        if other_value != value {
-                                            include_parameters.insert(name.clone());
-                                        }
-                                    },
-                                    None => {
-                                        include_parameters.insert(name.clone());
-                                    },
-                                }
-                            }
-                            for (name, _) in &other.parameters {
-                                match dataset.parameters.get(name) {
-                                    Some(_) => {},
-                                    None => {
-                                        include_parameters.insert(name.clone());
-                                    },
-                                }
-                            }
-                        },
-                        None => {
-                            first_dataset = Some(entry.1);
-                        }
-                    }
-                }
-            }
 
-            let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
+        cc.draw_series(std::iter::once(Polygon::new(band(|p| p.2, |p| p.3), entry.4.mix(0.15).filled())))?;
+        cc.draw_series(std::iter::once(Polygon::new(band(|p| p.1, |p| p.2), entry.4.mix(0.3).filled())))?;
 
-            let mut cc = ChartBuilder::on(&area)
-                .x_label_area_size((5).percent_height())
-                .y_label_area_size((6).percent_height())
-                .margin((2).percent_height())
-                .margin_right((5).percent_height())
-                .caption(title, ("sans-serif", (3).percent_height()))
-                .build_cartesian_2d(0.0f64..data.max_commits as f64, 0.0f64..max_y)?;
+        let display_name = context.display_name(entry.1);
 
-            cc.configure_mesh()
-                .x_desc("Commits")
-                .x_labels(10)
-                .y_labels(8)
-                .label_style(("sans-serif", (2).percent_height()))
-                .x_label_formatter(&|v| format!("{:.0}", v))
-                .draw()?;
+        line_style::draw_line(&mut cc, points.iter().map(|p| (p.0, p.1)), entry.5, entry.3)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
+    }
 
-            let pixel_range = cc.plotting_area().get_pixel_range();
-            let coord_to_pixel_x = (pixel_range.0.end - pixel_range.0.start) as f64 / ((cc.x_range().end - cc.x_range().start) as f64);
-            let coord_to_pixel_y = (pixel_range.1.end - pixel_range.1.start) as f64 / ((cc.y_range().end - cc.y_range().start) as f64);
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
 
-            let pixel_offset = |origin: (f64, f64), pos: (f64, f64), offset: (i32, i32)| -> (i32, i32) {
-                (((pos.0 - origin.0) * coord_to_pixel_x) as i32 + offset.0, ((pos.1 - origin.1) * -coord_to_pixel_y) as i32 + offset.1)
-            };
+    Ok(())
+}
 
-            let marker_size = (pixel_height * 0.0025) as i32;
-            let errorbar_size = (pixel_height * 0.004) as i32;
-
-            for entry in &datasets {
-                let passed_filters = entry.1.passes_filters(&params.chart_specs[i].filters);
-                if passed_filters {
-                    let mut points: Vec<(f64, f64)> = Default::default();
-                    let mut points_neg: Vec<(f64, f64)> = Default::default();
-                    let mut points_pos: Vec<(f64, f64)> = Default::default();
-                    let mut errorbars: Vec<(f64, f64, f64, f64)> = Default::default();
-                    for value in &entry.1.sorted_values {
-                        let x = value.num_commits as f64;
-
-                        let value_data = match chart_type {
-                            ChartType::CommitTime => (x, value.commit_time.value_min, value.commit_time.get_range_start(), value.commit_time.get_mean(), value.commit_time.get_range_end(), value.commit_time.value_max),
-                            ChartType::CommitsPerSecond => (x, value.commits_per_second.value_min, value.commits_per_second.get_range_start(), value.commits_per_second.get_mean(), value.commits_per_second.get_range_end(), value.commits_per_second.value_max),
-                            ChartType::QueriesPerSecond => (x, value.queries_per_second.value_min, value.queries_per_second.get_range_start(), value.queries_per_second.get_mean(), value.queries_per_second.get_range_end(), value.queries_per_second.value_max),
-                        };
-
-                        points.push((value_data.0, value_data.3));
-                        points_neg.push((value_data.0, value_data.2));
-                        points_pos.push((value_data.0, value_data.4));
-                        errorbars.push((value_data.0, value_data.1, value_data.3, value_data.5));
-                    }
+fn draw_plugin_chart<DB: DrawingBackend>(area: &DrawingArea<DB, plotters::coord::Shift>, context: &ChartContext, name: &str) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let renderer = plugins::get_chart(name).ok_or_else(|| format!("Chart type is not registered: {}", name))?;
 
-                    let display_name = DataSet::get_name_including(entry.1.base_name.clone(), &entry.1.parameters, &include_parameters);
+    let mut series = Vec::new();
+    let mut min_x: f64 = 0.0;
+    let mut max_x: f64 = 0.0;
+    let mut min_y: f64 = 0.0;
+    let mut max_y: f64 = 0.0;
+    for entry in &context.datasets {
+        let points: Vec<(f64, f64)> = renderer.compute_series(&plugins::get_value_points(entry.1)).into_iter().filter(|(x, y)| x.is_finite() && y.is_finite()).collect();
+        for (x, y) in &points {
+            min_x = min_x.min(*x);
+            max_x = max_x.max(*x);
+            min_y = min_y.min(*y);
+            max_y = max_y.max(*y);
+        }
+        series.push((entry, points));
+    }
 
-                    cc.draw_series(LineSeries::new(points, entry.3))?
-                        .label(display_name)
-                        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + (pixel_height * 0.03) as i32, y)], entry.3));
+    let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
 
-                    //cc.draw_series(LineSeries::new(points_neg, entry.4))?;
-                    //cc.draw_series(LineSeries::new(points_pos, entry.4))?;
+    let mut cc = ChartBuilder::on(area)
+        .x_label_area_size((5).percent_height())
+        .y_label_area_size((7).percent_height())
+        .margin((2).percent_height())
+        .margin_right((5).percent_height())
+        .caption(context.title.clone(), context.fonts.title(&context.theme.text))
+        .build_cartesian_2d(min_x..max_x.max(min_x + f64::MIN_POSITIVE), context.spec.y_range.apply(min_y..max_y.max(min_y + f64::MIN_POSITIVE)))?;
 
-                    cc.draw_series(errorbars.iter().map(|(x, min, mean, _)| {
-                        EmptyElement::at((*x, *min))
-                        + Circle::new(pixel_offset((*x, *min), (*x, *mean), (0, 0)), marker_size, entry.2.filled())
-                    }))?;
+    cc.configure_mesh()
+        .x_desc(renderer.x_label())
+        .y_desc(renderer.y_label())
+        .x_labels(10)
+        .y_labels(8)
+        .label_style(context.fonts.tick_label(&context.theme.text))
+        .axis_desc_style(context.fonts.axis_label(&context.theme.text))
+        .axis_style(context.theme.text)
+        .bold_line_style(context.theme.bold_grid())
+        .light_line_style(context.theme.light_grid())
+        .draw()?;
 
-                    cc.draw_series(errorbars.iter().skip_while(|(_, min, _, max)| { max <= min }).map(|(x, min, _, max)| {
-                        EmptyElement::at((*x, *min))
-                        + PathElement::new(vec![(0, 0), pixel_offset((*x, *min), (*x, *max), (0, 0))], entry.2)
-                        + PathElement::new(vec![(-errorbar_size, 0), (errorbar_size, 0)], entry.2)
-                        + PathElement::new(vec![pixel_offset((*x, *min), (*x, *max), (-errorbar_size, 0)), pixel_offset((*x, *min), (*x, *max), (errorbar_size, 0))], entry.2)
-                    }))?;
-                }
-            }
+    for (entry, points) in series {
+        let display_name = context.display_name(entry.1);
 
-            cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(&BLACK).label_font(("sans-serif", (2).percent_height())).draw()?;
-        }
+        line_style::draw_line(&mut cc, points, entry.5, entry.3)?
+            .label(display_name)
+            .legend(|point| line_style::legend_line(point, (pixel_height * 0.03) as i32, entry.5, entry.3));
     }
 
+    cc.configure_series_labels().legend_area_size((5).percent_height()).margin((1).percent_height()).border_style(context.theme.text).label_font(context.fonts.legend(&context.theme.text)).draw()?;
+
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A dataset with a bucket at each commit count, with its disk size and commit time samples.
+    fn get_dataset(buckets: &[(u64, &[f64], f64)]) -> DataSet {
+        let mut dataset = DataSet::new("stress".to_string(), Default::default());
+        for (commits, disk_sizes, commit_time) in buckets {
+            let mut value = ValueSet::new(*commits);
+            for disk_size in *disk_sizes {
+                value.disk_size.add_sample(*disk_size);
+            }
+            value.commit_time.add_sample(*commit_time);
+            dataset.sorted_values.push(value);
+        }
+        dataset.max_commits = buckets.last().map_or(0, |bucket| bucket.0);
+        dataset
+    }
+
+    #[test]
+    fn rebucketed_orders_buckets_by_the_x_axis() {
+        let dataset = get_dataset(&[(1000, &[10.0], 0.1), (2000, &[25.0, 35.0], 0.2), (3000, &[20.0], 0.3), (4000, &[40.0], 0.4), (5000, &[], 0.5)]);
+        let x_axis = XAxis::Metric(ChartType::DiskSize);
+
+        let rebucketed = dataset.rebucketed(&x_axis, None);
+        // The bucket without a disk size has no place on the X axis, so is left out.
+        assert_eq!(rebucketed.sorted_values.iter().map(|value| value.num_commits).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!(rebucketed.sorted_values.iter().filter_map(|value| value.get_x(&x_axis)).collect::<Vec<_>>(), [10.0, 20.0, 30.0, 40.0]);
+        assert_eq!(rebucketed.sorted_values.iter().map(|value| value.commit_time.get_mean()).collect::<Vec<_>>(), [0.1, 0.3, 0.2, 0.4]);
+        assert_eq!(rebucketed.max_commit_time, 0.4);
+    }
+
+    #[test]
+    fn rebucketed_merges_buckets_in_the_same_bin() {
+        let dataset = get_dataset(&[(1000, &[10.0], 0.1), (2000, &[30.0], 0.2), (3000, &[20.0], 0.3), (4000, &[40.0], 0.4)]);
+        let x_axis = XAxis::Metric(ChartType::DiskSize);
+
+        let rebucketed = dataset.rebucketed(&x_axis, Some(2));
+        assert_eq!(rebucketed.sorted_values.len(), 2);
+        assert_eq!(rebucketed.sorted_values[0].disk_size.samples, [10.0, 20.0]);
+        assert_eq!(rebucketed.sorted_values[1].disk_size.samples, [30.0, 40.0]);
+        // Equal positions all fall in one bin.
+        let flat = get_dataset(&[(1000, &[10.0], 0.1), (2000, &[10.0], 0.2)]);
+        assert_eq!(flat.rebucketed(&x_axis, None).sorted_values.len(), 1);
+    }
+}
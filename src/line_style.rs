@@ -0,0 +1,54 @@
+// Dash patterns of the lines of datasets alongside their colours, for charts of more datasets than
+// the palette has colours, or for readers who cannot tell the colours apart. With `--style-by`
+// each value of a parameter gets its own pattern, e.g. `--style-by compress` draws the compressed
+// runs dashed. Otherwise datasets get the next pattern each time the palette's colours repeat.
+use super::*;
+use plotters::chart::{ChartContext, SeriesAnno};
+use plotters::coord::CoordTranslate;
+use plotters::element::{DashedPathElement, DynElement};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+static LINE_STYLES: [LineStyle; 3] = [LineStyle::Solid, LineStyle::Dashed, LineStyle::Dotted];
+
+impl LineStyle {
+    // The pattern of a dataset from its position in name order, or from its `--style-by` parameter
+    // value. Datasets without the parameter, such as derived series, are solid.
+    pub fn of_dataset(params: &Params, sort_index: usize, dataset: &DataSet) -> LineStyle {
+        let index = match &params.style_by {
+            Some(name) => dataset.parameters.get(name).map_or(0, palette::parameter_colour_index),
+            None => sort_index / params.palette.colours.len(),
+        };
+        LINE_STYLES[index % LINE_STYLES.len()]
+    }
+
+    // Lengths of the dashes and the gaps between them, in multiples of the stroke width.
+    fn dashes(&self, stroke_width: u32) -> Option<(u32, u32)> {
+        match self {
+            LineStyle::Solid => None,
+            LineStyle::Dashed => Some((stroke_width * 6, stroke_width * 4)),
+            LineStyle::Dotted => Some((stroke_width, stroke_width * 3)),
+        }
+    }
+}
+
+// Draws the line through the points in the pattern, returning the series for its legend entry.
+pub(crate) fn draw_line<'a, 'c, DB: DrawingBackend, CT: CoordTranslate<From = (f64, f64)>, I: IntoIterator<Item = (f64, f64)>>(cc: &'c mut ChartContext<'a, DB, CT>, points: I, line_style: LineStyle, style: ShapeStyle) -> Result<&'c mut SeriesAnno<'a, DB>, DrawingAreaErrorKind<DB::ErrorType>> where I::IntoIter: Clone {
+    match line_style.dashes(style.stroke_width) {
+        None => cc.draw_series(LineSeries::new(points, style)),
+        Some((dash, gap)) => cc.draw_series(DashedLineSeries::new(points, dash, gap, style)),
+    }
+}
+
+// The legend entry of a line drawn with `draw_line`, starting at the point.
+pub(crate) fn legend_line<DB: DrawingBackend>((x, y): (i32, i32), length: i32, line_style: LineStyle, style: ShapeStyle) -> DynElement<'static, DB, (i32, i32)> {
+    match line_style.dashes(style.stroke_width) {
+        None => PathElement::new(vec![(x, y), (x + length, y)], style).into_dyn(),
+        Some((dash, gap)) => DashedPathElement::new(vec![(x, y), (x + length, y)], dash, gap, style).into_dyn(),
+    }
+}
@@ -0,0 +1,141 @@
+// Records streamed by running stress tests over a socket with `--listen`, in place of data files,
+// for when tailing files across containers is unreliable. Each connection is a run, sending the
+// lines of a data file as they are written: a CSV header line and then a line per timing sample,
+// or JSON lines with `--input-format json`. The image is drawn again every `--flush-interval`
+// seconds in which records arrived, until the visualizer is stopped.
+use super::*;
+use std::io::{BufReader, Read};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+// A TCP address such as `0.0.0.0:9000`, or otherwise the path of a Unix socket.
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(std::os::unix::net::UnixListener),
+}
+
+impl Listener {
+    fn bind(address: &str) -> Result<Listener, Box<dyn Error>> {
+        if let Ok(socket_address) = address.parse::<std::net::SocketAddr>() {
+            let listener = TcpListener::bind(socket_address).map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+            log::info!("Listening for records on {}", listener.local_addr()?);
+            return Ok(Listener::Tcp(listener))
+        }
+        Listener::bind_unix(address)
+    }
+
+    // A socket left behind by an earlier run is replaced, but not any other file.
+    #[cfg(unix)]
+    fn bind_unix(path: &str) -> Result<Listener, Box<dyn Error>> {
+        use std::os::unix::fs::FileTypeExt;
+        if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            std::fs::remove_file(path)?;
+        }
+        let listener = std::os::unix::net::UnixListener::bind(path).map_err(|e| format!("Failed to listen on {}: {}", path, e))?;
+        log::info!("Listening for records on {}", path);
+        Ok(Listener::Unix(listener))
+    }
+
+    #[cfg(not(unix))]
+    fn bind_unix(address: &str) -> Result<Listener, Box<dyn Error>> {
+        Err(format!("Invalid address '{}', expected HOST:PORT, as Unix sockets are not supported on this platform", address).into())
+    }
+
+    // The stream of the next connection and where it came from.
+    fn accept(&self) -> std::io::Result<(Box<dyn Read + Send>, String)> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, peer) = listener.accept()?;
+                Ok((Box::new(stream), peer.to_string()))
+            },
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok((Box::new(stream), "unix socket".to_string()))
+            },
+        }
+    }
+}
+
+// Reads the records of each connection on its own thread, and draws the charts of everything read
+// so far to the output file on this one.
+pub(crate) fn listen(address: &str, args: &Args, params: &Params, output_path: &std::path::Path, output_format: &OutputFormat) -> Result<(), Box<dyn Error>> {
+    let listener = Listener::bind(address)?;
+    let input_format = args.input_format.clone().unwrap_or(InputFormat::Csv);
+    let schema = match &args.schema {
+        Some(path) => Some(std::sync::Arc::new(Schema::load(path)?)),
+        None => None,
+    };
+    let strict = args.strict;
+
+    let (sender, receiver) = mpsc::channel::<(usize, Record)>();
+    std::thread::spawn(move || {
+        for run in 0.. {
+            let (stream, peer) = match listener.accept() {
+                Ok(connection) => connection,
+                Err(e) => {
+                    log::warn!("Failed to accept connection: {}", e);
+                    continue;
+                },
+            };
+            log::info!("Run {} connected from {}", run + 1, peer);
+
+            let (sender, input_format, schema) = (sender.clone(), input_format.clone(), schema.clone());
+            std::thread::spawn(move || {
+                let location = PathBuf::from(format!("run {} ({})", run + 1, peer));
+                let result = source::stream_records(&location, BufReader::new(stream), &input_format, schema.as_deref(), |record| match record {
+                    Ok(record) => sender.send((run, record)).is_ok(),
+                    Err(error) if strict => {
+                        log::warn!("Closing connection at malformed line {}", error);
+                        false
+                    },
+                    Err(error) => {
+                        log::warn!("Skipping malformed line {}", error);
+                        true
+                    },
+                });
+                match result {
+                    Ok(()) => log::info!("Run {} disconnected", run + 1),
+                    Err(e) => log::warn!("Run {} disconnected: {}", run + 1, e),
+                }
+            });
+        }
+    });
+
+    let flush_interval = Duration::from_secs_f64(args.flush_interval.max(0.1));
+    let mut data = StressTestData { low_memory: args.low_memory, ..StressTestData::new() };
+    let mut num_new_records = 0;
+    let mut last_flush = Instant::now();
+    loop {
+        match receiver.recv_timeout(flush_interval.saturating_sub(last_flush.elapsed())) {
+            Ok((run, record)) => {
+                let sample = get_sample(&record, run);
+                data.add_sample(record.base_name, record.parameters, record.total_commits, &sample);
+                num_new_records += 1;
+            },
+            Err(mpsc::RecvTimeoutError::Timeout) => {},
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Err("Stopped accepting connections".into()),
+        }
+
+        if last_flush.elapsed() < flush_interval {
+            continue;
+        }
+        last_flush = Instant::now();
+        if num_new_records > 0 {
+            flush(&mut data, params, output_path, output_format)?;
+            log::info!("Wrote file: {} with {} new records", output_path.display(), num_new_records);
+            num_new_records = 0;
+        }
+    }
+}
+
+// Derived series are made again from the latest samples of the datasets they are derived from.
+fn flush(data: &mut StressTestData, params: &Params, output_path: &std::path::Path, output_format: &OutputFormat) -> Result<(), Box<dyn Error>> {
+    data.datasets.retain(|_, dataset| !dataset.derived);
+    for series in &params.derived_series {
+        data.add_derived_series(series)?;
+    }
+    draw_to_file(output_path, output_format, Some(data), params, &params.chart_specs)
+}
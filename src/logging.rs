@@ -0,0 +1,114 @@
+// Status messages of the command line tool, written to stderr through the `log` facade so that
+// standard output only holds results such as summaries and `--check` rows. `--quiet` leaves only
+// warnings, and `--verbose` adds what each phase did and how long it took. Programs using the
+// library see the same messages through whichever logger they install.
+use log::{Level, LevelFilter, Log, Metadata};
+use std::io::{IsTerminal, Write};
+use std::sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, mpsc::RecvTimeoutError, OnceLock};
+use std::time::{Duration, Instant};
+
+static LOGGER: Logger = Logger;
+
+static START: OnceLock<Instant> = OnceLock::new();
+
+// Lines read from data files and lines parsed, counted as they go so that long loads can show
+// progress.
+static LINES_READ: AtomicUsize = AtomicUsize::new(0);
+static LINES_PARSED: AtomicUsize = AtomicUsize::new(0);
+
+// Whether the progress line is on the terminal, so must be cleared before a message is written.
+static PROGRESS_SHOWN: AtomicBool = AtomicBool::new(false);
+
+// How often the progress line is redrawn, and how long a load runs before it is first drawn, so
+// that quick loads do not flash it.
+static PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+static PROGRESS_DELAY: Duration = Duration::from_millis(500);
+
+// Messages of this crate only, not those of its dependencies.
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level() && metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+
+    // Verbose messages start with the seconds since the tool started, so the time of each phase
+    // can be read off.
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return
+        }
+        let mut stderr = std::io::stderr().lock();
+        if PROGRESS_SHOWN.swap(false, Ordering::Relaxed) {
+            let _ = write!(stderr, "\r\x1b[K");
+        }
+        let _ = match (record.level(), log::max_level()) {
+            (Level::Error | Level::Warn, _) => writeln!(stderr, "Warning: {}", record.args()),
+            (_, LevelFilter::Debug | LevelFilter::Trace) => writeln!(stderr, "[{:>8.3}s] {}", get_elapsed().as_secs_f64(), record.args()),
+            _ => writeln!(stderr, "{}", record.args()),
+        };
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+// Installs the logger unless one is already installed, e.g. by a program running the tool through
+// the library, and sets the level of the messages shown.
+pub(crate) fn init(verbose: bool, quiet: bool) {
+    START.get_or_init(Instant::now);
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(match (verbose, quiet) {
+        (true, _) => LevelFilter::Debug,
+        (_, true) => LevelFilter::Warn,
+        _ => LevelFilter::Info,
+    });
+}
+
+// Time since the logger was installed.
+pub(crate) fn get_elapsed() -> Duration {
+    START.get().map_or(Duration::ZERO, |start| start.elapsed())
+}
+
+pub(crate) fn add_lines_read(num_lines: usize) {
+    LINES_READ.fetch_add(num_lines, Ordering::Relaxed);
+}
+
+pub(crate) fn get_lines_read() -> usize {
+    LINES_READ.load(Ordering::Relaxed)
+}
+
+pub(crate) fn add_lines_parsed(num_lines: usize) {
+    LINES_PARSED.fetch_add(num_lines, Ordering::Relaxed);
+}
+
+// Runs the work with a line on the terminal counting the lines read and parsed so far, redrawn
+// until it finishes. Not drawn when stderr is redirected or messages are quiet.
+pub(crate) fn with_progress<T>(label: &str, work: impl FnOnce() -> T) -> T {
+    if !std::io::stderr().is_terminal() || !log::log_enabled!(Level::Info) {
+        return work()
+    }
+
+    let (start, start_read, start_parsed) = (Instant::now(), get_lines_read(), LINES_PARSED.load(Ordering::Relaxed));
+    let (done, finished) = std::sync::mpsc::channel::<()>();
+    std::thread::scope(|scope| {
+        scope.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = finished.recv_timeout(PROGRESS_INTERVAL) {
+                if start.elapsed() >= PROGRESS_DELAY {
+                    let mut stderr = std::io::stderr().lock();
+                    let (read, parsed) = (get_lines_read() - start_read, LINES_PARSED.load(Ordering::Relaxed) - start_parsed);
+                    let _ = write!(stderr, "\r\x1b[K{}: {} lines read, {} parsed in {:.1}s", label, read, parsed, start.elapsed().as_secs_f64());
+                    let _ = stderr.flush();
+                    PROGRESS_SHOWN.store(true, Ordering::Relaxed);
+                }
+            }
+            if PROGRESS_SHOWN.swap(false, Ordering::Relaxed) {
+                let _ = write!(std::io::stderr().lock(), "\r\x1b[K");
+            }
+        });
+        let result = work();
+        drop(done);
+        result
+    })
+}
@@ -0,0 +1,64 @@
+// Metadata of the machine a data file was produced on, read with `--machine-metadata` from the file
+// next to it with the same name and a `.machine.toml` or `.machine.json` extension, e.g.
+// `run1.machine.toml` for `run1.csv`. Fields such as `hostname`, `cpu`, `disk`, `kernel` and
+// `parity-db-version` become parameters prefixed with `machine-`, e.g. `machine-hostname`, and are
+// listed below the charts, so that results from different machines are not compared unknowingly.
+// Nested fields are flattened as in stress test configs.
+use super::*;
+use std::path::Path;
+
+static PARAMETER_PREFIX: &str = "machine";
+
+// The fields of the metadata file of a data file, or None without one.
+pub fn load_machine_metadata(data_path: &Path) -> Result<Option<BTreeMap<String, ParameterValue>>, Box<dyn Error>> {
+    if is_stdin(data_path) {
+        return Ok(None)
+    }
+
+    let toml_path = compression::uncompressed_path(data_path).with_extension("machine.toml");
+    let json_path = compression::uncompressed_path(data_path).with_extension("machine.json");
+
+    let mut fields: BTreeMap<String, ParameterValue> = Default::default();
+    if toml_path.is_file() {
+        log::info!("Reading machine metadata: {}", toml_path.display());
+        let text = std::fs::read_to_string(&toml_path)?;
+        let value: toml::Value = toml::from_str(&text).map_err(|e| format!("Failed to parse machine metadata {}: {}", toml_path.display(), e))?;
+        workload::add_toml_value("", &value, &mut fields);
+    } else if json_path.is_file() {
+        log::info!("Reading machine metadata: {}", json_path.display());
+        let text = std::fs::read_to_string(&json_path)?;
+        let value: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("Failed to parse machine metadata {}: {}", json_path.display(), e))?;
+        workload::add_json_value("", &value, &mut fields);
+    } else {
+        return Ok(None)
+    }
+
+    Ok(Some(fields))
+}
+
+// Parameters of the datasets of a data file, none without a metadata file.
+pub fn get_machine_parameters(data_path: &Path) -> Result<BTreeMap<String, ParameterValue>, Box<dyn Error>> {
+    let fields = load_machine_metadata(data_path)?.unwrap_or_default();
+    Ok(fields.into_iter().map(|(name, value)| (workload::parameter_name(PARAMETER_PREFIX, &name), value)).collect())
+}
+
+// Lines of the block drawn below the charts, one for each set of data files with the same metadata,
+// e.g. `run1.csv, run2.csv: cpu=EPYC 7543, hostname=bench-1`.
+pub fn get_metadata_lines(data_paths: &[PathBuf]) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut groups: Vec<(Vec<String>, String)> = Default::default();
+    for path in data_paths {
+        let Some(fields) = load_machine_metadata(path)? else {
+            log::warn!("No machine metadata next to {}", path.display());
+            continue;
+        };
+
+        let text = fields.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join(", ");
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| path.display().to_string());
+        match groups.iter_mut().find(|(_, group_text)| *group_text == text) {
+            Some((file_names, _)) => file_names.push(file_name),
+            None => groups.push((vec![file_name], text)),
+        }
+    }
+
+    Ok(groups.into_iter().map(|(file_names, text)| format!("{}: {}", file_names.join(", "), text)).collect())
+}
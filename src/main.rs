@@ -1,3 +1,14 @@
+use parity_db_visualizer::error::RegressionError;
+
+// Exit code of a `--check` run that found a regression, as opposed to any other failure.
+const REGRESSION_EXIT_CODE: i32 = 2;
+
 fn main() {
-    parity_db_visualizer::run_visualizer().unwrap();
+    if let Err(e) = parity_db_visualizer::run_visualizer() {
+        eprintln!("Error: {}", e);
+        match e.downcast_ref::<RegressionError>() {
+            Some(_) => std::process::exit(REGRESSION_EXIT_CODE),
+            None => std::process::exit(1),
+        }
+    }
 }
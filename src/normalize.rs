@@ -0,0 +1,107 @@
+// Relative performance with `--normalize-to <filter>`. Every metric sample is divided by the mean
+// of a reference dataset at the same commit count, so that the reference is drawn at 1.0.
+use super::*;
+
+// Replaces the datasets with their values relative to their reference dataset. A filter matching a
+// single dataset makes it the reference of every dataset. Otherwise each dataset is paired, as for
+// derived series, with the matching dataset which has the same values for the parameters the
+// filter does not name, and datasets without one are left out. The baseline is normalized against
+// its own reference datasets.
+pub(crate) fn normalize_to(data: &mut StressTestData, filters: &ParameterFilterSet) -> Result<(), Box<dyn Error>> {
+    let filter_text = filters.display_text(&Default::default());
+    let constrained: HashSet<&String> = filters.parameter_names().collect();
+    let unconstrained_parameters = |dataset: &DataSet| -> BTreeMap<String, ParameterValue> {
+        dataset.parameters.iter().filter(|(name, _)| !constrained.contains(name)).map(|(name, value)| (name.clone(), value.clone())).collect()
+    };
+
+    let references: Vec<&DataSet> = data.datasets.values().filter(|d| !d.derived && d.passes_filters(filters)).collect();
+    if references.is_empty() {
+        return Err(format!("Reference filter '{}' of --normalize-to did not match any dataset", filter_text).into())
+    }
+
+    let mut datasets: HashMap<String, DataSet> = Default::default();
+    let mut left_out: Vec<&String> = Default::default();
+    for (name, dataset) in &data.datasets {
+        let reference = match references.len() {
+            1 => Some(references[0]),
+            _ => references.iter().find(|r| unconstrained_parameters(r) == unconstrained_parameters(dataset)).copied(),
+        };
+        match reference {
+            Some(reference) => {
+                datasets.insert(name.clone(), normalized(dataset, reference));
+            },
+            None => left_out.push(name),
+        }
+    }
+
+    if !left_out.is_empty() {
+        left_out.sort();
+        log::warn!("Left out {} datasets without a reference dataset matching '{}':\n  {}", left_out.len(), filter_text, left_out.iter().map(|name| name.as_str()).collect::<Vec<_>>().join("\n  "));
+    }
+
+    data.datasets = datasets;
+    data.update_maxima();
+
+    if let Some(baseline) = &mut data.baseline {
+        normalize_to(baseline, filters).map_err(|e| format!("Baseline: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Commit counts missing from the reference are dropped, as are metrics whose reference mean is
+// zero. Only the metric chart values are kept, see `ChartType::is_metric`.
+fn normalized(dataset: &DataSet, reference: &DataSet) -> DataSet {
+    let mut result = DataSet::new(dataset.base_name.clone(), dataset.parameters.clone());
+    result.derived = dataset.derived;
+
+    for value in &dataset.sorted_values {
+        let Ok(index) = reference.sorted_values.binary_search_by_key(&value.num_commits, |v| v.num_commits) else {
+            continue;
+        };
+        let reference_value = &reference.sorted_values[index];
+
+        let mut normalized_value = ValueSet::new(value.num_commits);
+        let divide = |samples: &SampleSet, reference: &SampleSet, result: &mut SampleSet| {
+            let mean = reference.get_mean();
+            if reference.is_empty() || !mean.is_finite() || mean == 0.0 {
+                return
+            }
+            match samples.digest {
+                Some(_) => result.merge(&samples.scaled(1.0 / mean)),
+                None => {
+                    for sample in &samples.samples {
+                        result.add_sample(sample / mean);
+                    }
+                },
+            }
+        };
+        divide(&value.commit_time, &reference_value.commit_time, &mut normalized_value.commit_time);
+        divide(&value.sample_commit_time, &reference_value.sample_commit_time, &mut normalized_value.sample_commit_time);
+        divide(&value.commits_per_second, &reference_value.commits_per_second, &mut normalized_value.commits_per_second);
+        divide(&value.queries_per_second, &reference_value.queries_per_second, &mut normalized_value.queries_per_second);
+        divide(&value.disk_size, &reference_value.disk_size, &mut normalized_value.disk_size);
+        divide(&value.write_amplification, &reference_value.write_amplification, &mut normalized_value.write_amplification);
+        divide(&value.memory_usage, &reference_value.memory_usage, &mut normalized_value.memory_usage);
+        divide(&value.heap_usage, &reference_value.heap_usage, &mut normalized_value.heap_usage);
+        for (name, metric) in &value.plugin_metrics {
+            if let Some(reference_metric) = reference_value.plugin_metrics.get(name) {
+                divide(metric, reference_metric, normalized_value.plugin_metrics.entry(name.clone()).or_default());
+            }
+        }
+        normalized_value.timestamp.merge(&value.timestamp);
+
+        result.max_commits = std::cmp::max(result.max_commits, normalized_value.num_commits);
+        result.max_commit_time = result.max_commit_time.max(normalized_value.commit_time.value_max);
+        result.max_commits_per_second = result.max_commits_per_second.max(normalized_value.commits_per_second.value_max);
+        result.max_queries_per_second = result.max_queries_per_second.max(normalized_value.queries_per_second.value_max);
+        result.max_disk_size = result.max_disk_size.max(normalized_value.disk_size.value_max);
+        result.max_write_amplification = result.max_write_amplification.max(normalized_value.write_amplification.value_max);
+        result.max_memory_usage = result.max_memory_usage.max(normalized_value.memory_usage.value_max);
+        result.max_heap_usage = result.max_heap_usage.max(normalized_value.heap_usage.value_max);
+
+        result.sorted_values.push(normalized_value);
+    }
+
+    result
+}
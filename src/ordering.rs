@@ -0,0 +1,81 @@
+// Order of the datasets in the legend of each chart. Names are compared with their numbers in
+// numeric order, so that `readers=2` comes before `readers=10`, and `--sort-by` orders datasets by
+// a parameter or by the final mean of a metric instead.
+use super::*;
+use std::cmp::Ordering;
+
+// Text with each run of digits compared by its value, and otherwise character by character.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(a_first), Some(b_first)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len())
+        };
+
+        let ordering = match (a_first.is_ascii_digit(), b_first.is_ascii_digit()) {
+            (true, true) => {
+                let a_digits = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+                let b_digits = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+                let (a_number, b_number) = (a[..a_digits].trim_start_matches('0'), b[..b_digits].trim_start_matches('0'));
+                let ordering = a_number.len().cmp(&b_number.len()).then_with(|| a_number.cmp(b_number));
+                a = &a[a_digits..];
+                b = &b[b_digits..];
+                ordering
+            },
+            _ => {
+                a = &a[a_first.len_utf8()..];
+                b = &b[b_first.len_utf8()..];
+                a_first.cmp(&b_first)
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering
+        }
+    }
+}
+
+// A parameter or metric given to `--sort-by`, with `:asc` or `:desc` for the direction.
+#[derive(Debug, Clone)]
+pub struct SortOrder {
+    pub key: String,
+    pub descending: bool,
+}
+
+impl SortOrder {
+    pub fn parse(text: &str) -> Result<SortOrder, String> {
+        let (key, descending) = match text.rsplit_once(':') {
+            Some((key, "asc")) => (key, false),
+            Some((key, "desc")) => (key, true),
+            Some(_) => return Err(format!("invalid sort order '{}', expected a parameter or metric with an optional :asc or :desc", text)),
+            None => (text, false),
+        };
+        match key.trim() {
+            "" => Err(format!("invalid sort order '{}', expected a parameter or metric with an optional :asc or :desc", text)),
+            key => Ok(SortOrder { key: key.to_string(), descending }),
+        }
+    }
+
+    // Datasets without the parameter, or without any samples of the metric, such as derived
+    // series, come last whichever the direction.
+    pub fn cmp(&self, a: &DataSet, b: &DataSet) -> Ordering {
+        match ChartType::get_from_string(&self.key).filter(|chart_type| chart_type.is_metric()) {
+            Some(chart_type) => self.cmp_present(get_final_mean(a, &chart_type), get_final_mean(b, &chart_type), |a, b| a.total_cmp(b)),
+            None => self.cmp_present(a.parameters.get(&self.key), b.parameters.get(&self.key), |a, b| a.display_cmp(b)),
+        }
+    }
+
+    fn cmp_present<T>(&self, a: Option<T>, b: Option<T>, cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) if self.descending => cmp(&a, &b).reverse(),
+            (Some(a), Some(b)) => cmp(&a, &b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+}
+
+// Mean of the metric at the last commit count with samples of it.
+fn get_final_mean(dataset: &DataSet, chart_type: &ChartType) -> Option<f64> {
+    dataset.sorted_values.iter().rev().filter_map(|value| value.get_sample_set(chart_type)).find(|sample_set| !sample_set.is_empty()).map(|sample_set| sample_set.get_mean())
+}
@@ -0,0 +1,139 @@
+// Samples dropped with `--drop-outliers` before means and ranges are computed, so that a single
+// stall, such as a slow fsync, does not widen the error bars of a whole commit bucket.
+use super::*;
+use std::fmt;
+
+static DEFAULT_IQR_MULTIPLE: f64 = 1.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutlierFilter {
+    // Samples further than this many standard deviations from the mean of their bucket.
+    ZScore(f64),
+    // Samples beyond this multiple of the interquartile range below the first or above the third
+    // quartile of their bucket (Tukey's fences).
+    Iqr(f64),
+}
+
+impl OutlierFilter {
+    // Parses a z-score such as `3`, or `iqr` with an optional multiple of the interquartile range
+    // such as `iqr:3`.
+    pub fn parse(text: &str) -> Result<OutlierFilter, String> {
+        let text = text.trim();
+        let error = || format!("invalid outlier filter '{}', expected a z-score such as 3, or iqr with an optional multiple such as iqr:1.5", text);
+        let parse_positive = |value: &str| value.trim().parse::<f64>().ok().filter(|v| v.is_finite() && *v > 0.0).ok_or_else(error);
+
+        match text.split_once(':') {
+            Some((name, multiple)) if name.trim().eq_ignore_ascii_case("iqr") => Ok(OutlierFilter::Iqr(parse_positive(multiple)?)),
+            None if text.eq_ignore_ascii_case("iqr") => Ok(OutlierFilter::Iqr(DEFAULT_IQR_MULTIPLE)),
+            None => Ok(OutlierFilter::ZScore(parse_positive(text)?)),
+            _ => Err(error()),
+        }
+    }
+
+    // Range of the values kept, from the finite samples. None when there are too few samples to
+    // tell an outlier apart.
+    fn get_bounds(&self, samples: &SampleSet) -> Option<(f64, f64)> {
+        let sorted: Vec<f64> = samples.sorted_samples().iter().copied().filter(|sample| sample.is_finite()).collect();
+        if sorted.len() < 3 {
+            return None
+        }
+
+        match self {
+            OutlierFilter::ZScore(z_score) => {
+                let mut statistics = RunningStatistics::new();
+                for sample in &sorted {
+                    statistics.add_sample(*sample);
+                }
+                let limit = z_score * statistics.variance().sqrt();
+                Some((statistics.mean() - limit, statistics.mean() + limit))
+            },
+            OutlierFilter::Iqr(multiple) => {
+                let first_quartile = statistics::percentile(&sorted, 25.0);
+                let third_quartile = statistics::percentile(&sorted, 75.0);
+                let limit = multiple * (third_quartile - first_quartile);
+                Some((first_quartile - limit, third_quartile + limit))
+            },
+        }
+    }
+
+    // Replaces the samples with those within the bounds, in their original order. Samples which
+    // are not finite, such as rates of samples without any queries, are kept.
+    pub fn apply(&self, samples: &mut SampleSet) {
+        let Some((min, max)) = self.get_bounds(samples) else {
+            return
+        };
+
+        let mut result = SampleSet::new();
+        result.dropped_outliers = samples.dropped_outliers;
+        for sample in &samples.samples {
+            match sample.is_finite() && (*sample < min || *sample > max) {
+                true => result.dropped_outliers += 1,
+                false => result.add_sample(*sample),
+            }
+        }
+        *samples = result;
+    }
+}
+
+impl fmt::Display for OutlierFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutlierFilter::ZScore(z_score) => write!(f, "{}", z_score),
+            OutlierFilter::Iqr(multiple) => write!(f, "iqr:{}", multiple),
+        }
+    }
+}
+
+// Drops the outliers of every metric of every bucket, of the baseline too, and reports how many
+// were dropped from each dataset.
+pub(crate) fn drop_outliers(data: &mut StressTestData, filter: &OutlierFilter) {
+    drop_dataset_outliers(data, filter);
+    print_dropped(data, &format!("Dropped outliers (--drop-outliers {}):", filter));
+
+    if let Some(baseline) = &mut data.baseline {
+        drop_dataset_outliers(baseline, filter);
+        print_dropped(baseline, "Dropped baseline outliers:");
+    }
+}
+
+// Cumulative commit times are left alone, as a stall shifts every later bucket of its run rather
+// than standing out in one. The commit rates of each run are only kept as running statistics, so
+// run deviation charts still include every sample.
+fn drop_dataset_outliers(data: &mut StressTestData, filter: &OutlierFilter) {
+    for dataset in data.datasets.values_mut() {
+        for value in &mut dataset.sorted_values {
+            filter.apply(&mut value.sample_commit_time);
+            filter.apply(&mut value.commits_per_second);
+            filter.apply(&mut value.queries_per_second);
+            filter.apply(&mut value.query_latency);
+            filter.apply(&mut value.disk_size);
+            filter.apply(&mut value.write_amplification);
+            filter.apply(&mut value.memory_usage);
+            filter.apply(&mut value.heap_usage);
+            for sample_set in value.plugin_metrics.values_mut() {
+                filter.apply(sample_set);
+            }
+        }
+        dataset.update_maxima();
+    }
+    data.update_maxima();
+}
+
+fn print_dropped(data: &StressTestData, heading: &str) {
+    let names = data.sorted_names();
+
+    log::info!("{}", heading);
+    for name in names {
+        let mut dropped: BTreeMap<String, usize> = Default::default();
+        for value in &data.datasets[name].sorted_values {
+            for (metric, sample_set) in value.get_summary_sample_sets() {
+                *dropped.entry(metric).or_default() += sample_set.dropped_outliers;
+            }
+        }
+        let counts: Vec<String> = dropped.iter().filter(|(_, count)| **count > 0).map(|(metric, count)| format!("{} {}", metric, count)).collect();
+        match counts.is_empty() {
+            true => log::info!("  {}: none", name),
+            false => log::info!("  {}: {}", name, counts.join(", ")),
+        }
+    }
+}
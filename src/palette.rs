@@ -0,0 +1,121 @@
+use super::*;
+
+// Named palettes selectable with `--palette`. The first is the default.
+static NAMED_PALETTES: [(&str, &[RGBColor]); 4] = [
+    ("default", &[
+        full_palette::LIGHTBLUE,
+        full_palette::GREEN,
+        full_palette::YELLOW,
+        full_palette::RED,
+        full_palette::BLACK,
+        full_palette::BROWN_400,
+        full_palette::PINK,
+        full_palette::ORANGE,
+        full_palette::GREY,
+    ]),
+    ("category10", &[
+        RGBColor(0x1f, 0x77, 0xb4),
+        RGBColor(0xff, 0x7f, 0x0e),
+        RGBColor(0x2c, 0xa0, 0x2c),
+        RGBColor(0xd6, 0x27, 0x28),
+        RGBColor(0x94, 0x67, 0xbd),
+        RGBColor(0x8c, 0x56, 0x4b),
+        RGBColor(0xe3, 0x77, 0xc2),
+        RGBColor(0x7f, 0x7f, 0x7f),
+        RGBColor(0xbc, 0xbd, 0x22),
+        RGBColor(0x17, 0xbe, 0xcf),
+    ]),
+    // Distinguishable with the common forms of colour blindness.
+    ("okabe-ito", &[
+        RGBColor(0xe6, 0x9f, 0x00),
+        RGBColor(0x56, 0xb4, 0xe9),
+        RGBColor(0x00, 0x9e, 0x73),
+        RGBColor(0xf0, 0xe4, 0x42),
+        RGBColor(0x00, 0x72, 0xb2),
+        RGBColor(0xd5, 0x5e, 0x00),
+        RGBColor(0xcc, 0x79, 0xa7),
+        RGBColor(0x00, 0x00, 0x00),
+    ]),
+    // Evenly spaced along the viridis colour map, which stays distinguishable with colour
+    // blindness and in greyscale, as it rises steadily in lightness.
+    ("viridis", &[
+        RGBColor(0x44, 0x01, 0x54),
+        RGBColor(0x46, 0x32, 0x7e),
+        RGBColor(0x36, 0x5c, 0x8d),
+        RGBColor(0x27, 0x7f, 0x8e),
+        RGBColor(0x1f, 0xa1, 0x87),
+        RGBColor(0x4a, 0xc1, 0x6d),
+        RGBColor(0xa0, 0xda, 0x39),
+        RGBColor(0xfd, 0xe7, 0x25),
+    ]),
+];
+
+// Difference in relative luminance below which a colour does not stand out from the background.
+static MIN_CONTRAST: f64 = 0.1;
+
+// The colours datasets are drawn in, either a named palette or a list of hex colours such as
+// `#1f77b4,#ff7f0e`. Colours are reused once every one has been assigned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub colours: Vec<RGBColor>,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette { colours: NAMED_PALETTES[0].1.to_vec() }
+    }
+}
+
+impl Palette {
+    pub fn parse(text: &str) -> Result<Palette, String> {
+        let text = text.trim();
+        if let Some((_, colours)) = NAMED_PALETTES.iter().find(|(name, _)| *name == text) {
+            return Ok(Palette { colours: colours.to_vec() })
+        }
+
+        let names: Vec<&str> = NAMED_PALETTES.iter().map(|(name, _)| *name).collect();
+        let error = || format!("invalid palette '{}', expected one of {} or hex colours like #1f77b4,#ff7f0e", text, names.join(", "));
+        let colours = text.split(',').filter(|c| !c.trim().is_empty()).map(parse_hex_colour).collect::<Option<Vec<RGBColor>>>().ok_or_else(error)?;
+        match colours.is_empty() {
+            true => Err(error()),
+            false => Ok(Palette { colours }),
+        }
+    }
+
+    pub fn colour(&self, index: usize) -> RGBColor {
+        self.colours[index % self.colours.len()]
+    }
+
+    // The Okabe-Ito palette used by `--accessible`, leaving out any colour too close to the
+    // background to see, such as black on the dark theme.
+    pub fn accessible(background: RGBColor) -> Palette {
+        let colours = Palette::parse("okabe-ito").expect("Named palette").colours;
+        Palette { colours: colours.into_iter().filter(|colour| (luminance(*colour) - luminance(background)).abs() >= MIN_CONTRAST).collect() }
+    }
+}
+
+fn luminance(colour: RGBColor) -> f64 {
+    (0.2126 * colour.0 as f64 + 0.7152 * colour.1 as f64 + 0.0722 * colour.2 as f64) / 255.0
+}
+
+pub(crate) fn parse_hex_colour(text: &str) -> Option<RGBColor> {
+    let text = text.trim();
+    let digits = text.strip_prefix('#').unwrap_or(text);
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None
+    }
+    let value = u32::from_str_radix(digits, 16).ok()?;
+    Some(RGBColor((value >> 16) as u8, (value >> 8) as u8, value as u8))
+}
+
+// Palette index for a dataset coloured by a parameter value, independent of which other datasets
+// were loaded so that a configuration keeps its colour across runs. Integers are used as the index
+// directly, so small counts such as readers get distinct colours.
+pub fn parameter_colour_index(value: &ParameterValue) -> usize {
+    match value {
+        ParameterValue::Bool(v) => *v as usize,
+        ParameterValue::Int(v) => *v as usize,
+        ParameterValue::Float(v) => crc32fast::hash(v.to_string().as_bytes()) as usize,
+        ParameterValue::Text(v) => crc32fast::hash(v.as_bytes()) as usize,
+    }
+}
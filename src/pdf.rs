@@ -0,0 +1,327 @@
+// Multi-page reports written with `--output-format pdf`: a cover page with the parameters of the
+// run, a page for each chart and a table of summary statistics. The PDF is written directly, with
+// each chart as a compressed bitmap and the text in the standard Helvetica fonts, which every
+// viewer provides without embedding.
+use super::*;
+use std::path::Path;
+
+// A4 landscape, in points.
+static PAGE_WIDTH: f64 = 842.0;
+static PAGE_HEIGHT: f64 = 595.0;
+static MARGIN: f64 = 40.0;
+
+// Objects at fixed numbers, followed by those of the pages.
+static CATALOG_OBJECT: usize = 1;
+static PAGES_OBJECT: usize = 2;
+static FONT_OBJECT: usize = 3;
+static BOLD_FONT_OBJECT: usize = 4;
+
+// Average width of Helvetica characters relative to the font size, for wrapping and truncating
+// text without the font metrics.
+static CHARACTER_WIDTH: f64 = 0.52;
+
+// A line of text, as cells at offsets from the left margin.
+struct Line {
+    size: f64,
+    bold: bool,
+    cells: Vec<(f64, String)>,
+}
+
+impl Line {
+    fn new(size: f64, bold: bool, text: impl Into<String>) -> Line {
+        Line { size, bold, cells: vec![(0.0, text.into())] }
+    }
+
+    fn blank() -> Line {
+        Line { size: 10.0, bold: false, cells: Default::default() }
+    }
+
+    fn height(&self) -> f64 {
+        self.size * 1.5
+    }
+}
+
+// The content stream of a page and the RGB bitmap drawn by it, if any, as width, height and
+// pixels.
+struct Page {
+    content: String,
+    image: Option<(u32, u32, Vec<u8>)>,
+}
+
+// Writes the charts, with the cover page and summary statistics of the data if there is any.
+pub(crate) fn write_report(path: &Path, data: Option<&StressTestData>, params: &Params, chart_specs: &[ChartSpec]) -> Result<(), Box<dyn Error>> {
+    let mut pages: Vec<Page> = Default::default();
+    pages.extend(get_text_pages(&get_cover_lines(data, params, chart_specs), &[]));
+    for spec in chart_specs {
+        pages.push(get_chart_page(data, params, spec)?);
+    }
+    if let Some(data) = data {
+        let (header, rows) = get_summary_table(data, params);
+        pages.extend(get_text_pages(&rows, &header));
+    }
+
+    std::fs::write(path, get_document(&pages, params.footer.as_deref())).map_err(|e| format!("Failed to write report {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+fn get_cover_lines(data: Option<&StressTestData>, params: &Params, chart_specs: &[ChartSpec]) -> Vec<Line> {
+    let mut lines = vec![
+        Line::new(24.0, true, params.title.as_deref().unwrap_or("Stress Test Report")),
+        Line::new(10.0, false, format!("Generated by {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))),
+        Line::blank(),
+    ];
+    let text_width = PAGE_WIDTH - 2.0 * MARGIN;
+
+    if let Some(data) = data {
+        let names = data.sorted_names();
+        let datasets: Vec<&DataSet> = names.iter().map(|name| &data.datasets[*name]).collect();
+
+        lines.push(Line::new(14.0, true, "Run Parameters"));
+        lines.push(Line::new(10.0, false, format!("{} datasets of up to {} commits", datasets.len(), data.max_commits)));
+        let mut parameters: BTreeMap<&String, Vec<&ParameterValue>> = Default::default();
+        for dataset in &datasets {
+            for (name, value) in &dataset.parameters {
+                let values = parameters.entry(name).or_default();
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+        }
+        for (name, mut values) in parameters {
+            values.sort_by(|a, b| a.display_cmp(b));
+            let values_text = values.iter().map(|value| config::label_value(&params.parameter_labels, name, &value.to_string())).collect::<Vec<String>>().join(", ");
+            let label = config::label_name(&params.parameter_labels, name);
+            for (index, text) in wrap(&values_text, 10.0, text_width - 200.0).into_iter().enumerate() {
+                let mut cells = vec![(200.0, text)];
+                if index == 0 {
+                    cells.insert(0, (0.0, label.clone()));
+                }
+                lines.push(Line { size: 10.0, bold: false, cells });
+            }
+        }
+        lines.push(Line::blank());
+
+        lines.push(Line::new(14.0, true, "Datasets"));
+        let include_parameters = terminal::get_varying_parameters(&datasets);
+        for dataset in &datasets {
+            let name = DataSet::get_name_including(dataset.base_name.clone(), &dataset.parameters, &include_parameters, &params.parameter_labels);
+            lines.extend(wrap(&name, 10.0, text_width).into_iter().map(|text| Line::new(10.0, false, text)));
+        }
+        lines.push(Line::blank());
+    }
+
+    lines.push(Line::new(14.0, true, "Charts"));
+    for (index, spec) in chart_specs.iter().enumerate() {
+        let text = format!("{}. {}", index + 1, params.get_chart_title(spec));
+        lines.extend(wrap(&text, 10.0, text_width).into_iter().map(|text| Line::new(10.0, false, text)));
+    }
+
+    lines
+}
+
+// The chart drawn as on its own with `--separate-files`, but as wide as fits the page. The title
+// and footer are on the cover and every page instead.
+fn get_chart_page(data: Option<&StressTestData>, params: &Params, spec: &ChartSpec) -> Result<Page, Box<dyn Error>> {
+    let (width, height) = (params.chart_size * 3 / 2, params.chart_size);
+    let mut pixels = vec![0u8; width as usize * height as usize * 3];
+    {
+        let area = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+        area.fill(&params.theme.background)?;
+        if let Some(data) = data {
+            draw_stress_test_data(&area, data, params, std::slice::from_ref(spec))?;
+        }
+        area.present()?;
+    }
+
+    let scale = ((PAGE_WIDTH - 2.0 * MARGIN) / width as f64).min((PAGE_HEIGHT - 2.0 * MARGIN) / height as f64);
+    let (image_width, image_height) = (width as f64 * scale, height as f64 * scale);
+    let content = format!("q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Chart Do Q\n", image_width, image_height, (PAGE_WIDTH - image_width) / 2.0, (PAGE_HEIGHT - image_height) / 2.0);
+
+    Ok(Page { content, image: Some((width, height, pixels)) })
+}
+
+// Statistics of each dataset and metric over every commit bucket, as with `--stats-out`.
+fn get_summary_table(data: &StressTestData, params: &Params) -> (Vec<Line>, Vec<Line>) {
+    let size = 7.0;
+    let columns = [0.0, 250.0, 345.0, 395.0, 447.0, 499.0, 551.0, 603.0, 655.0, 707.0];
+    let row = |bold: bool, texts: Vec<String>| Line { size, bold, cells: columns.iter().copied().zip(texts).collect() };
+
+    let header = vec![
+        Line::new(14.0, true, "Summary Statistics"),
+        row(true, ["Dataset", "Metric", "Samples", "Mean", "Stddev", "Min", "Max", "p50", "p95", "p99"].map(String::from).to_vec()),
+    ];
+
+    let names = data.sorted_names();
+    let datasets: Vec<&DataSet> = names.iter().map(|name| &data.datasets[*name]).collect();
+    let include_parameters = terminal::get_varying_parameters(&datasets);
+
+    let rows = summary::get_summary_rows(data, None).iter().map(|summary_row| {
+        let dataset = &data.datasets[&summary_row.dataset];
+        let name = DataSet::get_name_including(dataset.base_name.clone(), &dataset.parameters, &include_parameters, &params.parameter_labels);
+        let mut texts = vec![truncate(&name, size, columns[1] - columns[0] - 5.0), truncate(&summary_row.metric, size, columns[2] - columns[1] - 5.0), summary_row.samples.to_string()];
+        texts.extend([summary_row.mean, summary_row.stddev, summary_row.min, summary_row.max, summary_row.p50, summary_row.p95, summary_row.p99].map(format_value));
+        row(false, texts)
+    }).collect();
+
+    (header, rows)
+}
+
+fn format_value(value: f64) -> String {
+    match value.abs() {
+        v if v >= 100000.0 => format!("{:.0}", value),
+        v if v >= 100.0 => format!("{:.2}", value),
+        _ => format!("{:.4}", value),
+    }
+}
+
+// Lines flowed onto as many pages as they need, with the header lines at the top of each page.
+fn get_text_pages(lines: &[Line], header: &[Line]) -> Vec<Page> {
+    let bottom = MARGIN + 20.0;
+    let mut pages: Vec<Page> = Default::default();
+    let mut page: Option<String> = None;
+    let mut y = 0.0;
+    for line in lines {
+        if page.is_some() && y - line.height() < bottom {
+            pages.extend(page.take().map(|content| Page { content, image: None }));
+        }
+        let content = page.get_or_insert_with(|| {
+            y = PAGE_HEIGHT - MARGIN;
+            let mut content = String::new();
+            for header_line in header {
+                y -= header_line.height();
+                write_line(&mut content, header_line, y);
+            }
+            content
+        });
+        y -= line.height();
+        write_line(content, line, y);
+    }
+    pages.extend(page.map(|content| Page { content, image: None }));
+    pages
+}
+
+fn write_line(content: &mut String, line: &Line, y: f64) {
+    let font = match line.bold {
+        true => "F2",
+        false => "F1",
+    };
+    for (offset, text) in &line.cells {
+        *content += &format!("BT /{} {} Tf {:.2} {:.2} Td {} Tj ET\n", font, line.size, MARGIN + offset, y, pdf_string(text));
+    }
+}
+
+// Splits text at spaces into lines which fit the width, and breaks words which do not fit alone.
+fn wrap(text: &str, size: f64, width: f64) -> Vec<String> {
+    let max_characters = ((width / (size * CHARACTER_WIDTH)) as usize).max(1);
+    let mut lines: Vec<String> = Default::default();
+    let mut line = String::new();
+    for word in text.split(' ') {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_characters {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line += word;
+        while line.chars().count() > max_characters {
+            let split = line.char_indices().nth(max_characters).map(|(index, _)| index).unwrap_or(line.len());
+            let rest = line.split_off(split);
+            lines.push(std::mem::replace(&mut line, rest));
+        }
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn truncate(text: &str, size: f64, width: f64) -> String {
+    let max_characters = ((width / (size * CHARACTER_WIDTH)) as usize).max(4);
+    match text.chars().count() > max_characters {
+        true => format!("{}...", text.chars().take(max_characters - 3).collect::<String>()),
+        false => text.to_string(),
+    }
+}
+
+// A PDF string literal. Characters outside Latin-1, which the fonts are encoded in, are replaced.
+pub(crate) fn pdf_string(text: &str) -> String {
+    let mut result = String::from("(");
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                result.push('\\');
+                result.push(c);
+            },
+            ' '..='~' => result.push(c),
+            '\n' => result += "\\n",
+            '\u{a0}'..='\u{ff}' => result += &format!("\\{:03o}", c as u32),
+            _ => result.push('?'),
+        }
+    }
+    result.push(')');
+    result
+}
+
+fn get_document(pages: &[Page], footer_text: Option<&str>) -> Vec<u8> {
+    let mut objects: Vec<Vec<u8>> = vec![Default::default(); 4];
+    objects[CATALOG_OBJECT - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_OBJECT).into_bytes();
+    objects[FONT_OBJECT - 1] = b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec();
+    objects[BOLD_FONT_OBJECT - 1] = b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec();
+
+    let mut page_objects: Vec<usize> = Default::default();
+    for (index, page) in pages.iter().enumerate() {
+        let mut content = page.content.clone();
+        let footer = format!("{} / {}", index + 1, pages.len());
+        content += &format!("BT /F1 8 Tf {:.2} {:.2} Td {} Tj ET\n", PAGE_WIDTH - MARGIN - footer.len() as f64 * 8.0 * CHARACTER_WIDTH, MARGIN / 2.0, pdf_string(&footer));
+        if let Some(text) = footer_text {
+            content += &format!("BT /F1 8 Tf {:.2} {:.2} Td {} Tj ET\n", MARGIN, MARGIN / 2.0, pdf_string(&truncate(text, 8.0, PAGE_WIDTH - 2.0 * MARGIN - 60.0)));
+        }
+        objects.push(get_stream(String::new(), content.as_bytes()));
+        let content_object = objects.len();
+
+        let image_resources = match &page.image {
+            Some((width, height, pixels)) => {
+                let compressed = miniz_oxide::deflate::compress_to_vec_zlib(pixels, 6);
+                objects.push(get_stream(format!("/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /FlateDecode", width, height), &compressed));
+                format!(" /XObject << /Chart {} 0 R >>", objects.len())
+            },
+            None => String::new(),
+        };
+
+        objects.push(format!("<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R /F2 {} 0 R >>{} >> /Contents {} 0 R >>",
+            PAGES_OBJECT, PAGE_WIDTH, PAGE_HEIGHT, FONT_OBJECT, BOLD_FONT_OBJECT, image_resources, content_object).into_bytes());
+        page_objects.push(objects.len());
+    }
+
+    let kids: Vec<String> = page_objects.iter().map(|object| format!("{} 0 R", object)).collect();
+    objects[PAGES_OBJECT - 1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_objects.len()).into_bytes();
+
+    // The comment after the header marks the file as binary for transfer programs.
+    let mut document: Vec<u8> = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets: Vec<usize> = Default::default();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document.extend(format!("{} 0 obj\n", index + 1).as_bytes());
+        document.extend(object);
+        document.extend(b"\nendobj\n");
+    }
+
+    let xref_offset = document.len();
+    document.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        document.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    document.extend(format!("trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, CATALOG_OBJECT, xref_offset).as_bytes());
+    document
+}
+
+fn get_stream(dictionary: String, data: &[u8]) -> Vec<u8> {
+    let entries = match dictionary.is_empty() {
+        true => format!("/Length {}", data.len()),
+        false => format!("{} /Length {}", dictionary, data.len()),
+    };
+    let mut bytes = format!("<< {} >>\nstream\n", entries).into_bytes();
+    bytes.extend(data);
+    bytes.extend(b"\nendstream");
+    bytes
+}
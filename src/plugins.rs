@@ -0,0 +1,118 @@
+// Registry for metrics and charts defined outside of this crate. Register them before calling
+// `run_visualizer` and they can be selected with `--chart-type` like the built in chart types.
+//
+// A metric extractor computes one value per row of the data file. Its chart plots the mean and
+// spread of the value at each commit count, the same as `commits-per-second`. A chart renderer
+// computes its own series from the samples of each dataset, which are then drawn as lines.
+use super::*;
+use std::sync::{Arc, RwLock};
+
+// The columns of a single row of a data file.
+#[derive(Debug, Clone)]
+pub struct Row<'a> {
+    pub base_name: &'a str,
+    pub parameters: &'a BTreeMap<String, ParameterValue>,
+    pub total_commits: u64,
+    pub total_commit_time: f64,
+    pub commits: u64,
+    pub commit_time: f64,
+    pub queries: u64,
+    pub query_time: f64,
+    // Bytes, for stress tests which write these columns.
+    pub disk_size: Option<u64>,
+    pub bytes_written: Option<u64>,
+    pub rss: Option<u64>,
+    pub heap: Option<u64>,
+}
+
+// All samples of a dataset at one commit count.
+#[derive(Debug, Clone)]
+pub struct ValuePoint {
+    pub commits: u64,
+    pub commit_time: Vec<f64>,
+    pub sample_commit_time: Vec<f64>,
+    pub commits_per_second: Vec<f64>,
+    pub queries_per_second: Vec<f64>,
+    pub query_latency: Vec<f64>,
+    pub disk_size: Vec<f64>,
+    pub write_amplification: Vec<f64>,
+    pub memory_usage: Vec<f64>,
+    pub heap_usage: Vec<f64>,
+    // Values of registered metrics, keyed by metric name.
+    pub metrics: BTreeMap<String, Vec<f64>>,
+}
+
+pub trait MetricExtractor: Send + Sync {
+    // Chart type name used on the command line.
+    fn name(&self) -> &str;
+    fn title(&self) -> String;
+    // Returns None to skip the row.
+    fn extract(&self, row: &Row) -> Option<f64>;
+    // Changes along with how values are extracted, so that data files cached with `--cache` are
+    // parsed again.
+    fn cache_key(&self) -> String {
+        self.name().to_string()
+    }
+}
+
+pub trait ChartRenderer: Send + Sync {
+    // Chart type name used on the command line.
+    fn name(&self) -> &str;
+    fn title(&self) -> String;
+    fn x_label(&self) -> String;
+    fn y_label(&self) -> String;
+    fn compute_series(&self, values: &[ValuePoint]) -> Vec<(f64, f64)>;
+}
+
+static METRICS: RwLock<Vec<Arc<dyn MetricExtractor>>> = RwLock::new(Vec::new());
+static CHARTS: RwLock<Vec<Arc<dyn ChartRenderer>>> = RwLock::new(Vec::new());
+
+// A metric with the name of one already registered replaces it.
+pub fn register_metric(metric: Arc<dyn MetricExtractor>) {
+    let mut metrics = METRICS.write().expect("Metric registry poisoned");
+    metrics.retain(|registered| registered.name() != metric.name());
+    metrics.push(metric);
+}
+
+pub fn register_chart(chart: Arc<dyn ChartRenderer>) {
+    CHARTS.write().expect("Chart registry poisoned").push(chart);
+}
+
+pub fn metrics() -> Vec<Arc<dyn MetricExtractor>> {
+    METRICS.read().expect("Metric registry poisoned").clone()
+}
+
+pub fn get_metric(name: &str) -> Option<Arc<dyn MetricExtractor>> {
+    metrics().into_iter().find(|metric| metric.name() == name)
+}
+
+pub fn get_chart(name: &str) -> Option<Arc<dyn ChartRenderer>> {
+    CHARTS.read().expect("Chart registry poisoned").iter().find(|chart| chart.name() == name).cloned()
+}
+
+// Names of all registered chart types, metrics first.
+pub fn chart_type_names() -> Vec<String> {
+    let mut names: Vec<String> = metrics().iter().map(|metric| metric.name().to_string()).collect();
+    names.extend(CHARTS.read().expect("Chart registry poisoned").iter().map(|chart| chart.name().to_string()));
+    names
+}
+
+pub fn extract_metrics(row: &Row) -> BTreeMap<String, f64> {
+    metrics().iter().filter_map(|metric| metric.extract(row).map(|value| (metric.name().to_string(), value))).collect()
+}
+
+pub(crate) fn get_value_points(dataset: &DataSet) -> Vec<ValuePoint> {
+    dataset.sorted_values.iter().map(|value| ValuePoint {
+        commits: value.num_commits,
+        commit_time: value.commit_time.samples.clone(),
+        sample_commit_time: value.sample_commit_time.samples.clone(),
+        commits_per_second: value.commits_per_second.samples.clone(),
+        queries_per_second: value.queries_per_second.samples.clone(),
+        query_latency: value.query_latency.samples.clone(),
+        disk_size: value.disk_size.samples.clone(),
+        write_amplification: value.write_amplification.samples.clone(),
+        memory_usage: value.memory_usage.samples.clone(),
+        heap_usage: value.heap_usage.samples.clone(),
+        metrics: value.plugin_metrics.iter().map(|(name, sample_set)| (name.clone(), sample_set.samples.clone())).collect(),
+    }).collect()
+}
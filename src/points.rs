@@ -0,0 +1,75 @@
+// Points of every plotted series written with `--export-points`, so that the aggregated data can be
+// analysed further without computing the means again. Each series is written to its own file,
+// named by the position and type of its chart and the name of the series.
+use super::*;
+use serde::Serialize;
+use std::path::Path;
+use testing::SeriesSnapshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum PointsFormat {
+    Csv,
+    // An object with the chart title, the series and dataset names and the points.
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct PointRow {
+    x: f64,
+    // The plotted value, which is the mean of the samples on metric charts.
+    mean: f64,
+    // Only for the means of metric charts.
+    min: Option<f64>,
+    max: Option<f64>,
+    range: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct SeriesFile<'a> {
+    chart: String,
+    series: &'a str,
+    dataset: &'a str,
+    points: Vec<PointRow>,
+}
+
+static CSV_HEADER: &str = "x,mean,min,max,range";
+
+pub(crate) fn export_points(data: &StressTestData, params: &Params, directory: &Path, format: PointsFormat) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(directory).map_err(|e| format!("Failed to create directory {}: {}", directory.display(), e))?;
+
+    let series = testing::chart_series(data, params)?;
+    for (chart, spec) in params.chart_specs.iter().enumerate() {
+        for (index, snapshot) in series.iter().filter(|s| s.chart == chart).enumerate() {
+            let extension = match format {
+                PointsFormat::Csv => "csv",
+                PointsFormat::Json => "json",
+            };
+            let path = directory.join(format!("{}-{}-{}-{}.{}", chart + 1, spec.chart_type, index + 1, get_slug(&snapshot.name), extension));
+
+            let rows = get_rows(snapshot);
+            let text = match format {
+                PointsFormat::Csv => {
+                    let mut text = String::from(CSV_HEADER);
+                    text += "\n";
+                    for row in &rows {
+                        let optional = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+                        text += &format!("{},{},{},{},{}\n", row.x, row.mean, optional(row.min), optional(row.max), optional(row.range));
+                    }
+                    text
+                },
+                PointsFormat::Json => serde_json::to_string_pretty(&SeriesFile { chart: params.get_chart_title(spec), series: &snapshot.name, dataset: &snapshot.dataset, points: rows })?,
+            };
+            std::fs::write(&path, text).map_err(|e| format!("Failed to write points {}: {}", path.display(), e))?;
+            log::debug!("Wrote {} points to {}", snapshot.points.len(), path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn get_rows(snapshot: &SeriesSnapshot) -> Vec<PointRow> {
+    snapshot.points.iter().enumerate().map(|(index, (x, mean))| {
+        let spread = snapshot.spread.get(index);
+        PointRow { x: *x, mean: *mean, min: spread.map(|s| s.min), max: spread.map(|s| s.max), range: spread.map(|s| s.range) }
+    }).collect()
+}
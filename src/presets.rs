@@ -0,0 +1,111 @@
+// Built-in sets of charts for the standard views of a stress test, added with `--preset` so that
+// they need no chart types or filters. Each preset is written as a chart template of a config file
+// would be, and is drawn in a grid which suits its charts.
+use super::*;
+use config::ChartTemplate;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Preset {
+    // Commits and queries per second over the run, the final throughput of each dataset and how
+    // much it varies between runs.
+    ThroughputOverview,
+    // Commit times with their percentiles, outliers, autocorrelation and sampling, and query
+    // latency.
+    LatencyDeepDive,
+    // Throughput and commit time compared between the numbers of readers.
+    ReadersScaling,
+}
+
+static THROUGHPUT_OVERVIEW: &str = r#"
+[[charts]]
+type = "commits-per-second"
+spread = "ci95"
+
+[[charts]]
+type = "queries-per-second"
+filter = "readers>0"
+spread = "ci95"
+
+[[charts]]
+type = "final-throughput-bars"
+
+[[charts]]
+type = "commits-per-second-run-variance"
+"#;
+
+static LATENCY_DEEP_DIVE: &str = r#"
+[[charts]]
+type = "commit-time"
+log-y = true
+
+[[charts]]
+type = "commit-time-rolling-percentile"
+
+[[charts]]
+type = "commit-time-scatter"
+
+[[charts]]
+type = "query-latency"
+filter = "readers>0"
+
+[[charts]]
+type = "commit-time-autocorrelation"
+
+[[charts]]
+type = "sample-interval-histogram"
+"#;
+
+static READERS_SCALING: &str = r#"
+[[charts]]
+type = "final-throughput-bars"
+title = "Final Throughput by Readers"
+
+[[charts]]
+type = "commits-per-second"
+title = "Commits per Second by Readers"
+filter = "readers==0; readers>0"
+spread = "none"
+
+[[charts]]
+type = "queries-per-second"
+title = "Queries per Second by Readers"
+filter = "readers>0"
+spread = "none"
+
+[[charts]]
+type = "commit-time"
+title = "Commit Time by Readers"
+filter = "readers==0; readers>0"
+spread = "none"
+"#;
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::ThroughputOverview => "throughput-overview",
+            Preset::LatencyDeepDive => "latency-deep-dive",
+            Preset::ReadersScaling => "readers-scaling",
+        }
+    }
+
+    pub fn layout(&self) -> Layout {
+        match self {
+            Preset::ThroughputOverview | Preset::ReadersScaling => Layout { rows: 2, cols: 2 },
+            Preset::LatencyDeepDive => Layout { rows: 3, cols: 2 },
+        }
+    }
+
+    fn template(&self) -> ChartTemplate {
+        let text = match self {
+            Preset::ThroughputOverview => THROUGHPUT_OVERVIEW,
+            Preset::LatencyDeepDive => LATENCY_DEEP_DIVE,
+            Preset::ReadersScaling => READERS_SCALING,
+        };
+        toml::from_str(text).unwrap_or_else(|e| panic!("Invalid preset {}: {}", self.name(), e))
+    }
+
+    // Options the preset leaves unset fall back to the command line, as for templates.
+    pub fn get_charts(&self, defaults: &ChartOptions) -> Result<Vec<ChartSpec>, Box<dyn Error>> {
+        config::instantiate_charts(&self.template().charts, &Default::default(), &format!("preset {}", self.name()), defaults)
+    }
+}
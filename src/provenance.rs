@@ -0,0 +1,159 @@
+use sha2::{Digest, Sha256};
+use std::{error::Error, path::{Path, PathBuf}};
+
+// Information needed to reproduce a chart, stored in the output file alongside the image.
+#[derive(Debug)]
+pub struct Provenance {
+    pub entries: Vec<(String, String)>,
+}
+
+impl Provenance {
+    pub fn new(config_path: Option<&PathBuf>, data_paths: &[PathBuf]) -> Result<Provenance, Box<dyn Error>> {
+        let mut entries: Vec<(String, String)> = Default::default();
+
+        entries.push(("Software".to_string(), format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))));
+
+        let command_line: Vec<String> = std::env::args().map(|arg| match arg.contains(' ') {
+            true => format!("\"{}\"", arg),
+            false => arg,
+        }).collect();
+        entries.push(("Command line".to_string(), command_line.join(" ")));
+
+        if let Some(path) = config_path {
+            entries.push(("Config sha256".to_string(), format!("{}  {}", file_sha256(path)?, path.display())));
+        }
+
+        // Standard input has been consumed by the time the output is written.
+        let data_hashes: Result<Vec<String>, Box<dyn Error>> = data_paths.iter().map(|path| match crate::is_stdin(path) {
+            true => Ok("-  (standard input)".to_string()),
+            false => Ok(format!("{}  {}", file_sha256(path)?, path.display())),
+        }).collect();
+        entries.push(("Data sha256".to_string(), data_hashes?.join("\n")));
+
+        Ok(Provenance { entries })
+    }
+
+    // Adds the entries as tEXt chunks just before the IEND chunk of an existing PNG file.
+    pub fn write_png_text(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let bytes = std::fs::read(path)?;
+        let iend = find_png_chunk(&bytes, b"IEND").ok_or_else(|| format!("Not a valid PNG file: {}", path.display()))?;
+
+        let mut output = Vec::with_capacity(bytes.len());
+        output.extend_from_slice(&bytes[..iend]);
+        for (keyword, text) in &self.entries {
+            let mut data = latin1(keyword);
+            data.push(0);
+            data.extend(latin1(text));
+            write_png_chunk(&mut output, b"tEXt", &data);
+        }
+        output.extend_from_slice(&bytes[iend..]);
+
+        std::fs::write(path, output)?;
+        Ok(())
+    }
+
+    // Adds the entries as a <metadata> element at the start of an existing SVG file.
+    pub fn write_svg_metadata(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let svg_start = text.find("<svg").ok_or_else(|| format!("Not a valid SVG file: {}", path.display()))?;
+        let insert_at = text[svg_start..].find('>').ok_or_else(|| format!("Not a valid SVG file: {}", path.display()))? + svg_start + 1;
+
+        let mut metadata = String::from("\n<metadata>\n");
+        for (keyword, value) in &self.entries {
+            metadata += &format!("<entry name=\"{}\">{}</entry>\n", xml_escape(keyword), xml_escape(value));
+        }
+        metadata += "</metadata>";
+
+        let mut output = String::with_capacity(text.len() + metadata.len());
+        output += &text[..insert_at];
+        output += &metadata;
+        output += &text[insert_at..];
+
+        std::fs::write(path, output)?;
+        Ok(())
+    }
+
+    // Adds the entries to the document information dictionary of an existing PDF file, as an
+    // incremental update appended to the end of the file.
+    pub fn write_pdf_info(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut bytes = std::fs::read(path)?;
+        let invalid = || format!("Not a valid PDF file: {}", path.display());
+
+        let trailer = find_last(&bytes, b"trailer").ok_or_else(invalid)?;
+        let trailer_text = String::from_utf8_lossy(&bytes[trailer..]).to_string();
+        let previous_xref = trailer_text.split("startxref").nth(1).and_then(|rest| rest.split_whitespace().next()).and_then(|offset| offset.parse::<usize>().ok()).ok_or_else(invalid)?;
+        let size = trailer_text.split("/Size ").nth(1).and_then(|rest| rest.split_whitespace().next()).and_then(|size| size.parse::<usize>().ok()).ok_or_else(invalid)?;
+        let root = trailer_text.split("/Root ").nth(1).and_then(|rest| rest.split(" R").next()).ok_or_else(invalid)?.to_string();
+
+        let entries: Vec<String> = self.entries.iter().map(|(keyword, text)| format!("/{} {}", pdf_name(keyword), crate::pdf::pdf_string(text))).collect();
+        let info_offset = bytes.len();
+        bytes.extend(format!("{} 0 obj\n<< {} >>\nendobj\n", size, entries.join(" ")).as_bytes());
+
+        let xref_offset = bytes.len();
+        bytes.extend(format!("xref\n{} 1\n{:010} 00000 n \ntrailer\n<< /Size {} /Root {} R /Info {} 0 R /Prev {} >>\nstartxref\n{}\n%%EOF\n",
+            size, info_offset, size + 1, root, size, previous_xref, xref_offset).as_bytes());
+
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
+// Characters other than letters and digits are written as `#` and their hex code, e.g. spaces.
+fn pdf_name(text: &str) -> String {
+    text.bytes().map(|b| match b.is_ascii_alphanumeric() {
+        true => (b as char).to_string(),
+        false => format!("#{:02x}", b),
+    }).collect()
+}
+
+fn find_last(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes.windows(pattern.len()).rposition(|window| window == pattern)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn file_sha256(path: &Path) -> Result<String, Box<dyn Error>> {
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {} for checksum: {}", path.display(), e))?;
+    let hash = Sha256::digest(&bytes);
+    Ok(hash.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// tEXt chunks are Latin-1, so anything outside of it is replaced.
+fn latin1(text: &str) -> Vec<u8> {
+    text.chars().map(|c| match (c as u32) < 256 {
+        true => c as u8,
+        false => b'?',
+    }).collect()
+}
+
+// Byte offset of the first chunk with the given type.
+fn find_png_chunk(bytes: &[u8], chunk_type: &[u8; 4]) -> Option<usize> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+    if bytes.len() < SIGNATURE.len() || bytes[..SIGNATURE.len()] != SIGNATURE {
+        return None
+    }
+
+    let mut offset = SIGNATURE.len();
+    while offset + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[offset..offset + 4].try_into().ok()?) as usize;
+        if &bytes[offset + 4..offset + 8] == chunk_type {
+            return Some(offset)
+        }
+        // Length, type and CRC fields are 12 bytes in total.
+        offset += length + 12;
+    }
+    None
+}
+
+fn write_png_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(chunk_type);
+    hasher.update(data);
+
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(data);
+    output.extend_from_slice(&hasher.finalize().to_be_bytes());
+}
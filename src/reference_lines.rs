@@ -0,0 +1,62 @@
+// Horizontal lines at target values of a metric with `--hline`, e.g.
+// `--hline commits-per-second=20000:SLO` for a throughput every run has to reach, drawn with their
+// labels on every chart of the metric. The Y axis is stretched to take them in, so that a target
+// far above the runs is still shown.
+use super::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReferenceLine {
+    pub chart_type: ChartType,
+    pub value: f64,
+    pub label: Option<String>,
+}
+
+impl ReferenceLine {
+    // `CHART_TYPE=VALUE` optionally followed by `:LABEL`, where the label may be quoted.
+    pub fn parse(text: &str) -> Result<ReferenceLine, Box<dyn Error>> {
+        let (name, rest) = text.split_once('=').ok_or_else(|| format!("Invalid --hline '{}', expected CHART_TYPE=VALUE or CHART_TYPE=VALUE:LABEL", text))?;
+        let chart_type = ChartType::get_from_string(name.trim())
+            .filter(ChartType::is_metric)
+            .ok_or_else(|| format!("Invalid --hline '{}': '{}' is not a metric chart type", text, name.trim()))?;
+        let (value_text, label) = match rest.split_once(':') {
+            Some((value_text, label)) => (value_text, Some(label.trim())),
+            None => (rest, None),
+        };
+        let value = value_text.trim().parse::<f64>().ok().filter(|value| value.is_finite())
+            .ok_or_else(|| format!("Invalid --hline '{}': '{}' is not a number", text, value_text.trim()))?;
+        let label = label.map(|label| label.strip_prefix('"').and_then(|l| l.strip_suffix('"')).unwrap_or(label).to_string()).filter(|label| !label.is_empty());
+        Ok(ReferenceLine { chart_type, value, label })
+    }
+
+    // Only charts whose Y axis is in the units of the metric, rather than transformed or normalized.
+    pub(crate) fn applies_to(&self, spec: &ChartSpec, params: &Params) -> bool {
+        spec.chart_type == self.chart_type && spec.transform.is_identity() && params.normalize_to.is_none()
+    }
+}
+
+// Draws each line across the chart, with its label above its right end. Called before the series
+// are drawn, so that the lines stay behind them.
+pub(crate) fn draw_reference_lines<'a, DB: DrawingBackend + 'a, Y: Ranged<ValueType = f64>>(cc: &mut plotters::chart::ChartContext<'a, DB, Cartesian2d<plotters::coord::types::RangedCoordf64, Y>>, context: &ChartContext, params: &Params, pixel_height: f64) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let (x_range, y_range) = (cc.x_range(), cc.y_range());
+    let visible: Vec<&ReferenceLine> = params.reference_lines.iter()
+        .filter(|line| line.applies_to(context.spec, params) && (y_range.start..=y_range.end).contains(&line.value))
+        .collect();
+    if visible.is_empty() {
+        return Ok(())
+    }
+
+    let colour = context.theme.muted_text();
+    for line in &visible {
+        cc.draw_series(LineSeries::new(vec![(x_range.start, line.value), (x_range.end, line.value)], colour.stroke_width(params.stroke_width as u32)))?;
+    }
+
+    let font_size = pixel_height * 0.015;
+    let offset = (pixel_height * 0.005) as i32;
+    let style = (context.fonts.family.as_str(), font_size).into_font().color(&colour).pos(Pos::new(HPos::Right, VPos::Bottom));
+    cc.draw_series(visible.iter().filter_map(|line| line.label.as_ref().map(|label| {
+        EmptyElement::at((x_range.end, line.value))
+        + Text::new(label.clone(), (-offset, -offset), style.clone())
+    })))?;
+
+    Ok(())
+}
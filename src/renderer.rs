@@ -0,0 +1,274 @@
+// Drawing of the charts separated from the aggregation of their datasets, so that crates using
+// this one can draw the same charts with another backend, e.g. writing a Vega-Lite spec or a
+// gnuplot script. `render_with` filters, groups, smooths and downsamples the datasets of each chart
+// and fits its Y range, then hands the chart to the renderer. `PlottersRenderer` draws the PNG,
+// SVG, GIF and PDF output.
+use super::*;
+use plotters::coord::Shift;
+
+pub trait Renderer {
+    // Draws the chart at the index of the chart specs. Charts are rendered in order.
+    fn render_chart(&mut self, index: usize, context: &ChartContext, data: &StressTestData, params: &Params) -> Result<(), Box<dyn Error>>;
+}
+
+// Draws each chart into its own part of a drawing area, split by the layout of the params.
+pub struct PlottersRenderer<DB: DrawingBackend> {
+    areas: Vec<DrawingArea<DB, Shift>>,
+}
+
+impl<DB: DrawingBackend> PlottersRenderer<DB> {
+    pub fn new(area: &DrawingArea<DB, Shift>, params: &Params, num_charts: usize) -> Result<PlottersRenderer<DB>, Box<dyn Error>> {
+        let layout = params.get_layout(num_charts)?;
+        Ok(PlottersRenderer { areas: area.split_evenly((layout.rows, layout.cols)) })
+    }
+}
+
+impl<DB: DrawingBackend> Renderer for PlottersRenderer<DB> where DB::ErrorType: 'static {
+    fn render_chart(&mut self, index: usize, context: &ChartContext, data: &StressTestData, params: &Params) -> Result<(), Box<dyn Error>> {
+        let Some(area) = self.areas.get(index) else {
+            return Ok(())
+        };
+        let spec = context.spec;
+
+        match &spec.chart_type {
+            ChartType::CommitTimeAutocorrelation => return draw_autocorrelation_chart(area, context),
+            ChartType::SampleIntervalHistogram => return draw_sample_interval_histogram(area, context),
+            ChartType::CommitTimeRollingPercentile => return draw_rolling_percentile_chart(area, context),
+            ChartType::CommitsPerSecondRunVariance => return draw_run_variance_chart(area, context),
+            ChartType::QueryLatency => return draw_query_latency_chart(area, context),
+            ChartType::CommitTimeOverWallClock => return draw_wall_clock_chart(area, context),
+            ChartType::CommitTimeScatter | ChartType::CommitsPerSecondScatter | ChartType::QueriesPerSecondScatter => return draw_scatter_chart(area, context),
+            ChartType::FinalThroughputBars => return draw_final_throughput_chart(area, context),
+            ChartType::QueryCommitRatio => return draw_bucket_line_chart(area, context, "Queries per Commit", DataSet::get_query_commit_ratio),
+            ChartType::CommitsPerSecondVariation => return draw_bucket_line_chart(area, context, "Coefficient of Variation (Std Dev / Mean)", DataSet::get_commits_per_second_variation),
+            ChartType::PluginChart(name) => return draw_plugin_chart(area, context, name),
+            _ => {},
+        }
+
+        if let (BaselineMode::Delta | BaselineMode::Significance, Some(baseline)) = (&params.baseline_mode, &data.baseline) {
+            return draw_baseline_delta_chart(area, context, baseline, params.baseline_mode == BaselineMode::Significance)
+        }
+        if let Some(secondary) = &spec.secondary {
+            return dual_axis::draw_dual_axis_chart(area, context, data, params, secondary)
+        }
+
+        let pixel_height = (area.get_pixel_range().1.end - area.get_pixel_range().1.start) as f64;
+
+        let mut builder = ChartBuilder::on(area);
+        builder
+            .x_label_area_size((5).percent_height())
+            .y_label_area_size((6).percent_height())
+            .margin((2).percent_height())
+            .margin_right((5).percent_height())
+            .caption(context.title.clone(), context.fonts.title(&context.theme.text));
+
+        let x_range = spec.x_range.apply(match params.x_axis {
+            XAxis::Commits => 0.0f64..data.max_commits as f64,
+            XAxis::Time | XAxis::Metric(_) => get_x_range(context.datasets.iter().map(|entry| entry.1), &params.x_axis).unwrap_or(0.0..1.0),
+        });
+
+        let (min_y, max_y) = (context.y_range.start, context.y_range.end);
+        match spec.options.log_y {
+            false => draw_metric_chart(builder.build_cartesian_2d(x_range, spec.y_range.apply(min_y..max_y))?, context, data, params, pixel_height),
+            true => {
+                // The axis starts at the smallest positive value rather than zero.
+                let min_y = context.min_positive_y.unwrap_or(1.0);
+                let max_y = match max_y > min_y {
+                    true => max_y,
+                    false => min_y * 10.0,
+                };
+                // A pinned maximum below every value still needs a positive minimum.
+                let y_range = match spec.y_range.apply(min_y..max_y) {
+                    range if range.start > 0.0 => range,
+                    range => range.end / 10.0..range.end,
+                };
+                draw_metric_chart(builder.build_cartesian_2d(x_range, y_range.log_scale())?, context, data, params, pixel_height)
+            },
+        }
+    }
+}
+
+// Renders each of the chart specs from the datasets which pass its filters.
+pub fn render_with(renderer: &mut dyn Renderer, data: &StressTestData, params: &Params, chart_specs: &[ChartSpec]) -> Result<(), Box<dyn Error>> {
+    let datasets = get_dataset_entries(data, params);
+    for (index, spec) in chart_specs.iter().enumerate() {
+        with_chart_context(&datasets, spec, data, params, |context| renderer.render_chart(index, context, data, params))?;
+    }
+    Ok(())
+}
+
+// Draws the charts on the threads of the rayon pool, each into a buffer the size of its part of the
+// area, which are then copied into the area. Only worth it for bitmap backends, as other backends
+// would draw the copied buffers pixel by pixel.
+pub(crate) fn render_in_parallel<DB: DrawingBackend>(area: &DrawingArea<DB, Shift>, data: &StressTestData, params: &Params, chart_specs: &[ChartSpec]) -> Result<(), Box<dyn Error>> where DB::ErrorType: 'static {
+    let layout = params.get_layout(chart_specs.len())?;
+    let areas = area.split_evenly((layout.rows, layout.cols));
+    let datasets = get_dataset_entries(data, params);
+
+    // Drawing areas are not shared between threads, so only their sizes are. Errors are not sent
+    // between threads either, so only their messages are kept.
+    let charts: Vec<((u32, u32), &ChartSpec)> = areas.iter().map(|chart_area| chart_area.dim_in_pixel()).zip(chart_specs).collect();
+    let buffers = charts.par_iter().map(|((width, height), spec)| {
+        let (width, height) = (*width, *height);
+        let mut pixels = vec![0u8; width as usize * height as usize * 3];
+        with_chart_context(&datasets, spec, data, params, |context| -> Result<(), Box<dyn Error>> {
+            let buffer_area = BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area();
+            buffer_area.fill(&params.theme.background)?;
+            PlottersRenderer { areas: vec![buffer_area.clone()] }.render_chart(0, context, data, params)?;
+            buffer_area.present()?;
+            Ok(())
+        }).map_err(|e| e.to_string())?;
+        Ok(((width, height), pixels))
+    }).collect::<Result<Vec<_>, String>>()?;
+
+    for (chart_area, (size, pixels)) in areas.iter().zip(buffers) {
+        let bitmap = BitMapElement::with_owned_buffer((0, 0), size, pixels).ok_or("Chart buffer does not match the size of its area")?;
+        chart_area.draw(&bitmap)?;
+    }
+    Ok(())
+}
+
+// Each dataset with how it is drawn, in legend order.
+fn get_dataset_entries<'a>(data: &'a StressTestData, params: &Params) -> Vec<DatasetEntry<'a>> {
+    let mut datasets = Vec::new();
+    for (index, name) in data.sorted_names().into_iter().enumerate() {
+        let dataset = &data.datasets[name];
+        let colour = get_dataset_colour(params, index, dataset);
+        datasets.push((name, dataset, colour.stroke_width(params.stroke_width as u32), colour.stroke_width(params.stroke_width as u32 * 2), colour.mix(0.75), LineStyle::of_dataset(params, index, dataset), get_dataset_marker(params, index)));
+    }
+
+    // Colours stay with the name order, so that a dataset keeps its colour across sort orders.
+    if let Some(sort_by) = &params.sort_by {
+        datasets.sort_by(|a, b| sort_by.cmp(a.1, b.1));
+    }
+
+    datasets
+}
+
+// Gathers the datasets of the chart spec which pass its filters, smoothed and downsampled as they
+// are drawn, and fits the Y range to them, for the chart to be rendered from.
+fn with_chart_context<R>(datasets: &[DatasetEntry], spec: &ChartSpec, data: &StressTestData, params: &Params, render: impl FnOnce(&ChartContext) -> R) -> R {
+    let chart_type = &spec.chart_type;
+    let title = params.get_chart_title(spec);
+
+    let mut min_y: f64 = 0.0;
+    let mut max_y: f64 = 0.0;
+    let mut min_positive_y: Option<f64> = None;
+    for entry in datasets {
+        let passed_filters = entry.1.passes_filters(&spec.filters) && (chart_type.is_metric() || !entry.1.derived);
+        if passed_filters {
+            let dataset_max_y = match chart_type {
+                ChartType::CommitTime => entry.1.max_commit_time,
+                ChartType::CommitsPerSecond => entry.1.max_commits_per_second,
+                ChartType::QueriesPerSecond => entry.1.max_queries_per_second,
+                ChartType::DiskSize => entry.1.max_disk_size,
+                ChartType::WriteAmplification => entry.1.max_write_amplification,
+                ChartType::MemoryUsage => entry.1.max_memory_usage,
+                ChartType::HeapUsage => entry.1.max_heap_usage,
+                ChartType::CommitTimeAutocorrelation | ChartType::SampleIntervalHistogram | ChartType::CommitTimeRollingPercentile | ChartType::CommitsPerSecondRunVariance | ChartType::QueryLatency | ChartType::CommitTimeOverWallClock => 0.0,
+                ChartType::CommitTimeScatter | ChartType::CommitsPerSecondScatter | ChartType::QueriesPerSecondScatter | ChartType::FinalThroughputBars | ChartType::QueryCommitRatio | ChartType::CommitsPerSecondVariation => 0.0,
+                ChartType::PluginMetric(_) | ChartType::PluginChart(_) => 0.0,
+            };
+
+            // Derived and plugin metrics have no precomputed maximum and may be negative.
+            let precomputed_max = !entry.1.derived && !matches!(chart_type, ChartType::PluginMetric(_));
+            if spec.transform.is_identity() && precomputed_max {
+                max_y = max_y.max(dataset_max_y);
+            } else if let Some((dataset_min_y, dataset_max_y)) = entry.1.get_transformed_range(chart_type, &spec.transform) {
+                min_y = min_y.min(dataset_min_y);
+                max_y = max_y.max(dataset_max_y);
+            }
+
+            if let (BaselineMode::Overlay, Some(baseline)) = (&params.baseline_mode, data.get_baseline(entry.0)) {
+                if let Some((baseline_min_y, baseline_max_y)) = baseline.get_transformed_range(chart_type, &spec.transform) {
+                    min_y = min_y.min(baseline_min_y);
+                    max_y = max_y.max(baseline_max_y);
+                }
+            }
+
+            if spec.options.log_y {
+                let overlay = match params.baseline_mode {
+                    BaselineMode::Overlay => data.get_baseline(entry.0),
+                    BaselineMode::Delta | BaselineMode::Significance => None,
+                };
+                for dataset in std::iter::once(entry.1).chain(overlay) {
+                    if let Some(value) = dataset.get_min_positive(chart_type, &spec.transform) {
+                        min_positive_y = Some(min_positive_y.map_or(value, |min| min.min(value)));
+                    }
+                }
+            }
+        }
+    }
+
+    for line in params.reference_lines.iter().filter(|line| line.applies_to(spec, params)) {
+        min_y = min_y.min(line.value);
+        max_y = max_y.max(line.value);
+        if spec.options.log_y && line.value > 0.0 {
+            min_positive_y = Some(min_positive_y.map_or(line.value, |min| min.min(line.value)));
+        }
+    }
+
+    // The groups of a filter split with `;` are listed one after another in the legend.
+    let mut chart_datasets: Vec<&DatasetEntry> = datasets.iter().filter(|entry| entry.1.passes_filters(&spec.filters) && (chart_type.is_metric() || !entry.1.derived)).collect();
+    chart_datasets.sort_by_key(|entry| spec.filters.group_index(&entry.1.base_name, &entry.1.parameters));
+    let include_parameters = DataSet::get_varying_parameters(chart_datasets.iter().map(|entry| entry.1));
+
+    // Smoothed datasets replace the chart's datasets, unless overlaid on a metric chart.
+    let smoothed_data: Vec<DataSet> = match spec.options.smooth {
+        Some(window) => chart_datasets.iter().map(|entry| entry.1.smoothed(window)).collect(),
+        None => Default::default(),
+    };
+    let smoothed_datasets: Vec<DatasetEntry> = chart_datasets.iter().zip(&smoothed_data).map(|(entry, dataset)| (entry.0, dataset, entry.2, entry.3, entry.4, entry.5, entry.6)).collect();
+    let (chart_datasets, smoothed) = match (spec.options.smooth, chart_type.is_metric() && spec.options.smooth_mode == SmoothMode::Overlay) {
+        (None, _) => (chart_datasets, Vec::new()),
+        (Some(_), true) => (chart_datasets, smoothed_datasets.iter().collect()),
+        (Some(_), false) => (smoothed_datasets.iter().collect(), Vec::new()),
+    };
+
+    // Dense datasets, smoothed or not, are drawn with their buckets merged.
+    let downsampled_data: Vec<Option<DataSet>> = chart_datasets.iter().chain(&smoothed).map(|entry| params.get_downsampled(entry.1, chart_type)).collect();
+    let downsampled_datasets: Vec<DatasetEntry> = chart_datasets.iter().chain(&smoothed).zip(&downsampled_data).map(|(entry, downsampled)| (entry.0, downsampled.as_ref().unwrap_or(entry.1), entry.2, entry.3, entry.4, entry.5, entry.6)).collect();
+    let (chart_datasets, smoothed) = downsampled_datasets.split_at(chart_datasets.len());
+    let (chart_datasets, smoothed): (Vec<&DatasetEntry>, Vec<&DatasetEntry>) = (chart_datasets.iter().collect(), smoothed.iter().collect());
+
+    let context = ChartContext {
+        title,
+        spec,
+        datasets: chart_datasets,
+        smoothed,
+        include_parameters,
+        y_range: min_y..max_y,
+        min_positive_y,
+        parameter_labels: &params.parameter_labels,
+        max_commits: data.max_commits,
+        annotations: &params.annotations,
+        theme: &params.theme,
+        fonts: &params.fonts,
+    };
+    render(&context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name).to_string_lossy().into_owned()
+    }
+
+    // The pixels of the charts of the params, drawn in parallel or one after another.
+    fn draw(params: &Params, data: &StressTestData, parallel: bool) -> Vec<u8> {
+        let (width, height) = params.get_image_size(&params.get_layout(params.chart_specs.len()).unwrap());
+        let mut pixels = vec![0u8; (width * height * 3) as usize];
+        draw_to_area(&BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area(), Some(data), params, &params.chart_specs, parallel).unwrap();
+        pixels
+    }
+
+    #[test]
+    fn parallel_drawing_matches_serial_drawing() {
+        let (writers, readers) = (fixture("writers.csv"), fixture("readers.csv"));
+        let prepared = testing::prepare(&["-d", &writers, &readers, "-c", "commit-time", "commits-per-second", "queries-per-second", "commit-time-scatter", "--small-image"]).unwrap();
+        assert_eq!(prepared.params.chart_specs.len(), 4);
+        assert!(draw(&prepared.params, &prepared.data, true) == draw(&prepared.params, &prepared.data, false), "Charts drawn in parallel differ from those drawn serially");
+    }
+}
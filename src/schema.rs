@@ -0,0 +1,155 @@
+// Layouts of CSV data files given with `--schema`, for versions of the stress test which name or
+// order their columns differently. A TOML or YAML file, selected by extension, lists the columns
+// with their role, e.g.
+//
+//   header = false
+//
+//   [[columns]]
+//   name = "test"
+//   role = "name"
+//
+//   [[columns]]
+//   name = "threads"
+//   type = "int"
+//   role = "parameter"
+//
+//   [[columns]]
+//   name = "commits_done"
+//   role = "x-axis"
+//
+//   [[columns]]
+//   name = "elapsed"
+//   role = "metric"
+//   field = "total_commit_time"
+//
+// Metric columns hold the measurement named by `field`, or by their own name when it is not given.
+// Columns are found by name in the header line, or without a header are the columns of each line
+// in the order listed. Columns of the header which are not listed are ignored.
+use super::*;
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Schema {
+    #[serde(default = "default_header")]
+    pub header: bool,
+    pub columns: Vec<SchemaColumn>,
+}
+
+fn default_header() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct SchemaColumn {
+    pub name: String,
+    // Parameter columns only. Values are otherwise read as the first of bool, int, float and text
+    // which they parse as.
+    #[serde(rename = "type")]
+    pub column_type: Option<ColumnType>,
+    pub role: ColumnRole,
+    // Metric columns only.
+    pub field: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColumnRole {
+    // The base name of the dataset.
+    Name,
+    Parameter,
+    Metric,
+    // The total number of commits, which metric charts are plotted against.
+    XAxis,
+    Ignore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColumnType {
+    Bool,
+    Int,
+    Float,
+    Text,
+}
+
+impl ColumnType {
+    // None if the value is empty.
+    pub fn parse(&self, text: &str) -> Result<Option<ParameterValue>, String> {
+        if text.is_empty() {
+            return Ok(None)
+        }
+        let value = match self {
+            ColumnType::Bool => text.parse().ok().map(ParameterValue::Bool),
+            ColumnType::Int => text.parse().ok().map(ParameterValue::Int),
+            ColumnType::Float => text.parse().ok().filter(|v: &f64| v.is_finite()).map(ParameterValue::Float),
+            ColumnType::Text => Some(ParameterValue::Text(text.to_string())),
+        };
+        let name = match self {
+            ColumnType::Bool => "bool",
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Text => "text",
+        };
+        value.map(Some).ok_or_else(|| format!("'{}' is not of type {}", text, name))
+    }
+}
+
+impl Schema {
+    pub fn load(path: &Path) -> Result<Schema, VisualizerError> {
+        let text = std::fs::read_to_string(path).map_err(|source| VisualizerError::Io { path: path.to_path_buf(), source })?;
+        let invalid = |reason: String| VisualizerError::InvalidSchema { path: path.to_path_buf(), reason };
+        let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+        let schema: Schema = match extension.as_deref() {
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&text).map_err(|e| invalid(e.to_string()))?,
+            _ => toml::from_str(&text).map_err(|e| invalid(e.to_string()))?,
+        };
+        schema.validate().map_err(invalid)?;
+        Ok(schema)
+    }
+
+    // The measurement held by each metric and x-axis column, with its position in the schema.
+    pub fn fields(&self) -> impl Iterator<Item = (usize, &SchemaColumn, &str)> {
+        self.columns.iter().enumerate().filter_map(|(index, column)| match column.role {
+            ColumnRole::Metric => Some((index, column, column.field.as_deref().unwrap_or(&column.name))),
+            ColumnRole::XAxis => Some((index, column, "total_commits")),
+            _ => None,
+        })
+    }
+
+    // A single name column, and every measurement which is not optional, each in one column.
+    fn validate(&self) -> Result<(), String> {
+        for column in &self.columns {
+            if column.column_type.is_some() && column.role != ColumnRole::Parameter {
+                return Err(format!("column {} has a type, which only parameter columns can have", column.name))
+            }
+            if column.field.is_some() && column.role != ColumnRole::Metric {
+                return Err(format!("column {} has a field, which only metric columns can have", column.name))
+            }
+            if self.header && self.columns.iter().filter(|other| other.name == column.name).count() > 1 {
+                return Err(format!("column {} is listed more than once", column.name))
+            }
+        }
+
+        match self.columns.iter().filter(|column| column.role == ColumnRole::Name).count() {
+            1 => {},
+            0 => return Err("no column has the name role".to_string()),
+            _ => return Err("several columns have the name role".to_string()),
+        }
+
+        let fields: Vec<&str> = self.fields().map(|(_, _, field)| field).collect();
+        if let Some(field) = fields.iter().find(|field| !source::is_metric_column(field)) {
+            return Err(format!("unknown metric field {}, expected one of {}", field, source::METRIC_COLUMNS.iter().chain(&source::OPTIONAL_METRIC_COLUMNS).copied().collect::<Vec<_>>().join(", ")))
+        }
+        for field in source::METRIC_COLUMNS.iter().chain(&source::OPTIONAL_METRIC_COLUMNS) {
+            match fields.iter().filter(|f| *f == field).count() {
+                0 if source::METRIC_COLUMNS.contains(field) => return Err(format!("no column holds {}", field)),
+                0 | 1 => {},
+                _ => return Err(format!("several columns hold {}", field)),
+            }
+        }
+        Ok(())
+    }
+}
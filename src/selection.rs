@@ -0,0 +1,72 @@
+// Datasets kept or left out by name with `--include-dataset` and `--exclude-dataset`, across every
+// chart, e.g. to drop a misconfigured run without a parameter filter on each chart. Names are
+// those listed when the data files are read, e.g. `stress (readers=4 writers=1)`, and a pattern
+// matches every dataset whose name contains it.
+use super::*;
+
+// Removes the datasets which match none of the included patterns, when there are any, or any of
+// the excluded ones. The baseline is left alone, as datasets without a match in it are not drawn.
+pub(crate) fn select_datasets(data: &mut StressTestData, include: &[String], exclude: &[String]) {
+    if include.is_empty() && exclude.is_empty() {
+        return
+    }
+
+    for (option, patterns) in [("--include-dataset", include), ("--exclude-dataset", exclude)] {
+        for pattern in patterns {
+            if !data.datasets.keys().any(|name| name.contains(pattern.as_str())) {
+                log::warn!("{} '{}' matches no dataset", option, pattern);
+            }
+        }
+    }
+
+    let num_datasets = data.datasets.len();
+    data.datasets.retain(|name, _| {
+        (include.is_empty() || include.iter().any(|pattern| name.contains(pattern.as_str())))
+            && !exclude.iter().any(|pattern| name.contains(pattern.as_str()))
+    });
+
+    match data.datasets.len() {
+        0 => log::warn!("No datasets are left after --include-dataset and --exclude-dataset"),
+        num_kept if num_kept < num_datasets => log::info!("Kept {} of {} datasets by name", num_kept, num_datasets),
+        _ => {},
+    }
+    data.update_maxima();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_data() -> StressTestData {
+        let mut data = StressTestData::new();
+        for (readers, commit_time) in [(0, 0.1), (4, 0.2), (10, 0.4)] {
+            let sample = Sample { run: 0, commit_time, sample_commit_time: commit_time, commits_per_second: 1000.0 / commit_time, queries_per_second: 0.0, query_latency: None, disk_size: None, write_amplification: None, memory_usage: None, heap_usage: None, plugin_metrics: Default::default(), timestamp: None };
+            data.add_sample("stress".to_string(), BTreeMap::from([("readers".to_string(), ParameterValue::Int(readers))]), 1000, &sample);
+        }
+        data
+    }
+
+    fn select(include: &[&str], exclude: &[&str]) -> StressTestData {
+        let mut data = get_data();
+        let to_strings = |patterns: &[&str]| patterns.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>();
+        select_datasets(&mut data, &to_strings(include), &to_strings(exclude));
+        data
+    }
+
+    #[test]
+    fn includes_datasets_whose_names_contain_a_pattern() {
+        assert_eq!(select(&["readers=0)", "readers=4"], &[]).sorted_names(), ["stress (readers=0)", "stress (readers=4)"]);
+        assert_eq!(select(&["stress"], &[]).sorted_names().len(), 3);
+        assert!(select(&["writers"], &[]).datasets.is_empty());
+    }
+
+    #[test]
+    fn excludes_after_including() {
+        // `readers=1` is part of `readers=10`, which is then excluded.
+        let data = select(&["readers=1", "readers=4"], &["readers=10"]);
+        assert_eq!(data.sorted_names(), ["stress (readers=4)"]);
+        // The maxima are those of the datasets left.
+        assert_eq!(data.max_commit_time, 0.2);
+        assert_eq!(select(&[], &["readers=0"]).sorted_names(), ["stress (readers=4)", "stress (readers=10)"]);
+    }
+}
@@ -0,0 +1,621 @@
+// Inputs of the visualizer. A data source returns the records of one run of the stress tests, one
+// record per timing sample. Data files are read by the CSV and JSON sources, and other sources,
+// such as a database of benchmark results, can be passed to `load_from_sources`.
+use super::*;
+use std::borrow::Cow;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::Arc;
+
+// The measurements and parameters of a single timing sample, as in a line of a data file.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub base_name: String,
+    pub parameters: BTreeMap<String, ParameterValue>,
+    pub total_commits: u64,
+    pub total_commit_time: f64,
+    pub commits: u64,
+    pub commit_time: f64,
+    pub queries: u64,
+    pub query_time: f64,
+    // Bytes, for stress tests which write these columns.
+    pub disk_size: Option<u64>,
+    pub bytes_written: Option<u64>,
+    pub rss: Option<u64>,
+    pub heap: Option<u64>,
+    // Seconds since the Unix epoch at which the sample was taken.
+    pub timestamp: Option<f64>,
+}
+
+pub trait DataSource: Send + Sync {
+    // Where the records are read from, shown in progress messages and errors.
+    fn location(&self) -> PathBuf;
+
+    // Value of the `run` parameter of the datasets with `--tag-by-file`.
+    fn run_name(&self) -> String {
+        self.location().file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+    }
+
+    // Parameters of every record which does not have them itself, such as the settings of the
+    // stress test config next to a data file.
+    fn parameters(&self) -> Result<BTreeMap<String, ParameterValue>, VisualizerError> {
+        Ok(Default::default())
+    }
+
+    // The records in order, read and parsed `batch_lines` lines at a time. A malformed record is
+    // returned as an error, which is reported and skipped unless strict, so that the records after
+    // it are still read. An error reading the source itself is returned last, and fails the load.
+    fn records(&self, batch_lines: usize) -> Result<Box<dyn Iterator<Item = Result<Record, VisualizerError>> + '_>, VisualizerError>;
+
+    // The records of the lines after a position, for sources which are appended to, so that
+    // `--cache` only parses what was added since the last run. None for sources which cannot be
+    // read from a position, such as standard input.
+    fn appended_records(&self, _start: FilePosition) -> Option<Result<AppendedRecords, VisualizerError>> {
+        None
+    }
+}
+
+// Where reading a data file stopped, just after its last complete line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FilePosition {
+    pub offset: u64,
+    // Number of lines before the offset, blank ones and the header included.
+    pub lines: usize,
+}
+
+pub struct AppendedRecords {
+    // Records of the complete lines after the start, in order.
+    pub records: Vec<Result<Record, VisualizerError>>,
+    // Records of a last line without a newline, which may still be being written.
+    pub unfinished: Vec<Result<Record, VisualizerError>>,
+    // Position after the complete lines.
+    pub end: FilePosition,
+}
+
+// A CSV data file with a header line naming the columns, or `-` for standard input. The columns
+// are those of the schema when there is one.
+pub struct CsvSource {
+    pub path: PathBuf,
+    pub schema: Option<Arc<Schema>>,
+}
+
+// A data file with one JSON object per line, with the same fields as the CSV columns.
+pub struct JsonSource {
+    pub path: PathBuf,
+}
+
+// The source of a data file, in the given format or else the one of its extension. JSON fields
+// are always found by name, so the schema only applies to CSV files.
+pub fn file_source(path: &Path, input_format: Option<InputFormat>, schema: Option<Arc<Schema>>) -> Box<dyn DataSource> {
+    match input_format.unwrap_or_else(|| InputFormat::from_path(path)) {
+        InputFormat::Csv => Box::new(CsvSource { path: path.to_path_buf(), schema }),
+        InputFormat::Json => Box::new(JsonSource { path: path.to_path_buf() }),
+    }
+}
+
+impl DataSource for CsvSource {
+    fn location(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    fn run_name(&self) -> String {
+        get_file_run_name(&self.path)
+    }
+
+    fn parameters(&self) -> Result<BTreeMap<String, ParameterValue>, VisualizerError> {
+        get_file_parameters(&self.path)
+    }
+
+    fn records(&self, batch_lines: usize) -> Result<Box<dyn Iterator<Item = Result<Record, VisualizerError>> + '_>, VisualizerError> {
+        let mut reader = open_file(&self.path)?;
+        let layout = read_csv_layout(&self.path, &mut reader, self.schema.as_deref())?;
+        // Line numbers count from 1, the header included.
+        let header_lines = layout.header_lines;
+        Ok(Box::new(ChunkedRecords::new(&self.path, reader, header_lines, batch_lines, move |line| parse_csv_line(&layout, line))))
+    }
+
+    // Any header is read again for the layout of the columns, and skipped when reading from the
+    // start. Compressed files cannot be read from a position, so are read in full.
+    fn appended_records(&self, start: FilePosition) -> Option<Result<AppendedRecords, VisualizerError>> {
+        if is_stdin(&self.path) || compression::Compression::of_file(&self.path).is_some() {
+            return None
+        }
+        let read = || {
+            let layout = read_csv_layout(&self.path, open_file(&self.path)?, self.schema.as_deref())?;
+            let mut appended = read_appended_lines(&self.path, start)?;
+            appended.lines.retain(|(line_number, _)| *line_number > layout.header_lines);
+            appended.unfinished.retain(|(line_number, _)| *line_number > layout.header_lines);
+            Ok(appended.parse(&self.path, |line| parse_csv_line(&layout, line)))
+        };
+        Some(read())
+    }
+}
+
+impl DataSource for JsonSource {
+    fn location(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    fn run_name(&self) -> String {
+        get_file_run_name(&self.path)
+    }
+
+    fn parameters(&self) -> Result<BTreeMap<String, ParameterValue>, VisualizerError> {
+        get_file_parameters(&self.path)
+    }
+
+    fn records(&self, batch_lines: usize) -> Result<Box<dyn Iterator<Item = Result<Record, VisualizerError>> + '_>, VisualizerError> {
+        Ok(Box::new(ChunkedRecords::new(&self.path, open_file(&self.path)?, 0, batch_lines, parse_json_line)))
+    }
+
+    fn appended_records(&self, start: FilePosition) -> Option<Result<AppendedRecords, VisualizerError>> {
+        match is_stdin(&self.path) || compression::Compression::of_file(&self.path).is_some() {
+            true => None,
+            false => Some(read_appended_lines(&self.path, start).map(|appended| appended.parse(&self.path, parse_json_line))),
+        }
+    }
+}
+
+fn get_file_run_name(path: &Path) -> String {
+    match is_stdin(path) {
+        true => "stdin".to_string(),
+        false => compression::uncompressed_path(path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+    }
+}
+
+// Standard input has no stress test config alongside it.
+fn get_file_parameters(path: &Path) -> Result<BTreeMap<String, ParameterValue>, VisualizerError> {
+    match is_stdin(path) {
+        true => Ok(Default::default()),
+        false => workload::load_workload_parameters(path)
+            .map_err(|e| VisualizerError::InvalidFile { path: path.to_path_buf(), reason: format!("Failed to read stress test config: {}", e) }),
+    }
+}
+
+fn open_file(path: &Path) -> Result<Box<dyn BufRead>, VisualizerError> {
+    if is_stdin(path) {
+        return compression::decompressed(path, std::io::stdin().lock())
+    }
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .open(path).map_err(|source| VisualizerError::Io { path: path.to_path_buf(), source })?;
+    compression::decompressed(path, std::io::BufReader::new(file))
+}
+
+// Non-blank lines of a data file after any header, with their line numbers.
+type NumberedLines = Vec<(usize, String)>;
+
+// Number of lines parsed together by one thread. Large enough that merging the parts of a file
+// costs little next to parsing them.
+pub(crate) static CHUNK_LINES: usize = 16384;
+
+// Number of lines read and parsed together, one chunk per thread, or a single chunk with
+// `--low-memory` so that only the records of one chunk are held at a time.
+pub(crate) fn batch_lines(low_memory: bool) -> usize {
+    match low_memory {
+        true => CHUNK_LINES,
+        false => CHUNK_LINES * rayon::current_num_threads(),
+    }
+}
+
+// Chunks of lines are parsed in parallel, keeping the records in line order.
+fn parse_lines(path: &Path, lines: &NumberedLines, parse: impl Fn(&str) -> Result<Record, String> + Sync) -> Vec<Result<Record, VisualizerError>> {
+    let parts: Vec<Vec<Result<Record, VisualizerError>>> = lines.par_chunks(CHUNK_LINES).map(|chunk| {
+        let records = chunk.iter().map(|(line_number, line)| parse(line).map_err(|reason| VisualizerError::InvalidLine { path: path.to_path_buf(), line_number: *line_number, reason })).collect();
+        logging::add_lines_parsed(chunk.len());
+        records
+    }).collect();
+    parts.into_iter().flatten().collect()
+}
+
+// Records of the lines of a data file, read a batch of chunks at a time and each batch parsed in
+// parallel, so that only one batch of lines is held however long the file is.
+struct ChunkedRecords<P> {
+    path: PathBuf,
+    lines: std::io::Lines<Box<dyn BufRead>>,
+    // Number of the last line read.
+    line_number: usize,
+    batch_lines: usize,
+    parse: P,
+    parsed: std::vec::IntoIter<Result<Record, VisualizerError>>,
+    finished: bool,
+}
+
+impl<P: Fn(&str) -> Result<Record, String> + Sync> ChunkedRecords<P> {
+    // Lines are numbered on from the lines already read, such as a header line.
+    fn new(path: &Path, reader: Box<dyn BufRead>, lines_read: usize, batch_lines: usize, parse: P) -> ChunkedRecords<P> {
+        ChunkedRecords { path: path.to_path_buf(), lines: reader.lines(), line_number: lines_read, batch_lines, parse, parsed: Vec::new().into_iter(), finished: false }
+    }
+
+    // Parses the next batch of lines, with any error reading them after their records.
+    fn parse_batch(&mut self) {
+        let batch_lines = self.batch_lines;
+        let mut lines = NumberedLines::with_capacity(batch_lines);
+        let mut read_error = None;
+        while lines.len() < batch_lines {
+            match self.lines.next() {
+                Some(Ok(line)) => {
+                    self.line_number += 1;
+                    logging::add_lines_read(1);
+                    if !line.trim().is_empty() {
+                        lines.push((self.line_number, line));
+                    }
+                },
+                Some(Err(source)) => {
+                    read_error = Some(VisualizerError::Io { path: self.path.clone(), source });
+                    self.finished = true;
+                    break;
+                },
+                None => {
+                    self.finished = true;
+                    break;
+                },
+            }
+        }
+
+        let mut parsed = parse_lines(&self.path, &lines, &self.parse);
+        parsed.extend(read_error.map(Err));
+        self.parsed = parsed.into_iter();
+    }
+}
+
+impl<P: Fn(&str) -> Result<Record, String> + Sync> Iterator for ChunkedRecords<P> {
+    type Item = Result<Record, VisualizerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.parsed.next() {
+                return Some(record)
+            }
+            if self.finished {
+                return None
+            }
+            self.parse_batch();
+        }
+    }
+}
+
+// Lines of a data file after a position, up to the last newline, and the unfinished line after it.
+struct AppendedLines {
+    lines: NumberedLines,
+    unfinished: NumberedLines,
+    end: FilePosition,
+}
+
+impl AppendedLines {
+    fn parse(&self, path: &Path, parse: impl Fn(&str) -> Result<Record, String> + Sync) -> AppendedRecords {
+        AppendedRecords { records: parse_lines(path, &self.lines, &parse), unfinished: parse_lines(path, &self.unfinished, &parse), end: self.end }
+    }
+}
+
+// Non-blank lines from a position of a data file, numbered on from the lines before it.
+fn read_appended_lines(path: &Path, start: FilePosition) -> Result<AppendedLines, VisualizerError> {
+    let io_error = |source| VisualizerError::Io { path: path.to_path_buf(), source };
+    let mut file = std::fs::File::open(path).map_err(io_error)?;
+    file.seek(SeekFrom::Start(start.offset)).map_err(io_error)?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).map_err(io_error)?;
+
+    let complete_len = bytes.iter().rposition(|b| *b == b'\n').map_or(0, |index| index + 1);
+    let unfinished = bytes.split_off(complete_len);
+    let to_text = |bytes: Vec<u8>| String::from_utf8(bytes).map_err(|e| io_error(std::io::Error::new(std::io::ErrorKind::InvalidData, e)));
+    let (text, unfinished) = (to_text(bytes)?, to_text(unfinished)?);
+
+    let number_lines = |text: &str, first: usize| -> NumberedLines {
+        text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).map(|(index, line)| (first + index, line.to_string())).collect()
+    };
+    // Complete text ends with a newline, so has one line per newline.
+    let num_lines = text.lines().count();
+    logging::add_lines_read(num_lines);
+    Ok(AppendedLines {
+        lines: number_lines(&text, start.lines + 1),
+        unfinished: number_lines(&unfinished, start.lines + num_lines + 1),
+        end: FilePosition { offset: start.offset + complete_len as u64, lines: start.lines + num_lines },
+    })
+}
+
+// Columns holding the measurements of a sample. Every other column apart from the name is a test
+// parameter.
+pub(crate) static METRIC_COLUMNS: [&str; 6] = ["total_commits", "total_commit_time", "commits", "commit_time", "queries", "query_time"];
+
+// Measurements only written by some versions of the stress test, in bytes. `bytes_written` is the
+// total written to disk since the start of the run. `rss` and `heap` are the resident set and heap
+// sizes of the stress test process. `timestamp` is when the sample was taken, see
+// `time_axis::parse_timestamp`.
+pub(crate) static OPTIONAL_METRIC_COLUMNS: [&str; 5] = ["disk_size", "bytes_written", "rss", "heap", "timestamp"];
+
+pub(crate) fn is_metric_column(column: &str) -> bool {
+    METRIC_COLUMNS.contains(&column) || OPTIONAL_METRIC_COLUMNS.contains(&column)
+}
+
+// Positions of the columns of a data file, from its header line or schema.
+struct ColumnLayout {
+    pub name: usize,
+    pub metrics: HashMap<&'static str, usize>,
+    // Position, parameter name and any type from the schema of each parameter column.
+    pub parameters: Vec<(usize, String, Option<ColumnType>)>,
+    // Lines before the first data line, 1 for the header line or 0 without one.
+    pub header_lines: usize,
+}
+
+impl ColumnLayout {
+    pub fn new(header: &str) -> Result<ColumnLayout, String> {
+        let fields = split_csv_fields(header)?;
+        let columns: Vec<&str> = fields.iter().map(|c| c.as_ref()).collect();
+
+        let name = columns.iter().position(|c| *c == "name").ok_or("No name column")?;
+
+        let mut metrics: HashMap<&'static str, usize> = Default::default();
+        for metric in METRIC_COLUMNS {
+            let index = columns.iter().position(|c| *c == metric).ok_or_else(|| format!("No {} column", metric))?;
+            metrics.insert(metric, index);
+        }
+        for metric in OPTIONAL_METRIC_COLUMNS {
+            if let Some(index) = columns.iter().position(|c| *c == metric) {
+                metrics.insert(metric, index);
+            }
+        }
+
+        let parameters = columns.iter().enumerate()
+            .filter(|(index, column)| *index != name && !is_metric_column(column))
+            .map(|(index, column)| (index, column.replace('_', "-"), None))
+            .collect();
+
+        Ok(ColumnLayout { name, metrics, parameters, header_lines: 1 })
+    }
+
+    // Columns are found by name in the header, or without one are in the order of the schema.
+    pub fn from_schema(schema: &Schema, header: Option<&str>) -> Result<ColumnLayout, String> {
+        let header_columns: Option<Vec<Cow<str>>> = header.map(split_csv_fields).transpose()?;
+        let position = |index: usize, column: &SchemaColumn| match &header_columns {
+            Some(columns) => columns.iter().position(|c| *c == column.name.as_str()).ok_or_else(|| format!("No {} column", column.name)),
+            None => Ok(index),
+        };
+
+        let mut name = 0;
+        let mut parameters: Vec<(usize, String, Option<ColumnType>)> = Default::default();
+        for (index, column) in schema.columns.iter().enumerate() {
+            match column.role {
+                ColumnRole::Name => name = position(index, column)?,
+                ColumnRole::Parameter => parameters.push((position(index, column)?, column.name.replace('_', "-"), column.column_type)),
+                ColumnRole::Metric | ColumnRole::XAxis | ColumnRole::Ignore => {},
+            }
+        }
+
+        let mut metrics: HashMap<&'static str, usize> = Default::default();
+        for (index, column, field) in schema.fields() {
+            let field = METRIC_COLUMNS.iter().chain(&OPTIONAL_METRIC_COLUMNS).find(|metric| **metric == field).ok_or_else(|| format!("Unknown metric field {}", field))?;
+            metrics.insert(field, position(index, column)?);
+        }
+
+        Ok(ColumnLayout { name, metrics, parameters, header_lines: header_columns.map_or(0, |_| 1) })
+    }
+
+    pub fn metric<T: std::str::FromStr>(&self, elements: &[&str], metric: &str) -> Result<T, String> {
+        let text = elements.get(self.metrics[metric]).ok_or_else(|| format!("Missing {}", metric))?;
+        text.parse().map_err(|_| format!("Invalid {} '{}'", metric, text))
+    }
+
+    // None if the file has no such column or the line leaves it empty.
+    pub fn optional_metric<T: std::str::FromStr>(&self, elements: &[&str], metric: &str) -> Result<Option<T>, String> {
+        match self.metrics.get(metric).and_then(|index| elements.get(*index)) {
+            Some(text) if !text.is_empty() => text.parse().map(Some).map_err(|_| format!("Invalid {} '{}'", metric, text)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn timestamp(&self, elements: &[&str]) -> Result<Option<f64>, String> {
+        match self.metrics.get("timestamp").and_then(|index| elements.get(*index)) {
+            Some(text) if !text.is_empty() => time_axis::parse_timestamp(text).map(Some).ok_or_else(|| format!("Invalid timestamp '{}'", text)),
+            _ => Ok(None),
+        }
+    }
+}
+
+// Layout of the columns of a CSV file, from its header line and any schema. Only the header line
+// is read.
+fn read_csv_layout(path: &Path, reader: impl BufRead, schema: Option<&Schema>) -> Result<ColumnLayout, VisualizerError> {
+    let invalid = |reason: String| VisualizerError::InvalidFile { path: path.to_path_buf(), reason };
+    if let Some(schema) = schema.filter(|schema| !schema.header) {
+        return ColumnLayout::from_schema(schema, None).map_err(invalid)
+    }
+
+    let header = reader.lines().next()
+        .ok_or_else(|| invalid("File is empty".to_string()))?
+        .map_err(|source| VisualizerError::Io { path: path.to_path_buf(), source })?;
+    match schema {
+        Some(schema) => ColumnLayout::from_schema(schema, Some(&header)).map_err(|reason| invalid(format!("Header does not match schema: {}", reason))),
+        None => ColumnLayout::new(&header).map_err(|reason| invalid(format!("Invalid header: {}", reason))),
+    }
+}
+
+// Records of lines as they arrive, such as from a connection of `--listen`, in the format of a data
+// file, until the stream ends. CSV streams start with their header line unless the schema says
+// otherwise.
+pub(crate) fn stream_records(location: &Path, mut reader: impl BufRead, input_format: &InputFormat, schema: Option<&Schema>, mut on_record: impl FnMut(Result<Record, VisualizerError>) -> bool) -> Result<(), VisualizerError> {
+    let layout = match input_format {
+        InputFormat::Csv => Some(read_csv_layout(location, &mut reader, schema)?),
+        InputFormat::Json => None,
+    };
+    let first_line = layout.as_ref().map_or(1, |layout| layout.header_lines + 1);
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|source| VisualizerError::Io { path: location.to_path_buf(), source })?;
+        logging::add_lines_read(1);
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = match &layout {
+            Some(layout) => parse_csv_line(layout, &line),
+            None => parse_json_line(&line),
+        };
+        if !on_record(record.map_err(|reason| VisualizerError::InvalidLine { path: location.to_path_buf(), line_number: index + first_line, reason })) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Fields of a CSV line, trimmed. Fields may be quoted to hold commas or surrounding spaces, with
+// `""` for a quote within them, as spreadsheets and `--stats-out` write them. Lines without any
+// quotes are simply split on commas.
+pub(crate) fn split_csv_fields(line: &str) -> Result<Vec<Cow<'_, str>>, String> {
+    if !line.contains('"') {
+        return Ok(line.split(',').map(|field| Cow::Borrowed(field.trim())).collect())
+    }
+
+    let mut fields: Vec<Cow<str>> = Default::default();
+    let mut rest = line;
+    loop {
+        let field_text = rest.trim_start();
+        let (field, after) = match field_text.strip_prefix('"') {
+            Some(quoted) => {
+                let mut value = String::new();
+                let mut chars = quoted.char_indices().peekable();
+                let end = loop {
+                    match chars.next() {
+                        Some((_, '"')) if chars.peek().is_some_and(|(_, c)| *c == '"') => {
+                            value.push('"');
+                            chars.next();
+                        },
+                        Some((index, '"')) => break index + 1,
+                        Some((_, c)) => value.push(c),
+                        None => return Err(format!("Unterminated quoted field '{}'", field_text)),
+                    }
+                };
+                let after = quoted[end..].trim_start();
+                if !after.is_empty() && !after.starts_with(',') {
+                    return Err(format!("Unexpected text after quoted field \"{}\"", value))
+                }
+                (Cow::Owned(value), after)
+            },
+            None => {
+                let end = field_text.find(',').unwrap_or(field_text.len());
+                (Cow::Borrowed(field_text[..end].trim()), &field_text[end..])
+            },
+        };
+        fields.push(field);
+        match after.strip_prefix(',') {
+            Some(next) => rest = next,
+            None => return Ok(fields),
+        }
+    }
+}
+
+fn parse_csv_line(layout: &ColumnLayout, line: &str) -> Result<Record, String> {
+    let fields = split_csv_fields(line)?;
+    let elements: Vec<&str> = fields.iter().map(|e| e.as_ref()).collect();
+
+    let base_name = elements.get(layout.name).ok_or("Missing name")?.to_string();
+
+    let mut parameters: BTreeMap<String, ParameterValue> = Default::default();
+    for (index, name, column_type) in &layout.parameters {
+        let Some(text) = elements.get(*index) else {
+            continue;
+        };
+        let value = match column_type {
+            Some(column_type) => column_type.parse(text).map_err(|reason| format!("Invalid {}: {}", name, reason))?,
+            None => ParameterValue::parse(text),
+        };
+        if let Some(value) = value {
+            parameters.insert(name.clone(), value);
+        }
+    }
+
+    Ok(Record {
+        base_name,
+        parameters,
+        total_commits: layout.metric(&elements, "total_commits")?,
+        total_commit_time: layout.metric(&elements, "total_commit_time")?,
+        commits: layout.metric(&elements, "commits")?,
+        commit_time: layout.metric(&elements, "commit_time")?,
+        queries: layout.metric(&elements, "queries")?,
+        query_time: layout.metric(&elements, "query_time")?,
+        disk_size: layout.optional_metric(&elements, "disk_size")?,
+        bytes_written: layout.optional_metric(&elements, "bytes_written")?,
+        rss: layout.optional_metric(&elements, "rss")?,
+        heap: layout.optional_metric(&elements, "heap")?,
+        timestamp: layout.timestamp(&elements)?,
+    })
+}
+
+// A line of an NDJSON file, one object per line with the same fields as the CSV columns. Every
+// other field is a parameter, with nested objects flattened as for stress test configs.
+fn parse_json_line(line: &str) -> Result<Record, String> {
+    let value: serde_json::Value = serde_json::from_str(line).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let object = value.as_object().ok_or("Not a JSON object")?;
+
+    let field = |name: &str| object.get(name).ok_or_else(|| format!("Missing {}", name));
+    let u64_field = |name: &str| field(name)?.as_u64().ok_or_else(|| format!("Invalid {} '{}'", name, object[name]));
+    let f64_field = |name: &str| field(name)?.as_f64().ok_or_else(|| format!("Invalid {} '{}'", name, object[name]));
+    let optional_u64_field = |name: &str| match object.get(name) {
+        Some(value) if !value.is_null() => value.as_u64().map(Some).ok_or_else(|| format!("Invalid {} '{}'", name, value)),
+        _ => Ok(None),
+    };
+    // Either seconds since the epoch or a date and time string.
+    let timestamp = match object.get("timestamp") {
+        Some(serde_json::Value::Number(number)) => number.as_f64().and_then(time_axis::from_epoch).map(Some).ok_or_else(|| format!("Invalid timestamp '{}'", number))?,
+        Some(serde_json::Value::String(text)) => Some(time_axis::parse_timestamp(text).ok_or_else(|| format!("Invalid timestamp '{}'", text))?),
+        Some(serde_json::Value::Null) | None => None,
+        Some(value) => return Err(format!("Invalid timestamp '{}'", value)),
+    };
+
+    let base_name = field("name")?.as_str().ok_or_else(|| format!("Invalid name '{}'", object["name"]))?.to_string();
+
+    let mut parameters: BTreeMap<String, ParameterValue> = Default::default();
+    for (key, value) in object.iter().filter(|(key, _)| *key != "name" && !is_metric_column(key)) {
+        workload::add_json_value(&workload::parameter_name("", key), value, &mut parameters);
+    }
+
+    Ok(Record {
+        base_name,
+        parameters,
+        total_commits: u64_field("total_commits")?,
+        total_commit_time: f64_field("total_commit_time")?,
+        commits: u64_field("commits")?,
+        commit_time: f64_field("commit_time")?,
+        queries: u64_field("queries")?,
+        query_time: f64_field("query_time")?,
+        disk_size: optional_u64_field("disk_size")?,
+        bytes_written: optional_u64_field("bytes_written")?,
+        rss: optional_u64_field("rss")?,
+        heap: optional_u64_field("heap")?,
+        timestamp,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(line: &str) -> Vec<String> {
+        split_csv_fields(line).unwrap().into_iter().map(|field| field.into_owned()).collect()
+    }
+
+    #[test]
+    fn splits_unquoted_fields_on_commas() {
+        assert_eq!(fields("stress, 0 ,1000,,0.05"), ["stress", "0", "1000", "", "0.05"]);
+    }
+
+    #[test]
+    fn keeps_commas_quotes_and_spaces_in_quoted_fields() {
+        assert_eq!(fields(r#""stress, compressed",4,"say ""hi""",  " padded " ,"""#), ["stress, compressed", "4", r#"say "hi""#, " padded ", ""]);
+    }
+
+    #[test]
+    fn reports_malformed_quoting() {
+        assert_eq!(split_csv_fields(r#"stress,"unterminated"#).unwrap_err(), r#"Unterminated quoted field '"unterminated'"#);
+        assert_eq!(split_csv_fields(r#""stress" name,4"#).unwrap_err(), r#"Unexpected text after quoted field "stress""#);
+    }
+
+    #[test]
+    fn parses_quoted_parameters_of_lines() {
+        let layout = ColumnLayout::new("name,label,total_commits,total_commit_time,commits,commit_time,queries,query_time").unwrap();
+        let record = parse_csv_line(&layout, r#""stress, a","x,y",2000,0.11,1000,0.06,10,0.5"#).unwrap();
+        assert_eq!(record.base_name, "stress, a");
+        assert_eq!(record.parameters["label"], ParameterValue::Text("x,y".to_string()));
+        assert_eq!((record.total_commits, record.commits, record.queries), (2000, 1000, 10));
+        assert_eq!(record.commit_time, 0.06);
+    }
+}
@@ -0,0 +1,114 @@
+// Charts given whole in one string with `--chart`, rather than by pairing `--chart-type`,
+// `--chart-filter` and the other per-chart lists by position, e.g.
+// `commits-per-second where readers>0 title 'Read-heavy' logy`. The chart type comes first, then
+// any of the clauses below in any order. The filter of `where` runs up to the next clause, and
+// quoted text, with `'` or `"`, is never read as a clause, so that titles may hold any words.
+use super::*;
+use clap::ValueEnum;
+
+static CLAUSES: [&str; 11] = ["where", "title", "transform", "y-range", "logy", "smooth", "spread", "range", "trend", "y-unit", "secondary"];
+
+#[derive(Debug)]
+struct Token {
+    text: String,
+    quoted: bool,
+}
+
+impl Token {
+    fn is_clause(&self) -> bool {
+        !self.quoted && CLAUSES.contains(&self.text.as_str())
+    }
+}
+
+// Words separated by whitespace, with quoted text kept together as one word.
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens: Vec<Token> = Default::default();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '\'' || c == '"' {
+            let mut quoted = String::new();
+            loop {
+                match chars.next() {
+                    Some(next) if next == c => break,
+                    Some(next) => quoted.push(next),
+                    None => return Err(format!("Unclosed quote {} in chart '{}'", c, text)),
+                }
+            }
+            tokens.push(Token { text: quoted, quoted: true });
+            continue;
+        }
+
+        let mut word = c.to_string();
+        while let Some(next) = chars.peek().filter(|next| !next.is_whitespace()) {
+            word.push(*next);
+            chars.next();
+        }
+        tokens.push(Token { text: word, quoted: false });
+    }
+    Ok(tokens)
+}
+
+// The chart of a `--chart` string, with the options it does not set taken from the defaults.
+pub fn parse_chart_spec(text: &str, defaults: &ChartOptions) -> Result<ChartSpec, Box<dyn Error>> {
+    let tokens = tokenize(text)?;
+    let Some((first, clauses)) = tokens.split_first() else {
+        return Err("Empty chart, expected a chart type followed by clauses such as `where readers>0`".into())
+    };
+    let chart_type = parse_chart_type(&first.text)?;
+
+    let mut options = defaults.clone();
+    options.smooth = options.smooth.filter(|_| chart_type.supports_smoothing());
+    options.trend = options.trend.filter(|_| chart_type.is_metric());
+    let mut spec = ChartSpec { options, ..ChartSpec::new(chart_type, ParameterFilterSet::from_static("")) };
+
+    let mut index = 0;
+    while let Some(clause) = clauses.get(index) {
+        index += 1;
+        let word = match clause.quoted {
+            true => "",
+            false => clause.text.as_str(),
+        };
+        match word {
+            // The filter may hold spaces, so takes every word up to the next clause.
+            "where" => {
+                let end = clauses[index..].iter().position(Token::is_clause).map_or(clauses.len(), |position| index + position);
+                let filter_text = clauses[index..end].iter().map(|token| token.text.as_str()).collect::<Vec<_>>().join(" ");
+                if filter_text.trim().is_empty() {
+                    return Err(format!("No filter given after where in chart '{}'", text).into())
+                }
+                spec.filters = ParameterFilterSet::parse(&filter_text)?;
+                index = end;
+            },
+            "logy" => spec.options.log_y = true,
+            "title" => spec.title = Some(take_value(clauses, &mut index, text)?.to_string()),
+            "transform" => spec.transform = Transform::parse(take_value(clauses, &mut index, text)?)?,
+            "y-range" => spec.y_range = AxisRange::parse(take_value(clauses, &mut index, text)?)?,
+            "smooth" => {
+                let value = take_value(clauses, &mut index, text)?;
+                spec.options.smooth = Some(value.parse().map_err(|_| format!("Invalid smoothing window '{}' in chart '{}'", value, text))?);
+            },
+            "spread" => spec.options.spread = Spread::from_str(take_value(clauses, &mut index, text)?, true).map_err(|e| format!("Invalid spread in chart '{}': {}", text, e))?,
+            "range" => spec.options.range = parse_sample_range(take_value(clauses, &mut index, text)?).map_err(|e| format!("Invalid range in chart '{}': {}", text, e))?,
+            "trend" => spec.options.trend = Some(TrendFit::from_str(take_value(clauses, &mut index, text)?, true).map_err(|e| format!("Invalid trend in chart '{}': {}", text, e))?),
+            "secondary" => spec.secondary = Some(parse_chart_type(take_value(clauses, &mut index, text)?)?),
+            "y-unit" => spec.options.y_unit = YUnit::from_str(take_value(clauses, &mut index, text)?, true).map_err(|e| format!("Invalid Y unit in chart '{}': {}", text, e))?,
+            _ => return Err(format!("Unexpected '{}' in chart '{}', expected one of: {}", clause.text, text, CLAUSES.join(", ")).into()),
+        }
+    }
+
+    Ok(spec)
+}
+
+// The value of the clause before the index, moving past it.
+fn take_value<'a>(clauses: &'a [Token], index: &mut usize, text: &str) -> Result<&'a str, String> {
+    match clauses.get(*index).filter(|value| !value.is_clause()) {
+        Some(value) => {
+            *index += 1;
+            Ok(&value.text)
+        },
+        None => Err(format!("No value given after {} in chart '{}'", clauses[*index - 1].text, text)),
+    }
+}
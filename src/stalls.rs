@@ -0,0 +1,116 @@
+use super::*;
+
+#[derive(Debug, Clone)]
+pub struct StallDetection {
+    // A commit bucket is a stall when its mean sample commit time exceeds this multiple of the
+    // local median.
+    pub threshold: f64,
+    // Number of neighbouring commit buckets, not samples, used to compute the local median.
+    pub window: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Stall {
+    // Commit count of the first bucket in the stall.
+    pub commits: u64,
+    // Largest ratio of commit time to the local median within the stall.
+    pub magnitude: f64,
+    // Seconds from the sample before the stall to its last sample, from the `timestamp` column,
+    // so that it does not depend on how many samples fall in a bucket. Without timestamps, the
+    // total mean sample commit time of its buckets.
+    pub duration: f64,
+}
+
+// A commit bucket to look for stalls in.
+#[derive(Debug, Clone)]
+pub struct StallBucket {
+    pub commits: u64,
+    // Mean commit time of the samples in the bucket.
+    pub commit_time: f64,
+    // Mean seconds between the samples of each run in the previous bucket and this one, when the
+    // samples have timestamps.
+    pub interval: Option<f64>,
+}
+
+impl StallDetection {
+    // Consecutive spiking buckets are merged into a single stall.
+    pub fn detect(&self, values: &[StallBucket]) -> Vec<Stall> {
+        let mut stalls: Vec<Stall> = Default::default();
+
+        let half_window = self.window / 2;
+        let mut in_stall = false;
+        for i in 0..values.len() {
+            let start = i.saturating_sub(half_window);
+            let end = std::cmp::min(values.len(), i + half_window + 1);
+            let neighbours: Vec<f64> = values[start..end].iter().map(|v| v.commit_time).collect();
+            let local_median = statistics::median(&neighbours);
+
+            let bucket = &values[i];
+            let value = bucket.commit_time;
+            if local_median > 0.0 && value > local_median * self.threshold {
+                let magnitude = value / local_median;
+                let duration = bucket.interval.unwrap_or(value);
+                match stalls.last_mut() {
+                    Some(stall) if in_stall => {
+                        stall.magnitude = stall.magnitude.max(magnitude);
+                        stall.duration += duration;
+                    },
+                    _ => {
+                        stalls.push(Stall { commits: bucket.commits, magnitude, duration });
+                    },
+                }
+                in_stall = true;
+            } else {
+                in_stall = false;
+            }
+        }
+
+        stalls
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buckets(commit_times: &[f64], intervals: &[Option<f64>]) -> Vec<StallBucket> {
+        commit_times.iter().enumerate().map(|(index, commit_time)| StallBucket { commits: (index as u64 + 1) * 1000, commit_time: *commit_time, interval: intervals.get(index).copied().flatten() }).collect()
+    }
+
+    #[test]
+    fn merges_consecutive_spikes_into_one_stall() {
+        let detection = StallDetection { threshold: 3.0, window: 5 };
+        let stalls = detection.detect(&buckets(&[1.0, 1.0, 1.0, 10.0, 12.0, 1.0, 1.0, 1.0], &[]));
+        assert_eq!(stalls.len(), 1);
+        assert_eq!(stalls[0].commits, 4000);
+        assert_eq!(stalls[0].magnitude, 12.0);
+        // Without timestamps the duration is the total commit time of the spiking buckets.
+        assert_eq!(stalls[0].duration, 22.0);
+    }
+
+    #[test]
+    fn takes_durations_from_the_intervals_between_samples() {
+        let detection = StallDetection { threshold: 3.0, window: 5 };
+        let intervals = [None, Some(1.5), Some(1.5), Some(0.5), Some(0.75), Some(1.5), None, Some(1.5)];
+        let stalls = detection.detect(&buckets(&[1.0, 1.0, 1.0, 10.0, 12.0, 1.0, 1.0, 1.0], &intervals));
+        assert_eq!(stalls.len(), 1);
+        assert_eq!(stalls[0].duration, 1.25);
+    }
+
+    #[test]
+    fn separate_spikes_are_separate_stalls() {
+        let detection = StallDetection { threshold: 2.0, window: 3 };
+        let stalls = detection.detect(&buckets(&[1.0, 5.0, 1.0, 1.0, 1.0, 3.0, 1.0], &[]));
+        let found: Vec<(u64, f64)> = stalls.iter().map(|stall| (stall.commits, stall.magnitude)).collect();
+        assert_eq!(found, [(2000, 5.0), (6000, 3.0)]);
+    }
+
+    #[test]
+    fn steady_commit_times_have_no_stalls() {
+        let detection = StallDetection { threshold: 2.0, window: 5 };
+        assert!(detection.detect(&buckets(&[1.0, 1.2, 0.9, 1.5, 1.1, 1.9, 1.0], &[])).is_empty());
+        assert!(detection.detect(&[]).is_empty());
+        // A zero median has no multiple to exceed.
+        assert!(detection.detect(&buckets(&[0.0, 0.0, 0.0, 1.0, 0.0], &[])).is_empty());
+    }
+}
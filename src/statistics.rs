@@ -0,0 +1,107 @@
+// Sample autocorrelation of a series for lags 0..=max_lag. A constant series has no variance to
+// correlate against, so every lag is reported as zero.
+pub fn autocorrelation(values: &[f64], max_lag: usize) -> Vec<f64> {
+    let num_values = values.len();
+    if num_values == 0 {
+        return Vec::new()
+    }
+
+    let mean = values.iter().sum::<f64>() / num_values as f64;
+    let variance: f64 = values.iter().map(|v| (v - mean) * (v - mean)).sum();
+
+    let max_lag = max_lag.min(num_values - 1);
+    let mut result = Vec::with_capacity(max_lag + 1);
+    for lag in 0..=max_lag {
+        if variance <= 0.0 {
+            result.push(0.0);
+            continue;
+        }
+
+        let mut covariance = 0.0;
+        for i in 0..(num_values - lag) {
+            covariance += (values[i] - mean) * (values[i + lag] - mean);
+        }
+        result.push(covariance / variance);
+    }
+
+    result
+}
+
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+// Counts of values falling into num_bins equal-width bins spanning min..max. Values outside the
+// range are clamped into the first or last bin.
+pub fn histogram(values: &[f64], min: f64, max: f64, num_bins: usize) -> Vec<u64> {
+    let mut counts = vec![0u64; num_bins];
+    if num_bins == 0 || max <= min {
+        return counts
+    }
+
+    let bin_width = (max - min) / num_bins as f64;
+    for value in values {
+        let bin = ((value - min) / bin_width).floor().max(0.0) as usize;
+        counts[bin.min(num_bins - 1)] += 1;
+    }
+
+    counts
+}
+
+// Percentile (0-100) of already sorted values, linearly interpolating between closest ranks.
+pub fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0
+    }
+
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * fraction
+}
+
+// Two-sided 95% critical values of Student's t-distribution for 1 to 30 degrees of freedom.
+static T_CRITICAL_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+    2.201, 2.179, 2.160, 2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086,
+    2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+
+// Critical value of the t-distribution for a two-sided 95% interval. Beyond the table the
+// Cornish-Fisher expansion around the normal quantile is accurate to well under 0.1%.
+pub fn t_critical_95(degrees_of_freedom: u64) -> f64 {
+    match degrees_of_freedom {
+        0 => f64::INFINITY,
+        1..=30 => T_CRITICAL_95[degrees_of_freedom as usize - 1],
+        _ => {
+            let z: f64 = 1.959964;
+            let df = degrees_of_freedom as f64;
+            z + (z.powi(3) + z) / (4.0 * df) + (5.0 * z.powi(5) + 16.0 * z.powi(3) + 3.0 * z) / (96.0 * df * df)
+        },
+    }
+}
+
+// Deterministic pseudo-random offset in -0.5..0.5 derived from a seed (splitmix64), so jittered
+// output is identical between runs.
+pub fn jitter(seed: u64) -> f64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^= z >> 31;
+
+    (z >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+}
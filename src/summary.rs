@@ -0,0 +1,104 @@
+// Summary statistics of each dataset and metric, written with `--stats-out` for use in reports.
+use super::*;
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SummaryRow {
+    pub dataset: String,
+    pub metric: String,
+    // Number of commit buckets included and the commit count of the first of them.
+    pub buckets: usize,
+    pub first_commits: u64,
+    pub samples: usize,
+    pub mean: f64,
+    pub stddev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+static CSV_HEADER: &str = "dataset,metric,buckets,first_commits,samples,mean,stddev,min,max,p50,p95,p99";
+
+// Writes CSV, or JSON if the path has a `.json` extension. With `last_buckets` only the final
+// commit buckets of each dataset are included.
+pub(crate) fn write_summary(data: &StressTestData, path: &Path, last_buckets: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let rows = get_summary_rows(data, last_buckets);
+
+    let text = match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()).as_deref() {
+        Some("json") => serde_json::to_string_pretty(&rows)?,
+        _ => {
+            let mut text = String::from(CSV_HEADER);
+            text += "\n";
+            for row in &rows {
+                text += &format!("{},{},{},{},{},{},{},{},{},{},{},{}\n", csv_field(&row.dataset), csv_field(&row.metric), row.buckets, row.first_commits, row.samples,
+                    row.mean, row.stddev, row.min, row.max, row.p50, row.p95, row.p99);
+            }
+            text
+        },
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, text).map_err(|e| format!("Failed to write summary statistics {}: {}", path.display(), e))?;
+
+    Ok(())
+}
+
+pub(crate) fn get_summary_rows(data: &StressTestData, last_buckets: Option<usize>) -> Vec<SummaryRow> {
+    let names = data.sorted_names();
+
+    let mut rows: Vec<SummaryRow> = Default::default();
+    for name in names {
+        let dataset = &data.datasets[name];
+
+        let num_values = dataset.sorted_values.len();
+        let values = &dataset.sorted_values[num_values - last_buckets.unwrap_or(num_values).min(num_values)..];
+        let Some(first_value) = values.first() else {
+            continue;
+        };
+
+        let mut metrics: BTreeMap<String, SampleSet> = Default::default();
+        for value in values {
+            for (metric, sample_set) in value.get_summary_sample_sets() {
+                let combined = metrics.entry(metric).or_default();
+                // Rates are NaN for samples without any queries, which the identity transform
+                // leaves out.
+                combined.merge(&sample_set.transformed(&Transform::default()));
+            }
+        }
+
+        for (metric, sample_set) in metrics {
+            if sample_set.is_empty() {
+                continue;
+            }
+
+            rows.push(SummaryRow {
+                dataset: name.clone(),
+                metric,
+                buckets: values.len(),
+                first_commits: first_value.num_commits,
+                samples: sample_set.len(),
+                mean: sample_set.get_mean(),
+                stddev: sample_set.statistics.variance().sqrt(),
+                min: sample_set.value_min,
+                max: sample_set.value_max,
+                p50: sample_set.get_percentile(50.0),
+                p95: sample_set.get_percentile(95.0),
+                p99: sample_set.get_percentile(99.0),
+            });
+        }
+    }
+
+    rows
+}
+
+fn csv_field(text: &str) -> String {
+    match text.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", text.replace('"', "\"\"")),
+        false => text.to_string(),
+    }
+}
@@ -0,0 +1,187 @@
+// Charts drawn as Unicode braille plots on standard output with `--terminal`, for a quick look at
+// results over SSH without an image viewer. Each character cell holds a 2x4 grid of dots.
+use super::*;
+use std::io::IsTerminal;
+use testing::SeriesSnapshot;
+
+// Rows of character cells in each plot, and the width used when `COLUMNS` is not set.
+static PLOT_ROWS: usize = 20;
+static DEFAULT_COLUMNS: usize = 100;
+
+// Braille dot bits by column and row within a cell.
+static BRAILLE_DOTS: [[u8; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+pub(crate) fn print_charts(data: &StressTestData, params: &Params) -> Result<(), Box<dyn Error>> {
+    let series = testing::chart_series(data, params)?;
+
+    let names = data.sorted_names();
+    let colours: HashMap<&String, RGBColor> = names.iter().enumerate().map(|(index, name)| (*name, get_dataset_colour(params, index, &data.datasets[*name]))).collect();
+
+    let columns = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).unwrap_or(DEFAULT_COLUMNS);
+    let colour_output = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+
+    for (chart, spec) in params.chart_specs.iter().enumerate() {
+        let chart_series: Vec<&SeriesSnapshot> = series.iter().filter(|s| s.chart == chart).collect();
+        let chart_colours: Vec<RGBColor> = chart_series.iter().map(|s| colours.get(&s.dataset).copied().unwrap_or(BLACK)).collect();
+
+        println!();
+        println!("{}", params.get_chart_title(spec));
+        print_plot(&chart_series, &chart_colours, spec.options.log_y, &spec.y_range, columns, colour_output);
+
+        let datasets: Vec<&DataSet> = chart_series.iter().filter_map(|s| data.datasets.get(&s.dataset)).collect();
+        let include_parameters = get_varying_parameters(&datasets);
+        for (snapshot, colour) in chart_series.iter().zip(&chart_colours) {
+            let Some(dataset) = data.datasets.get(&snapshot.dataset) else {
+                continue;
+            };
+            // Keep the suffix of series such as `<name> smoothed` or `<name> baseline`.
+            let suffix = snapshot.name.strip_prefix(&snapshot.dataset).unwrap_or_default();
+            let display_name = DataSet::get_name_including(dataset.base_name.clone(), &dataset.parameters, &include_parameters, &params.parameter_labels);
+            let display_name = match spec.filters.group_label(&dataset.base_name, &dataset.parameters, &params.parameter_labels) {
+                Some(group) => format!("{}: {}", group, display_name),
+                None => display_name,
+            };
+            println!("  {} {}{}", paint("⣿⣿", *colour, colour_output), display_name, suffix);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_plot(series: &[&SeriesSnapshot], colours: &[RGBColor], log_y: bool, y_range: &AxisRange, columns: usize, colour_output: bool) {
+    // A log axis plots the exponent of each positive value.
+    let scale = |y: f64| match log_y {
+        true => (y > 0.0).then(|| y.log10()),
+        false => Some(y),
+    };
+    let points: Vec<Vec<(f64, f64)>> = series.iter().map(|s| {
+        s.points.iter().filter(|(x, y)| x.is_finite() && y.is_finite()).filter_map(|(x, y)| scale(*y).map(|y| (*x, y))).collect()
+    }).collect();
+
+    let all_points = || points.iter().flatten();
+    let (Some(min_x), Some(max_x)) = (all_points().map(|p| p.0).reduce(f64::min), all_points().map(|p| p.0).reduce(f64::max)) else {
+        println!("  (no data)");
+        return
+    };
+    // As in images, linear axes start from zero unless there are negative values.
+    let mut min_y = all_points().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let mut max_y = all_points().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    if !log_y {
+        min_y = min_y.min(0.0);
+    }
+    if max_y <= min_y {
+        max_y = min_y + 1.0;
+    }
+    // Points beyond a pinned range are left out.
+    let pinned = AxisRange { min: y_range.min.and_then(scale), max: y_range.max.and_then(scale) };
+    let range = pinned.apply(min_y..max_y);
+    (min_y, max_y) = (range.start, range.end);
+
+    let unscale = |y: f64| match log_y {
+        true => 10f64.powf(y),
+        false => y,
+    };
+    let labels = [format_value(unscale(max_y)), format_value(unscale((min_y + max_y) / 2.0)), format_value(unscale(min_y))];
+    let gutter = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+
+    let cells_x = columns.saturating_sub(gutter + 3).max(10);
+    let (dots_x, dots_y) = (cells_x * 2, PLOT_ROWS * 4);
+    let mut cells: Vec<(u8, Option<usize>)> = vec![(0, None); cells_x * PLOT_ROWS];
+
+    let to_dot = |(x, y): (f64, f64)| {
+        let dot_x = match max_x > min_x {
+            true => (x - min_x) / (max_x - min_x) * (dots_x - 1) as f64,
+            false => 0.0,
+        };
+        let dot_y = (max_y - y) / (max_y - min_y) * (dots_y - 1) as f64;
+        (dot_x.round() as i64, dot_y.round() as i64)
+    };
+
+    for (index, series_points) in points.iter().enumerate() {
+        let dots: Vec<(i64, i64)> = series_points.iter().map(|p| to_dot(*p)).collect();
+        let mut set = |(x, y): (i64, i64)| {
+            if x < 0 || y < 0 || x >= dots_x as i64 || y >= dots_y as i64 {
+                return
+            }
+            let cell = &mut cells[(y as usize / 4) * cells_x + x as usize / 2];
+            cell.0 |= BRAILLE_DOTS[x as usize % 2][y as usize % 4];
+            cell.1 = Some(index);
+        };
+        match dots.len() {
+            1 => set(dots[0]),
+            _ => {
+                for pair in dots.windows(2) {
+                    for dot in line_dots(pair[0], pair[1]) {
+                        set(dot);
+                    }
+                }
+            },
+        }
+    }
+
+    for row in 0..PLOT_ROWS {
+        let label = match row {
+            0 => &labels[0],
+            _ if row == PLOT_ROWS / 2 => &labels[1],
+            _ if row == PLOT_ROWS - 1 => &labels[2],
+            _ => "",
+        };
+        let tick = match label.is_empty() {
+            true => '│',
+            false => '┤',
+        };
+        let mut line = format!("{:>width$} {}", label, tick, width = gutter);
+        for (bits, index) in &cells[row * cells_x..(row + 1) * cells_x] {
+            match index {
+                Some(index) => line += &paint(&char::from_u32(0x2800 + *bits as u32).unwrap_or(' ').to_string(), colours[*index], colour_output),
+                None => line.push(' '),
+            }
+        }
+        println!("{}", line);
+    }
+
+    let min_label = format_value(min_x);
+    let max_label = format_value(max_x);
+    println!("{:>width$} └{}", "", "─".repeat(cells_x), width = gutter);
+    println!("{:>width$}  {}{:>fill$}", "", min_label, max_label, width = gutter, fill = cells_x.saturating_sub(min_label.chars().count()));
+}
+
+// Dots on the straight line between two dots, including both ends.
+fn line_dots(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).max(1);
+    (0..=steps).map(|step| {
+        let fraction = step as f64 / steps as f64;
+        ((from.0 as f64 + (to.0 - from.0) as f64 * fraction).round() as i64, (from.1 as f64 + (to.1 - from.1) as f64 * fraction).round() as i64)
+    }).collect()
+}
+
+// Parameters which differ between the datasets, so that legends name only what tells them apart.
+pub(crate) fn get_varying_parameters(datasets: &[&DataSet]) -> HashSet<String> {
+    let mut names: HashSet<String> = Default::default();
+    let Some(first) = datasets.first() else {
+        return names
+    };
+    for dataset in datasets {
+        for name in first.parameters.keys().chain(dataset.parameters.keys()) {
+            if first.parameters.get(name) != dataset.parameters.get(name) {
+                names.insert(name.clone());
+            }
+        }
+    }
+    names
+}
+
+fn format_value(value: f64) -> String {
+    match value.abs() {
+        v if v >= 1000.0 || v == 0.0 => format!("{:.0}", value),
+        v if v >= 1.0 => format!("{:.1}", value),
+        _ => format!("{:.3}", value),
+    }
+}
+
+fn paint(text: &str, colour: RGBColor, colour_output: bool) -> String {
+    match colour_output {
+        true => format!("\x1b[38;2;{};{};{}m{}\x1b[0m", colour.0, colour.1, colour.2, text),
+        false => text.to_string(),
+    }
+}
@@ -0,0 +1,370 @@
+// Helpers for regression testing charts against stored golden outputs. Charts are rendered from
+// command line style arguments into memory, so tests need only fixture CSVs and golden files.
+// Golden files are PNG images compared within a tolerance, hashes of the exact pixels, or text
+// snapshots of the plotted series. `--golden` checks the charts of a run the same way.
+//
+// Set the UPDATE_GOLDEN environment variable, or pass `--bless`, to write the current output as
+// the new golden file instead of comparing against it.
+use super::*;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+// An RGB image rendered in memory.
+#[derive(Debug, Clone)]
+pub struct RenderedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageTolerance {
+    // Largest per-channel difference for two pixels to still be considered equal.
+    pub channel: u8,
+    // Fraction of pixels which may differ before the images are considered different.
+    pub differing_fraction: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageComparison {
+    pub differing_pixels: usize,
+    pub total_pixels: usize,
+    pub max_channel_difference: u8,
+}
+
+// A single plotted series, as computed before drawing.
+#[derive(Debug, Clone)]
+pub struct SeriesSnapshot {
+    pub chart: usize,
+    // Full name of the dataset the series was computed from.
+    pub dataset: String,
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+    // The spread of the samples around each point of a metric chart's mean line, otherwise empty.
+    pub spread: Vec<PointSpread>,
+}
+
+// Smallest and largest sample at a plotted mean, and half the width of the range around it of
+// twice the standard deviation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointSpread {
+    pub min: f64,
+    pub max: f64,
+    pub range: f64,
+}
+
+impl Default for ImageTolerance {
+    fn default() -> ImageTolerance {
+        ImageTolerance { channel: 8, differing_fraction: 0.001 }
+    }
+}
+
+impl ImageComparison {
+    pub fn passes(&self, tolerance: &ImageTolerance) -> bool {
+        self.differing_pixels as f64 <= self.total_pixels as f64 * tolerance.differing_fraction
+    }
+}
+
+pub(crate) struct Prepared {
+    pub(crate) params: Params,
+    pub(crate) data: StressTestData,
+}
+
+// The params and data of a run with the arguments, prepared as `run` prepares them.
+pub(crate) fn prepare(arguments: &[&str]) -> Result<Prepared, Box<dyn Error>> {
+    let arguments: Vec<std::ffi::OsString> = std::iter::once(env!("CARGO_PKG_NAME")).chain(arguments.iter().copied()).map(Into::into).collect();
+    expression::register_metrics(&arguments)?;
+    let matches = Args::command().try_get_matches_from(arguments)?;
+    let mut args = Args::from_arg_matches(&matches)?;
+
+    let config = match &args.config {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    config.apply(&mut args, &matches)?;
+    apply_compare(&mut args);
+    resolve_data_paths(&mut args)?;
+
+    let (params, data) = prepare_data(&args, &matches, &config)?;
+    Ok(Prepared { params, data: data.ok_or("No data paths given")? })
+}
+
+// Renders the charts described by the arguments, e.g. `["-d", "fixture.csv", "-c", "commit-time"]`.
+pub fn render(arguments: &[&str]) -> Result<RenderedImage, Box<dyn Error>> {
+    let prepared = prepare(arguments)?;
+    render_data(&prepared.data, &prepared.params)
+}
+
+// Renders the charts of the params as they are written to a PNG file, title and footer included.
+pub fn render_data(data: &StressTestData, params: &Params) -> Result<RenderedImage, Box<dyn Error>> {
+    let (width, height) = params.get_image_size(&params.get_layout(params.chart_specs.len())?);
+
+    let mut pixels = vec![0u8; (width * height * 3) as usize];
+    draw_to_area(&BitMapBackend::with_buffer(&mut pixels, (width, height)).into_drawing_area(), Some(data), params, &params.chart_specs, true)?;
+
+    Ok(RenderedImage { width, height, pixels })
+}
+
+// The size and SHA-256 of the pixels, e.g. `2160x2160 3f2a...`. Any change of a single pixel
+// changes the hash, so hashes suit golden files only where fonts render the same on every machine.
+pub fn image_hash(image: &RenderedImage) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(&image.pixels);
+    let hash: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}x{} {}", image.width, image.height, hash)
+}
+
+pub fn compare_images(actual: &RenderedImage, expected: &RenderedImage, tolerance: &ImageTolerance) -> Result<ImageComparison, Box<dyn Error>> {
+    if (actual.width, actual.height) != (expected.width, expected.height) {
+        return Err(format!("Image size {}x{} does not match expected {}x{}", actual.width, actual.height, expected.width, expected.height).into())
+    }
+
+    let mut comparison = ImageComparison { differing_pixels: 0, total_pixels: (actual.width * actual.height) as usize, max_channel_difference: 0 };
+    for (a, e) in actual.pixels.chunks(3).zip(expected.pixels.chunks(3)) {
+        let difference = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap_or(0);
+        comparison.max_channel_difference = comparison.max_channel_difference.max(difference);
+        if difference > tolerance.channel {
+            comparison.differing_pixels += 1;
+        }
+    }
+
+    Ok(comparison)
+}
+
+// Compares against a golden PNG. On failure the actual image is written next to it with an
+// `.actual.png` extension for inspection.
+pub fn check_golden_image(actual: &RenderedImage, golden_path: &Path, tolerance: &ImageTolerance) -> Result<(), Box<dyn Error>> {
+    if update_golden() {
+        return save_png(actual, golden_path)
+    }
+
+    let golden = image::open(golden_path).map_err(|e| format!("Failed to read golden image {}: {}", golden_path.display(), e))?.into_rgb8();
+    let expected = RenderedImage { width: golden.width(), height: golden.height(), pixels: golden.into_raw() };
+
+    let comparison = compare_images(actual, &expected, tolerance)?;
+    if !comparison.passes(tolerance) {
+        let actual_path = golden_path.with_extension("actual.png");
+        save_png(actual, &actual_path)?;
+        return Err(format!("{} of {} pixels differ from {} (max channel difference {}), actual image written to {}",
+            comparison.differing_pixels, comparison.total_pixels, golden_path.display(), comparison.max_channel_difference, actual_path.display()).into())
+    }
+
+    Ok(())
+}
+
+// Compares against a golden hash file written by `image_hash`. On failure the actual image is
+// written next to it with an `.actual.png` extension for inspection.
+pub fn check_golden_hash(actual: &RenderedImage, golden_path: &Path) -> Result<(), Box<dyn Error>> {
+    if update_golden() {
+        return save_hash(actual, golden_path)
+    }
+
+    let expected = std::fs::read_to_string(golden_path).map_err(|e| format!("Failed to read golden hash {}: {}", golden_path.display(), e))?;
+    let actual_hash = image_hash(actual);
+    if actual_hash != expected.trim() {
+        let actual_path = golden_path.with_extension("actual.png");
+        save_png(actual, &actual_path)?;
+        return Err(format!("Image hash {} does not match {} in {}, actual image written to {}", actual_hash, expected.trim(), golden_path.display(), actual_path.display()).into())
+    }
+
+    Ok(())
+}
+
+// Checks the charts of a run against a golden PNG, or against a golden hash file when it has any
+// other extension, for `--golden`. With `bless` the golden file is written instead.
+pub(crate) fn check_golden(data: &StressTestData, params: &Params, golden_path: &Path, bless: bool) -> Result<(), Box<dyn Error>> {
+    let actual = render_data(data, params)?;
+    let is_image = golden_path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+
+    if bless || update_golden() {
+        if let Some(parent) = golden_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+        match is_image {
+            true => save_png(&actual, golden_path)?,
+            false => save_hash(&actual, golden_path)?,
+        }
+        log::info!("Wrote golden file: {}", golden_path.display());
+        return Ok(())
+    }
+
+    match is_image {
+        true => check_golden_image(&actual, golden_path, &ImageTolerance::default())?,
+        false => check_golden_hash(&actual, golden_path)?,
+    }
+    log::info!("Charts match golden file {}", golden_path.display());
+    Ok(())
+}
+
+fn save_hash(image: &RenderedImage, path: &Path) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, image_hash(image) + "\n").map_err(|e| format!("Failed to write golden hash {}: {}", path.display(), e).into())
+}
+
+fn save_png(image: &RenderedImage, path: &Path) -> Result<(), Box<dyn Error>> {
+    image::save_buffer(path, &image.pixels, image.width, image.height, image::ColorType::Rgb8)
+        .map_err(|e| format!("Failed to write image {}: {}", path.display(), e).into())
+}
+
+fn update_golden() -> bool {
+    std::env::var_os("UPDATE_GOLDEN").is_some()
+}
+
+// The series each chart would plot, for data level snapshots which are unaffected by fonts and
+// rendering details.
+pub fn computed_series(arguments: &[&str]) -> Result<Vec<SeriesSnapshot>, Box<dyn Error>> {
+    let prepared = prepare(arguments)?;
+    chart_series(&prepared.data, &prepared.params)
+}
+
+// The series of each chart of the params, in the order of the legend when drawn. Also used to draw
+// charts in the terminal.
+pub(crate) fn chart_series(data: &StressTestData, params: &Params) -> Result<Vec<SeriesSnapshot>, Box<dyn Error>> {
+    let mut datasets: Vec<(&String, &DataSet)> = data.sorted_names().into_iter().map(|name| (name, &data.datasets[name])).collect();
+    if let Some(sort_by) = &params.sort_by {
+        datasets.sort_by(|a, b| sort_by.cmp(a.1, b.1));
+    }
+
+    let mut result: Vec<SeriesSnapshot> = Default::default();
+    for (chart, spec) in params.chart_specs.iter().enumerate() {
+        let mut datasets = datasets.clone();
+        datasets.sort_by_key(|(_, dataset)| spec.filters.group_index(&dataset.base_name, &dataset.parameters));
+        for (name, dataset) in &datasets {
+            if !dataset.passes_filters(&spec.filters) || (dataset.derived && !spec.chart_type.is_metric()) {
+                continue;
+            }
+
+            // As when drawing, smoothed data replaces the dataset unless overlaid on a metric chart.
+            let smoothed = spec.options.smooth.map(|window| dataset.smoothed(window));
+            let overlay_smoothed = spec.chart_type.is_metric() && spec.options.smooth_mode == SmoothMode::Overlay;
+            let dataset = match (&smoothed, overlay_smoothed) {
+                (Some(smoothed), false) => smoothed,
+                _ => *dataset,
+            };
+
+            // Dense datasets are downsampled as when drawing.
+            let downsampled = params.get_downsampled(dataset, &spec.chart_type);
+            let dataset = downsampled.as_ref().unwrap_or(dataset);
+            let downsampled_smoothed = smoothed.as_ref().filter(|_| overlay_smoothed).and_then(|smoothed| params.get_downsampled(smoothed, &spec.chart_type));
+            let smoothed = downsampled_smoothed.as_ref().or(smoothed.as_ref());
+
+            let mut push = |series_name: String, points: Vec<(f64, f64)>, spread: Vec<PointSpread>| result.push(SeriesSnapshot { chart, dataset: name.to_string(), name: series_name, points, spread });
+            let options = &spec.options;
+            match &spec.chart_type {
+                ChartType::CommitTime | ChartType::CommitsPerSecond | ChartType::QueriesPerSecond | ChartType::DiskSize | ChartType::WriteAmplification | ChartType::MemoryUsage | ChartType::HeapUsage | ChartType::PluginMetric(_) => {
+                    let baseline = data.get_baseline(name);
+                    match (&params.baseline_mode, &data.baseline) {
+                        (BaselineMode::Delta, Some(_)) => {
+                            if let Some(baseline) = baseline {
+                                push(format!("{} change", name), dataset.get_relative_change(baseline, &spec.chart_type, &spec.transform), Default::default());
+                            }
+                        },
+                        (BaselineMode::Significance, Some(_)) => {
+                            if let Some(baseline) = baseline {
+                                let comparisons = compare::compare_buckets(dataset, baseline, &spec.chart_type, &spec.transform);
+                                push(format!("{} change", name), comparisons.iter().map(|c| (c.commits, c.change_percent)).collect(), Default::default());
+                                push(format!("{} significant", name), comparisons.iter().filter(|c| c.significant).map(|c| (c.commits, c.change_percent)).collect(), Default::default());
+                            }
+                        },
+                        _ => {
+                            push(name.to_string(), dataset.get_mean_points(&spec.chart_type, &spec.transform), dataset.get_mean_spread(&spec.chart_type, &spec.transform));
+                            if let (Some(smoothed), true) = (smoothed, overlay_smoothed) {
+                                push(format!("{} smoothed", name), smoothed.get_mean_points(&spec.chart_type, &spec.transform), smoothed.get_mean_spread(&spec.chart_type, &spec.transform));
+                            }
+                            let downsampled_baseline = baseline.and_then(|baseline| params.get_downsampled(baseline, &spec.chart_type));
+                            if let Some(baseline) = downsampled_baseline.as_ref().or(baseline) {
+                                push(format!("{} baseline", name), baseline.get_mean_points(&spec.chart_type, &spec.transform), baseline.get_mean_spread(&spec.chart_type, &spec.transform));
+                            }
+                            if let Some(trend) = options.trend.and_then(|fit| Trend::fit(&dataset.get_mean_points(&spec.chart_type, &spec.transform), fit)) {
+                                push(format!("{} trend", name), trend.get_points(), Default::default());
+                            }
+                        },
+                    }
+                },
+                ChartType::CommitTimeAutocorrelation => push(name.to_string(), dataset.get_commit_time_autocorrelation(), Default::default()),
+                ChartType::SampleIntervalHistogram => push(name.to_string(), dataset.get_sample_intervals().into_iter().enumerate().map(|(i, v)| (i as f64, v)).collect(), Default::default()),
+                ChartType::CommitTimeOverWallClock => push(name.to_string(), dataset.get_commits_per_second_over_wall_clock(), Default::default()),
+                ChartType::QueryCommitRatio => push(name.to_string(), dataset.get_query_commit_ratio(), Default::default()),
+                ChartType::CommitsPerSecondVariation => push(name.to_string(), dataset.get_commits_per_second_variation(), Default::default()),
+                ChartType::CommitTimeScatter | ChartType::CommitsPerSecondScatter | ChartType::QueriesPerSecondScatter => push(name.to_string(), dataset.get_scatter_points(&spec.chart_type, options.jitter), Default::default()),
+                ChartType::CommitTimeRollingPercentile => push(name.to_string(), dataset.get_rolling_commit_time_percentile(options.rolling_percentile, options.rolling_window), Default::default()),
+                ChartType::CommitsPerSecondRunVariance => {
+                    let deviation = dataset.get_commits_per_second_run_deviation(options.variance_window);
+                    push(format!("{} between runs", name), deviation.iter().map(|(x, between, _)| (*x, *between)).collect(), Default::default());
+                    push(format!("{} within run", name), deviation.iter().map(|(x, _, within)| (*x, *within)).collect(), Default::default());
+                },
+                ChartType::FinalThroughputBars => {
+                    for (metric, chart_type) in [("commits", ChartType::CommitsPerSecond), ("queries", ChartType::QueriesPerSecond)] {
+                        let mean = dataset.get_final_mean(&chart_type, &spec.transform, options.final_buckets);
+                        push(format!("{} {}", name, metric), mean.into_iter().map(|mean| (dataset.max_commits as f64, mean)).collect(), Default::default());
+                    }
+                },
+                ChartType::QueryLatency => {
+                    for percentile in [50.0, 90.0, 99.0] {
+                        let points = dataset.sorted_values.iter().filter(|v| !v.query_latency.is_empty()).map(|v| (v.num_commits as f64, v.query_latency.get_percentile(percentile))).collect();
+                        push(format!("{} p{}", name, percentile), points, Default::default());
+                    }
+                },
+                ChartType::PluginChart(chart_name) => {
+                    let renderer = plugins::get_chart(chart_name).ok_or_else(|| format!("Chart type is not registered: {}", chart_name))?;
+                    push(name.to_string(), renderer.compute_series(&plugins::get_value_points(dataset)), Default::default());
+                },
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+// Stable text form of series snapshots, one `x y` pair per line under a header per series.
+pub fn snapshot_text(series: &[SeriesSnapshot]) -> String {
+    let mut text = String::new();
+    for snapshot in series {
+        text += &format!("# chart {}: {}\n", snapshot.chart, snapshot.name);
+        for (x, y) in &snapshot.points {
+            text += &format!("{:.6} {:.6}\n", x, y);
+        }
+    }
+    text
+}
+
+// Compares against a golden snapshot file. Headers must match exactly and values must be within
+// the relative tolerance.
+pub fn check_golden_series(series: &[SeriesSnapshot], golden_path: &Path, relative_tolerance: f64) -> Result<(), Box<dyn Error>> {
+    let actual = snapshot_text(series);
+    if update_golden() {
+        std::fs::write(golden_path, actual)?;
+        return Ok(())
+    }
+
+    let expected = std::fs::read_to_string(golden_path).map_err(|e| format!("Failed to read golden series {}: {}", golden_path.display(), e))?;
+
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    if actual_lines.len() != expected_lines.len() {
+        return Err(format!("Series snapshot has {} lines but {} has {}", actual_lines.len(), golden_path.display(), expected_lines.len()).into())
+    }
+
+    for (line_number, (a, e)) in actual_lines.iter().zip(&expected_lines).enumerate() {
+        let matches = match (parse_point(a), parse_point(e)) {
+            (Some(a), Some(e)) => within_tolerance(a.0, e.0, relative_tolerance) && within_tolerance(a.1, e.1, relative_tolerance),
+            _ => a == e,
+        };
+        if !matches {
+            return Err(format!("{}:{}: expected '{}', got '{}'", golden_path.display(), line_number + 1, e, a).into())
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_point(line: &str) -> Option<(f64, f64)> {
+    let (x, y) = line.split_once(' ')?;
+    Some((x.parse().ok()?, y.parse().ok()?))
+}
+
+fn within_tolerance(actual: f64, expected: f64, relative_tolerance: f64) -> bool {
+    if actual.is_nan() && expected.is_nan() {
+        return true
+    }
+    (actual - expected).abs() <= relative_tolerance * expected.abs().max(1e-9)
+}
@@ -0,0 +1,99 @@
+// Colours of everything apart from the datasets themselves, selected with `--theme`. The light
+// theme matches the plotters defaults the charts were originally drawn with.
+use super::*;
+use serde::Deserialize;
+use std::path::Path;
+
+static THEME_NAMES: [&str; 2] = ["light", "dark"];
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub background: RGBColor,
+    // Captions, labels, axes and legend borders.
+    pub text: RGBColor,
+    // Grid lines are blended over the background, the major lines more strongly than the minor.
+    pub grid: RGBColor,
+    // Used unless `--palette` is given.
+    pub palette: Palette,
+}
+
+// A theme file, where unset colours come from the `base` theme, or the light theme.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    background: Option<String>,
+    text: Option<String>,
+    grid: Option<String>,
+    palette: Option<String>,
+}
+
+impl Theme {
+    pub fn light() -> Theme {
+        Theme { background: WHITE, text: BLACK, grid: BLACK, palette: Palette::default() }
+    }
+
+    // For embedding in dark dashboards. The default palette includes black, so a palette without
+    // it is used instead.
+    pub fn dark() -> Theme {
+        Theme {
+            background: RGBColor(0x1e, 0x1e, 0x1e),
+            text: RGBColor(0xdc, 0xdc, 0xdc),
+            grid: WHITE,
+            palette: Palette::parse("category10").expect("Named palette"),
+        }
+    }
+
+    pub fn is_named(text: &str) -> bool {
+        THEME_NAMES.contains(&text)
+    }
+
+    fn named(name: &str) -> Option<Theme> {
+        match name {
+            "light" => Some(Theme::light()),
+            "dark" => Some(Theme::dark()),
+            _ => None,
+        }
+    }
+
+    /// The named theme `light` or `dark`, or otherwise a TOML file with `background`, `text` and
+    /// `grid` hex colours, a `palette` as accepted by `--palette` and the `base` theme for unset
+    /// values.
+    pub fn load(text: &str) -> Result<Theme, Box<dyn Error>> {
+        if let Some(theme) = Theme::named(text) {
+            return Ok(theme)
+        }
+
+        let path = Path::new(text);
+        let file_text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read theme file {}, expected {} or a TOML theme file: {}", path.display(), THEME_NAMES.join(", "), e))?;
+        let file: ThemeFile = toml::from_str(&file_text).map_err(|e| format!("Failed to parse theme file {}: {}", path.display(), e))?;
+        let invalid = |e: String| format!("Invalid theme file {}: {}", path.display(), e);
+
+        let mut theme = match &file.base {
+            Some(base) => Theme::named(base).ok_or_else(|| invalid(format!("unknown base theme '{}', expected one of {}", base, THEME_NAMES.join(", "))))?,
+            None => Theme::light(),
+        };
+        for (value, colour) in [(&file.background, &mut theme.background), (&file.text, &mut theme.text), (&file.grid, &mut theme.grid)] {
+            if let Some(value) = value {
+                *colour = palette::parse_hex_colour(value).ok_or_else(|| invalid(format!("invalid colour '{}', expected a hex colour like #1e1e1e", value)))?;
+            }
+        }
+        if let Some(palette) = &file.palette {
+            theme.palette = Palette::parse(palette).map_err(invalid)?;
+        }
+        Ok(theme)
+    }
+
+    pub fn bold_grid(&self) -> RGBAColor {
+        self.grid.mix(0.2)
+    }
+
+    pub fn light_grid(&self) -> RGBAColor {
+        self.grid.mix(0.1)
+    }
+
+    // Annotations and other marks which should stand back from the data.
+    pub fn muted_text(&self) -> RGBAColor {
+        self.text.mix(0.6)
+    }
+}
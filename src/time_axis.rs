@@ -0,0 +1,52 @@
+// Metric charts plotted against the time of each sample with `--x-axis time`, from the optional
+// `timestamp` column, so that throughput dips can be lined up with events in other logs. Times
+// are held as seconds since the Unix epoch and labelled in the local time zone.
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+// Numeric timestamps above this are taken to be in milliseconds, as seconds would be thousands of
+// years away.
+static MAX_SECONDS: f64 = 1e11;
+
+static NAIVE_FORMATS: [&str; 2] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"];
+
+// Seconds since the epoch, from Unix seconds or milliseconds, an RFC 3339 date and time such as
+// `2024-03-01T12:00:00Z`, or a date and time without an offset such as `2024-03-01 12:00:00`,
+// which is taken to be local time.
+pub(crate) fn parse_timestamp(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if let Ok(value) = text.parse::<f64>() {
+        return from_epoch(value)
+    }
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(get_seconds(&time))
+    }
+    NAIVE_FORMATS.iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .and_then(|time| Local.from_local_datetime(&time).earliest())
+        .map(|time| get_seconds(&time))
+}
+
+// Seconds from a number of Unix seconds or milliseconds.
+pub(crate) fn from_epoch(value: f64) -> Option<f64> {
+    match (value.is_finite(), value.abs() > MAX_SECONDS) {
+        (false, _) => None,
+        (true, false) => Some(value),
+        (true, true) => Some(value / 1000.0),
+    }
+}
+
+fn get_seconds<Tz: TimeZone>(time: &DateTime<Tz>) -> f64 {
+    time.timestamp() as f64 + time.timestamp_subsec_nanos() as f64 / 1e9
+}
+
+// Local time of day, with the date as well when the axis spans more than a day.
+pub(crate) fn format_time_label(seconds: f64, span: f64) -> String {
+    let Some(time) = Local.timestamp_opt(seconds.floor() as i64, 0).single() else {
+        return String::new()
+    };
+    match span > 86400.0 {
+        true => time.format("%m-%d %H:%M").to_string(),
+        false => time.format("%H:%M:%S").to_string(),
+    }
+}
@@ -0,0 +1,78 @@
+use std::fmt;
+
+// A single step applied to each metric value before it is plotted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransformStep {
+    Reciprocal,
+    Log,
+    Scale(f64),
+}
+
+// A chain of steps applied in order, e.g. `reciprocal, scale:1000`. An empty chain leaves values
+// unchanged.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transform {
+    pub steps: Vec<TransformStep>,
+}
+
+impl TransformStep {
+    pub fn parse(text: &str) -> Result<TransformStep, String> {
+        let text = text.trim();
+        match text {
+            "reciprocal" => Ok(TransformStep::Reciprocal),
+            "log" => Ok(TransformStep::Log),
+            _ => match text.strip_prefix("scale:") {
+                Some(factor) => factor.trim().parse::<f64>().map(TransformStep::Scale).map_err(|_| format!("Invalid scale factor in transform: {}", text)),
+                None => Err(format!("Unknown transform: {}", text)),
+            },
+        }
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        match self {
+            TransformStep::Reciprocal => 1.0 / value,
+            TransformStep::Log => value.log10(),
+            TransformStep::Scale(factor) => value * factor,
+        }
+    }
+}
+
+impl Transform {
+    pub fn parse(text: &str) -> Result<Transform, String> {
+        let mut steps = Vec::new();
+        for step_text in text.split(',').filter(|s| !s.trim().is_empty()) {
+            steps.push(TransformStep::parse(step_text)?);
+        }
+        Ok(Transform { steps })
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    // Returns None when the result is not a plottable number, e.g. the log of zero.
+    pub fn apply(&self, value: f64) -> Option<f64> {
+        let result = self.steps.iter().fold(value, |v, step| step.apply(v));
+        match result.is_finite() {
+            true => Some(result),
+            false => None,
+        }
+    }
+}
+
+impl fmt::Display for TransformStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TransformStep::Reciprocal => write!(f, "reciprocal"),
+            TransformStep::Log => write!(f, "log"),
+            TransformStep::Scale(factor) => write!(f, "scale:{}", factor),
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text: Vec<String> = self.steps.iter().map(|step| step.to_string()).collect();
+        write!(f, "{}", text.join(", "))
+    }
+}
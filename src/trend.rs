@@ -0,0 +1,105 @@
+// Trend lines of `--trend`, least squares fits over the mean points of each dataset of a metric
+// chart. The slope is given in the legend, e.g. `-120 cps per 1M commits`, so that how throughput
+// decays as the database grows can be read off the chart.
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TrendFit {
+    // A straight line, with the change per million commits, or per hour against time.
+    Linear,
+    // A straight line against the logarithm of the commit count, with the change for every tenfold
+    // increase in commits. Suits metrics which change quickly at first and level off.
+    LogLinear,
+}
+
+#[derive(Debug, Clone)]
+pub struct Trend {
+    pub fit: TrendFit,
+    pub intercept: f64,
+    // Per unit of X, or per tenfold increase of X when log-linear.
+    pub slope: f64,
+    min_x: f64,
+    max_x: f64,
+}
+
+// Points along a log-linear trend, which is curved on a linear X axis.
+static CURVE_POINTS: usize = 100;
+
+impl Trend {
+    // The fit over the points, None without two distinct X values. Log-linear fits leave out
+    // points at or below zero.
+    pub fn fit(points: &[(f64, f64)], fit: TrendFit) -> Option<Trend> {
+        let fitted: Vec<(f64, f64)> = points.iter()
+            .filter(|(x, y)| x.is_finite() && y.is_finite() && (fit == TrendFit::Linear || *x > 0.0))
+            .map(|(x, y)| match fit {
+                TrendFit::Linear => (*x, *y),
+                TrendFit::LogLinear => (x.log10(), *y),
+            })
+            .collect();
+        if fitted.len() < 2 {
+            return None
+        }
+
+        let n = fitted.len() as f64;
+        let mean_x = fitted.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = fitted.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = fitted.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+        let variance: f64 = fitted.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if variance <= 0.0 {
+            return None
+        }
+
+        let slope = covariance / variance;
+        let (min_x, max_x) = points.iter().filter(|(x, _)| fit == TrendFit::Linear || *x > 0.0).fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), (x, _)| (min.min(*x), max.max(*x)));
+        Some(Trend { fit, intercept: mean_y - slope * mean_x, slope, min_x, max_x })
+    }
+
+    pub fn value_at(&self, x: f64) -> f64 {
+        match self.fit {
+            TrendFit::Linear => self.intercept + self.slope * x,
+            TrendFit::LogLinear => self.intercept + self.slope * x.log10(),
+        }
+    }
+
+    // The line across the X range of the fitted points.
+    pub fn get_points(&self) -> Vec<(f64, f64)> {
+        let xs: Vec<f64> = match self.fit {
+            TrendFit::Linear => vec![self.min_x, self.max_x],
+            TrendFit::LogLinear => {
+                let (start, end) = (self.min_x.log10(), self.max_x.log10());
+                (0..=CURVE_POINTS).map(|i| 10f64.powf(start + (end - start) * i as f64 / CURVE_POINTS as f64)).collect()
+            },
+        };
+        xs.into_iter().map(|x| (x, self.value_at(x))).collect()
+    }
+
+    // The slope in the units of the chart, e.g. `-120 cps per 1M commits`.
+    pub fn describe(&self, spec: &ChartSpec, x_axis: &XAxis) -> String {
+        let (change, per) = match (self.fit, x_axis) {
+            (TrendFit::Linear, XAxis::Commits) => (self.slope * 1e6, "per 1M commits".to_string()),
+            (TrendFit::Linear, XAxis::Time) => (self.slope * 3600.0, "per hour".to_string()),
+            (TrendFit::Linear, XAxis::Metric(chart_type)) => (self.slope, format!("per unit of {}", chart_type)),
+            (TrendFit::LogLinear, XAxis::Metric(chart_type)) => (self.slope, format!("per 10x {}", chart_type)),
+            (TrendFit::LogLinear, _) => (self.slope, "per 10x commits".to_string()),
+        };
+        // Transformed values are no longer in the units of the chart type.
+        let unit = match (&spec.chart_type, spec.transform.is_identity()) {
+            (ChartType::CommitsPerSecond, true) => " cps",
+            (ChartType::QueriesPerSecond, true) => " qps",
+            (ChartType::CommitTime, true) => " s",
+            (ChartType::DiskSize, true) | (ChartType::MemoryUsage, true) | (ChartType::HeapUsage, true) => " MB",
+            _ => "",
+        };
+        format!("{}{} {}", format_change(change), unit, per)
+    }
+}
+
+// With a sign and three significant digits.
+fn format_change(value: f64) -> String {
+    let decimals = match value != 0.0 && value.is_finite() {
+        true => (2 - value.abs().log10().floor() as i32).clamp(0, 6) as usize,
+        false => 0,
+    };
+    format!("{:+.*}", decimals, value)
+}
@@ -0,0 +1,123 @@
+// Units of the Y axis labels, so that throughputs read as `150k` rather than `150000` and commit
+// times as `250 ms` rather than `0.25`. `--y-unit` picks the unit instead of fitting it to the
+// range of each chart.
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum YUnit {
+    // Fitted to the largest value on the axis.
+    Auto,
+    // Plain values, without a suffix.
+    None,
+    K,
+    #[value(name = "M")]
+    #[serde(rename = "M")]
+    M,
+    Us,
+    Ms,
+    S,
+}
+
+// What the Y values of a chart measure, which decides the units that apply to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum YQuantity {
+    // Operations per second.
+    Rate,
+    Seconds,
+    // Anything else, labelled as plotters does.
+    Other,
+}
+
+impl YQuantity {
+    // Transformed values are no longer in the units of the chart type.
+    pub(crate) fn of_chart(spec: &ChartSpec) -> YQuantity {
+        if !spec.transform.is_identity() {
+            return YQuantity::Other
+        }
+        match spec.chart_type {
+            ChartType::CommitsPerSecond | ChartType::QueriesPerSecond | ChartType::CommitsPerSecondScatter | ChartType::QueriesPerSecondScatter
+                | ChartType::CommitsPerSecondRunVariance | ChartType::CommitTimeOverWallClock | ChartType::FinalThroughputBars => YQuantity::Rate,
+            ChartType::CommitTime => YQuantity::Seconds,
+            _ => YQuantity::Other,
+        }
+    }
+}
+
+impl YUnit {
+    // The unit of the labels of an axis over the range. Units which do not apply to the quantity,
+    // such as `ms` on a throughput chart, are fitted as with `auto`.
+    fn resolve(self, quantity: YQuantity, range: &std::ops::Range<f64>) -> YUnit {
+        let max = range.start.abs().max(range.end.abs());
+        match (quantity, self) {
+            (YQuantity::Rate, YUnit::None | YUnit::K | YUnit::M) | (YQuantity::Seconds, YUnit::None | YUnit::Us | YUnit::Ms | YUnit::S) => self,
+            (YQuantity::Rate, _) if max >= 1e6 => YUnit::M,
+            (YQuantity::Rate, _) if max >= 1e3 => YUnit::K,
+            (YQuantity::Rate, _) => YUnit::None,
+            (YQuantity::Seconds, _) if max >= 1.0 => YUnit::S,
+            (YQuantity::Seconds, _) if max >= 1e-3 => YUnit::Ms,
+            (YQuantity::Seconds, _) => YUnit::Us,
+            (YQuantity::Other, _) => YUnit::None,
+        }
+    }
+
+    fn scale(&self) -> f64 {
+        match self {
+            YUnit::K => 1e3,
+            YUnit::M => 1e6,
+            YUnit::Us => 1e-6,
+            YUnit::Ms => 1e-3,
+            YUnit::Auto | YUnit::None | YUnit::S => 1.0,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            YUnit::K => "k",
+            YUnit::M => "M",
+            YUnit::Us => " us",
+            YUnit::Ms => " ms",
+            YUnit::S => " s",
+            YUnit::Auto | YUnit::None => "",
+        }
+    }
+}
+
+// Formatter of the Y labels of a chart over the range, or None to keep the labels of plotters for
+// quantities without units. Linear axes show one more decimal than the spacing of about eight
+// labels needs, and log axes three significant digits, with trailing zeros dropped. Zero has no
+// unit.
+pub(crate) fn get_y_label_formatter(unit: YUnit, quantity: YQuantity, range: std::ops::Range<f64>, log_y: bool) -> Option<impl Fn(&f64) -> String> {
+    if quantity == YQuantity::Other {
+        return None
+    }
+    let unit = unit.resolve(quantity, &range);
+    let step = (range.end - range.start).abs() / unit.scale() / 8.0;
+    let linear_decimals = get_decimals(step) + 1;
+    Some(move |value: &f64| {
+        let scaled = value / unit.scale();
+        let decimals = match log_y {
+            true => get_decimals(scaled.abs()) + 2,
+            false => linear_decimals,
+        };
+        match trim_zeros(format!("{:.*}", decimals.clamp(0, 6) as usize, scaled)).as_str() {
+            "0" | "-0" => "0".to_string(),
+            text => format!("{}{}", text, unit.suffix()),
+        }
+    })
+}
+
+// Decimals needed to show the first significant digit of the value.
+fn get_decimals(value: f64) -> i32 {
+    match value > 0.0 && value.is_finite() {
+        true => -value.log10().floor() as i32,
+        false => 0,
+    }
+}
+
+fn trim_zeros(text: String) -> String {
+    match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.').to_string(),
+        false => text,
+    }
+}
@@ -0,0 +1,113 @@
+// Charts whose filters match no datasets, which would otherwise be drawn empty without a word, and
+// filters on parameters which no dataset has, which constrain nothing. Each is reported with the
+// parameters and values the datasets do have, suggesting the closest where a name or value looks
+// mistyped. With `--strict` a chart matching no datasets is an error.
+use super::*;
+use filter::{ParameterFilter, BASE_NAME_FILTER};
+
+// Values listed for a parameter before the rest are counted instead.
+static MAX_LISTED_VALUES: usize = 10;
+
+pub(crate) fn check_chart_filters(data: &StressTestData, params: &Params, strict: bool) -> Result<(), Box<dyn Error>> {
+    if data.datasets.is_empty() {
+        return Ok(())
+    }
+
+    let mut known: BTreeMap<&str, Vec<String>> = Default::default();
+    for dataset in data.datasets.values() {
+        known.entry(BASE_NAME_FILTER).or_default().push(dataset.base_name.clone());
+        for (name, value) in &dataset.parameters {
+            known.entry(name).or_default().push(value.to_string());
+        }
+    }
+    for values in known.values_mut() {
+        values.sort_by(|a, b| match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.total_cmp(&b),
+            _ => a.cmp(b),
+        });
+        values.dedup();
+    }
+
+    let mut unmatched: Vec<String> = Default::default();
+    for (index, spec) in params.chart_specs.iter().enumerate() {
+        if spec.filters.text().trim().is_empty() {
+            continue;
+        }
+
+        let matches = data.datasets.values().any(|dataset| dataset.passes_filters(&spec.filters) && (spec.chart_type.is_metric() || !dataset.derived));
+        let mut hints: Vec<String> = Default::default();
+        for filter in spec.filters.filters() {
+            let name = filter.name();
+            match known.get(name.as_str()) {
+                None => hints.push(match get_closest(name, known.keys().copied()) {
+                    Some(closest) => format!("no dataset has the parameter '{}', did you mean '{}'?", name, closest),
+                    None => format!("no dataset has the parameter '{}', the parameters are {}", name, known.keys().copied().collect::<Vec<_>>().join(", ")),
+                }),
+                Some(values) if !matches => hints.push(describe_values(filter, values)),
+                Some(_) => {},
+            }
+        }
+
+        let chart = format!("Chart {} ({})", index + 1, params.get_chart_title(spec));
+        match (matches, hints.is_empty()) {
+            (true, true) => {},
+            (true, false) => log::warn!("{} has a filter on a missing parameter: {}", chart, hints.join("; ")),
+            (false, _) => unmatched.push(format!("{} matches no datasets with filter '{}': {}", chart, spec.filters.text(), hints.join("; "))),
+        }
+    }
+
+    if strict && unmatched.len() == 1 {
+        return Err(unmatched.remove(0).into())
+    }
+    for message in &unmatched {
+        log::warn!("{}", message);
+    }
+    match (strict, unmatched.len()) {
+        (true, num) if num > 0 => Err(format!("{} charts match no datasets", num).into()),
+        _ => Ok(()),
+    }
+}
+
+// The values of the filter's parameter among the datasets, with the closest to a text value which
+// none of them have.
+fn describe_values(filter: &ParameterFilter, values: &[String]) -> String {
+    let name = filter.name();
+    let mut listed = values.iter().take(MAX_LISTED_VALUES).map(|value| value.as_str()).collect::<Vec<_>>().join(", ");
+    if values.len() > MAX_LISTED_VALUES {
+        listed += &format!(" and {} more", values.len() - MAX_LISTED_VALUES);
+    }
+
+    let suggestion = match filter {
+        ParameterFilter::Text(_, _, value) if !values.contains(value) => get_closest(value, values.iter().map(|value| value.as_str())),
+        _ => None,
+    };
+    match suggestion {
+        Some(closest) => format!("{} has the values {}, did you mean '{}'?", name, listed, closest),
+        None => format!("{} has the values {}", name, listed),
+    }
+}
+
+// The candidate within a few edits of the text, ignoring case, as a likely typo of it.
+fn get_closest<'a>(text: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = std::cmp::max(1, text.chars().count() / 3);
+    candidates
+        .map(|candidate| (edit_distance(&text.to_lowercase(), &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Levenshtein distance, the insertions, deletions and substitutions turning one text into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,88 @@
+// Parameters read from the parity-db stress test config used to produce a data file. The config is
+// found next to the data file with the same name and a `.toml` or `.json` extension, e.g.
+// `run1.toml` for `run1.csv` or `run1.csv.gz`.
+//
+// Nested settings are flattened into kebab-case names joined with `-`, so `[columns] btree = true`
+// becomes the parameter `columns-btree`. Boolean, non-negative integer and string settings are
+// kept, as those are the parameter values datasets can be labelled and filtered by.
+use super::*;
+use std::path::Path;
+
+pub fn load_workload_parameters(data_path: &Path) -> Result<BTreeMap<String, ParameterValue>, Box<dyn Error>> {
+    let mut parameters: BTreeMap<String, ParameterValue> = Default::default();
+
+    let toml_path = compression::uncompressed_path(data_path).with_extension("toml");
+    let json_path = compression::uncompressed_path(data_path).with_extension("json");
+
+    // A JSON data file is not its own stress test config.
+    if toml_path.is_file() && toml_path != data_path {
+        log::info!("Reading stress test config: {}", toml_path.display());
+        let text = std::fs::read_to_string(&toml_path)?;
+        let value: toml::Value = toml::from_str(&text).map_err(|e| format!("Failed to parse stress test config {}: {}", toml_path.display(), e))?;
+        add_toml_value("", &value, &mut parameters);
+    } else if json_path.is_file() && json_path != data_path {
+        log::info!("Reading stress test config: {}", json_path.display());
+        let text = std::fs::read_to_string(&json_path)?;
+        let value: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("Failed to parse stress test config {}: {}", json_path.display(), e))?;
+        add_json_value("", &value, &mut parameters);
+    }
+
+    Ok(parameters)
+}
+
+pub(crate) fn parameter_name(prefix: &str, key: &str) -> String {
+    let key = key.replace('_', "-").to_lowercase();
+    match prefix.is_empty() {
+        true => key,
+        false => format!("{}-{}", prefix, key),
+    }
+}
+
+pub(crate) fn add_toml_value(name: &str, value: &toml::Value, parameters: &mut BTreeMap<String, ParameterValue>) {
+    match value {
+        toml::Value::Boolean(v) => {
+            parameters.insert(name.to_string(), ParameterValue::Bool(*v));
+        },
+        toml::Value::Integer(v) => {
+            if let Ok(v) = u64::try_from(*v) {
+                parameters.insert(name.to_string(), ParameterValue::Int(v));
+            }
+        },
+        toml::Value::Float(v) => {
+            parameters.insert(name.to_string(), ParameterValue::Float(*v));
+        },
+        toml::Value::String(v) => {
+            parameters.insert(name.to_string(), ParameterValue::Text(v.clone()));
+        },
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                add_toml_value(&parameter_name(name, key), value, parameters);
+            }
+        },
+        _ => {},
+    }
+}
+
+pub(crate) fn add_json_value(name: &str, value: &serde_json::Value, parameters: &mut BTreeMap<String, ParameterValue>) {
+    match value {
+        serde_json::Value::Bool(v) => {
+            parameters.insert(name.to_string(), ParameterValue::Bool(*v));
+        },
+        serde_json::Value::Number(v) => {
+            if let Some(v) = v.as_u64() {
+                parameters.insert(name.to_string(), ParameterValue::Int(v));
+            } else if let Some(v) = v.as_f64() {
+                parameters.insert(name.to_string(), ParameterValue::Float(v));
+            }
+        },
+        serde_json::Value::String(v) => {
+            parameters.insert(name.to_string(), ParameterValue::Text(v.clone()));
+        },
+        serde_json::Value::Object(object) => {
+            for (key, value) in object {
+                add_json_value(&parameter_name(name, key), value, parameters);
+            }
+        },
+        _ => {},
+    }
+}
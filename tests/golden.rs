@@ -0,0 +1,31 @@
+// Renders charts of the fixture CSVs and checks them against the golden files next to them. Run
+// with UPDATE_GOLDEN set to write the current output as the golden files after an intended change.
+//
+// The series snapshots are checked by default. The golden images include text, whose rendering
+// varies with the fonts of the machine, so they are only checked with `cargo test -- --ignored`
+// on the machine which wrote them.
+use parity_db_visualizer::testing::{check_golden_image, check_golden_series, computed_series, render, ImageTolerance};
+use std::path::PathBuf;
+
+fn fixture(name: &str) -> String {
+    fixture_path(name).to_string_lossy().into_owned()
+}
+
+fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+}
+
+#[test]
+#[ignore = "fonts render differently between machines, run with --ignored"]
+fn commits_per_second_matches_golden_image() {
+    let (writers, readers) = (fixture("writers.csv"), fixture("readers.csv"));
+    let image = render(&["-d", &writers, &readers, "-c", "commits-per-second"]).unwrap();
+    check_golden_image(&image, &fixture_path("commits-per-second.png"), &ImageTolerance::default()).unwrap();
+}
+
+#[test]
+fn commit_time_matches_golden_series() {
+    let (writers, readers) = (fixture("writers.csv"), fixture("readers.csv"));
+    let series = computed_series(&["-d", &writers, &readers, "-c", "commit-time", "queries-per-second"]).unwrap();
+    check_golden_series(&series, &fixture_path("commit-time.series"), 1e-9).unwrap();
+}